@@ -0,0 +1,153 @@
+use crate::config::Config;
+use crate::country;
+use crate::error::CellError;
+use std::collections::HashMap;
+use std::fs::File;
+
+#[derive(Debug, Default, Clone)]
+/*
+    Maps regional or renamed model variants to one canonical model name, so that
+    "Galaxy S20 5G UW" and "Galaxy S20 5G" are treated as the same phone.
+ */
+pub struct ModelAliases {
+    // (lowercased oem or "" for any oem, lowercased alias) -> canonical model
+    aliases: HashMap<(String, String), String>,
+}
+
+impl ModelAliases {
+    pub fn new() -> ModelAliases {
+        ModelAliases { aliases: HashMap::new() }
+    }
+
+    /*
+        Reads an alias file with the headers oem,alias,canonical. The oem may be left
+        blank to apply the alias to every OEM.
+
+        Runtime: O(n)
+     */
+    pub fn from_csv(filename: &str) -> Result<ModelAliases, CellError> {
+        let mut reader = csv::Reader::from_reader(File::open(filename)?);
+        let mut aliases = ModelAliases::new();
+
+        for result in reader.records() {
+            let record = result?;
+            let alias = record.get(1).unwrap_or_default();
+            let canonical = record.get(2).unwrap_or_default();
+            if alias.trim().is_empty() || canonical.trim().is_empty() {
+                let row = record.position().map(|position| position.line() as usize);
+                return Err(CellError::InvalidField { row, field: "alias row", value: record.iter().collect::<Vec<_>>().join(",") });
+            }
+            aliases.insert(record.get(0).unwrap_or_default(), alias, canonical);
+        }
+
+        Ok(aliases)
+    }
+
+    // Adds an alias. An empty oem applies it to every OEM.
+    pub fn insert(&mut self, oem: &str, alias: &str, canonical: &str) {
+        let key = (oem.trim().to_lowercase(), alias.trim().to_lowercase());
+        self.aliases.insert(key, canonical.trim().to_string());
+    }
+
+    /*
+        Returns the canonical name of a model. An alias for the specific OEM wins over one
+        for every OEM; models without an alias are returned unchanged.
+
+        Runtime: O(1)
+     */
+    pub fn canonical<'a>(&'a self, oem: &str, model: &'a str) -> &'a str {
+        let alias = model.trim().to_lowercase();
+
+        self.aliases
+            .get(&(oem.trim().to_lowercase(), alias.clone()))
+            .or_else(|| self.aliases.get(&(String::new(), alias)))
+            .map(|canonical| canonical.as_str())
+            .unwrap_or(model)
+    }
+}
+
+// Built-in company names and old names -> the OEM the dataset files them under. Names are
+// matched case-insensitively.
+const BUILTIN_OEM_ALIASES: [(&str, &str); 22] = [
+    ("Apple Inc.", "Apple"),
+    ("Google LLC", "Google"),
+    ("Honor Device", "Honor"),
+    ("HTC Corp", "HTC"),
+    ("HTC Corporation", "HTC"),
+    ("Huawei Technologies", "Huawei"),
+    ("Lenovo Group", "Lenovo"),
+    ("LG Electronics", "LG"),
+    ("Microsoft Mobile", "Microsoft"),
+    ("Motorola Mobility", "Motorola"),
+    ("Nokia Corporation", "Nokia"),
+    ("OnePlus Technology", "OnePlus"),
+    ("Research In Motion", "BlackBerry"),
+    ("RIM", "BlackBerry"),
+    ("Samsung Electronics", "Samsung"),
+    ("Sharp Corporation", "Sharp"),
+    ("Sony Mobile Communications", "Sony Mobile"),
+    ("TCL Communication", "TCL"),
+    ("Tecno Mobile", "TECNO"),
+    ("Vivo Mobile", "vivo"),
+    ("Xiaomi Inc.", "Xiaomi"),
+    ("ZTE Corporation", "ZTE"),
+];
+
+#[derive(Debug, Default, Clone)]
+/*
+    Maps the spellings of one OEM, e.g. "HTC Corporation" and "htc", to the name the
+    per-OEM analyses group by: user overrides from the config first, then the built-in
+    aliases, then the case the country table writes a known OEM in. Other OEMs are kept as
+    they are written.
+ */
+pub struct OemAliases {
+    // lowercased alias -> canonical OEM
+    overrides: HashMap<String, String>,
+}
+
+impl OemAliases {
+    // Aliases that only use the built-in tables.
+    pub fn new() -> OemAliases {
+        OemAliases { overrides: HashMap::new() }
+    }
+
+    /*
+        The [oem_aliases] section of the config layered over the built-in aliases.
+
+        Runtime: O(n)
+     */
+    pub fn from_config(config: &Config) -> OemAliases {
+        let mut aliases = OemAliases::new();
+
+        for (alias, canonical) in &config.oem_aliases {
+            aliases.insert(alias, canonical);
+        }
+
+        aliases
+    }
+
+    // Adds or replaces the OEM an alias stands for.
+    pub fn insert(&mut self, alias: &str, canonical: &str) {
+        self.overrides.insert(alias.trim().to_lowercase(), canonical.trim().to_string());
+    }
+
+    /*
+        The name an OEM is grouped under.
+
+        Runtime: O(1) for overrides, O(k) over the built-in tables otherwise
+     */
+    pub fn canonical<'a>(&'a self, oem: &'a str) -> &'a str {
+        let oem = oem.trim();
+
+        if let Some(canonical) = self.overrides.get(&oem.to_lowercase()) {
+            return canonical;
+        }
+
+        BUILTIN_OEM_ALIASES
+            .iter()
+            .find(|(alias, _)| alias.eq_ignore_ascii_case(oem))
+            .map(|&(_, canonical)| canonical)
+            .or_else(|| country::known_oem(oem))
+            .unwrap_or(oem)
+    }
+}
@@ -0,0 +1,661 @@
+use crate::column::Column;
+use crate::display::DisplayFamily;
+use crate::grouping::FloatGrouping;
+use crate::intern;
+use crate::os::OsFamily;
+use crate::pipeline::{Aggregate, GroupBy, MeanOf};
+use crate::resolution::{AspectRatio, ResolutionClass};
+use crate::view::DatasetView;
+use crate::Cell;
+use std::collections::{BTreeMap, HashMap};
+
+/// Fewest values a mean, median or share is computed from before reports mark it as low
+/// confidence.
+pub const MIN_SAMPLE_SIZE: usize = 5;
+
+/**
+    The note for a result computed from `n` values: "low confidence (n=3)" when n is below
+    `min`, otherwise None. Empty subsets have no result to annotate, so n = 0 has no note.
+
+    Runtime: O(1)
+ */
+pub fn low_confidence(n: usize, min: usize) -> Option<String> {
+    (n > 0 && n < min).then(|| format!("low confidence (n={})", n))
+}
+
+/**
+    The n most common values of a column as (value, count), most common first. Ties go to the
+    value that sorts first, so the result is the same on every run. Missing values are not
+    counted; float columns are counted by their bucket in `grouping`, e.g. "6.1".
+
+    Runtime: O(n + v log v)
+ */
+pub fn mode(cells: &DatasetView, column: Column, n: usize, grouping: &FloatGrouping) -> Vec<(String, usize)> {
+    // Interned columns are counted by pointer, without copying each value.
+    let mut ranked: Vec<(String, usize)> = if intern::INTERNED.contains(&column) {
+        let counts = intern::count(cells.iter().filter_map(|cell| cell.interned(column)));
+        counts.into_iter().map(|(value, count)| (value.to_string(), count)).collect()
+    } else {
+        let mut counts: HashMap<String, usize> = HashMap::new();
+        for cell in cells {
+            if let Some(value) = grouping.key(cell, column) {
+                *counts.entry(value).or_insert(0) += 1;
+            }
+        }
+        counts.into_iter().collect()
+    };
+    ranked.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    ranked.truncate(n);
+
+    ranked
+}
+
+/// Mean display size per OEM, fed one cell at a time so it also works on a stream.
+pub fn display_size_by_oem() -> GroupBy<MeanOf> {
+    GroupBy::new(Column::Oem, || MeanOf::new(Column::DisplaySize))
+}
+
+/**
+    The groups of a grouped mean as (group, mean, count), highest mean first. Ties go to the
+    group with more values, then by name. Groups without any value are left out.
+
+    Runtime: O(g log g)
+ */
+pub fn rank_means(groups: &GroupBy<MeanOf>) -> Vec<(String, f32, usize)> {
+    let mut ranked: Vec<(String, f32, usize)> = groups
+        .groups()
+        .filter_map(|(group, mean)| mean.mean().map(|value| (group.clone(), value as f32, mean.count())))
+        .collect();
+    ranked.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| b.2.cmp(&a.2)).then_with(|| a.0.cmp(&b.0)));
+
+    ranked
+}
+
+/**
+    Rank the OEMs by the mean display size of their phones, as (OEM, mean inches, phones
+    with a display size).
+
+    Runtime: O(n + o log o)
+ */
+pub fn avg_display_size_per_oem(cells: &DatasetView) -> Vec<(String, f32, usize)> {
+    let mut groups = display_size_by_oem();
+    for cell in cells {
+        groups.update(cell);
+    }
+
+    rank_means(&groups)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// How long an OEM takes from announcing its phones to releasing them.
+pub struct LaunchDelay {
+    pub oem: String,
+    /// The mean months from announcement to release.
+    pub mean_months: f32,
+    /// Phones with both an announcement and release date.
+    pub phones: usize,
+    /// Of those, the phones whose both dates give the month; the others count by quarter or
+    /// by year.
+    pub by_month: usize,
+}
+
+/**
+    The mean delay from announcement to release of each OEM's released phones, longest
+    first (ties by OEM). A delay is counted in months when both dates give one, otherwise
+    by quarter or whole years, so a phone announced and released in the same year without
+    months counts as no delay.
+
+    Runtime: O(n + o log o)
+ */
+pub fn average_launch_delay_by_oem(cells: &DatasetView) -> Vec<LaunchDelay> {
+    let mut delays: BTreeMap<&str, (i64, usize, usize)> = BTreeMap::new();
+
+    for cell in cells {
+        if let (Some(oem), Some(announced), Some(released)) = (cell.oem.as_deref(), cell.launch_date, cell.release_date) {
+            let (months, phones, by_month) = delays.entry(oem).or_default();
+            *months += announced.months_until(&released) as i64;
+            *phones += 1;
+            *by_month += usize::from(announced.month.is_some() && released.month.is_some());
+        }
+    }
+
+    let mut delays: Vec<LaunchDelay> = delays
+        .into_iter()
+        .map(|(oem, (months, phones, by_month))| LaunchDelay { oem: oem.to_string(), mean_months: months as f32 / phones as f32, phones, by_month })
+        .collect();
+    delays.sort_by(|a, b| b.mean_months.total_cmp(&a.mean_months).then_with(|| a.oem.cmp(&b.oem)));
+    delays
+}
+
+/**
+    Count the phones of each display family announced every year, to follow display
+    technology adoption. Returns (year, [(family, count)]) sorted by year, with the families
+    in declaration order and only those with phones that year.
+
+    Runtime: O(n log n)
+ */
+pub fn display_families_per_year(cells: &DatasetView) -> Vec<(u32, Vec<(DisplayFamily, usize)>)> {
+    let mut counts: BTreeMap<u32, BTreeMap<DisplayFamily, usize>> = BTreeMap::new();
+
+    for cell in cells {
+        if let (Some(year), Some(family)) = (cell.launch_announced, cell.display_family()) {
+            *counts.entry(year).or_default().entry(family).or_insert(0) += 1;
+        }
+    }
+
+    counts.into_iter().map(|(year, families)| (year, families.into_iter().collect())).collect()
+}
+
+/**
+    Count the phones of each resolution class announced every year, to follow how displays
+    got sharper. Returns (year, [(class, count)]) sorted by year, with the classes lowest
+    first and only those with phones that year.
+
+    Runtime: O(n log n)
+ */
+pub fn resolution_classes_per_year(cells: &DatasetView) -> Vec<(u32, Vec<(ResolutionClass, usize)>)> {
+    let mut counts: BTreeMap<u32, BTreeMap<ResolutionClass, usize>> = BTreeMap::new();
+
+    for cell in cells {
+        if let (Some(year), Some(class)) = (cell.launch_announced, cell.resolution_class()) {
+            *counts.entry(year).or_default().entry(class).or_insert(0) += 1;
+        }
+    }
+
+    counts.into_iter().map(|(year, classes)| (year, classes.into_iter().collect())).collect()
+}
+
+/**
+    Count the phones of each aspect ratio announced every year, to follow how displays got
+    taller. Returns (year, [(ratio, count)]) sorted by year, with the ratios squarest first.
+
+    Runtime: O(n log n)
+ */
+pub fn aspect_ratios_per_year(cells: &DatasetView) -> Vec<(u32, Vec<(AspectRatio, usize)>)> {
+    let mut counts: BTreeMap<u32, Vec<(AspectRatio, usize)>> = BTreeMap::new();
+
+    for cell in cells {
+        if let (Some(year), Some(ratio)) = (cell.launch_announced, cell.aspect_ratio()) {
+            let ratios = counts.entry(year).or_default();
+            match ratios.iter_mut().find(|(seen, _)| *seen == ratio) {
+                Some((_, count)) => *count += 1,
+                None => ratios.push((ratio, 1)),
+            }
+        }
+    }
+
+    for ratios in counts.values_mut() {
+        ratios.sort_by(|a, b| a.0.value().total_cmp(&b.0.value()));
+    }
+    counts.into_iter().collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+/// How many phones of a group have a known SIM, and how many of those take two SIMs or an eSIM.
+pub struct SimShare {
+    pub phones: usize,
+    pub dual_sim: usize,
+    pub esim: usize,
+}
+
+impl SimShare {
+    fn add(&mut self, cell: &Cell) {
+        if let Some(sim) = cell.sim() {
+            self.phones += 1;
+            self.dual_sim += sim.dual_sim as usize;
+            self.esim += sim.esim as usize;
+        }
+    }
+
+    /// Fraction of the phones that take two SIMs, between 0 and 1.
+    pub fn dual_sim_share(&self) -> f32 {
+        if self.phones == 0 { 0.0 } else { self.dual_sim as f32 / self.phones as f32 }
+    }
+
+    /// Fraction of the phones that support an eSIM, between 0 and 1.
+    pub fn esim_share(&self) -> f32 {
+        if self.phones == 0 { 0.0 } else { self.esim as f32 / self.phones as f32 }
+    }
+}
+
+/**
+    Dual-SIM and eSIM counts of the phones announced each year, sorted by year. Phones
+    without a body_sim are left out.
+
+    Runtime: O(n log n)
+ */
+pub fn sim_share_per_year(cells: &DatasetView) -> Vec<(u32, SimShare)> {
+    let mut shares: BTreeMap<u32, SimShare> = BTreeMap::new();
+
+    for cell in cells.iter().filter(|cell| cell.sim().is_some()) {
+        if let Some(year) = cell.launch_announced {
+            shares.entry(year).or_default().add(cell);
+        }
+    }
+
+    shares.into_iter().collect()
+}
+
+/**
+    Dual-SIM and eSIM counts of each OEM's phones, sorted by OEM.
+
+    Runtime: O(n log n)
+ */
+pub fn sim_share_per_oem(cells: &DatasetView) -> Vec<(String, SimShare)> {
+    let mut shares: BTreeMap<String, SimShare> = BTreeMap::new();
+
+    for cell in cells.iter().filter(|cell| cell.sim().is_some()) {
+        if let Some(oem) = &cell.oem {
+            shares.entry(oem.to_string()).or_default().add(cell);
+        }
+    }
+
+    shares.into_iter().collect()
+}
+
+/**
+    How many phones take two SIMs in at least one of their variants.
+
+    Runtime: O(n)
+ */
+pub fn count_dual_sim(cells: &DatasetView) -> usize {
+    cells.iter().filter(|cell| cell.sim().is_some_and(|sim| sim.dual_sim)).count()
+}
+
+/**
+    The share of the phones announced each year with a known SIM that support an eSIM, as
+    (year, share between 0 and 1, phones with a known SIM), sorted by year.
+
+    Runtime: O(n log n)
+ */
+pub fn esim_share_per_year(cells: &DatasetView) -> Vec<YearMean> {
+    sim_share_per_year(cells).into_iter().map(|(year, share)| (year, share.esim_share(), share.phones)).collect()
+}
+
+/**
+    The n thinnest phones by depth as (OEM, model, depth in mm), thinnest first. Ties go by
+    OEM and model. Phones without parsable dimensions are left out.
+
+    Runtime: O(n log n)
+ */
+pub fn thinnest_phones(cells: &DatasetView, n: usize) -> Vec<(String, String, f32)> {
+    let mut phones: Vec<(String, String, f32)> = cells
+        .iter()
+        .filter_map(|cell| {
+            let depth = cell.dimensions()?.depth;
+            Some((cell.oem.as_deref().unwrap_or_default().to_string(), cell.model.as_deref().unwrap_or_default().to_string(), depth))
+        })
+        .collect();
+    phones.sort_by(|a, b| a.2.total_cmp(&b.2).then_with(|| a.0.cmp(&b.0)).then_with(|| a.1.cmp(&b.1)));
+    phones.truncate(n);
+
+    phones
+}
+
+/**
+    The phone with the densest display of each announcement year as (year, OEM, model, ppi),
+    sorted by year. Ties go by OEM and model. Phones without a plausible ppi are left out.
+
+    Runtime: O(n log n)
+ */
+pub fn highest_ppi_per_year(cells: &DatasetView) -> Vec<(u32, String, String, f32)> {
+    let mut densest: BTreeMap<u32, (&str, &str, f32)> = BTreeMap::new();
+
+    for cell in cells {
+        if let (Some(year), Some(ppi)) = (cell.launch_announced, cell.ppi()) {
+            let phone = (cell.oem.as_deref().unwrap_or_default(), cell.model.as_deref().unwrap_or_default(), ppi);
+            let best = densest.entry(year).or_insert(phone);
+            if ppi.total_cmp(&best.2).then_with(|| best.0.cmp(phone.0)).then_with(|| best.1.cmp(phone.1)).is_gt() {
+                *best = phone;
+            }
+        }
+    }
+
+    densest.into_iter().map(|(year, (oem, model, ppi))| (year, oem.to_string(), model.to_string(), ppi)).collect()
+}
+
+/**
+    The mean depth of the phones announced each year as (year, mean mm, phones with parsable
+    dimensions), sorted by year.
+
+    Runtime: O(n log n)
+ */
+pub fn average_thickness_per_year(cells: &DatasetView) -> Vec<(u32, f32, usize)> {
+    let mut totals: BTreeMap<u32, (f32, usize)> = BTreeMap::new();
+
+    for cell in cells {
+        if let (Some(year), Some(dimensions)) = (cell.launch_announced, cell.dimensions()) {
+            let total = totals.entry(year).or_insert((0.0, 0));
+            total.0 += dimensions.depth;
+            total.1 += 1;
+        }
+    }
+
+    totals.into_iter().map(|(year, (sum, count))| (year, sum / count as f32, count)).collect()
+}
+
+/**
+    The mean estimated screen-to-body ratio of the phones announced each year as (year, mean
+    ratio between 0 and 1, phones with an estimate), sorted by year.
+
+    Runtime: O(n log n)
+ */
+pub fn screen_to_body_ratio_per_year(cells: &DatasetView) -> Vec<(u32, f32, usize)> {
+    let mut totals: BTreeMap<u32, (f32, usize)> = BTreeMap::new();
+
+    for cell in cells {
+        if let (Some(year), Some(ratio)) = (cell.launch_announced, cell.screen_to_body_ratio()) {
+            let total = totals.entry(year).or_insert((0.0, 0));
+            total.0 += ratio;
+            total.1 += 1;
+        }
+    }
+
+    totals.into_iter().map(|(year, (sum, count))| (year, sum / count as f32, count)).collect()
+}
+
+/**
+    The mean version of the OS of a family the phones announced each year shipped with, as
+    (year, mean version as a decimal, phones with a version), sorted by year, e.g. the
+    average shipped Android version. Upgrades are not counted.
+
+    Runtime: O(n log n)
+ */
+pub fn average_os_version_per_year(cells: &DatasetView, family: OsFamily) -> Vec<(u32, f32, usize)> {
+    let mut totals: BTreeMap<u32, (f32, usize)> = BTreeMap::new();
+
+    for cell in cells {
+        let version = cell.os().filter(|os| os.family == family).and_then(|os| os.version);
+        if let (Some(year), Some(version)) = (cell.launch_announced, version) {
+            let total = totals.entry(year).or_insert((0.0, 0));
+            total.0 += version.value();
+            total.1 += 1;
+        }
+    }
+
+    totals.into_iter().map(|(year, (sum, count))| (year, sum / count as f32, count)).collect()
+}
+
+/// A mean by year as (year, mean, values it was computed from).
+pub type YearMean = (u32, f32, usize);
+
+/**
+    The mean number of OS upgrades platform_os promises the phones of each OEM announced
+    each year, as (OEM, [(year, mean upgrades, phones with a shipped version)]) sorted by OEM
+    then year. A phone without an upgrade clause counts as none.
+
+    Runtime: O(n log n)
+ */
+pub fn os_upgrades_per_oem_year(cells: &DatasetView) -> Vec<(String, Vec<YearMean>)> {
+    let mut totals: BTreeMap<&str, BTreeMap<u32, (f32, usize)>> = BTreeMap::new();
+
+    for cell in cells {
+        let upgrades = cell.os().and_then(|os| os.upgrades());
+        if let (Some(oem), Some(year), Some(upgrades)) = (cell.oem.as_deref(), cell.launch_announced, upgrades) {
+            let total = totals.entry(oem).or_default().entry(year).or_insert((0.0, 0));
+            total.0 += upgrades as f32;
+            total.1 += 1;
+        }
+    }
+
+    totals
+        .into_iter()
+        .map(|(oem, years)| (oem.to_string(), years.into_iter().map(|(year, (sum, count))| (year, sum / count as f32, count)).collect()))
+        .collect()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// The median specs of the phones announced in one year, a synthesized "typical phone".
+pub struct PhoneProfile {
+    pub year: u32,
+    pub phones: usize,
+    pub body_weight: Option<f32>,
+    pub display_size: Option<f32>,
+    pub resolution: Option<ResolutionClass>,
+    pub sensors: Option<f32>,
+}
+
+// The median of some values, the mean of the middle two for an even count.
+fn median(mut values: Vec<f32>) -> Option<f32> {
+    values.sort_by(f32::total_cmp);
+
+    let len = values.len();
+    if len == 0 {
+        None
+    } else if len.is_multiple_of(2) {
+        Some((values[len / 2 - 1] + values[len / 2]) / 2.0)
+    } else {
+        Some(values[len / 2])
+    }
+}
+
+/**
+    The median body weight, display size, resolution class and sensor count of the phones
+    announced each year, sorted by year. Each median only looks at the phones that have the
+    value; the resolution class is the lower middle class for an even count.
+
+    Runtime: O(n log n)
+ */
+pub fn profile_per_year(cells: &DatasetView) -> Vec<PhoneProfile> {
+    let mut years: BTreeMap<u32, Vec<&Cell>> = BTreeMap::new();
+    for cell in cells {
+        if let Some(year) = cell.launch_announced {
+            years.entry(year).or_default().push(cell);
+        }
+    }
+
+    years
+        .into_iter()
+        .map(|(year, cells)| {
+            let mut resolutions: Vec<ResolutionClass> = cells.iter().filter_map(|cell| cell.resolution_class()).collect();
+            resolutions.sort();
+
+            PhoneProfile {
+                year,
+                phones: cells.len(),
+                body_weight: median(cells.iter().filter_map(|cell| cell.body_weight).collect()),
+                display_size: median(cells.iter().filter_map(|cell| cell.display_size).collect()),
+                resolution: resolutions.get(resolutions.len().saturating_sub(1) / 2).copied(),
+                sensors: median(cells.iter().filter_map(|cell| cell.sensor_count()).map(|count| count as f32).collect()),
+            }
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// The phones announced in one decade and the OEM that announced the most of them.
+pub struct DecadeSummary {
+    /// First year of the decade, e.g. 1990 for the 1990s.
+    pub decade: u32,
+    pub launches: usize,
+    /// The leading OEM and its launches; ties go to the OEM first by name.
+    pub leader: Option<(String, usize)>,
+}
+
+impl DecadeSummary {
+    /// The decade as written, e.g. "1990s".
+    pub fn label(&self) -> String {
+        format!("{}s", self.decade)
+    }
+}
+
+/**
+    Count the launches of every quarter as (year, quarter, count), sorted by date. Phones
+    whose launch_announced gives only the year are left out.
+
+    Runtime: O(n log n)
+ */
+pub fn launches_per_quarter(cells: &DatasetView) -> Vec<(u32, u8, usize)> {
+    let mut quarters: BTreeMap<(u32, u8), usize> = BTreeMap::new();
+
+    for cell in cells {
+        if let Some((year, Some(quarter))) = cell.launch_date.map(|date| (date.year, date.quarter)) {
+            *quarters.entry((year, quarter)).or_insert(0) += 1;
+        }
+    }
+
+    quarters.into_iter().map(|((year, quarter), count)| (year, quarter, count)).collect()
+}
+
+/**
+    Count the launches of every decade by announcement year, with the OEM that announced the
+    most phones in each, sorted by decade. Only decades with launches are listed.
+
+    Runtime: O(n log n)
+ */
+pub fn launches_per_decade(cells: &DatasetView) -> Vec<DecadeSummary> {
+    let mut decades: BTreeMap<u32, BTreeMap<&str, usize>> = BTreeMap::new();
+    let mut launches: BTreeMap<u32, usize> = BTreeMap::new();
+
+    for cell in cells {
+        if let Some(year) = cell.launch_announced {
+            let decade = year - year % 10;
+            *launches.entry(decade).or_insert(0) += 1;
+            if let Some(oem) = cell.oem.as_deref() {
+                *decades.entry(decade).or_default().entry(oem).or_insert(0) += 1;
+            }
+        }
+    }
+
+    launches
+        .into_iter()
+        .map(|(decade, launches)| {
+            // Reversed names make max_by_key prefer the first name on equal counts.
+            let leader = decades
+                .get(&decade)
+                .and_then(|oems| oems.iter().max_by_key(|(oem, count)| (**count, std::cmp::Reverse(**oem))))
+                .map(|(oem, count)| (oem.to_string(), *count));
+            DecadeSummary { decade, launches, leader }
+        })
+        .collect()
+}
+
+/// How many years after its last launch, counted to the latest launch in the data, an OEM
+/// counts as dormant.
+pub const DORMANT_AFTER_YEARS: u32 = 2;
+
+#[derive(Debug, Clone, PartialEq)]
+/// How regularly one OEM launched phones, over the years it announced any.
+pub struct OemCadence {
+    pub oem: String,
+    pub launches: usize,
+    pub first_year: u32,
+    pub last_year: u32,
+    /// Years with at least one launch.
+    pub active_years: usize,
+    /// Runs of years without launches between two launch years, as (first, last) year.
+    pub gaps: Vec<(u32, u32)>,
+    /// The year after the last launch, if that was DORMANT_AFTER_YEARS or more before the
+    /// latest launch of any OEM.
+    pub dormant_since: Option<u32>,
+}
+
+impl OemCadence {
+    /// Average launches in the years with any.
+    pub fn launches_per_active_year(&self) -> f64 {
+        self.launches as f64 / self.active_years as f64
+    }
+}
+
+/**
+    The launch cadence of every OEM with announcement years: launches per active year, the
+    gaps between launch years, and when it went dormant. Sorted by launches, most first, then
+    by name.
+
+    Runtime: O(n log n)
+ */
+pub fn oem_cadence(cells: &DatasetView) -> Vec<OemCadence> {
+    let mut years: BTreeMap<&str, BTreeMap<u32, usize>> = BTreeMap::new();
+    for cell in cells {
+        if let (Some(oem), Some(year)) = (cell.oem.as_deref(), cell.launch_announced) {
+            *years.entry(oem).or_default().entry(year).or_insert(0) += 1;
+        }
+    }
+    let Some(latest) = years.values().filter_map(|launches| launches.keys().next_back()).max().copied() else {
+        return Vec::new();
+    };
+
+    let mut cadences: Vec<OemCadence> = years
+        .into_iter()
+        .map(|(oem, launches)| {
+            let active: Vec<u32> = launches.keys().copied().collect();
+            let (first_year, last_year) = (active[0], active[active.len() - 1]);
+            let gaps = active.windows(2).filter(|pair| pair[1] > pair[0] + 1).map(|pair| (pair[0] + 1, pair[1] - 1)).collect();
+
+            OemCadence {
+                oem: oem.to_string(),
+                launches: launches.values().sum(),
+                first_year,
+                last_year,
+                active_years: active.len(),
+                gaps,
+                dormant_since: (latest - last_year >= DORMANT_AFTER_YEARS).then_some(last_year + 1),
+            }
+        })
+        .collect();
+
+    cadences.sort_by(|a, b| b.launches.cmp(&a.launches).then_with(|| a.oem.cmp(&b.oem)));
+    cadences
+}
+
+/**
+    Split values into `bins` equal-width bins from the smallest value to the largest, as
+    (low, high, count) in order. The last bin includes the largest value; with a single
+    distinct value there is one bin. No values give no bins.
+
+    Runtime: O(n)
+ */
+pub fn histogram(values: &[f32], bins: usize) -> Vec<(f32, f32, usize)> {
+    let (Some(min), Some(max)) = (values.iter().copied().reduce(f32::min), values.iter().copied().reduce(f32::max)) else {
+        return Vec::new();
+    };
+    let bins = if max > min { bins.max(1) } else { 1 };
+    let width = (max - min) / bins as f32;
+    let mut counts = vec![0; bins];
+
+    for value in values {
+        let bin = if width > 0.0 { ((value - min) / width) as usize } else { 0 };
+        counts[bin.min(bins - 1)] += 1;
+    }
+
+    counts.into_iter().enumerate().map(|(bin, count)| (min + bin as f32 * width, min + (bin + 1) as f32 * width, count)).collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// A least-squares line `y = slope * x + intercept` through n points, and how well it fits.
+pub struct Regression {
+    pub slope: f64,
+    pub intercept: f64,
+    pub r_squared: f64,
+    pub n: usize,
+}
+
+/**
+    Fit a least-squares line through (x, y) points. None with fewer than two points or when
+    every x is the same, as no line fits then. r² is 1 when every y is the same.
+
+    Runtime: O(n)
+ */
+pub fn linear_regression(points: &[(f32, f32)]) -> Option<Regression> {
+    let n = points.len();
+    if n < 2 {
+        return None;
+    }
+    let mean = |value: fn(&(f32, f32)) -> f32| points.iter().map(|point| value(point) as f64).sum::<f64>() / n as f64;
+    let (mean_x, mean_y) = (mean(|point| point.0), mean(|point| point.1));
+
+    let (mut sxx, mut sxy, mut syy) = (0.0, 0.0, 0.0);
+    for &(x, y) in points {
+        let (dx, dy) = (x as f64 - mean_x, y as f64 - mean_y);
+        sxx += dx * dx;
+        sxy += dx * dy;
+        syy += dy * dy;
+    }
+    if sxx == 0.0 {
+        return None;
+    }
+
+    let slope = sxy / sxx;
+    let r_squared = if syy == 0.0 { 1.0 } else { sxy * sxy / (sxx * syy) };
+    Some(Regression { slope, intercept: mean_y - slope * mean_x, r_squared, n })
+}
@@ -0,0 +1,104 @@
+use crate::column::Column;
+use crate::parse::ParseReport;
+use serde::Deserialize;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+// What happens to a parsed number outside its column's plausible range.
+pub enum BoundsAction {
+    // It is kept, and listed in the parse report.
+    #[default]
+    Flag,
+    // It is replaced by the nearest bound, and listed in the parse report.
+    Clamp,
+    // It becomes None, and is listed in the parse report.
+    Reject,
+}
+
+impl fmt::Display for BoundsAction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BoundsAction::Flag => write!(f, "kept"),
+            BoundsAction::Clamp => write!(f, "clamped"),
+            BoundsAction::Reject => write!(f, "rejected"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+// The smallest and largest plausible value of a column, both included.
+pub struct Range {
+    pub min: f32,
+    pub max: f32,
+}
+
+impl Range {
+    pub fn contains(&self, value: f32) -> bool {
+        self.min <= value && value <= self.max
+    }
+}
+
+impl fmt::Display for Range {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}-{}", self.min, self.max)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Deserialize)]
+#[serde(default)]
+/*
+    Plausibility bounds for the parsed numbers, checked while loading. Read from the [bounds]
+    section of the config, e.g.
+
+        [bounds]
+        action = "clamp"
+        body_weight = { min = 20, max = 1000 }
+        display_size = { min = 1, max = 15 }
+
+    Columns without a range are not checked, and by default none has one.
+ */
+pub struct Bounds {
+    pub action: BoundsAction,
+    pub body_weight: Option<Range>,
+    pub display_size: Option<Range>,
+}
+
+impl Bounds {
+    // Ranges wide enough for every phone so far: 20-1000 g and 1-15 inches.
+    pub const PLAUSIBLE: Bounds = Bounds {
+        action: BoundsAction::Flag,
+        body_weight: Some(Range { min: 20.0, max: 1000.0 }),
+        display_size: Some(Range { min: 1.0, max: 15.0 }),
+    };
+
+    pub fn range(&self, column: Column) -> Option<Range> {
+        match column {
+            Column::BodyWeight => self.body_weight,
+            Column::DisplaySize => self.display_size,
+            _ => None,
+        }
+    }
+
+    /*
+        Check a parsed value against its column's range. A value outside it is added to the
+        report with its line and flagged, clamped or rejected by the action.
+
+        Runtime: O(1)
+     */
+    pub fn check(&self, line: usize, column: Column, value: Option<f32>, report: &mut ParseReport) -> Option<f32> {
+        let (Some(number), Some(range)) = (value, self.range(column)) else {
+            return value;
+        };
+        if range.contains(number) {
+            return value;
+        }
+
+        report.record_out_of_range(line, column, number, range, self.action);
+        match self.action {
+            BoundsAction::Flag => value,
+            BoundsAction::Clamp => Some(number.clamp(range.min, range.max)),
+            BoundsAction::Reject => None,
+        }
+    }
+}
@@ -0,0 +1,84 @@
+use crate::column::Column;
+use crate::grouping::FloatGrouping;
+use crate::table;
+use crate::view::DatasetView;
+use crate::Cell;
+use std::collections::HashMap;
+
+// The value a cell is grouped under for a column; the launch status is grouped by category
+// and float columns by their default bucket.
+fn group_key(cell: &Cell, column: Column) -> Option<String> {
+    match column {
+        Column::LaunchStatus => cell.status_category().map(|category| category.to_string()),
+        _ => FloatGrouping::DEFAULT.key(cell, column).map(|value| value.trim().to_string()),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/*
+    Counts of cells per value of one column (`by`), split into one count per value of a
+    second column (`split`), e.g. phones per OEM per launch status.
+ */
+pub struct Breakdown {
+    pub by: Column,
+    pub split: Column,
+    // The split values, most common first.
+    pub splits: Vec<String>,
+    // (value of `by`, count per split value, total), largest total first.
+    pub rows: Vec<(String, Vec<usize>, usize)>,
+}
+
+impl Breakdown {
+    /*
+        Count the cells. Cells missing either value are left out. Ties are ordered by name.
+
+        Runtime: O(n + g log g)
+     */
+    pub fn of(cells: &DatasetView, by: Column, split: Column) -> Breakdown {
+        let mut counts: HashMap<String, HashMap<String, usize>> = HashMap::new();
+        let mut split_totals: HashMap<String, usize> = HashMap::new();
+
+        for cell in cells {
+            if let (Some(group), Some(part)) = (group_key(cell, by), group_key(cell, split)) {
+                *split_totals.entry(part.clone()).or_insert(0) += 1;
+                *counts.entry(group).or_default().entry(part).or_insert(0) += 1;
+            }
+        }
+
+        let mut splits: Vec<(String, usize)> = split_totals.into_iter().collect();
+        splits.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        let splits: Vec<String> = splits.into_iter().map(|(part, _)| part).collect();
+
+        let mut rows: Vec<(String, Vec<usize>, usize)> = counts
+            .into_iter()
+            .map(|(group, parts)| {
+                let row: Vec<usize> = splits.iter().map(|part| parts.get(part).copied().unwrap_or(0)).collect();
+                let total = row.iter().sum();
+                (group, row, total)
+            })
+            .collect();
+        rows.sort_by(|a, b| b.2.cmp(&a.2).then_with(|| a.0.cmp(&b.0)));
+
+        Breakdown { by, split, splits, rows }
+    }
+
+    // The breakdown as a text table with a total column.
+    pub fn render(&self) -> String {
+        let mut headers = vec![self.by.name().to_string()];
+        headers.extend(self.splits.iter().cloned());
+        headers.push("total".to_string());
+
+        let rows: Vec<Vec<String>> = self
+            .rows
+            .iter()
+            .map(|(group, counts, total)| {
+                let mut row = vec![group.clone()];
+                row.extend(counts.iter().map(|count| count.to_string()));
+                row.push(total.to_string());
+                row
+            })
+            .collect();
+
+        table::render_rows(&headers, &rows)
+    }
+}
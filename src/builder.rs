@@ -0,0 +1,138 @@
+use crate::column::Column;
+use crate::date::LaunchDate;
+use crate::error::CellError;
+use crate::Cell;
+use std::sync::Arc;
+
+#[derive(Debug, Clone, Default)]
+/*
+    Builds a cell field by field instead of spelling out all twelve options:
+
+        let cell = CellBuilder::new().oem("Apple").model("iPhone 15").body_weight(171.0).build()?;
+
+    Fields that are never set stay None. build() checks the result before returning it.
+ */
+pub struct CellBuilder {
+    cell: Cell,
+}
+
+impl Cell {
+    pub fn builder() -> CellBuilder {
+        CellBuilder::new()
+    }
+}
+
+impl CellBuilder {
+    pub fn new() -> CellBuilder {
+        CellBuilder::default()
+    }
+
+    pub fn oem(mut self, oem: impl Into<Arc<str>>) -> CellBuilder {
+        self.cell.oem = Some(oem.into());
+        self
+    }
+
+    pub fn model(mut self, model: impl Into<Box<str>>) -> CellBuilder {
+        self.cell.model = Some(model.into());
+        self
+    }
+
+    pub fn launch_announced(mut self, year: u32) -> CellBuilder {
+        self.cell.launch_announced = Some(year);
+        self.cell.sync_launch_date();
+        self
+    }
+
+    // Sets launch_announced to the date's year, keeping its month and quarter.
+    pub fn launch_date(mut self, date: LaunchDate) -> CellBuilder {
+        self.cell.launch_announced = Some(date.year);
+        self.cell.launch_date = Some(date);
+        self
+    }
+
+    pub fn launch_status(mut self, status: impl Into<Arc<str>>) -> CellBuilder {
+        self.cell.launch_status = Some(status.into());
+        self.cell.sync_release_date();
+        self
+    }
+
+    // Sets a release date the launch status agrees with; call it after launch_status.
+    pub fn release_date(mut self, date: LaunchDate) -> CellBuilder {
+        self.cell.release_date = Some(date);
+        self.cell.sync_release_date();
+        self
+    }
+
+    pub fn body_dimensions(mut self, dimensions: impl Into<Box<str>>) -> CellBuilder {
+        self.cell.body_dimensions = Some(dimensions.into());
+        self
+    }
+
+    // In grams.
+    pub fn body_weight(mut self, grams: f32) -> CellBuilder {
+        self.cell.body_weight = Some(grams);
+        self
+    }
+
+    pub fn body_sim(mut self, sim: impl Into<Arc<str>>) -> CellBuilder {
+        self.cell.body_sim = Some(sim.into());
+        self
+    }
+
+    pub fn display_type(mut self, display_type: impl Into<Arc<str>>) -> CellBuilder {
+        self.cell.display_type = Some(display_type.into());
+        self
+    }
+
+    // In inches.
+    pub fn display_size(mut self, inches: f32) -> CellBuilder {
+        self.cell.display_size = Some(inches);
+        self
+    }
+
+    pub fn display_resolution(mut self, resolution: impl Into<Box<str>>) -> CellBuilder {
+        self.cell.display_resolution = Some(resolution.into());
+        self
+    }
+
+    // The second, cover display of a foldable.
+    pub fn cover_display(mut self, inches: f32, resolution: impl Into<Box<str>>) -> CellBuilder {
+        self.cell.cover_display_size = Some(inches);
+        self.cell.cover_display_resolution = Some(resolution.into());
+        self
+    }
+
+    pub fn features_sensors(mut self, sensors: impl Into<Box<str>>) -> CellBuilder {
+        self.cell.features_sensors = Some(sensors.into());
+        self.cell.parse_sensors();
+        self
+    }
+
+    pub fn platform_os(mut self, os: impl Into<Arc<str>>) -> CellBuilder {
+        self.cell.platform_os = Some(os.into());
+        self
+    }
+
+    /*
+        The built cell. Every cell needs a non-blank OEM and model, and a body weight or
+        display size, when given, must be a positive number.
+
+        Runtime: O(1)
+     */
+    pub fn build(self) -> Result<Cell, CellError> {
+        let cell = self.cell;
+
+        for (column, value) in [(Column::Oem, cell.oem.as_deref()), (Column::Model, cell.model.as_deref())] {
+            if value.is_none_or(|value| value.trim().is_empty()) {
+                return Err(CellError::Required { column });
+            }
+        }
+        for (column, value) in [(Column::BodyWeight, cell.body_weight), (Column::DisplaySize, cell.display_size)] {
+            if let Some(value) = value.filter(|value| !value.is_finite() || *value <= 0.0) {
+                return Err(CellError::Parse { row: None, column, value: value.to_string() });
+            }
+        }
+
+        Ok(cell)
+    }
+}
@@ -0,0 +1,67 @@
+use crate::error::CellError;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+// Rows a long-running operation handles between two checks, so checking the clock costs
+// little next to the work.
+pub const CHECK_EVERY: usize = 1024;
+
+#[derive(Debug, Clone, Default)]
+/*
+    Cooperative cancellation of a query, e.g. a slow search or a huge group-by. The query
+    checks the token as it goes and stops with CellError::Cancelled or TimedOut, leaving the
+    loaded dataset as it was. Clones share the flag, so another thread (a REPL's Ctrl-C
+    handler, a server's request timeout) can cancel the query a clone was given to.
+ */
+pub struct Cancel {
+    flag: Arc<AtomicBool>,
+    deadline: Option<(Instant, Duration)>,
+}
+
+impl Cancel {
+    // A token that only stops when cancelled.
+    pub fn new() -> Cancel {
+        Cancel::default()
+    }
+
+    // A token that also stops once the timeout has passed from now.
+    pub fn with_timeout(timeout: Duration) -> Cancel {
+        Cancel { flag: Arc::default(), deadline: Some((Instant::now() + timeout, timeout)) }
+    }
+
+    // Asks every query holding this token or a clone of it to stop.
+    pub fn cancel(&self) {
+        self.flag.store(true, Ordering::Relaxed);
+    }
+
+    // Clears a cancel, so the token can be handed to the next query, e.g. the next command of
+    // a REPL.
+    pub fn reset(&self) {
+        self.flag.store(false, Ordering::Relaxed);
+    }
+
+    // The flag that cancels the token and its clones, for a signal handler to set.
+    pub fn shared_flag(&self) -> Arc<AtomicBool> {
+        Arc::clone(&self.flag)
+    }
+
+    /*
+        Err once the token is cancelled or past its deadline.
+
+        Runtime: O(1)
+     */
+    pub fn check(&self) -> Result<(), CellError> {
+        if self.flag.load(Ordering::Relaxed) {
+            return Err(CellError::Cancelled);
+        }
+        match self.deadline {
+            Some((deadline, timeout)) if Instant::now() >= deadline => Err(CellError::TimedOut { after: timeout }),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.check().is_err()
+    }
+}
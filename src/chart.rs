@@ -0,0 +1,81 @@
+// Length of the longest bar.
+pub const BAR_WIDTH: usize = 40;
+
+/*
+    Render counts as a horizontal ASCII bar chart, one line per item in the given order:
+    the label, a bar of '#' scaled so the largest count fills `width`, then the count and its
+    share of `total` (pass None to use the sum of the items, e.g. when not showing all of
+    them). Non-zero counts always get at least one '#'.
+
+    Runtime: O(n * width)
+ */
+pub fn bar_chart(items: &[(String, usize)], total: Option<usize>, width: usize) -> String {
+    let total = total.unwrap_or_else(|| items.iter().map(|(_, count)| count).sum());
+    let largest = items.iter().map(|&(_, count)| count).max().unwrap_or(0);
+    let label_width = items.iter().map(|(label, _)| label.chars().count()).max().unwrap_or(0);
+    let count_width = largest.to_string().len();
+
+    let mut chart = String::new();
+    for (label, count) in items {
+        let length = if largest == 0 { 0 } else { (count * width).div_ceil(largest) };
+        let share = if total == 0 { 0.0 } else { *count as f32 / total as f32 * 100.0 };
+        chart.push_str(&format!(
+            "{:<label_width$} | {:<width$} {:>count_width$} ({:.1}%)\n",
+            label,
+            "#".repeat(length),
+            count,
+            share,
+            label_width = label_width,
+            width = width,
+            count_width = count_width
+        ));
+    }
+
+    chart
+}
+
+// Block characters from lowest to highest, used by sparkline.
+const SPARKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/*
+    A one-line chart of a series: one block character per value, scaled to the largest value
+    in the series. Zeros are drawn as spaces so gaps in activity stand out.
+
+    Runtime: O(n)
+ */
+pub fn sparkline(values: &[usize]) -> String {
+    let largest = values.iter().copied().max().unwrap_or(0);
+
+    values
+        .iter()
+        .map(|&value| match value {
+            0 => ' ',
+            _ => SPARKS[(value * SPARKS.len()).div_ceil(largest) - 1],
+        })
+        .collect()
+}
+
+/*
+    A bar of exactly `width` characters split between parts in proportion to their counts,
+    each drawn with its own character, e.g. "SSSSHHHHHHFF". Boundaries are rounded, so a part
+    much smaller than 1/width of the total may not show. Empty when every count is zero.
+
+    Runtime: O(p + width)
+ */
+pub fn stacked_bar(parts: &[(char, usize)], width: usize) -> String {
+    let total: usize = parts.iter().map(|&(_, count)| count).sum();
+    if total == 0 {
+        return String::new();
+    }
+
+    let mut bar = String::new();
+    let (mut sum, mut drawn) = (0, 0);
+    for &(symbol, count) in parts {
+        sum += count;
+        let end = (sum * width * 2 + total) / (total * 2);
+        bar.extend(std::iter::repeat_n(symbol, end - drawn));
+        drawn = end;
+    }
+
+    bar
+}
@@ -0,0 +1,1430 @@
+use crate::analysis;
+use crate::breakdown::Breakdown;
+use crate::chart;
+use crate::cancel::Cancel;
+use crate::column::{Column, HeaderIndex};
+use crate::config::Config;
+use crate::country::{self, CountryMap};
+use crate::dataset::Dataset;
+use crate::edits::{Edit, PendingEdits, RawTable};
+use crate::error::CellError;
+use crate::external::{self, ExternalSorter, SpillGroupBy};
+use crate::filter::{self, Filter, PRESETS};
+use crate::fixture;
+use crate::heatmap;
+use crate::history::{self, History, RunMetrics};
+use crate::latex;
+use crate::metric::{Metrics, NumericAccessor};
+use crate::normalize::Normalizer;
+use crate::output;
+use crate::parse::ParseReport;
+use crate::partition::{self, PartitionFormat};
+use crate::pipeline::{Aggregate, Derive, Pipeline, Sink};
+use crate::query::SavedQuery;
+use crate::random::Rng;
+use crate::reader::CellReader;
+use crate::repl::Repl;
+use crate::report::ReportFormat;
+use crate::schema::Schema;
+use crate::sketch::{self, HyperLogLog, TDigest};
+use crate::table;
+use crate::tags::Tags;
+use crate::units::UnitProfile;
+use crate::tui;
+use crate::viewer::TableViewer;
+use crate::watch::{LiveDataset, Refresh};
+use crate::wizard::{self, Samples};
+use crate::worldmap;
+use crate::{Cell, DEFAULT_AFTER_YEAR};
+use clap::{Parser, Subcommand};
+use std::collections::BTreeSet;
+use std::error::Error;
+use std::path::Path;
+use std::time::Duration;
+
+// Input file used when no --file flag is given.
+pub const DEFAULT_CSV_FILE: &str = "cells.csv";
+
+#[derive(Debug, Parser)]
+#[command(name = "cells", about = "Clean, explore and analyse a CSV dataset of mobile phones")]
+// The command line. Without a command every analysis of the report is printed.
+pub struct Cli {
+    /// CSV file to read
+    #[arg(long, global = true, default_value = DEFAULT_CSV_FILE)]
+    pub file: String,
+    /// Time the load and each analysis of the report
+    #[arg(long, global = true)]
+    pub timings: bool,
+    /// Print the estimated memory of the loaded dataset
+    #[arg(long, global = true)]
+    pub mem_stats: bool,
+    /// Rank the launch years after this one
+    #[arg(long, global = true, default_value_t = DEFAULT_AFTER_YEAR)]
+    pub after_year: u32,
+    /// TOML file mapping the CSV headers to columns, for dataset variants
+    #[arg(long, global = true)]
+    pub schema: Option<String>,
+    /// Fail with a line-numbered report if any numeric value is not a number
+    #[arg(long, global = true)]
+    pub strict: bool,
+    /// Keep the text of fields as the file has it: no Unicode, quote or whitespace cleaning
+    #[arg(long, global = true)]
+    pub raw_text: bool,
+    /// Decimal places of the numbers in reports, tables and exports (overrides the config)
+    #[arg(long, global = true)]
+    pub decimals: Option<usize>,
+    /// Mark results from fewer rows than this as low confidence (overrides the config)
+    #[arg(long, global = true)]
+    pub min_sample: Option<usize>,
+    /// Run the report on a random 10% of the rows, with counts scaled up and labelled approximate
+    #[arg(long, global = true)]
+    pub quick: bool,
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Print the analyses of the report (all of them unless --only is given)
+    Report(ReportArgs),
+    /// Run every analysis into one report and render it as text, JSON or Markdown
+    ReportAll(ReportAllArgs),
+    /// Stream the file once and print percentiles, distinct counts and top OEMs
+    Stats(StatsArgs),
+    /// Print the rows matching a filter expression, or write them to a file with --out
+    #[command(visible_alias = "filter")]
+    Query(QueryArgs),
+    /// Print every field of one row
+    Show(ShowArgs),
+    /// Queue, review and save edits to the file
+    Crud {
+        #[command(subcommand)]
+        command: CrudCommand,
+    },
+    /// Attach free-form tags to rows, kept in a sidecar next to the file
+    Tag {
+        #[command(subcommand)]
+        command: TagCommand,
+    },
+    /// Count phones per value of one column, split by another
+    Breakdown(BreakdownArgs),
+    /// Clean, filter and derive columns into a new file in one streaming pass
+    Etl(EtlArgs),
+    /// Print the first rows as a table
+    Head(LookArgs),
+    /// Print the last rows as a table
+    Tail(LookArgs),
+    /// Print a random selection of rows as a table
+    Sample(SampleArgs),
+    /// Draw launches per OEM and year
+    Heatmap(HeatmapArgs),
+    /// Export rows or summary statistics as a LaTeX table
+    Latex(LatexArgs),
+    /// Draw phones per OEM country on an SVG world map
+    Map(MapArgs),
+    /// Print the count, null count, mean, min and max of every column
+    Describe,
+    /// Print the most common values of a column with their counts
+    Mode(ModeArgs),
+    /// Chart how the values of a numeric column or derived value are spread
+    Histogram(HistogramArgs),
+    /// Fit a line through two numeric columns or derived values
+    Regression(RegressionArgs),
+    /// List the names stats --column, histogram and regression accept
+    Metrics,
+    /// Print the column summaries of the rows matching two filters side by side
+    CompareGroups(CompareGroupsArgs),
+    /// Write one file per year (or OEM, ...) plus a manifest.json index
+    Partition(PartitionArgs),
+    /// Record the dataset's headline metrics in a history file, or show how they drifted
+    History(HistoryArgs),
+    /// Keep the file loaded and print its row count and means as rows are appended
+    Watch(WatchArgs),
+    /// Load several datasets by name and compare, join and combine them interactively
+    Repl,
+    /// Check how the headers of another CSV map to the columns, or map them with --wizard
+    Import(ImportArgs),
+    /// Write a reproducible test CSV, e.g. to regenerate cells_test.csv
+    MakeFixture(FixtureArgs),
+    /// Browse the rows in a scrollable table
+    View(ColumnsArgs),
+    /// Browse and edit the rows in a terminal UI
+    Tui,
+}
+
+#[derive(Debug, Default, clap::Args)]
+pub struct ReportArgs {
+    /// Only print these analyses (comma-separated, see --list)
+    #[arg(long, value_delimiter = ',')]
+    pub only: Vec<String>,
+    /// List the analyses of the report and exit
+    #[arg(long)]
+    pub list: bool,
+    /// Only analyse the rows matching this filter expression
+    #[arg(long, default_value = "")]
+    pub filter: String,
+    /// Also only analyse the rows matching these presets (comma-separated, see --list-presets)
+    #[arg(long, value_delimiter = ',')]
+    pub preset: Vec<String>,
+    /// List the filter presets and exit
+    #[arg(long)]
+    pub list_presets: bool,
+    /// Print the results as one JSON object keyed by analysis name
+    #[arg(long)]
+    pub json: bool,
+    /// Print weights and lengths in these units, the report once per profile (comma-separated)
+    #[arg(long, value_enum, value_delimiter = ',')]
+    pub units: Vec<UnitProfile>,
+    /// Write the results to this file instead of printing them
+    #[arg(long)]
+    pub out: Option<String>,
+}
+
+#[derive(Debug, clap::Args)]
+pub struct ReportAllArgs {
+    /// How to render the report
+    #[arg(long, value_enum, default_value_t = ReportFormat::Text)]
+    pub format: ReportFormat,
+    /// Only analyse the rows matching this filter expression
+    #[arg(long, default_value = "")]
+    pub filter: String,
+    /// Also only analyse the rows matching these presets (comma-separated)
+    #[arg(long, value_delimiter = ',')]
+    pub preset: Vec<String>,
+    /// Print weights and lengths in these units, the report once per profile (comma-separated)
+    #[arg(long, value_enum, value_delimiter = ',')]
+    pub units: Vec<UnitProfile>,
+    /// Write the report to this file instead of printing it
+    #[arg(long)]
+    pub out: Option<String>,
+}
+
+#[derive(Debug, clap::Args)]
+pub struct StatsArgs {
+    /// Spill to temp files to stay under about this much memory, e.g. 64M
+    #[arg(long, conflicts_with = "approximate")]
+    pub max_memory: Option<String>,
+    /// Estimate the percentiles with a t-digest
+    #[arg(long)]
+    pub approximate: bool,
+    /// Numeric values to give percentiles of (comma-separated), columns or derived values such as ppi
+    #[arg(long, value_delimiter = ',', default_value = "body_weight,display_size")]
+    pub column: Vec<String>,
+}
+
+#[derive(Debug, clap::Args)]
+pub struct HistoryArgs {
+    #[command(subcommand)]
+    pub command: Option<HistoryCommand>,
+    /// JSON-lines file the runs are kept in
+    #[arg(long, global = true, default_value = history::DEFAULT_HISTORY_FILE)]
+    pub history: String,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum HistoryCommand {
+    /// Append this run's row count, means and null rates to the history
+    Record,
+    /// Print the recorded runs and how the dataset drifted from the first to the last (the default)
+    Show,
+}
+
+#[derive(Debug, clap::Args)]
+pub struct HistogramArgs {
+    /// Numeric value to bin, a column or a derived value such as ppi (see `cells metrics`)
+    pub column: String,
+    /// How many equal-width bins to split the values into
+    #[arg(long, default_value_t = 10)]
+    pub bins: usize,
+    /// Only count the rows matching this filter expression
+    #[arg(long, default_value = "")]
+    pub filter: String,
+}
+
+#[derive(Debug, clap::Args)]
+pub struct RegressionArgs {
+    /// Numeric value on the x axis, e.g. launch_announced
+    pub x: String,
+    /// Numeric value on the y axis, e.g. ppi
+    pub y: String,
+    /// Only fit the rows matching this filter expression
+    #[arg(long, default_value = "")]
+    pub filter: String,
+}
+
+#[derive(Debug, clap::Args)]
+pub struct ColumnsArgs {
+    /// Columns to show (comma-separated); all columns when not given
+    #[arg(long, value_delimiter = ',')]
+    pub columns: Vec<Column>,
+}
+
+impl ColumnsArgs {
+    fn columns(&self) -> Vec<Column> {
+        if self.columns.is_empty() { Column::ALL.to_vec() } else { self.columns.clone() }
+    }
+}
+
+#[derive(Debug, clap::Args)]
+#[command(args_conflicts_with_subcommands = true)]
+pub struct QueryArgs {
+    #[command(subcommand)]
+    pub saved: Option<SavedQueryCommand>,
+    /// Filter expression, e.g. "oem = Samsung" (every row when empty)
+    #[arg(default_value = "")]
+    pub filter: String,
+    /// Sort by a column, e.g. body_weight or body_weight:desc
+    #[arg(long)]
+    pub sort: Option<String>,
+    /// Print at most this many rows
+    #[arg(short = 'n', long)]
+    pub limit: Option<usize>,
+    /// Only select the rows that also match these presets (comma-separated, see --list-presets)
+    #[arg(long, global = true, value_delimiter = ',')]
+    pub preset: Vec<String>,
+    /// List the filter presets and exit
+    #[arg(long)]
+    pub list_presets: bool,
+    /// Write the selected rows to this .csv or .json file instead of printing them
+    #[arg(long, global = true)]
+    pub out: Option<String>,
+    /// With --out, copy the rows' original CSV records instead of the cleaned values
+    #[arg(long, global = true, requires = "out")]
+    pub raw: bool,
+    /// Give up on the query after this many seconds, e.g. 2.5
+    #[arg(long, global = true)]
+    pub timeout: Option<f64>,
+    #[command(flatten)]
+    pub columns: ColumnsArgs,
+}
+
+impl QueryArgs {
+    // The ad-hoc query given on the command line.
+    fn to_saved(&self) -> SavedQuery {
+        SavedQuery { filter: self.filter.clone(), sort: self.sort.clone(), columns: self.columns.columns.clone(), limit: self.limit }
+    }
+}
+
+#[derive(Debug, Subcommand)]
+pub enum SavedQueryCommand {
+    /// Run a query saved under [queries.<name>] in the config
+    Run { name: String },
+    /// List the saved queries
+    List,
+}
+
+#[derive(Debug, clap::Args)]
+pub struct ModeArgs {
+    /// Column to find the most common value of
+    pub column: Column,
+    /// How many runners-up to list after the top value
+    #[arg(short = 'n', long, default_value_t = 4)]
+    pub runners_up: usize,
+    /// Only count the rows matching this filter expression
+    #[arg(long, default_value = "")]
+    pub filter: String,
+}
+
+#[derive(Debug, clap::Args)]
+pub struct CompareGroupsArgs {
+    /// Filter expression of the first group, e.g. "oem=Apple" ("" for every row)
+    pub left: String,
+    /// Filter expression of the second group
+    pub right: String,
+}
+
+#[derive(Debug, clap::Args)]
+pub struct ImportArgs {
+    /// CSV file with unknown headers
+    pub csv: String,
+    /// Ask which column each header maps to and save the answers as a schema
+    #[arg(long)]
+    pub wizard: bool,
+    /// Where the wizard saves the schema (default: next to the CSV, as <name>.schema.toml)
+    #[arg(long, requires = "wizard")]
+    pub save_as: Option<String>,
+}
+
+#[derive(Debug, clap::Args)]
+pub struct FixtureArgs {
+    /// Number of data rows
+    #[arg(long, default_value_t = 50)]
+    pub rows: usize,
+    /// Start with the tricky rows the tests rely on (missing fields, odd years, ...)
+    #[arg(long)]
+    pub edge_cases: bool,
+    /// Seed of the generated rows
+    #[arg(long, default_value_t = 1)]
+    pub seed: u64,
+    /// File to write (default: print the CSV)
+    #[arg(long)]
+    pub out: Option<String>,
+}
+
+#[derive(Debug, clap::Args)]
+pub struct WatchArgs {
+    /// Seconds between checks of the file
+    #[arg(long, default_value_t = 2)]
+    pub interval: u64,
+}
+
+#[derive(Debug, clap::Args)]
+pub struct ShowArgs {
+    /// Row index, counting from 0
+    pub index: usize,
+}
+
+#[derive(Debug, clap::Args)]
+pub struct LookArgs {
+    /// Number of rows
+    #[arg(short = 'n', default_value_t = 10)]
+    pub n: usize,
+    #[command(flatten)]
+    pub columns: ColumnsArgs,
+}
+
+#[derive(Debug, clap::Args)]
+pub struct SampleArgs {
+    #[command(flatten)]
+    pub look: LookArgs,
+    /// Seed of the random selection, to repeat a sample
+    #[arg(long)]
+    pub seed: Option<u64>,
+}
+
+#[derive(Debug, clap::Args)]
+pub struct BreakdownArgs {
+    /// Column to count by
+    #[arg(long, default_value = "oem")]
+    pub by: Column,
+    /// Column to split the counts by ("status" is short for launch_status)
+    #[arg(long, default_value = "launch_status", value_parser = split_column)]
+    pub split: Column,
+}
+
+// Parses --split, which also accepts "status" for launch_status.
+fn split_column(name: &str) -> Result<Column, String> {
+    if name == "status" { Ok(Column::LaunchStatus) } else { name.parse() }
+}
+
+#[derive(Debug, clap::Args)]
+pub struct EtlArgs {
+    /// Output file; the format comes from the extension (.csv, .json or .parquet)
+    #[arg(long)]
+    pub out: String,
+    /// Filter expression of the rows to keep
+    #[arg(long, default_value = "")]
+    pub filter: String,
+    /// Derived columns to add: device_class, country, screen_to_body_ratio
+    #[arg(long, value_delimiter = ',')]
+    pub derive: Vec<String>,
+}
+
+#[derive(Debug, clap::Args)]
+pub struct PartitionArgs {
+    /// Column whose values split the rows, usually launch_announced or oem
+    #[arg(long, default_value = "launch_announced")]
+    pub by: Column,
+    /// Directory to write the partitions and manifest.json to
+    #[arg(long)]
+    pub out_dir: String,
+    /// File format of the partitions
+    #[arg(long, value_enum, default_value_t = PartitionFormat::Csv)]
+    pub format: PartitionFormat,
+    /// Filter expression of the rows to export
+    #[arg(long, default_value = "")]
+    pub filter: String,
+}
+
+#[derive(Debug, clap::Args)]
+pub struct HeatmapArgs {
+    /// Number of OEMs with the most phones to draw
+    #[arg(long, default_value_t = 20)]
+    pub top: usize,
+    /// Write an SVG image instead of drawing in the terminal
+    #[arg(long)]
+    pub svg: Option<String>,
+}
+
+#[derive(Debug, clap::Args)]
+pub struct LatexArgs {
+    /// Export the headline statistics instead of the rows
+    #[arg(long)]
+    pub summary: bool,
+    /// Filter expression of the rows to export
+    #[arg(long, default_value = "")]
+    pub filter: String,
+    /// Caption of the table
+    #[arg(long)]
+    pub caption: Option<String>,
+    /// Write the table to this file instead of printing it
+    #[arg(long)]
+    pub out: Option<String>,
+    #[command(flatten)]
+    pub columns: ColumnsArgs,
+}
+
+#[derive(Debug, clap::Args)]
+pub struct MapArgs {
+    /// SVG file to write
+    #[arg(long)]
+    pub svg: String,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum CrudCommand {
+    /// Queue a new row
+    Insert {
+        /// Row index to insert at; the end when not given
+        #[arg(long)]
+        at: Option<usize>,
+        /// A field of the row as column=value (repeatable)
+        #[arg(long = "set", value_parser = field_value)]
+        values: Vec<(Column, String)>,
+    },
+    /// Queue changes to a row
+    Modify {
+        index: usize,
+        /// A changed field as column=value (repeatable)
+        #[arg(long = "set", value_parser = field_value)]
+        values: Vec<(Column, String)>,
+    },
+    /// Queue the removal of a row
+    Delete { index: usize },
+    /// Show the pending edits
+    Status,
+    /// Write the pending edits to the file
+    Save,
+    /// Drop the pending edits
+    Discard,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum TagCommand {
+    /// Tag a row, e.g. `cells tag add 12 verified`
+    Add { index: usize, tags: Vec<String> },
+    /// Take tags off a row
+    Remove { index: usize, tags: Vec<String> },
+    /// List the tagged phones, or only those with one tag
+    List { tag: Option<String> },
+}
+
+// Parses a `--set column=value` flag of an insert or modify.
+fn field_value(pair: &str) -> Result<(Column, String), String> {
+    let (name, value) = pair.split_once('=').ok_or_else(|| format!("expected column=value, got '{}'", pair))?;
+    Ok((name.parse()?, value.to_string()))
+}
+
+/*
+    Load a dataset with Dataset::load, for the commands that don't need its normalizer.
+
+    Runtime: O(n)
+ */
+pub fn load_dataset(file: &str, config: &Config) -> Result<Dataset, Box<dyn Error>> {
+    Ok(Dataset::load(file, config)?.0)
+}
+
+/*
+    The one line printed instead of the results when there are no rows to analyse, because
+    the file has none or because none match the filter.
+
+    Runtime: O(1)
+ */
+pub fn nothing_to_analyse(file: &str, report: &ParseReport, filter: &str) -> String {
+    match report.empty_input {
+        Some(empty) => format!("Nothing to analyse: {} ({})", empty, file),
+        None => format!("Nothing to analyse: no rows of {} match the filter '{}'", file, filter),
+    }
+}
+
+/*
+    cells head|tail [-n 10] [--columns oem,model]
+    cells sample [-n 10] [--columns oem,model] [--seed 42]
+
+    Print the first, last, or with a seed a repeatable random selection of rows as a table.
+
+    Runtime: O(n)
+ */
+pub fn quick_look(command: &str, file: &str, args: &LookArgs, seed: Option<u64>, config: &Config) -> Result<(), Box<dyn Error>> {
+    let dataset = load_dataset(file, config)?;
+    let n = args.n;
+
+    let rows: Vec<&Cell> = match command {
+        "head" => dataset.cells().iter().take(n).collect(),
+        "tail" => dataset.cells().iter().skip(dataset.len().saturating_sub(n)).collect(),
+        _ => {
+            let mut rng = match seed {
+                Some(seed) => Rng::new(seed),
+                None => Rng::from_time(),
+            };
+            dataset.sample(n, &mut rng)
+        }
+    };
+
+    print!("{}", table::render_table(&rows, &args.columns.columns(), &config.number_format));
+
+    Ok(())
+}
+
+/*
+    cells query ["expr"] [-n 20] [--columns oem,model]
+
+    Print the rows matching a filter expression as a table, followed by how many matched.
+    `cells query run <name>` runs a query saved in the config instead and `cells query list`
+    lists them. Rows must also match each --preset, and --list-presets lists those.
+    With --timeout the filter and sort give up after that many seconds, also when
+    exporting to --out.
+
+    Runtime: O(n log n)
+ */
+pub fn query(file: &str, args: &QueryArgs, config: &Config) -> Result<(), Box<dyn Error>> {
+    if args.list_presets {
+        list_presets();
+        return Ok(());
+    }
+    let mut saved = match &args.saved {
+        None => args.to_saved(),
+        Some(SavedQueryCommand::Run { name }) => match config.queries.get(name) {
+            Some(saved) => saved.clone(),
+            None => return Err(format!("no saved query named '{}' in the config", name).into()),
+        },
+        Some(SavedQueryCommand::List) => {
+            for (name, saved) in &config.queries {
+                println!("{}: {}", name, if saved.filter.is_empty() { "(every row)" } else { &saved.filter });
+            }
+            return Ok(());
+        }
+    };
+    saved.filter = filter::with_presets(&saved.filter, &args.preset)?;
+
+    let dataset = load_dataset(file, config)?;
+    let cancel = match args.timeout {
+        Some(seconds) => Cancel::with_timeout(Duration::try_from_secs_f64(seconds).map_err(|_| format!("invalid --timeout '{}'", seconds))?),
+        None => Cancel::new(),
+    };
+    if let Some(out) = &args.out {
+        return export_query(file, &dataset, &saved, out, args.raw, config, &cancel);
+    }
+
+    let matching = saved.select_until(&dataset.view(), &cancel)?;
+    let shown = &matching[..saved.limit.unwrap_or(matching.len()).min(matching.len())];
+
+    print!("{}", table::render_table(shown, &saved.columns(), &config.number_format));
+    println!("{} of {} rows match", matching.len(), dataset.len());
+
+    Ok(())
+}
+
+// Print each filter preset with what it selects and its filter expression.
+pub fn list_presets() {
+    for preset in &PRESETS {
+        println!("{}: {}\n    {}", preset.name, preset.description, preset.expression);
+    }
+}
+
+/*
+    Write the rows a query selects (sorted and limited) to a .csv or .json file. Cleaned rows
+    have every column whatever the projection, so the file can be loaded again, with JSON
+    numbers rounded by the config's number format; raw rows are the source file's records,
+    copied as they are with its header. Selecting the rows stops once `cancel` says so.
+
+    Runtime: O(n log n)
+ */
+fn export_query(file: &str, dataset: &Dataset, saved: &SavedQuery, out: &str, raw: bool, config: &Config, cancel: &Cancel) -> Result<(), Box<dyn Error>> {
+    let mut indices = saved.select_indices_until(dataset.cells(), cancel)?;
+    let matches = indices.len();
+    indices.truncate(saved.limit.unwrap_or(matches));
+
+    if raw {
+        if !out.ends_with(".csv") {
+            return Err("--raw rows can only be written to a .csv file".into());
+        }
+        let source = RawTable::read(file)?;
+        let rows = indices.iter().map(|&index| source.rows[index].clone()).collect();
+        RawTable { headers: source.headers, rows }.write(out)?;
+    } else if out.ends_with(".csv") {
+        Cell::write_csv(indices.iter().map(|&index| &dataset.cells()[index]), out)?;
+    } else if out.ends_with(".json") {
+        let cells: Vec<Cell> = indices.iter().map(|&index| config.number_format.round_cell(&dataset.cells()[index])).collect();
+        Cell::write_json(&cells, out)?;
+    } else {
+        return Err(format!("cannot tell the output format of '{}'; use .csv or .json", out).into());
+    }
+
+    println!("{} of {} rows match, wrote {} to {}", matches, dataset.len(), indices.len(), out);
+
+    Ok(())
+}
+
+/*
+    cells show <index>
+
+    Print every column of one row, one per line, then the file and line it came from.
+    Missing values are shown as '-'.
+
+    Runtime: O(n)
+ */
+pub fn show(file: &str, args: &ShowArgs, config: &Config) -> Result<(), Box<dyn Error>> {
+    let dataset = load_dataset(file, config)?;
+    let cell = dataset.cells().get(args.index).ok_or_else(|| format!("row {} is out of range ({} rows)", args.index, dataset.len()))?;
+
+    for column in Column::ALL {
+        println!("{}: {}", column, cell.text(column).unwrap_or_else(|| "-".to_string()));
+    }
+    if let Some(source) = &cell.source {
+        println!("source: {}", source);
+    }
+
+    Ok(())
+}
+
+/*
+    cells mode <column> [-n 4] [--filter "expr"]
+
+    Print the most common value of a column and the runners-up, each with its count and
+    share of the rows that have a value.
+
+    Runtime: O(n + v log v)
+ */
+pub fn mode(file: &str, args: &ModeArgs, config: &Config) -> Result<(), Box<dyn Error>> {
+    let dataset = load_dataset(file, config)?;
+    let view = dataset.view().matching(&Filter::parse(&args.filter)?);
+    if view.is_empty() {
+        println!("{}", nothing_to_analyse(file, dataset.parse_report(), &args.filter));
+        return Ok(());
+    }
+    let with_value = view.iter().filter(|cell| cell.text(args.column).is_some()).count();
+
+    let ranked = analysis::mode(&view, args.column, args.runners_up + 1, &config.float_grouping);
+    let Some((top, count)) = ranked.first() else {
+        println!("No {} values", args.column);
+        return Ok(());
+    };
+    let share = |count: usize| config.number_format.percent(count as f64 / with_value as f64);
+
+    println!("Most common {}: {} ({} of {} rows, {})", args.column, top, count, with_value, share(*count));
+    for (rank, (value, count)) in ranked.iter().enumerate().skip(1) {
+        println!("{:>3}. {} ({}, {})", rank + 1, value, count, share(*count));
+    }
+
+    Ok(())
+}
+
+/*
+    cells histogram <column> [--bins 10] [--filter "expr"]
+
+    Chart how many rows fall in each of --bins equal-width ranges of a numeric value, a
+    column or a derived value such as ppi. Rows without the value are left out.
+
+    Runtime: O(n)
+ */
+pub fn histogram(file: &str, args: &HistogramArgs, config: &Config) -> Result<(), Box<dyn Error>> {
+    let metrics = Metrics::default();
+    metric(&metrics, &args.column)?;
+    let dataset = load_dataset(file, config)?;
+    let view = dataset.view().matching(&Filter::parse(&args.filter)?);
+    let values = metrics.values(&args.column, &view)?;
+    if values.is_empty() {
+        println!("No {} values", args.column.trim());
+        return Ok(());
+    }
+
+    let number = |value: f32| config.number_format.format(value as f64);
+    let bars: Vec<(String, usize)> = analysis::histogram(&values, args.bins).into_iter().map(|(low, high, count)| (format!("{} - {}", number(low), number(high)), count)).collect();
+    println!("{} ({} values):", args.column.trim(), values.len());
+    print!("{}", chart::bar_chart(&bars, None, chart::BAR_WIDTH));
+
+    Ok(())
+}
+
+/*
+    cells regression <x> <y> [--filter "expr"]
+
+    Fit a least-squares line of y against x over the rows that have both values, e.g. ppi
+    against launch_announced, and print its slope, intercept and r².
+
+    Runtime: O(n)
+ */
+pub fn regression(file: &str, args: &RegressionArgs, config: &Config) -> Result<(), Box<dyn Error>> {
+    let metrics = Metrics::default();
+    let (x, y) = (metric(&metrics, &args.x)?, metric(&metrics, &args.y)?);
+    let dataset = load_dataset(file, config)?;
+    let view = dataset.view().matching(&Filter::parse(&args.filter)?);
+    let points: Vec<(f32, f32)> = view.iter().filter_map(|cell| Some((x(cell)?, y(cell)?))).collect();
+
+    let Some(fit) = analysis::linear_regression(&points) else {
+        println!("Too few rows with both {} and {} to fit a line ({} rows)", args.x.trim(), args.y.trim(), points.len());
+        return Ok(());
+    };
+    let number = |value: f64| config.number_format.format(value);
+    let sign = if fit.intercept < 0.0 { '-' } else { '+' };
+    println!("{} = {} * {} {} {}", args.y.trim(), number(fit.slope), args.x.trim(), sign, number(fit.intercept.abs()));
+    println!("r²: {} ({} rows){}", number(fit.r_squared), fit.n, analysis::low_confidence(fit.n, config.min_sample_size()).map_or(String::new(), |note| format!(" [{}]", note)));
+
+    Ok(())
+}
+
+// The accessor registered under a name, or an error pointing at `cells metrics`.
+fn metric<'a>(metrics: &'a Metrics, name: &str) -> Result<&'a NumericAccessor, String> {
+    metrics.get(name).map_err(|error| format!("{} (see `cells metrics`)", error))
+}
+
+// Print the names stats --column, histogram and regression accept.
+pub fn metrics() {
+    Metrics::default().names().for_each(|name| println!("{}", name));
+}
+
+/*
+    cells history [record|show] [--history cells_history.jsonl]
+
+    `record` appends the headline metrics of the --file (row count, mean weight and display
+    size, null rates) to the history file, so running it on each new version of cells.csv
+    builds a record of the dataset. `show`, the default, prints the runs with a sparkline
+    of the row counts and what changed from the first run to the last: rows, means, and
+    the columns whose null rate moved by more than a point.
+
+    Runtime: O(n log n) to record, O(r) to show
+ */
+pub fn history(file: &str, args: &HistoryArgs, config: &Config) -> Result<(), Box<dyn Error>> {
+    if let Some(HistoryCommand::Record) = args.command {
+        let run = RunMetrics::of(&load_dataset(file, config)?, file);
+        History::append(&args.history, &run)?;
+        println!("Recorded {} rows of {} in {}", run.rows, file, args.history);
+        return Ok(());
+    }
+
+    let history = History::load(&args.history)?;
+    let (Some(first), Some(last)) = (history.runs.first(), history.runs.last()) else {
+        println!("No runs in {} yet; add one with `cells history record`", args.history);
+        return Ok(());
+    };
+    print!("{}", history);
+    if history.runs.len() < 2 {
+        return Ok(());
+    }
+
+    let rows: Vec<usize> = history.runs.iter().map(|run| run.rows).collect();
+    let change = |before: Option<f64>, after: Option<f64>| match (before, after) {
+        (Some(before), Some(after)) => format!("{} -> {}", config.number_format.format(before), config.number_format.format(after)),
+        _ => "-".to_string(),
+    };
+    println!("Rows: {}  {} -> {} ({:+})", chart::sparkline(&rows), first.rows, last.rows, last.rows as i64 - first.rows as i64);
+    println!("Mean weight: {}", change(first.mean_weight, last.mean_weight));
+    println!("Mean display size: {}", change(first.mean_display_size, last.mean_display_size));
+    for (column, before, after) in history.null_drift(0.01) {
+        println!("Null rate of {}: {} -> {}", column, config.number_format.percent(before), config.number_format.percent(after));
+    }
+
+    Ok(())
+}
+
+/*
+    cells partition --out-dir parts [--by launch_announced|oem] [--format csv|json] [--filter "expr"]
+
+    Export the cleaned rows as one file per value of a column, with a manifest.json index.
+
+    Runtime: O(n log n)
+ */
+pub fn partition(file: &str, args: &PartitionArgs, config: &Config) -> Result<(), Box<dyn Error>> {
+    let dataset = load_dataset(file, config)?;
+    let view = dataset.view().matching(&Filter::parse(&args.filter)?);
+
+    let manifest = partition::export_partitioned(&view, args.by, &args.out_dir, args.format, &config.number_format)?;
+    println!("Wrote {} rows in {} partitions by {} to {}", manifest.rows, manifest.partitions.len(), args.by, args.out_dir);
+
+    Ok(())
+}
+
+/*
+    cells watch [--interval 2]
+
+    Load the file once, then check it every few seconds, parsing only the rows appended since
+    the last check (or reloading it if it was rewritten), until interrupted.
+
+    Runtime: O(m) per check for m appended bytes
+ */
+pub fn watch(file: &str, args: &WatchArgs, config: &Config) -> Result<(), Box<dyn Error>> {
+    let mut live = LiveDataset::open(file, config)?;
+    let print = |live: &LiveDataset, change: &str| {
+        let weight = live.dataset().column_stats(Column::BodyWeight).mean();
+        let weight = weight.map_or("-".to_string(), |mean| config.number_format.format(mean));
+        println!("{} rows{}, mean body weight {}", live.dataset().len(), change, weight);
+    };
+    print(&live, "");
+
+    loop {
+        std::thread::sleep(std::time::Duration::from_secs(args.interval));
+        match live.refresh()? {
+            Refresh::Unchanged => {}
+            Refresh::Appended(rows) => print(&live, &format!(" (+{} appended)", rows)),
+            Refresh::Reloaded => print(&live, " (file rewritten, reloaded)"),
+        }
+    }
+}
+
+/*
+    cells repl
+
+    Read commands from stdin over a workspace of named datasets (see repl::Repl), starting
+    with --file loaded under its name if it exists, e.g. `load old.csv as old` then
+    `diff old cells`. Ctrl-C stops the running command rather than the session; quit or
+    the end of the input leave it.
+
+    Runtime: that of the commands
+ */
+pub fn repl(file: &str, after_year: u32, config: &Config) -> Result<(), Box<dyn Error>> {
+    let cancel = Cancel::new();
+    #[cfg(unix)]
+    signal_hook::flag::register(signal_hook::consts::SIGINT, cancel.shared_flag())?;
+    let mut repl = Repl::new(config, after_year).with_cancel(cancel);
+    if Path::new(file).exists() {
+        print!("{}", repl.execute(&format!("load {}", file))?.unwrap_or_default());
+    }
+    println!("Type `help` for the commands.");
+
+    Ok(repl.run(&mut std::io::stdin().lock(), &mut std::io::stdout())?)
+}
+
+/*
+    cells import other.csv [--wizard [--save-as other.schema.toml]]
+
+    Show how each header of a CSV maps to the columns with the current schema, and whether
+    every column is found. With --wizard, ask for each header's column instead, showing a few
+    of its values, then save the answers as a schema file and point the config at it so later
+    runs read the file that way.
+
+    Runtime: O(c^2)
+ */
+pub fn import(args: &ImportArgs, config: &Config, config_path: &str) -> Result<(), Box<dyn Error>> {
+    let samples = Samples::read(&args.csv, wizard::SAMPLE_ROWS)?;
+
+    if !args.wizard {
+        let schema = Schema::from_config(config)?;
+        for (header, values) in samples.headers.iter().zip(&samples.values) {
+            let mapping = schema.mapping(header).map_or("(kept in extras)".to_string(), |mapping| mapping.to_string());
+            println!("{} -> {} ({})", header, mapping, values.join(" | "));
+        }
+        let headers = csv::StringRecord::from(samples.headers.clone());
+        if let Err(error) = HeaderIndex::from_headers_with(&headers, &schema) {
+            return Err(format!("{}; run `cells import --wizard {}` to map the headers", error, args.csv).into());
+        }
+        println!("Every column is found; the file can be read with --file {}", args.csv);
+        return Ok(());
+    }
+
+    let schema = wizard::ask_mapping(&samples, &mut std::io::stdin().lock(), &mut std::io::stdout())?;
+    let path = match &args.save_as {
+        Some(path) => path.clone(),
+        None => Path::new(&args.csv).with_extension("schema.toml").to_string_lossy().into_owned(),
+    };
+    schema.save(&path)?;
+    wizard::save_schema_path(config_path, &path)?;
+    println!("Saved the mapping to {} and set it as the schema in {}", path, config_path);
+
+    Ok(())
+}
+
+/*
+    cells make-fixture [--rows 50] [--edge-cases] [--seed 1] [--out cells_test.csv]
+
+    Write a test CSV that is the same on every run for the same arguments. cells_test.csv
+    is `--rows 4 --edge-cases`.
+
+    Runtime: O(r)
+ */
+pub fn make_fixture(args: &FixtureArgs) -> Result<(), Box<dyn Error>> {
+    let records = fixture::generate(args.rows, args.edge_cases, args.seed);
+
+    match &args.out {
+        Some(out) => {
+            fixture::write(&records, std::fs::File::create(out)?)?;
+            println!("Wrote {} rows to {}", records.len(), out);
+        }
+        None => fixture::write(&records, std::io::stdout().lock())?,
+    }
+
+    Ok(())
+}
+
+/*
+    cells describe
+
+    Print the summary of every column kept by the dataset: how many rows have a value, how
+    many don't, and the mean, min and max of the numeric columns ('-' for text columns).
+
+    Runtime: O(n log n) to load, then O(log n) per column
+ */
+pub fn describe(file: &str, config: &Config) -> Result<(), Box<dyn Error>> {
+    let dataset = load_dataset(file, config)?;
+    if dataset.is_empty() {
+        println!("{}", nothing_to_analyse(file, dataset.parse_report(), ""));
+        return Ok(());
+    }
+    let number = |value: Option<f64>| value.map_or("-".to_string(), |value| config.number_format.format(value));
+
+    println!("{:<20} {:>6} {:>6} {:>10} {:>10} {:>10}", "column", "count", "nulls", "mean", "min", "max");
+    for column in Column::ALL {
+        let summary = dataset.column_stats(column);
+        println!(
+            "{:<20} {:>6} {:>6} {:>10} {:>10} {:>10}",
+            column.name(),
+            summary.count,
+            summary.nulls,
+            number(summary.mean()),
+            number(summary.min.map(f64::from)),
+            number(summary.max.map(f64::from))
+        );
+    }
+    print_parse_warnings(dataset.parse_report());
+
+    Ok(())
+}
+
+/*
+    cells compare-groups "oem=Apple" "oem=Samsung"
+
+    Print the summary describe gives, for the rows matching each filter side by side, with
+    the second group minus the first: the rows, each column's count, and the mean, min and
+    max of the numeric columns.
+
+    Runtime: O(n log n)
+ */
+pub fn compare_groups(file: &str, args: &CompareGroupsArgs, config: &Config) -> Result<(), Box<dyn Error>> {
+    let dataset = load_dataset(file, config)?;
+    if dataset.is_empty() {
+        println!("{}", nothing_to_analyse(file, dataset.parse_report(), ""));
+        return Ok(());
+    }
+    let view = dataset.view();
+    let (left, right) = (view.matching(&Filter::parse(&args.left)?), view.matching(&Filter::parse(&args.right)?));
+
+    let format = &config.number_format;
+    let number = |value: Option<f64>| value.map_or("-".to_string(), |value| format.format(value));
+    let delta = |value: Option<f64>| match value {
+        Some(value) if value > 0.0 => format!("+{}", format.format(value)),
+        _ => number(value),
+    };
+    let label = |filter: &str| if filter.trim().is_empty() { "(every row)".to_string() } else { filter.trim().to_string() };
+    let (left_label, right_label) = (label(&args.left), label(&args.right));
+    let width = left_label.chars().count().max(right_label.chars().count()).max(10);
+
+    let row = |name: &str, left: Option<f64>, right: Option<f64>| {
+        let change = left.zip(right).map(|(left, right)| right - left);
+        println!("{:<24} {:>width$} {:>width$} {:>10}", name, number(left), number(right), delta(change), width = width);
+    };
+    println!("{:<24} {:>width$} {:>width$} {:>10}", "", left_label, right_label, "delta", width = width);
+    row("rows", Some(left.len() as f64), Some(right.len() as f64));
+
+    for comparison in left.stats().compare(&right.stats()) {
+        let name = comparison.left.column.name();
+        row(&format!("{} count", name), Some(comparison.left.count as f64), Some(comparison.right.count as f64));
+        if comparison.left.sum.is_some() || comparison.right.sum.is_some() {
+            row(&format!("{} mean", name), comparison.left.mean(), comparison.right.mean());
+            row(&format!("{} min", name), comparison.left.min.map(f64::from), comparison.right.min.map(f64::from));
+            row(&format!("{} max", name), comparison.left.max.map(f64::from), comparison.right.max.map(f64::from));
+        }
+    }
+
+    Ok(())
+}
+
+// One line about the values a lenient load left empty, by column, if there were any, and
+// one per value outside the configured bounds.
+pub fn print_parse_warnings(report: &ParseReport) {
+    report.warnings().iter().for_each(|warning| println!("{}", warning));
+}
+
+/*
+    cells view [--columns oem,model,...]
+
+    Browse the cleaned records in a scrollable table. OEM and model stay in place while the
+    other columns scroll sideways.
+
+    Runtime: O(n)
+ */
+pub fn view(file: &str, args: &ColumnsArgs, config: &Config) -> Result<(), Box<dyn Error>> {
+    let dataset = load_dataset(file, config)?;
+    let cells: Vec<&Cell> = dataset.cells().iter().collect();
+
+    Ok(tui::show(&mut TableViewer::from_cells(&cells, &args.columns(), &config.number_format))?)
+}
+
+/*
+    cells latex [--summary] [--filter "expr"] [--columns oem,model] [--caption text] [--out table.tex]
+
+    Export the selected rows, or with --summary their headline statistics, as a booktabs
+    LaTeX table. Written to --out if given, otherwise printed.
+
+    Runtime: O(n log n)
+ */
+pub fn latex(file: &str, args: &LatexArgs, after_year: u32, config: &Config) -> Result<(), Box<dyn Error>> {
+    let mut dataset = load_dataset(file, config)?;
+    let filter = Filter::parse(&args.filter)?;
+    dataset.retain(|cell| filter.matches(cell));
+
+    let caption = args.caption.as_deref();
+    let table = if args.summary {
+        latex::summary_table(&dataset.view(), &config.number_format, after_year, caption)
+    } else {
+        latex::cells_table(dataset.cells(), &args.columns.columns(), caption)
+    };
+
+    let mut out = output::open(args.out.as_deref())?;
+    out.write_text(&table)?;
+    out.flush()?;
+    if let Some(out) = &args.out {
+        println!("Wrote {} rows to {}", dataset.len(), out);
+    }
+
+    Ok(())
+}
+
+/*
+    cells heatmap [--top 20] [--svg heatmap.svg]
+
+    Draw launches per OEM and year for the OEMs with the most phones, as a coloured grid in
+    the terminal or, with --svg, as an SVG image.
+
+    Runtime: O(n log n)
+ */
+pub fn heatmap(file: &str, args: &HeatmapArgs, config: &Config) -> Result<(), Box<dyn Error>> {
+    let dataset = load_dataset(file, config)?;
+
+    let (years, mut rows) = Cell::oem_year_pivot(&dataset.view());
+    rows.truncate(args.top);
+
+    match &args.svg {
+        Some(out) => {
+            std::fs::write(out, heatmap::render_svg(&years, &rows))?;
+            println!("Wrote a {} x {} heatmap to {}", rows.len(), years.len(), out);
+        }
+        None => print!("{}", heatmap::render_terminal(&years, &rows)),
+    }
+
+    Ok(())
+}
+
+/*
+    cells map --svg map.svg
+
+    Draw the number of phones per OEM country on an SVG world map.
+
+    Runtime: O(n log n)
+ */
+pub fn map(file: &str, args: &MapArgs, config: &Config) -> Result<(), Box<dyn Error>> {
+    let out = &args.svg;
+    let dataset = load_dataset(file, config)?;
+
+    let counts = country::devices_per_country(&dataset.view(), &CountryMap::from_config(config));
+    std::fs::write(out, worldmap::render_svg(&counts))?;
+    println!("Wrote phone counts for {} countries to {}", counts.len(), out);
+
+    Ok(())
+}
+
+// Where the stats command collects the values it takes percentiles of.
+enum Quantiles {
+    Exact(ExternalSorter),
+    Approximate(TDigest),
+}
+
+impl Quantiles {
+    fn push(&mut self, value: f32) -> Result<(), CellError> {
+        match self {
+            Quantiles::Exact(sorter) => sorter.push(value),
+            Quantiles::Approximate(digest) => {
+                digest.push(value);
+                Ok(())
+            }
+        }
+    }
+
+    fn len(&self) -> u64 {
+        match self {
+            Quantiles::Exact(sorter) => sorter.len(),
+            Quantiles::Approximate(digest) => digest.len(),
+        }
+    }
+
+    fn quantiles(&mut self, qs: &[f32]) -> Result<Vec<Option<f32>>, CellError> {
+        match self {
+            Quantiles::Exact(sorter) => sorter.quantiles(qs),
+            Quantiles::Approximate(digest) => Ok(qs.iter().map(|&q| digest.quantile(q)).collect()),
+        }
+    }
+
+    // How the quantiles were computed.
+    fn note(&mut self) -> String {
+        match self {
+            Quantiles::Exact(sorter) => format!("{} runs spilled", sorter.spilled_runs()),
+            Quantiles::Approximate(digest) => format!("t-digest, {} centroids", digest.centroids()),
+        }
+    }
+}
+
+/*
+    cells stats [--max-memory 64M | --approximate] [--column body_weight,ppi]
+
+    Stream the file once and print the percentiles of each --column (body weight and
+    display size unless given; any name `cells metrics` lists), the OEMs with the most
+    phones and those with the largest displays on average, and estimates of the distinct
+    OEMs, models and display types (HyperLogLog, a fixed 16 KiB each). With --max-memory
+    the values and counts are spilled to temp files (external sort, spill-to-disk group-by)
+    so no more than roughly that much is held in RAM; without it everything stays in
+    memory. --approximate estimates the percentiles with a t-digest instead, which never
+    buffers all the values.
+
+    Runtime: O(n log n)
+ */
+pub fn stats(file: &str, args: &StatsArgs, config: &Config) -> Result<(), Box<dyn Error>> {
+    let max_memory = match &args.max_memory {
+        Some(size) => external::parse_bytes(size)?,
+        None => usize::MAX,
+    };
+
+    // The budget is shared by a sorter per column and the group-by.
+    let share = max_memory / (args.column.len() + 1);
+    let quantiles = || {
+        if args.approximate {
+            Quantiles::Approximate(TDigest::new(sketch::DEFAULT_COMPRESSION))
+        } else {
+            Quantiles::Exact(ExternalSorter::new(share))
+        }
+    };
+    let metrics = Metrics::default();
+    let mut values = args.column.iter().map(|name| Ok((name.trim(), metric(&metrics, name)?, quantiles()))).collect::<Result<Vec<_>, String>>()?;
+    let mut oems = SpillGroupBy::new(share);
+    let mut display_sizes = analysis::display_size_by_oem();
+    let (mut distinct_oems, mut distinct_models, mut distinct_types) = (HyperLogLog::new(), HyperLogLog::new(), HyperLogLog::new());
+    let mut reader = CellReader::open_with(file, &Schema::from_config(config)?, Normalizer::from_config(config))?;
+    let mut rows = 0;
+
+    for cell in reader.by_ref() {
+        let cell = cell?;
+        rows += 1;
+        display_sizes.update(&cell);
+
+        for (_, accessor, quantiles) in values.iter_mut() {
+            if let Some(value) = accessor(&cell) {
+                quantiles.push(value)?;
+            }
+        }
+        if let Some(oem) = &cell.oem {
+            oems.add(oem)?;
+            distinct_oems.add(oem.to_lowercase());
+        }
+        if let (Some(oem), Some(model)) = (&cell.oem, &cell.model) {
+            distinct_models.add((oem.to_lowercase(), model.to_lowercase()));
+        }
+        if let Some(display_type) = &cell.display_type {
+            distinct_types.add(display_type);
+        }
+    }
+
+    if rows == 0 {
+        println!("{}", nothing_to_analyse(file, reader.report(), ""));
+        return Ok(());
+    }
+    println!("Rows: {}", rows);
+    println!(
+        "Distinct (estimated): {} OEMs, {} models, {} display types",
+        distinct_oems.count(),
+        distinct_models.count(),
+        distinct_types.count()
+    );
+    for (name, _, values) in values.iter_mut() {
+        let note = values.note();
+        match values.quantiles(&[0.5, 0.9, 0.99])?.as_slice() {
+            [Some(median), Some(p90), Some(p99)] => {
+                let number = |value: f32| config.number_format.format(value as f64);
+                println!("{}: median {}, p90 {}, p99 {} ({} values, {})", name, number(*median), number(*p90), number(*p99), values.len(), note)
+            }
+            _ => println!("{}: no values", name),
+        }
+    }
+
+    let spilled = oems.spilled_runs();
+    let mut top: Vec<(String, usize)> = Vec::new();
+    oems.finish(|oem, count| {
+        top.push((oem.to_string(), count));
+        top.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        top.truncate(10);
+    })?;
+    println!("Top OEMs ({} runs spilled):", spilled);
+    for (oem, count) in top {
+        println!("{}: {}", oem, count);
+    }
+
+    println!("Largest average display size per OEM:");
+    for (oem, mean, count) in analysis::rank_means(&display_sizes).into_iter().take(10) {
+        let note = analysis::low_confidence(count, config.min_sample_size()).map_or(String::new(), |note| format!(" [{}]", note));
+        println!("{}: {} inches ({} phones){}", oem, config.number_format.format(mean as f64), count, note);
+    }
+
+    Ok(())
+}
+
+/*
+    cells breakdown [--by oem] [--split launch_status]
+
+    Count the phones per value of one column, split by the values of another, in one table.
+    The launch status is grouped into Available, Discontinued, Cancelled and Coming soon.
+
+    Runtime: O(n log n)
+ */
+pub fn breakdown(file: &str, args: &BreakdownArgs, config: &Config) -> Result<(), Box<dyn Error>> {
+    let dataset = load_dataset(file, config)?;
+
+    print!("{}", Breakdown::of(&dataset.view(), args.by, args.split).render());
+
+    Ok(())
+}
+
+/*
+    cells etl [--filter "expr"] [--derive name,name] --out cleaned.csv
+
+    Reads, cleans, filters, derives and writes in one streaming pass, so the whole dataset is
+    never held in memory. The output format comes from the --out extension (.csv, .json or
+    .parquet); Parquet rows are written a row group at a time. Derived numbers are written
+    with the configured number format.
+
+    Runtime: O(n)
+ */
+pub fn etl(file: &str, args: &EtlArgs, config: &Config) -> Result<(), Box<dyn Error>> {
+    let out = args.out.as_str();
+    let filter = Filter::parse(&args.filter)?;
+
+    let sink = if out.ends_with(".csv") {
+        Sink::Csv(out.to_string())
+    } else if out.ends_with(".json") {
+        Sink::Json(out.to_string())
+    } else if out.ends_with(".parquet") {
+        Sink::Parquet(out.to_string())
+    } else {
+        return Err(format!("cannot tell the output format of '{}'; use .csv, .json or .parquet", out).into());
+    };
+
+    let mut pipeline = Pipeline::new()
+        .source(file)
+        .schema(Schema::from_config(config)?)
+        .normalizer(Normalizer::from_config(config))
+        .number_format(config.number_format)
+        .filter(move |cell| filter.matches(cell));
+
+    for name in args.derive.iter().map(|name| name.trim()) {
+        pipeline = match name {
+            "" => pipeline,
+            "device_class" => pipeline.derive(Derive::device_class()),
+            "country" => pipeline.derive(Derive::country(CountryMap::from_config(config))),
+            "screen_to_body_ratio" => pipeline.derive(Derive::screen_to_body_ratio()),
+            _ => return Err(format!("unknown derived column '{}' (expected device_class, country or screen_to_body_ratio)", name).into()),
+        };
+    }
+
+    let summary = pipeline.sink(sink).run()?;
+    println!("Read {} rows, wrote {} rows to {}", summary.rows_read, summary.rows_kept, out);
+
+    Ok(())
+}
+
+/*
+    cells crud insert [--at 0] --set column=value ...
+    cells crud modify <index> --set column=value ...
+    cells crud delete <index>
+    cells crud status | save | discard
+
+    Edits are queued in a journal next to the --file CSV and only written to it by `save`.
+    `status` shows how many records each kind of edit touches and the changed fields.
+
+    Runtime: O(n) per command
+ */
+pub fn edit(file: &str, command: &CrudCommand, config: &Config) -> Result<(), Box<dyn Error>> {
+    let mut pending = PendingEdits::load(file)?;
+
+    match command {
+        CrudCommand::Insert { at, values } => {
+            let index = match at {
+                Some(at) => *at,
+                None => pending.preview()?.rows.len(),
+            };
+            pending.push(Edit::Insert { index, values: values.clone() })?;
+            println!("Queued insert at row {}", index);
+        }
+        CrudCommand::Modify { index, values } => {
+            pending.push(Edit::Modify { index: *index, values: values.clone() })?;
+            println!("Queued modify of row {}", index);
+        }
+        CrudCommand::Delete { index } => {
+            pending.push(Edit::Delete { index: *index })?;
+            println!("Queued delete of row {}", index);
+        }
+        CrudCommand::Status => {
+            let (inserted, modified, deleted) = pending.summary();
+            if inserted + modified + deleted == 0 {
+                println!("No pending changes to {}", file);
+            } else {
+                println!("Pending changes to {}: {} inserted, {} modified, {} deleted", file, inserted, modified, deleted);
+                for diff in pending.diffs()? {
+                    print!("{}", diff);
+                }
+            }
+        }
+        CrudCommand::Save => {
+            let count = pending.edits().len();
+            pending.save(&Normalizer::from_config(config))?;
+            println!("Saved {} changes to {}", count, file);
+        }
+        CrudCommand::Discard => {
+            pending.discard()?;
+            println!("Discarded pending changes to {}", file);
+        }
+    }
+
+    Ok(())
+}
+
+/*
+    cells tag add <row> <tag>... | remove <row> <tag>... | list [tag]
+
+    Tags are attached to the oem and model of a data row (0-based, as for crud) and kept in
+    a sidecar next to the --file CSV, so they are kept when the file is saved or its rows
+    move. Queries filter on them with `tag = verified`.
+
+    Runtime: O(n) to read the file's rows, O(p) to list the tagged phones
+ */
+pub fn tag(file: &str, command: &TagCommand, config: &Config) -> Result<(), Box<dyn Error>> {
+    let normalizer = Normalizer::from_config(config);
+    let mut tags = Tags::load(file, &normalizer)?;
+    let phone = |index: usize| -> Result<(String, String), Box<dyn Error>> {
+        let table = RawTable::read(file)?;
+        let rows = table.rows.len();
+        table.phone(index).ok_or_else(|| format!("row {} does not exist: the file has {} rows", index, rows).into())
+    };
+
+    match command {
+        TagCommand::Add { index, tags: added } => {
+            let (oem, model) = phone(*index)?;
+            let added = added.iter().filter(|tag| tags.add(&normalizer, &oem, &model, tag)).count();
+            tags.save(file)?;
+            println!("Added {} tags to {} {}: {}", added, oem, model, tags.get(&normalizer, &oem, &model).join(", "));
+        }
+        TagCommand::Remove { index, tags: removed } => {
+            let (oem, model) = phone(*index)?;
+            let removed = removed.iter().filter(|tag| tags.remove(&normalizer, &oem, &model, tag)).count();
+            tags.save(file)?;
+            println!("Removed {} tags from {} {}", removed, oem, model);
+        }
+        TagCommand::List { tag } => {
+            let wanted = |phone_tags: &BTreeSet<String>| tag.as_ref().is_none_or(|tag| phone_tags.iter().any(|other| other.eq_ignore_ascii_case(tag)));
+            let phones: Vec<_> = tags.phones().filter(|(_, _, phone_tags)| wanted(phone_tags)).collect();
+            if phones.is_empty() {
+                println!("No tagged phones in {}", file);
+            }
+            for (oem, model, phone_tags) in phones {
+                println!("{} {}: {}", oem, model, phone_tags.iter().cloned().collect::<Vec<_>>().join(", "));
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,382 @@
+use crate::error::CellError;
+use crate::provenance::{Provenance, SOURCE_FILE, SOURCE_LINE};
+use crate::schema::{Mapping, Schema};
+use crate::Cell;
+use csv::StringRecord;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+// One of the twelve columns of the dataset. The names match the CSV headers.
+pub enum Column {
+    Oem,
+    Model,
+    LaunchAnnounced,
+    LaunchStatus,
+    BodyDimensions,
+    BodyWeight,
+    BodySim,
+    DisplayType,
+    DisplaySize,
+    DisplayResolution,
+    FeaturesSensors,
+    PlatformOs,
+}
+
+impl Column {
+    // Every column in CSV order.
+    pub const ALL: [Column; 12] = [
+        Column::Oem,
+        Column::Model,
+        Column::LaunchAnnounced,
+        Column::LaunchStatus,
+        Column::BodyDimensions,
+        Column::BodyWeight,
+        Column::BodySim,
+        Column::DisplayType,
+        Column::DisplaySize,
+        Column::DisplayResolution,
+        Column::FeaturesSensors,
+        Column::PlatformOs,
+    ];
+
+    // The CSV header name of the column.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Column::Oem => "oem",
+            Column::Model => "model",
+            Column::LaunchAnnounced => "launch_announced",
+            Column::LaunchStatus => "launch_status",
+            Column::BodyDimensions => "body_dimensions",
+            Column::BodyWeight => "body_weight",
+            Column::BodySim => "body_sim",
+            Column::DisplayType => "display_type",
+            Column::DisplaySize => "display_size",
+            Column::DisplayResolution => "display_resolution",
+            Column::FeaturesSensors => "features_sensors",
+            Column::PlatformOs => "platform_os",
+        }
+    }
+
+    // Finds the column with the given header name.
+    pub fn from_name(name: &str) -> Option<Column> {
+        Column::ALL.into_iter().find(|column| column.name() == name.trim())
+    }
+}
+
+impl FromStr for Column {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Column, String> {
+        Column::from_name(name).ok_or_else(|| format!("unknown column '{}'", name.trim()))
+    }
+}
+
+impl fmt::Display for Column {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(untagged)]
+/// The value of one field of a cell, typed by its column: launch_announced is an Int,
+/// body_weight and display_size are Floats and every other column is Text.
+pub enum FieldValue {
+    Text(String),
+    Int(u32),
+    Float(f32),
+    None,
+}
+
+impl FieldValue {
+    pub fn is_none(&self) -> bool {
+        *self == FieldValue::None
+    }
+
+    /// The value as text, or None if the field has no value.
+    pub fn into_text(self) -> Option<String> {
+        match self {
+            FieldValue::Text(text) => Some(text),
+            FieldValue::Int(number) => Some(number.to_string()),
+            FieldValue::Float(number) => Some(number.to_string()),
+            FieldValue::None => None,
+        }
+    }
+
+    /// The value as a number, or None for text and missing values.
+    pub fn as_f32(&self) -> Option<f32> {
+        match self {
+            FieldValue::Int(number) => Some(*number as f32),
+            FieldValue::Float(number) => Some(*number),
+            FieldValue::Text(_) | FieldValue::None => None,
+        }
+    }
+}
+
+impl fmt::Display for FieldValue {
+    // Missing values are written as nothing.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FieldValue::Text(text) => write!(f, "{}", text),
+            FieldValue::Int(number) => write!(f, "{}", number),
+            FieldValue::Float(number) => write!(f, "{}", number),
+            FieldValue::None => Ok(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Where each column is in the records of a CSV file, found by header name so the columns
+/// may come in any order. A schema can rename or ignore headers; the other headers that are not
+/// one of the columns are kept as extras. The source_file and source_line headers of an
+/// export carry the provenance of its rows instead.
+pub struct HeaderIndex {
+    positions: [usize; 12],
+    extras: Vec<(String, usize)>,
+    // The file the records come from, if known, and the positions of carried provenance.
+    file: Option<Arc<str>>,
+    carried: Option<(usize, usize)>,
+}
+
+impl HeaderIndex {
+    /*
+        Find every column in a header row. Header names are matched ignoring case and
+        surrounding spaces. Fails with the first column that has no header.
+
+        Runtime: O(c)
+     */
+    pub fn from_headers(headers: &StringRecord) -> Result<HeaderIndex, CellError> {
+        HeaderIndex::from_headers_with(headers, &Schema::default())
+    }
+
+    /*
+        Find every column in a header row, reading the headers through a schema. When several
+        headers map to the same column the first one is used. An empty file, with no header
+        row, is read as having no rows rather than missing every column.
+
+        Runtime: O(c * m)
+     */
+    pub fn from_headers_with(headers: &StringRecord, schema: &Schema) -> Result<HeaderIndex, CellError> {
+        // A file without even a header row has no records either, so no column is missing.
+        if headers.is_empty() {
+            return Ok(HeaderIndex { positions: [usize::MAX; 12], extras: Vec::new(), file: None, carried: None });
+        }
+        let mappings: Vec<Option<Mapping>> = headers.iter().map(|header| schema.mapping(header)).collect();
+        let mut positions = [0; 12];
+
+        for (position, column) in positions.iter_mut().zip(Column::ALL) {
+            *position = mappings.iter().position(|&mapping| mapping == Some(Mapping::Field(column))).ok_or(CellError::MissingColumn { column })?;
+        }
+
+        let find = |name: &str| headers.iter().position(|header| header.trim().eq_ignore_ascii_case(name));
+        let carried = find(SOURCE_FILE).zip(find(SOURCE_LINE));
+        let extras = headers
+            .iter()
+            .zip(&mappings)
+            .enumerate()
+            .filter(|(position, (header, mapping))| {
+                let is_carried = carried.is_some_and(|(file, line)| *position == file || *position == line);
+                mapping.is_none() && !header.trim().is_empty() && !is_carried
+            })
+            .map(|(position, (header, _))| (header.trim().to_string(), position))
+            .collect();
+
+        Ok(HeaderIndex { positions, extras, file: None, carried })
+    }
+
+    // Names the file the records are read from, so each cell records where it came from.
+    pub fn in_file(mut self, file: &str) -> HeaderIndex {
+        self.file = Some(file.into());
+        self
+    }
+
+    /*
+        Where a record came from: the source_file and source_line it carries, if it does,
+        otherwise the given line of the file being read. None when neither is known.
+
+        Runtime: O(1)
+     */
+    pub fn provenance(&self, record: &StringRecord, line: usize) -> Option<Provenance> {
+        let carried = self.carried.and_then(|(file, line)| {
+            let file = record.get(file)?.trim();
+            let line = record.get(line)?.trim().parse().ok()?;
+            (!file.is_empty()).then(|| Provenance::new(file, line))
+        });
+
+        carried.or_else(|| self.file.clone().map(|file| Provenance { file, line }))
+    }
+
+    /*
+        The values of the extra columns in a record, by header name. Blank and '-' values are
+        missing, so they are left out.
+
+        Runtime: O(e log e)
+     */
+    pub fn extras(&self, record: &StringRecord) -> BTreeMap<String, String> {
+        self.extras
+            .iter()
+            .filter_map(|(header, position)| {
+                let value = record.get(*position)?.trim();
+                (!value.is_empty() && value != "-").then(|| (header.clone(), value.to_string()))
+            })
+            .collect()
+    }
+
+    // The raw value of a column in a record, blank if the record is too short.
+    pub fn get<'a>(&self, record: &'a StringRecord, column: Column) -> &'a str {
+        // The variants are declared in CSV order, so a column's discriminant is its index in ALL.
+        record.get(self.positions[column as usize]).unwrap_or_default()
+    }
+}
+
+impl Cell {
+    // The value of an extra column, by header name ignoring case.
+    pub fn extra(&self, header: &str) -> Option<&str> {
+        let header = header.trim();
+        self.extras.iter().find(|(name, _)| name.eq_ignore_ascii_case(header)).map(|(_, value)| value.as_str())
+    }
+
+    /*
+        Returns the value of a column, typed by the column.
+
+        Runtime: O(1)
+     */
+    pub fn field(&self, column: Column) -> FieldValue {
+        let text = |value: Option<&str>| value.map_or(FieldValue::None, |text| FieldValue::Text(text.to_string()));
+
+        match column {
+            Column::Oem => text(self.oem.as_deref()),
+            Column::Model => text(self.model.as_deref()),
+            Column::LaunchAnnounced => self.launch_announced.map_or(FieldValue::None, FieldValue::Int),
+            Column::LaunchStatus => text(self.launch_status.as_deref()),
+            Column::BodyDimensions => text(self.body_dimensions.as_deref()),
+            Column::BodyWeight => self.body_weight.map_or(FieldValue::None, FieldValue::Float),
+            Column::BodySim => text(self.body_sim.as_deref()),
+            Column::DisplayType => text(self.display_type.as_deref()),
+            Column::DisplaySize => self.display_size.map_or(FieldValue::None, FieldValue::Float),
+            Column::DisplayResolution => text(self.display_resolution.as_deref()),
+            Column::FeaturesSensors => text(self.features_sensors.as_deref()),
+            Column::PlatformOs => text(self.platform_os.as_deref()),
+        }
+    }
+
+    /*
+        Returns the value of the column with the given header name, e.g. "body_weight".
+
+        Runtime: O(c)
+     */
+    pub fn get_field(&self, name: &str) -> Result<FieldValue, CellError> {
+        Ok(self.field(Column::from_name(name).ok_or_else(|| CellError::UnknownColumn { name: name.trim().to_string() })?))
+    }
+
+    /*
+        Sets the value of the column with the given header name. launch_announced takes an
+        Int and the float columns take a Float or an Int. Text is set as set_text sets it, so
+        it is parsed for a numeric column, and None clears the column. Any other value leaves
+        the cell unchanged.
+
+        Runtime: O(c)
+     */
+    pub fn set_field(&mut self, name: &str, value: FieldValue) -> Result<(), CellError> {
+        let column = Column::from_name(name).ok_or_else(|| CellError::UnknownColumn { name: name.trim().to_string() })?;
+        self.set_field_value(column, value)
+    }
+
+    // Like set_field, for a column that is already known. Runtime: O(1)
+    pub fn set_field_value(&mut self, column: Column, value: FieldValue) -> Result<(), CellError> {
+        let invalid = |value: &FieldValue| CellError::Parse { row: None, column, value: value.to_string() };
+
+        match (column, value) {
+            (_, FieldValue::None) => self.set_text(column, ""),
+            (_, FieldValue::Text(text)) => self.set_text(column, &text),
+            (Column::LaunchAnnounced, FieldValue::Int(year)) => {
+                self.launch_announced = Some(year);
+                self.sync_launch_date();
+                Ok(())
+            }
+            (Column::BodyWeight, value @ (FieldValue::Int(_) | FieldValue::Float(_))) => {
+                self.body_weight = value.as_f32();
+                Ok(())
+            }
+            (Column::DisplaySize, value @ (FieldValue::Int(_) | FieldValue::Float(_))) => {
+                self.display_size = value.as_f32();
+                Ok(())
+            }
+            (_, value) => Err(invalid(&value)),
+        }
+    }
+
+    /*
+        Returns the value of a column as text, or None if the value is missing.
+
+        Runtime: O(1)
+     */
+    pub fn text(&self, column: Column) -> Option<String> {
+        self.field(column).into_text()
+    }
+
+    // The shared string of one of the intern::INTERNED columns, or None for a missing value
+    // or any other column.
+    pub fn interned(&self, column: Column) -> Option<&Arc<str>> {
+        match column {
+            Column::Oem => self.oem.as_ref(),
+            Column::LaunchStatus => self.launch_status.as_ref(),
+            Column::BodySim => self.body_sim.as_ref(),
+            Column::DisplayType => self.display_type.as_ref(),
+            Column::PlatformOs => self.platform_os.as_ref(),
+            _ => None,
+        }
+    }
+
+    /*
+        Returns the value of a numeric column (launch_announced, body_weight, display_size) as
+        a number, or None for a missing value or a text column.
+
+        Runtime: O(1)
+     */
+    pub fn number(&self, column: Column) -> Option<f32> {
+        self.field(column).as_f32()
+    }
+
+    /*
+        Sets a column from text. Numeric columns must parse as a number, otherwise the cell is
+        left unchanged; a blank or '-' value clears the column.
+
+        Runtime: O(1)
+     */
+    pub fn set_text(&mut self, column: Column, value: &str) -> Result<(), CellError> {
+        let invalid = || CellError::Parse { row: None, column, value: value.to_string() };
+        let value = Cell::check_empty(value).map(|value| value.trim().to_string());
+
+        match column {
+            Column::Oem => self.oem = value.map(Into::into),
+            Column::Model => self.model = value.map(Into::into),
+            Column::LaunchAnnounced => {
+                self.launch_announced = value.map(|v| v.parse()).transpose().map_err(|_| invalid())?;
+                self.sync_launch_date();
+            }
+            Column::LaunchStatus => {
+                self.launch_status = value.map(Into::into);
+                self.sync_release_date();
+            }
+            Column::BodyDimensions => self.body_dimensions = value.map(Into::into),
+            Column::BodyWeight => self.body_weight = value.map(|v| v.parse()).transpose().map_err(|_| invalid())?,
+            Column::BodySim => self.body_sim = value.map(Into::into),
+            Column::DisplayType => self.display_type = value.map(Into::into),
+            Column::DisplaySize => self.display_size = value.map(|v| v.parse()).transpose().map_err(|_| invalid())?,
+            Column::DisplayResolution => self.display_resolution = value.map(Into::into),
+            Column::FeaturesSensors => {
+                self.features_sensors = value.map(Into::into);
+                self.parse_sensors();
+            }
+            Column::PlatformOs => self.platform_os = value.map(Into::into),
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,81 @@
+use crate::analysis::MIN_SAMPLE_SIZE;
+use crate::bounds::Bounds;
+use crate::error::CellError;
+use crate::format::NumberFormat;
+use crate::grouping::FloatGrouping;
+use crate::normalize::NormalizationConfig;
+use crate::parse::ParseMode;
+use crate::query::SavedQuery;
+use crate::units::UnitProfile;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+
+// Name of the config file the `cells` binary reads from the current directory, if it is there.
+pub const DEFAULT_CONFIG_FILE: &str = "cells.toml";
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+// User settings read from a TOML file. Every section is optional.
+pub struct Config {
+    // Extra or corrected OEM -> country entries, checked before the built-in table.
+    pub oem_countries: HashMap<String, String>,
+    // Extra or corrected OEM alias -> OEM entries, applied while parsing over the built-in ones.
+    pub oem_aliases: HashMap<String, String>,
+    // Path of a CSV alias file (oem,alias,canonical) applied to models after loading.
+    pub model_aliases: Option<String>,
+    // Path of a TOML schema file mapping the CSV headers of a dataset variant to columns.
+    pub schema: Option<String>,
+    // Whether numeric values that are not numbers fail the load (strict) or become empty.
+    pub parse_mode: ParseMode,
+    // Keep the text of fields as the file has it instead of cleaning it (see text::clean).
+    pub raw_text: bool,
+    // Plausible ranges of the parsed numbers, and what to do with values outside them.
+    pub bounds: Bounds,
+    // Results from fewer values than this are marked as low confidence (default MIN_SAMPLE_SIZE).
+    pub min_sample_size: Option<usize>,
+    // Which cleaning stages read_csv runs, and in what order.
+    pub normalization: NormalizationConfig,
+    // The units reports print weights and lengths in (metric, imperial or mixed, the default).
+    pub units: UnitProfile,
+    // Decimal places, rounding and trailing zeros of the numbers in reports, tables and exports.
+    pub number_format: NumberFormat,
+    // Bucket widths of the float columns when grouping or counting by them.
+    pub float_grouping: FloatGrouping,
+    // Named filter/sort/projection combinations for `cells query run <name>`.
+    pub queries: BTreeMap<String, SavedQuery>,
+}
+
+impl Config {
+    /*
+        Read and parse the config file at the given path.
+
+        Runtime: O(n)
+     */
+    pub fn load(path: &str) -> Result<Config, CellError> {
+        let text = fs::read_to_string(path)?;
+        let config = toml::from_str(&text).map_err(|error| CellError::Toml { path: path.to_string(), error })?;
+
+        Ok(config)
+    }
+
+    // Fewest values a reported mean, median or share should come from.
+    pub fn min_sample_size(&self) -> usize {
+        self.min_sample_size.unwrap_or(MIN_SAMPLE_SIZE)
+    }
+
+    /*
+        Read the config file if it exists, otherwise use the defaults.
+        A file that exists but cannot be parsed is still an error.
+
+        Runtime: O(n)
+     */
+    pub fn load_or_default(path: &str) -> Result<Config, CellError> {
+        if Path::new(path).exists() {
+            Config::load(path)
+        } else {
+            Ok(Config::default())
+        }
+    }
+}
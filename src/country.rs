@@ -0,0 +1,185 @@
+use crate::config::Config;
+use crate::view::DatasetView;
+use std::collections::HashMap;
+
+// Built-in OEM -> country-of-origin table. OEM names are matched case-insensitively.
+const BUILTIN_COUNTRIES: [(&str, &str); 71] = [
+    ("Acer", "Taiwan"),
+    ("Alcatel", "France"),
+    ("Apple", "United States"),
+    ("Asus", "Taiwan"),
+    ("Benefon", "Finland"),
+    ("BlackBerry", "Canada"),
+    ("Garmin-Asus", "Taiwan"),
+    ("Gigabyte", "Taiwan"),
+    ("Google", "United States"),
+    ("Haier", "China"),
+    ("Honor", "China"),
+    ("HP", "United States"),
+    ("HTC", "Taiwan"),
+    ("Huawei", "China"),
+    ("i-mate", "United Arab Emirates"),
+    ("i-mobile", "Thailand"),
+    ("Icemobile", "Argentina"),
+    ("Infinix", "China"),
+    ("Innostream", "South Korea"),
+    ("Kyocera", "Japan"),
+    ("Lava", "India"),
+    ("Lenovo", "China"),
+    ("LG", "South Korea"),
+    ("Maxon", "South Korea"),
+    ("Meizu", "China"),
+    ("Micromax", "India"),
+    ("Microsoft", "United States"),
+    ("Mitac", "Taiwan"),
+    ("Mitsubishi", "Japan"),
+    ("Motorola", "United States"),
+    ("NEC", "Japan"),
+    ("Neonode", "Sweden"),
+    ("Nokia", "Finland"),
+    ("Nothing", "United Kingdom"),
+    ("O2", "United Kingdom"),
+    ("OnePlus", "China"),
+    ("Oppo", "China"),
+    ("Orange", "France"),
+    ("Palm", "United States"),
+    ("Panasonic", "Japan"),
+    ("Pantech", "South Korea"),
+    ("Philips", "Netherlands"),
+    ("Plum", "United States"),
+    ("Qtek", "Taiwan"),
+    ("Realme", "China"),
+    ("Sagem", "France"),
+    ("Samsung", "South Korea"),
+    ("Sendo", "United Kingdom"),
+    ("Sewon", "South Korea"),
+    ("Sharp", "Japan"),
+    ("Siemens", "Germany"),
+    ("Sony", "Japan"),
+    ("Sony Ericsson", "Sweden"),
+    ("Sony Mobile", "Japan"),
+    ("Spice", "India"),
+    ("T-Mobile", "Germany"),
+    ("TCL", "China"),
+    ("TECNO", "China"),
+    ("Tel.Me.", "Russia"),
+    ("Telit", "Italy"),
+    ("Toshiba", "Japan"),
+    ("Ulefone", "China"),
+    ("Unnecto", "United States"),
+    ("Vertu", "United Kingdom"),
+    ("VK Mobile", "South Korea"),
+    ("vivo", "China"),
+    ("Vodafone", "United Kingdom"),
+    ("Wiko", "France"),
+    ("Xiaomi", "China"),
+    ("Yezz", "United States"),
+    ("ZTE", "China"),
+];
+
+#[derive(Debug, Default)]
+// OEM -> country lookup: user overrides from the config first, then the built-in table.
+pub struct CountryMap {
+    overrides: HashMap<String, String>,
+}
+
+impl CountryMap {
+    /*
+        Creates a map that only uses the built-in table.
+
+        Runtime: O(1)
+     */
+    pub fn new() -> CountryMap {
+        CountryMap { overrides: HashMap::new() }
+    }
+
+    /*
+        Creates a map with the [oem_countries] section of the config layered over the built-in table.
+
+        Runtime: O(n)
+     */
+    pub fn from_config(config: &Config) -> CountryMap {
+        let mut countries = CountryMap::new();
+
+        for (oem, country) in &config.oem_countries {
+            countries.insert(oem, country);
+        }
+
+        countries
+    }
+
+    // Adds or replaces the country for an OEM.
+    pub fn insert(&mut self, oem: &str, country: &str) {
+        self.overrides.insert(oem.to_lowercase(), country.to_string());
+    }
+
+    /*
+        Looks up the country of origin for an OEM.
+
+        Runtime: O(1) for overrides, O(k) over the built-in table otherwise
+     */
+    pub fn country_of(&self, oem: &str) -> Option<&str> {
+        let key = oem.trim().to_lowercase();
+
+        if let Some(country) = self.overrides.get(&key) {
+            return Some(country.as_str());
+        }
+
+        BUILTIN_COUNTRIES
+            .iter()
+            .find(|(name, _)| name.to_lowercase() == key)
+            .map(|&(_, country)| country)
+    }
+}
+
+// The spelling the built-in table gives an OEM, whatever the case it is written in.
+pub(crate) fn known_oem(oem: &str) -> Option<&'static str> {
+    BUILTIN_COUNTRIES.iter().find(|(name, _)| name.eq_ignore_ascii_case(oem.trim())).map(|&(name, _)| name)
+}
+
+/*
+    Function used to count the launches per country per year.
+    Returns (country, year, count) sorted by country and then year. Phones whose OEM has no
+    known country, or that have no launch year, are skipped.
+
+    Runtime: O(n log n)
+ */
+pub fn launches_per_country_per_year(cells: &DatasetView, countries: &CountryMap) -> Vec<(String, u32, usize)> {
+    let mut counts: HashMap<(String, u32), usize> = HashMap::new();
+
+    for cell in cells {
+        if let (Some(country), Some(year)) = (cell.country(countries), cell.launch_announced) {
+            let count = counts.entry((country.to_string(), year)).or_insert(0);
+            *count += 1;
+        }
+    }
+
+    let mut rows: Vec<(String, u32, usize)> = counts
+        .into_iter()
+        .map(|((country, year), count)| (country, year, count))
+        .collect();
+    rows.sort();
+
+    rows
+}
+
+/*
+    Function used to count the phones per country, most phones first and ties by name.
+    Phones whose OEM has no known country are skipped.
+
+    Runtime: O(n log n)
+ */
+pub fn devices_per_country(cells: &DatasetView, countries: &CountryMap) -> Vec<(String, usize)> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+
+    for cell in cells {
+        if let Some(country) = cell.country(countries) {
+            *counts.entry(country.to_string()).or_insert(0) += 1;
+        }
+    }
+
+    let mut rows: Vec<(String, usize)> = counts.into_iter().collect();
+    rows.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+
+    rows
+}
@@ -0,0 +1,739 @@
+use crate::aliases::ModelAliases;
+use crate::column::Column;
+use crate::config::Config;
+use crate::error::CellError;
+use crate::memory::MemoryUsage;
+use crate::normalize::Normalizer;
+use crate::parse::{EmptyInput, ParseMode, ParseReport};
+use crate::patch::CellPatch;
+use crate::provenance::Provenance;
+use crate::random::Rng;
+use crate::schema::Schema;
+use crate::sketch::BloomFilter;
+use crate::summary::{ColumnSummary, DatasetStats};
+use crate::tags::Tags;
+use crate::view::DatasetView;
+use crate::Cell;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fs::File;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+// How rows without a match in the other dataset are treated by a join.
+pub enum JoinKind {
+    // Keep every row of this dataset; unmatched rows get no supplemental data.
+    Left,
+    // Only keep rows that have a match.
+    Inner,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+// How a join matches rows of the other file to cells.
+pub enum JoinMatch {
+    // The key columns must be equal, apart from surrounding spaces.
+    Exact,
+    // Rows without an exact match may also match on key columns equal once case, spaces and
+    // punctuation are ignored, with launch years at most one apart.
+    Fuzzy,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+// How sure a join is that a row of the other file is the same phone as the cell.
+pub enum MatchConfidence {
+    Exact,
+    // A fuzzy match announced the same year.
+    SameYear,
+    // A fuzzy match announced a year apart, e.g. a late regional release.
+    NearYear,
+}
+
+impl MatchConfidence {
+    // The confidence as a score from 0 to 1, to sort or threshold joined rows by.
+    pub fn score(&self) -> f32 {
+        match self {
+            MatchConfidence::Exact => 1.0,
+            MatchConfidence::SameYear => 0.9,
+            MatchConfidence::NearYear => 0.7,
+        }
+    }
+}
+
+impl fmt::Display for MatchConfidence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MatchConfidence::Exact => write!(f, "exact"),
+            MatchConfidence::SameYear => write!(f, "fuzzy, same year"),
+            MatchConfidence::NearYear => write!(f, "fuzzy, one year apart"),
+        }
+    }
+}
+
+#[derive(Debug)]
+// A row of a join: the cell from this dataset plus the other file's columns and how well
+// they matched, if it matched.
+pub struct JoinedRow<'a> {
+    pub cell: &'a Cell,
+    pub supplemental: Option<HashMap<String, String>>,
+    pub confidence: Option<MatchConfidence>,
+}
+
+// The other file's columns of one row of a join, by header.
+type Values = HashMap<String, String>;
+
+// One row of the other side of a join: its key, its launch year when the join is fuzzy, and
+// its other columns.
+struct OtherRow {
+    key: Vec<String>,
+    year: Option<u32>,
+    values: Values,
+}
+
+// A join key value with case, spaces and punctuation dropped, so "Galaxy S20-5G" and "galaxy
+// s20 5g" are the same.
+fn loose_key(value: &str) -> String {
+    value.chars().filter(|c| c.is_alphanumeric()).flat_map(char::to_lowercase).collect()
+}
+
+#[derive(Debug, Clone, Copy)]
+/*
+    One of the non-overlapping chunks of Dataset::partitions: the partition's number, where
+    its cells start in the dataset, and the cells. Partitions borrow the cells, so each can be
+    handed to its own thread; to another machine, its cells can be written out with
+    Cell::write_csv_to and the results merged by partition number.
+ */
+pub struct DatasetPartition<'a> {
+    pub index: usize,
+    pub start: usize,
+    pub cells: &'a [Cell],
+}
+
+impl<'a> DatasetPartition<'a> {
+    // A view of the partition's cells, to filter and pass to the analyses.
+    pub fn view(&self) -> DatasetView<'a> {
+        DatasetView::of(self.cells)
+    }
+
+    // The index in the dataset of the partition's cell at `offset`.
+    pub fn dataset_index(&self, offset: usize) -> usize {
+        self.start + offset
+    }
+
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+}
+
+#[derive(Debug, Default)]
+// The loaded cells of one CSV file.
+pub struct Dataset {
+    cells: Vec<Cell>,
+    model_aliases: ModelAliases,
+    // Cleans the join keys of other files the way the cells were cleaned when loaded.
+    key_normalizer: Normalizer,
+    // Bloom filter over the (oem, model) keys, if build_key_filter() was called.
+    key_filter: Option<BloomFilter>,
+    // Per-column summaries, updated by every change to the cells.
+    stats: DatasetStats,
+    // The values that could not be parsed when the file was loaded.
+    parse_report: ParseReport,
+}
+
+impl Dataset {
+    // Wraps already loaded cells.
+    pub fn new(cells: Vec<Cell>) -> Dataset {
+        let stats = DatasetStats::of(&cells);
+        Dataset { cells, model_aliases: ModelAliases::new(), key_normalizer: Normalizer::default(), key_filter: None, stats, parse_report: ParseReport::default() }
+    }
+
+    /*
+        Reads a dataset from a CSV file using Cell::read_csv.
+
+        Runtime: O(n)
+     */
+    pub fn from_csv(filename: &str) -> Result<Dataset, CellError> {
+        Dataset::from_csv_with(filename, &mut Normalizer::default())
+    }
+
+    // Reads a dataset from a CSV file, cleaning fields with the given normalizer.
+    pub fn from_csv_with(filename: &str, normalizer: &mut Normalizer) -> Result<Dataset, CellError> {
+        Dataset::from_csv_with_schema(filename, &Schema::default(), normalizer)
+    }
+
+    /*
+        Reads a dataset with Cell::read_csv_parallel, parsing on every core. In strict mode a
+        file with any numeric value that is not a number is rejected with the full report; in
+        lenient mode those values become None and the report is kept as warnings.
+
+        Runtime: O(n / p) for p cores
+     */
+    pub fn from_csv_parallel(filename: &str, schema: &Schema, normalizer: &mut Normalizer, mode: ParseMode) -> Result<Dataset, CellError> {
+        let mut report = ParseReport::default();
+        let cells = Cell::read_csv_parallel(filename, schema, normalizer, &mut report)?;
+        if mode == ParseMode::Strict && !report.is_empty() {
+            return Err(CellError::Invalid(report));
+        }
+
+        let mut dataset = Dataset::new(cells);
+        dataset.parse_report = report;
+        dataset.key_normalizer = normalizer.fork();
+        Ok(dataset)
+    }
+
+    /*
+        Load a CSV file the way the commands load --file: with the config's schema, cleaning
+        and parse mode, then the file's tags and the config's model aliases. The normalizer
+        is returned too, with its counts of what the cleaning changed; it also names phones
+        as the tags were matched by.
+
+        Runtime: O(n / p) for p cores
+     */
+    pub fn load(filename: &str, config: &Config) -> Result<(Dataset, Normalizer), CellError> {
+        let mut normalizer = Normalizer::from_config(config);
+        let mut dataset = Dataset::from_csv_parallel(filename, &Schema::from_config(config)?, &mut normalizer, config.parse_mode)?;
+
+        dataset.apply_tags(&Tags::load(filename, &normalizer)?);
+        if let Some(path) = &config.model_aliases {
+            dataset.apply_model_aliases(ModelAliases::from_csv(path)?);
+        }
+
+        Ok((dataset, normalizer))
+    }
+
+    // Reads a dataset variant whose headers are mapped to columns by a schema. A file without
+    // rows gives an empty dataset whose parse report says why.
+    pub fn from_csv_with_schema(filename: &str, schema: &Schema, normalizer: &mut Normalizer) -> Result<Dataset, CellError> {
+        let mut dataset = Dataset::new(Cell::read_csv_with_schema(filename, schema, normalizer)?);
+        dataset.key_normalizer = normalizer.fork();
+        if dataset.is_empty() {
+            let headers = csv::Reader::from_path(filename)?.headers()?.clone();
+            dataset.parse_report.empty_input = Some(EmptyInput::of(&headers));
+        }
+        Ok(dataset)
+    }
+
+    /*
+        Reads several CSV files into one dataset, in order, as with from_csv_parallel. Every
+        cell keeps the file and line it came from, and the parse reports are combined; the
+        merged dataset only counts as empty input when every file is empty.
+
+        Runtime: O(n / p) for n rows over all files and p cores
+     */
+    pub fn from_csv_files(filenames: &[&str], schema: &Schema, normalizer: &mut Normalizer, mode: ParseMode) -> Result<Dataset, CellError> {
+        let mut dataset = Dataset::new(Vec::new());
+        dataset.key_normalizer = normalizer.fork();
+        for filename in filenames {
+            let part = Dataset::from_csv_parallel(filename, schema, normalizer, mode)?;
+            let empty_input = part.parse_report.empty_input.filter(|_| dataset.is_empty());
+            dataset.parse_report.extend(part.parse_report);
+            dataset.parse_report.empty_input = empty_input;
+            dataset.append(part.cells);
+        }
+        Ok(dataset)
+    }
+
+    // Where the cell at an index was read from, if it was read from a file.
+    pub fn provenance(&self, index: usize) -> Option<&Provenance> {
+        self.cells.get(index)?.source.as_ref()
+    }
+
+    // The values of the loaded file that were not numbers and became None.
+    pub fn parse_report(&self) -> &ParseReport {
+        &self.parse_report
+    }
+
+    // The cells in file order.
+    pub fn cells(&self) -> &[Cell] {
+        &self.cells
+    }
+
+    // A view of every cell, to filter and pass to the analyses.
+    pub fn view(&self) -> DatasetView<'_> {
+        DatasetView::of(&self.cells)
+    }
+
+    /*
+        Split the cells, in file order, into n non-overlapping partitions whose sizes differ
+        by at most one. There are fewer when there aren't n cells, so no partition is empty,
+        and none for an empty dataset; n = 0 counts as 1.
+
+        Runtime: O(n)
+     */
+    pub fn partitions(&self, n: usize) -> impl ExactSizeIterator<Item = DatasetPartition<'_>> + '_ {
+        let count = n.max(1).min(self.cells.len());
+        let size = self.cells.len().checked_div(count).unwrap_or(0);
+        let larger = self.cells.len().checked_rem(count).unwrap_or(0);
+
+        (0..count).map(move |index| {
+            // The first `larger` partitions take one extra cell.
+            let start = index * size + index.min(larger);
+            let end = start + size + usize::from(index < larger);
+            DatasetPartition { index, start, cells: &self.cells[start..end] }
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    // Estimated bytes used by the loaded cells, per column and in total.
+    pub fn memory_usage(&self) -> MemoryUsage {
+        MemoryUsage::of(&self.cells, self.cells.capacity())
+    }
+
+    // Keep only the cells for which `keep` returns true.
+    pub fn retain(&mut self, keep: impl Fn(&Cell) -> bool) {
+        self.cells.retain(|cell| keep(cell));
+        self.stats = DatasetStats::of(&self.cells);
+    }
+
+    /*
+        The count, null count, sum, min and max of a column, kept up to date by insert, modify,
+        delete and the other changes so they are never recomputed from the cells.
+
+        Runtime: O(log n)
+     */
+    pub fn column_stats(&self, column: Column) -> ColumnSummary {
+        self.stats.summary(column)
+    }
+
+    // Inserts a cell before the given index (or at the end). Nothing is displaced, so the
+    // result is None. Runtime: O(n)
+    pub fn insert(&mut self, index: usize, cell: Cell) -> Result<Option<Cell>, CellError> {
+        let displaced = Cell::insert_cell(&mut self.cells, index, cell)?;
+
+        self.index_cell(index);
+        Ok(displaced)
+    }
+
+    /*
+        Insert several cells before the given index (or at the end), keeping their order, in
+        one move of the cells after them instead of one per inserted cell.
+
+        Runtime: O(n + m log n) for m new cells
+     */
+    pub fn insert_many(&mut self, index: usize, cells: Vec<Cell>) -> Result<(), CellError> {
+        if index > self.cells.len() {
+            return Err(CellError::IndexOutOfBounds { index, len: self.cells.len() });
+        }
+
+        let count = cells.len();
+        self.cells.splice(index..index, cells);
+        for inserted in index..index + count {
+            self.index_cell(inserted);
+        }
+        Ok(())
+    }
+
+    /*
+        Remove every cell for which `predicate` returns true, in a single pass. Returns the
+        removed cells with the index each had before the removal, in order.
+
+        Runtime: O(n log n)
+     */
+    pub fn delete_where(&mut self, predicate: impl Fn(&Cell) -> bool) -> Vec<(usize, Cell)> {
+        let mut removed = Vec::new();
+        let mut kept = Vec::with_capacity(self.cells.len());
+
+        for (index, cell) in std::mem::take(&mut self.cells).into_iter().enumerate() {
+            if predicate(&cell) {
+                self.stats.remove(&cell);
+                removed.push((index, cell));
+            } else {
+                kept.push(cell);
+            }
+        }
+        self.cells = kept;
+
+        removed
+    }
+
+    /*
+        Apply a patch to every cell for which `predicate` returns true, e.g. fixing the OEM
+        casing of every Samsung record. Returns the changed cells as they were, with their
+        indexes.
+
+        Runtime: O(n + m log n) for m changed cells
+     */
+    pub fn modify_where(&mut self, predicate: impl Fn(&Cell) -> bool, patch: &CellPatch) -> Vec<(usize, Cell)> {
+        let mut changed = Vec::new();
+
+        for index in 0..self.cells.len() {
+            if !predicate(&self.cells[index]) {
+                continue;
+            }
+            let old = self.cells[index].clone();
+            self.cells[index].apply_patch(patch);
+            self.stats.remove(&old);
+            self.index_cell(index);
+            changed.push((index, old));
+        }
+
+        changed
+    }
+
+    /*
+        Add cells at the end, e.g. rows appended to the file since it was loaded. Their models
+        are canonicalized with the dataset's aliases, and the column summaries and key filter
+        are updated for just the new cells.
+
+        Runtime: O(m log n) for m new cells
+     */
+    pub fn append(&mut self, cells: Vec<Cell>) {
+        for mut cell in cells {
+            if let (Some(oem), Some(model)) = (&cell.oem, &cell.model) {
+                cell.model = Some(self.model_aliases.canonical(oem, model).into());
+            }
+            self.cells.push(cell);
+            self.index_cell(self.cells.len() - 1);
+        }
+    }
+
+    // Adds cells parsed from a later part of the file, as append does, with the values the
+    // parsing reported. Runtime: O(m log n) for m new cells
+    pub fn append_parsed(&mut self, cells: Vec<Cell>, report: ParseReport) {
+        self.parse_report.extend(report);
+        self.append(cells);
+    }
+
+    // Adds a new or changed cell to the summaries and the key filter, if there is one.
+    fn index_cell(&mut self, index: usize) {
+        let cell = &self.cells[index];
+        self.stats.add(cell);
+        if let (Some(filter), Some(oem), Some(model)) = (&mut self.key_filter, &cell.oem, &cell.model) {
+            filter.insert((oem.trim(), model.trim()));
+        }
+    }
+
+    // Replaces the cell at an index, returning the cell it replaced. Runtime: O(log n)
+    pub fn modify(&mut self, index: usize, cell: Cell) -> Result<Option<Cell>, CellError> {
+        let old = Cell::modify_cell(&mut self.cells, index, cell)?;
+
+        if let Some(old) = &old {
+            self.stats.remove(old);
+        }
+        self.index_cell(index);
+        Ok(old)
+    }
+
+    // Changes only the fields a patch has a value for, returning the cell as it was.
+    // Runtime: O(log n)
+    pub fn patch(&mut self, index: usize, patch: &CellPatch) -> Result<Option<Cell>, CellError> {
+        let old = Cell::modify_cell_with_patch(&mut self.cells, index, patch)?;
+
+        if let Some(old) = &old {
+            self.stats.remove(old);
+        }
+        self.index_cell(index);
+        Ok(old)
+    }
+
+    // Removes the cell at an index, returning it. Runtime: O(n)
+    pub fn delete(&mut self, index: usize) -> Result<Option<Cell>, CellError> {
+        let old = Cell::delete_cell(&mut self.cells, index)?;
+
+        if let Some(old) = &old {
+            self.stats.remove(old);
+        }
+        Ok(old)
+    }
+
+    /*
+        Pick up to n cells at random (reservoir sampling), returned in file order.
+
+        Runtime: O(n log n)
+     */
+    pub fn sample(&self, n: usize, rng: &mut Rng) -> Vec<&Cell> {
+        let mut picked: Vec<usize> = Vec::with_capacity(n);
+
+        for index in 0..self.cells.len() {
+            if picked.len() < n {
+                picked.push(index);
+            } else {
+                let slot = rng.below(index + 1);
+                if slot < n {
+                    picked[slot] = index;
+                }
+            }
+        }
+        picked.sort();
+
+        picked.into_iter().map(|index| &self.cells[index]).collect()
+    }
+
+    /*
+        Set the tags of every cell from a tag sidecar, before model aliases rename any cell.
+        Returns the number of tagged cells.
+
+        Runtime: O(n log p)
+     */
+    pub fn apply_tags(&mut self, tags: &Tags) -> usize {
+        tags.apply(&mut self.cells)
+    }
+
+    /*
+        Rename every aliased model to its canonical name and keep the aliases, so that later
+        joins canonicalize the other file's models the same way. Apply this before dedupe()
+        or join(). Returns the number of renamed cells.
+
+        Runtime: O(n)
+     */
+    pub fn apply_model_aliases(&mut self, aliases: ModelAliases) -> usize {
+        let mut renamed = 0;
+
+        for cell in &mut self.cells {
+            if let (Some(oem), Some(model)) = (&cell.oem, &cell.model) {
+                let canonical = aliases.canonical(oem, model);
+                if canonical != &**model {
+                    cell.model = Some(canonical.into());
+                    renamed += 1;
+                }
+            }
+        }
+
+        self.model_aliases = aliases;
+        renamed
+    }
+
+    // Cleans the join keys of other files with this normalizer's text cleaning, stages and
+    // OEM aliases; a dataset read from a file uses the normalizer it was read with.
+    pub fn set_key_normalizer(&mut self, normalizer: Normalizer) {
+        self.key_normalizer = normalizer;
+    }
+
+    /*
+        Remove cells whose oem+model was already seen earlier in the file, keeping the
+        first one. Returns the number of removed cells.
+
+        Runtime: O(n)
+     */
+    pub fn dedupe(&mut self) -> usize {
+        let before = self.cells.len();
+        let mut seen = HashSet::new();
+
+        self.cells.retain(|cell| match (&cell.oem, &cell.model) {
+            (Some(oem), Some(model)) => seen.insert((oem.to_lowercase(), model.trim().to_lowercase())),
+            _ => true,
+        });
+
+        self.stats = DatasetStats::of(&self.cells);
+        before - self.cells.len()
+    }
+
+    /*
+        Build a bloom filter over the trimmed (oem, model) key of every cell, so contains_model()
+        and oem+model joins can reject keys that aren't in this dataset without a full lookup.
+        Call it again after changing the cells.
+
+        Runtime: O(n)
+     */
+    pub fn build_key_filter(&mut self, false_positive_rate: f64) {
+        let mut filter = BloomFilter::new(self.cells.len(), false_positive_rate);
+        for cell in &self.cells {
+            if let (Some(oem), Some(model)) = (&cell.oem, &cell.model) {
+                filter.insert((oem.trim(), model.trim()));
+            }
+        }
+        self.key_filter = Some(filter);
+    }
+
+    /*
+        Whether some cell has exactly this OEM and model (after trimming). With a key
+        filter most missing models are rejected without scanning the cells.
+
+        Runtime: O(1) for most missing models with a key filter, O(n) otherwise
+     */
+    pub fn contains_model(&self, oem: &str, model: &str) -> bool {
+        let (oem, model) = (oem.trim(), model.trim());
+        if let Some(filter) = &self.key_filter {
+            if !filter.may_contain((oem, model)) {
+                return false;
+            }
+        }
+
+        self.cells.iter().any(|cell| cell.oem.as_deref().map(str::trim) == Some(oem) && cell.model.as_deref().map(str::trim) == Some(model))
+    }
+
+    // False if the key filter rules out any cell having this oem+model join key.
+    fn may_match(&self, on: &[Column], key: &[String]) -> bool {
+        let (Some(filter), [first, second]) = (&self.key_filter, on) else {
+            return true;
+        };
+
+        match (first, second) {
+            (Column::Oem, Column::Model) => filter.may_contain((key[0].as_str(), key[1].as_str())),
+            (Column::Model, Column::Oem) => filter.may_contain((key[1].as_str(), key[0].as_str())),
+            _ => true,
+        }
+    }
+
+    /*
+        Join this dataset with another CSV file, matching rows on the given columns.
+        The other file must have a header naming each `on` column (e.g. "oem", "model");
+        its remaining columns become the supplemental data of the matched rows. When joining on
+        oem and model with a key filter built, rows of the other file that can't match are
+        skipped instead of being kept in memory.
+        A cell with several matches in the other file appears once per match. The key
+        columns of the other file are cleaned as this dataset's cells were (text cleaning,
+        stages and OEM aliases, see set_key_normalizer), before the key filter is checked,
+        and its models go through the same aliases as apply_model_aliases().
+
+        Runtime: O(n + m)
+     */
+    pub fn join(&self, other_csv: &str, on: &[Column], how: JoinKind) -> Result<Vec<JoinedRow<'_>>, CellError> {
+        self.join_matching(other_csv, on, how, JoinMatch::Exact)
+    }
+
+    /*
+        Like join, choosing how rows match. A fuzzy join needs a launch_announced column in
+        the other file: a cell without an exact match takes the rows whose loose key equals
+        its own and whose year is closest to its launch_announced, at most one year off. The
+        key filter is not used, as it only knows exact keys.
+
+        Runtime: O(n + m)
+     */
+    pub fn join_matching(&self, other_csv: &str, on: &[Column], how: JoinKind, matching: JoinMatch) -> Result<Vec<JoinedRow<'_>>, CellError> {
+        let file = File::open(other_csv)?;
+        let mut reader = csv::Reader::from_reader(file);
+        let headers = reader.headers()?.clone();
+
+        let oem_index = headers.iter().position(|header| header.trim() == Column::Oem.name());
+        let mut key_indexes = Vec::new();
+        for column in on {
+            match headers.iter().position(|header| header.trim() == column.name()) {
+                Some(index) => key_indexes.push(index),
+                None => return Err(CellError::MissingColumn { column: *column }),
+            }
+        }
+
+        let year_index = match matching {
+            JoinMatch::Exact => None,
+            JoinMatch::Fuzzy => match headers.iter().position(|header| header.trim() == Column::LaunchAnnounced.name()) {
+                Some(index) => Some(index),
+                None => return Err(CellError::MissingColumn { column: Column::LaunchAnnounced }),
+            },
+        };
+
+        let mut normalizer = self.key_normalizer.fork();
+        let mut rows = Vec::new();
+        for result in reader.records() {
+            let record = result?;
+            let oem = oem_index.and_then(|index| record.get(index)).and_then(|raw| normalizer.normalize(Column::Oem, raw)).unwrap_or_default();
+            let key: Vec<String> = on
+                .iter()
+                .zip(&key_indexes)
+                .map(|(&column, &index)| {
+                    let value = normalizer.normalize(column, record.get(index).unwrap_or_default()).unwrap_or_default();
+                    if column == Column::Model {
+                        self.model_aliases.canonical(&oem, &value).trim().to_string()
+                    } else {
+                        value.trim().to_string()
+                    }
+                })
+                .collect();
+            if year_index.is_none() && !self.may_match(on, &key) {
+                continue;
+            }
+            let values: Values = headers
+                .iter()
+                .zip(record.iter())
+                .enumerate()
+                .filter(|(index, _)| !key_indexes.contains(index))
+                .map(|(_, (header, value))| (header.trim().to_string(), value.to_string()))
+                .collect();
+
+            let year = year_index.and_then(|index| record.get(index)).and_then(first_year);
+            rows.push(OtherRow { key, year, values });
+        }
+
+        Ok(self.match_rows(on, how, rows))
+    }
+
+    /*
+        Like join_matching, with the cells of another loaded dataset as the other side, so
+        they keep the cleaning, aliases and tags they were loaded with and a dataset that
+        was never written to a file can be joined. The other side's columns and extras
+        that are not join keys become the supplemental data, "" where a value is missing.
+
+        Runtime: O(n + m)
+     */
+    pub fn join_with<'a>(&'a self, other: &Dataset, on: &[Column], how: JoinKind, matching: JoinMatch) -> Vec<JoinedRow<'a>> {
+        let rows = other
+            .cells
+            .iter()
+            .map(|cell| {
+                let key = on.iter().map(|&column| cell.text(column).unwrap_or_default().trim().to_string()).collect();
+                let mut values: Values = Column::ALL
+                    .into_iter()
+                    .filter(|column| !on.contains(column))
+                    .map(|column| (column.name().to_string(), cell.text(column).unwrap_or_default()))
+                    .collect();
+                values.extend(cell.extras.iter().map(|(header, value)| (header.clone(), value.clone())));
+                let year = cell.launch_announced.filter(|_| matching == JoinMatch::Fuzzy);
+                OtherRow { key, year, values }
+            })
+            .filter(|row| matching == JoinMatch::Fuzzy || self.may_match(on, &row.key))
+            .collect();
+
+        self.match_rows(on, how, rows)
+    }
+
+    /*
+        Match every cell to the other side's rows: the rows with an equal key, or else, for
+        rows given a year, those with an equal loose key closest to its launch_announced.
+
+        Runtime: O(n + m)
+     */
+    fn match_rows<'a>(&'a self, on: &[Column], how: JoinKind, rows: Vec<OtherRow>) -> Vec<JoinedRow<'a>> {
+        let mut other_rows: HashMap<Vec<String>, Vec<Values>> = HashMap::new();
+        // loose key -> (launch year, values) of the rows with a year, for a fuzzy join
+        let mut loose_rows: HashMap<Vec<String>, Vec<(u32, Values)>> = HashMap::new();
+        for OtherRow { key, year, values } in rows {
+            if let Some(year) = year {
+                loose_rows.entry(key.iter().map(|value| loose_key(value)).collect()).or_default().push((year, values.clone()));
+            }
+            other_rows.entry(key).or_default().push(values);
+        }
+
+        let mut joined = Vec::new();
+
+        for cell in &self.cells {
+            let key: Option<Vec<String>> = on
+                .iter()
+                .map(|&column| cell.text(column).map(|value| value.trim().to_string()))
+                .collect();
+
+            let exact = key.as_ref().and_then(|key| other_rows.get(key)).map(|matches| matches.iter().map(|values| (values, MatchConfidence::Exact)).collect());
+            let matches: Vec<(&Values, MatchConfidence)> = exact.unwrap_or_else(|| {
+                let (Some(key), Some(year)) = (&key, cell.launch_announced) else {
+                    return Vec::new();
+                };
+                let candidates = loose_rows.get(&key.iter().map(|value| loose_key(value)).collect::<Vec<_>>()).map_or(&[][..], Vec::as_slice);
+                let closest = candidates.iter().map(|(other, _)| other.abs_diff(year)).filter(|&apart| apart <= 1).min();
+                let confidence = if closest == Some(0) { MatchConfidence::SameYear } else { MatchConfidence::NearYear };
+                candidates.iter().filter(|(other, _)| Some(other.abs_diff(year)) == closest).map(|(_, values)| (values, confidence)).collect()
+            });
+
+            if matches.is_empty() && how == JoinKind::Left {
+                joined.push(JoinedRow { cell, supplemental: None, confidence: None });
+            }
+            for (values, confidence) in matches {
+                joined.push(JoinedRow { cell, supplemental: Some(values.clone()), confidence: Some(confidence) });
+            }
+        }
+
+        joined
+    }
+}
+
+// The first four-digit run of a launch_announced, e.g. 2019 in "2019, September".
+fn first_year(text: &str) -> Option<u32> {
+    text.split(|c: char| !c.is_ascii_digit()).find(|digits| digits.len() == 4).and_then(|digits| digits.parse().ok())
+}
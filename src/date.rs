@@ -0,0 +1,92 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+// Month names in order, to place "2019, September" in its month and quarter.
+const MONTHS: [&str; 12] = ["january", "february", "march", "april", "may", "june", "july", "august", "september", "october", "november", "december"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+// When a phone was announced, as precisely as launch_announced says. Dates sort by year, then
+// month, then quarter, so a date with only a year comes before the dates within that year.
+pub struct LaunchDate {
+    pub year: u32,
+    // 1 to 12.
+    pub month: Option<u8>,
+    // 1 to 4, also known when only the month is.
+    pub quarter: Option<u8>,
+}
+
+impl LaunchDate {
+    // A date known only to the year.
+    pub fn of_year(year: u32) -> LaunchDate {
+        LaunchDate { year, month: None, quarter: None }
+    }
+
+    /*
+        Parse a launch_announced such as "2019, February", "2004, Q1", "Q3 2020" or "2003".
+        Only the announcement counts, so in "2010, January. Released 2010, March" the month
+        is January. Text without a 4-digit year, e.g. "Not officially announced yet", gives
+        None.
+
+        Runtime: O(n)
+     */
+    pub fn parse(launch_announced: &str) -> Option<LaunchDate> {
+        let lower = launch_announced.to_lowercase();
+        let announced = lower.split(". ").next().unwrap_or_default();
+        let year = announced.split(|c: char| !c.is_ascii_digit()).find(|digits| digits.len() == 4)?.parse().ok()?;
+        let month = month(announced);
+
+        Some(LaunchDate { year, month, quarter: quarter(announced) })
+    }
+
+    /*
+        The release date a text gives after "Released", as in "Available. Released 2019,
+        September" or "2010, January. Released 2010, March". None without one.
+
+        Runtime: O(n)
+     */
+    pub fn released(text: &str) -> Option<LaunchDate> {
+        let at = text.to_lowercase().find("released")?;
+        LaunchDate::parse(&text[at..])
+    }
+
+    /*
+        The months from this date to a later one (negative if it is earlier): by month when
+        both dates know theirs, by quarter when both know that, otherwise by year.
+
+        Runtime: O(1)
+     */
+    pub fn months_until(&self, later: &LaunchDate) -> i32 {
+        let years = (later.year as i32 - self.year as i32) * 12;
+        match (self.month, later.month, self.quarter, later.quarter) {
+            (Some(from), Some(to), _, _) => years + to as i32 - from as i32,
+            (_, _, Some(from), Some(to)) => years + 3 * (to as i32 - from as i32),
+            _ => years,
+        }
+    }
+}
+
+// The month a lowercase text names, 1 to 12.
+fn month(lower: &str) -> Option<u8> {
+    MONTHS.iter().position(|month| lower.contains(month)).map(|month| month as u8 + 1)
+}
+
+// The quarter of a lowercase text naming a month ("september") or a quarter ("q3").
+pub(crate) fn quarter(lower: &str) -> Option<u8> {
+    if let Some(month) = month(lower) {
+        return Some((month - 1) / 3 + 1);
+    }
+    lower
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .find_map(|word| word.strip_prefix('q')?.parse::<u8>().ok())
+        .filter(|quarter| (1..=4).contains(quarter))
+}
+
+impl fmt::Display for LaunchDate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (self.month, self.quarter) {
+            (Some(month), _) => write!(f, "{}-{:02}", self.year, month),
+            (None, Some(quarter)) => write!(f, "{} Q{}", self.year, quarter),
+            (None, None) => write!(f, "{}", self.year),
+        }
+    }
+}
@@ -0,0 +1,62 @@
+use crate::resolution;
+
+// Millimetres in an inch, to compare display sizes with body dimensions.
+pub const MM_PER_INCH: f32 = 25.4;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+// The size of a phone's body in millimetres.
+pub struct Dimensions {
+    pub height: f32,
+    pub width: f32,
+    pub depth: f32,
+}
+
+impl Dimensions {
+    /*
+        Parse a body_dimensions such as "159.8 x 76.1 x 8.1 mm (6.29 x 3.00 x 0.32 in)" or
+        "140 x 50 x 25 mm, 150 cc". Foldables listing "Unfolded: ... Folded: ..." use the
+        folded size, which is how thick the phone is in a pocket. None unless all three sizes
+        are plain numbers.
+
+        Runtime: O(1)
+     */
+    pub fn parse(body_dimensions: &str) -> Option<Dimensions> {
+        let text = match body_dimensions.find("Folded:") {
+            Some(start) => &body_dimensions[start + "Folded:".len()..],
+            None => body_dimensions,
+        };
+        let millimetres = &text[..text.find("mm")?];
+
+        let sizes: Vec<f32> = millimetres.split('x').map(|size| size.trim().trim_end_matches("mm").trim().parse().ok()).collect::<Option<_>>()?;
+        match sizes[..] {
+            [height, width, depth] => Some(Dimensions { height, width, depth }),
+            _ => None,
+        }
+    }
+
+    /*
+        The share of the front taken by a screen with the given diagonal and aspect ratio
+        (long side over short side), assuming a rectangular screen on a rectangular body.
+        None if the estimate is not between 0 and 1, which means the sizes disagree.
+
+        Runtime: O(1)
+     */
+    pub fn screen_to_body_ratio(&self, diagonal_inches: f32, aspect_ratio: f32) -> Option<f32> {
+        let diagonal = diagonal_inches * MM_PER_INCH;
+        let screen_area = diagonal * diagonal * aspect_ratio / (1.0 + aspect_ratio * aspect_ratio);
+        let ratio = screen_area / (self.height * self.width);
+
+        if ratio > 0.0 && ratio <= 1.0 { Some(ratio) } else { None }
+    }
+}
+
+/*
+    The aspect ratio (long side over short side) of a display_resolution such as "1080 x 2340
+    pixels, 19.5:9 ratio (~403 ppi density)".
+
+    Runtime: O(1)
+ */
+pub fn aspect_ratio(display_resolution: &str) -> Option<f32> {
+    let (first, second) = resolution::pixels(display_resolution)?;
+    Some(first.max(second) as f32 / first.min(second) as f32)
+}
@@ -0,0 +1,87 @@
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+// The display technology family, normalized from the free-text display_type. Ordered
+// newest technology first.
+pub enum DisplayFamily {
+    Amoled,
+    Oled,
+    Lcd,
+    Monochrome,
+    Other,
+}
+
+impl DisplayFamily {
+    /*
+        Classify a display_type such as "Super AMOLED capacitive touchscreen" or "TFT, 65K
+        colors". AMOLED variants (Super, Fluid, ...) are checked before other OLEDs; TFT,
+        STN, IPS and similar panels count as LCD. Values that only give a colour count are
+        Other.
+
+        Runtime: O(1)
+     */
+    pub fn from_display_type(display_type: &str) -> DisplayFamily {
+        let lower = display_type.to_lowercase();
+        let technology = lower.split(',').next().unwrap_or_default();
+        let has_word = |words: &[&str]| technology.split(|c: char| !c.is_ascii_alphanumeric()).any(|word| words.contains(&word));
+
+        if technology.contains("amoled") {
+            DisplayFamily::Amoled
+        } else if technology.contains("oled") {
+            DisplayFamily::Oled
+        } else if has_word(&["lcd", "ips", "tft", "tfd", "stn", "cstn", "csn", "ufb", "cgs", "ltps"]) {
+            DisplayFamily::Lcd
+        } else if has_word(&["monochrome", "grayscale", "greyscale", "alphanumeric"]) {
+            DisplayFamily::Monochrome
+        } else {
+            DisplayFamily::Other
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            DisplayFamily::Amoled => "AMOLED",
+            DisplayFamily::Oled => "OLED",
+            DisplayFamily::Lcd => "LCD",
+            DisplayFamily::Monochrome => "Monochrome",
+            DisplayFamily::Other => "Other",
+        }
+    }
+}
+
+impl fmt::Display for DisplayFamily {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/*
+    The size in inches of a second, cover display a foldable lists after its main one, as in
+    "7.6 inches, 180.9 cm2 (~84.9% screen-to-body ratio); 6.2 inches (cover)". None with a
+    single size.
+
+    Runtime: O(n)
+ */
+pub fn cover_display_size(display_size: &str) -> Option<f32> {
+    let words: Vec<&str> = display_size.split(|c: char| c.is_whitespace() || c == ',' || c == ';' || c == '(' || c == ')').filter(|word| !word.is_empty()).collect();
+    words.windows(2).filter(|pair| pair[1].starts_with("inch")).filter_map(|pair| pair[0].parse().ok()).nth(1)
+}
+
+/*
+    The resolution of a cover display, the second "width x height pixels" of a
+    display_resolution such as "1768 x 2208 pixels (~373 ppi density); 832 x 2268 pixels
+    (cover)", written as "832 x 2268 pixels". None with a single resolution.
+
+    Runtime: O(n)
+ */
+pub fn cover_display_resolution(display_resolution: &str) -> Option<String> {
+    let parts: Vec<&str> = display_resolution.split("pixels").collect();
+    parts[..parts.len() - 1]
+        .iter()
+        .filter_map(|before| match before.split_whitespace().collect::<Vec<_>>()[..] {
+            [.., width, "x", height] => Some((width.parse::<u32>().ok()?, height.parse::<u32>().ok()?)),
+            _ => None,
+        })
+        .nth(1)
+        .map(|(width, height)| format!("{} x {} pixels", width, height))
+}
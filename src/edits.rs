@@ -0,0 +1,302 @@
+use crate::column::Column;
+use crate::error::CellError;
+use crate::normalize::Normalizer;
+use crate::tags::Tags;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs::{self, File};
+use std::path::Path;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/*
+    One queued change to the CSV file. Indexes are 0-based data rows and refer to the file
+    as it looks after every earlier edit in the queue.
+ */
+pub enum Edit {
+    Insert { index: usize, values: Vec<(Column, String)> },
+    Modify { index: usize, values: Vec<(Column, String)> },
+    Delete { index: usize },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+// A changed field: (column, value before, value after).
+pub struct FieldChange {
+    pub column: Column,
+    pub before: String,
+    pub after: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+// What one queued edit does to one record.
+pub struct RecordDiff {
+    pub edit: usize,
+    pub kind: &'static str,
+    pub index: usize,
+    pub changes: Vec<FieldChange>,
+}
+
+impl fmt::Display for RecordDiff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "#{} {} row {}", self.edit + 1, self.kind, self.index)?;
+        for change in &self.changes {
+            writeln!(f, "    {}: {:?} -> {:?}", change.column, change.before, change.after)?;
+        }
+        Ok(())
+    }
+}
+
+// The raw header and rows of a CSV file, edited as text so saving keeps the original values.
+pub struct RawTable {
+    pub headers: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+}
+
+impl RawTable {
+    pub fn read(filename: &str) -> Result<RawTable, CellError> {
+        let mut reader = csv::Reader::from_reader(File::open(filename)?);
+        let headers = reader.headers()?.iter().map(|header| header.to_string()).collect();
+        let mut rows = Vec::new();
+
+        for result in reader.records() {
+            rows.push(result?.iter().map(|value| value.to_string()).collect());
+        }
+
+        Ok(RawTable { headers, rows })
+    }
+
+    pub fn write(&self, filename: &str) -> Result<(), CellError> {
+        let mut writer = csv::Writer::from_path(filename)?;
+        writer.write_record(&self.headers)?;
+        for row in &self.rows {
+            writer.write_record(row)?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+
+    fn position(&self, column: Column) -> Result<usize, CellError> {
+        self.headers
+            .iter()
+            .position(|header| header.trim() == column.name())
+            .ok_or(CellError::MissingColumn { column })
+    }
+
+    // The (oem, model) of a row, which tags are attached to.
+    pub fn phone(&self, index: usize) -> Option<(String, String)> {
+        let (oem, model) = (self.position(Column::Oem).ok()?, self.position(Column::Model).ok()?);
+        self.rows.get(index).map(|row| (row[oem].clone(), row[model].clone()))
+    }
+
+    /*
+        Apply one edit and return the fields it changed.
+
+        Runtime: O(n) for insert/delete, O(1) for modify
+     */
+    fn apply(&mut self, edit: &Edit) -> Result<Vec<FieldChange>, CellError> {
+        let mut changes = Vec::new();
+
+        match edit {
+            Edit::Insert { index, values } => {
+                if *index > self.rows.len() {
+                    return Err(CellError::IndexOutOfBounds { index: *index, len: self.rows.len() });
+                }
+                let mut row = vec![String::new(); self.headers.len()];
+                for (column, value) in values {
+                    row[self.position(*column)?] = value.clone();
+                    changes.push(FieldChange { column: *column, before: String::new(), after: value.clone() });
+                }
+                self.rows.insert(*index, row);
+            }
+            Edit::Modify { index, values } => {
+                let positions: Vec<usize> = values.iter().map(|(column, _)| self.position(*column)).collect::<Result<_, _>>()?;
+                let len = self.rows.len();
+                let row = self.rows.get_mut(*index).ok_or(CellError::IndexOutOfBounds { index: *index, len })?;
+                for ((column, value), position) in values.iter().zip(positions) {
+                    if row[position] != *value {
+                        changes.push(FieldChange { column: *column, before: row[position].clone(), after: value.clone() });
+                        row[position] = value.clone();
+                    }
+                }
+            }
+            Edit::Delete { index } => {
+                if *index >= self.rows.len() {
+                    return Err(CellError::IndexOutOfBounds { index: *index, len: self.rows.len() });
+                }
+                let row = self.rows.remove(*index);
+                for (header, value) in self.headers.iter().zip(row) {
+                    if let (Some(column), false) = (Column::from_name(header), value.is_empty()) {
+                        changes.push(FieldChange { column, before: value, after: String::new() });
+                    }
+                }
+            }
+        }
+
+        Ok(changes)
+    }
+}
+
+/*
+    Check that raw text typed for a column will load: after the default cleaning stages a
+    numeric column must hold a number. Blank values are always accepted.
+
+    Runtime: O(1)
+ */
+pub fn validate_raw(column: Column, raw: &str) -> Result<(), String> {
+    let cleaned = Normalizer::default().normalize(column, raw).unwrap_or_default();
+    if raw.trim().is_empty() || raw.trim() == "-" {
+        return Ok(());
+    }
+
+    let valid = match column {
+        Column::LaunchAnnounced => cleaned.parse::<u32>().is_ok(),
+        Column::BodyWeight | Column::DisplaySize => cleaned.parse::<f32>().is_ok(),
+        _ => true,
+    };
+
+    if valid {
+        Ok(())
+    } else {
+        let expected = if column == Column::LaunchAnnounced { "a year" } else { "a number" };
+        Err(format!("{} needs {}, got '{}'", column, expected, raw))
+    }
+}
+
+/*
+    Edits queued against one CSV file, kept in a JSON journal next to it (cells.csv ->
+    cells.csv.pending.json) until they are saved or discarded.
+ */
+pub struct PendingEdits {
+    csv_file: String,
+    edits: Vec<Edit>,
+}
+
+impl PendingEdits {
+    // Path of the journal that holds the pending edits of a CSV file.
+    pub fn journal_path(csv_file: &str) -> String {
+        format!("{}.pending.json", csv_file)
+    }
+
+    // Loads the pending edits of a CSV file; none if there is no journal.
+    pub fn load(csv_file: &str) -> Result<PendingEdits, CellError> {
+        let journal = PendingEdits::journal_path(csv_file);
+        let edits = if Path::new(&journal).exists() { serde_json::from_str(&fs::read_to_string(&journal)?)? } else { Vec::new() };
+
+        Ok(PendingEdits { csv_file: csv_file.to_string(), edits })
+    }
+
+    pub fn edits(&self) -> &[Edit] {
+        &self.edits
+    }
+
+    /*
+        Queue an edit and write the journal. The edit is checked against the file with every
+        earlier edit applied, so a bad index is reported now instead of at save time.
+
+        Runtime: O(n)
+     */
+    pub fn push(&mut self, edit: Edit) -> Result<(), CellError> {
+        let mut table = self.preview()?;
+        table.apply(&edit)?;
+
+        self.edits.push(edit);
+        fs::write(PendingEdits::journal_path(&self.csv_file), serde_json::to_string_pretty(&self.edits)?)?;
+        Ok(())
+    }
+
+    // Remove the most recently queued edit and return it.
+    pub fn undo(&mut self) -> Result<Option<Edit>, CellError> {
+        let edit = self.edits.pop();
+
+        if self.edits.is_empty() {
+            self.discard()?;
+        } else {
+            fs::write(PendingEdits::journal_path(&self.csv_file), serde_json::to_string_pretty(&self.edits)?)?;
+        }
+
+        Ok(edit)
+    }
+
+    // The file as it will look once the edits are saved.
+    pub fn preview(&self) -> Result<RawTable, CellError> {
+        let mut table = RawTable::read(&self.csv_file)?;
+        for edit in &self.edits {
+            table.apply(edit)?;
+        }
+        Ok(table)
+    }
+
+    // (inserted, modified, deleted) edit counts.
+    pub fn summary(&self) -> (usize, usize, usize) {
+        let mut counts = (0, 0, 0);
+
+        for edit in &self.edits {
+            match edit {
+                Edit::Insert { .. } => counts.0 += 1,
+                Edit::Modify { .. } => counts.1 += 1,
+                Edit::Delete { .. } => counts.2 += 1,
+            }
+        }
+
+        counts
+    }
+
+    /*
+        The field-level changes of every queued edit, in queue order.
+
+        Runtime: O(n * e)
+     */
+    pub fn diffs(&self) -> Result<Vec<RecordDiff>, CellError> {
+        let mut table = RawTable::read(&self.csv_file)?;
+        let mut diffs = Vec::new();
+
+        for (number, edit) in self.edits.iter().enumerate() {
+            let (kind, index) = match edit {
+                Edit::Insert { index, .. } => ("insert", *index),
+                Edit::Modify { index, .. } => ("modify", *index),
+                Edit::Delete { index } => ("delete", *index),
+            };
+            let changes = table.apply(edit)?;
+            diffs.push(RecordDiff { edit: number, kind, index, changes });
+        }
+
+        Ok(diffs)
+    }
+
+    /*
+        Write the edited file to disk and clear the queue. A modify that renames a tagged
+        phone moves its tags to the new oem and model, as the normalizer names them.
+
+        Runtime: O(n * e)
+     */
+    pub fn save(&mut self, normalizer: &Normalizer) -> Result<(), CellError> {
+        let mut table = RawTable::read(&self.csv_file)?;
+        let mut tags = Tags::load(&self.csv_file, normalizer)?;
+
+        for edit in &self.edits {
+            let before = match edit {
+                Edit::Modify { index, .. } => table.phone(*index).map(|phone| (*index, phone)),
+                _ => None,
+            };
+            table.apply(edit)?;
+            if let Some((index, (oem, model))) = before {
+                if let Some((new_oem, new_model)) = table.phone(index) {
+                    tags.rename(normalizer, &oem, &model, &new_oem, &new_model);
+                }
+            }
+        }
+
+        table.write(&self.csv_file)?;
+        tags.save(&self.csv_file)?;
+        self.discard()
+    }
+
+    // Drop every queued edit without touching the CSV file.
+    pub fn discard(&mut self) -> Result<(), CellError> {
+        self.edits.clear();
+        let journal = PendingEdits::journal_path(&self.csv_file);
+        if Path::new(&journal).exists() {
+            fs::remove_file(journal)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,260 @@
+use crate::column::Column;
+use crate::error::CellError;
+use crate::Cell;
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+// A source of phone data outside the CSV file, such as a web API. Lookups block; a slow
+// source is asked about several phones at once on threads by enrich_concurrently.
+pub trait LookupSource {
+    /*
+        Returns the values the source knows for a phone, keyed by column.
+        Values are text in the same format as the CSV file. A failure of the source itself
+        is a CellError::external.
+     */
+    fn lookup(&self, oem: &str, model: &str) -> Result<HashMap<Column, String>, CellError>;
+}
+
+// Keeps at least `min_interval` between two calls to the lookup source.
+pub struct RateLimiter {
+    min_interval: Duration,
+    last_call: Option<Instant>,
+}
+
+impl RateLimiter {
+    pub fn new(min_interval: Duration) -> RateLimiter {
+        RateLimiter { min_interval, last_call: None }
+    }
+
+    // Sleeps until the next call is allowed.
+    pub fn wait(&mut self) {
+        if let Some(last_call) = self.last_call {
+            let elapsed = last_call.elapsed();
+            if elapsed < self.min_interval {
+                thread::sleep(self.min_interval - elapsed);
+            }
+        }
+
+        self.last_call = Some(Instant::now());
+    }
+}
+
+/*
+    Lookup responses saved to a CSV file (oem,model,column,value) so a phone is only ever
+    requested once, even across runs.
+ */
+pub struct ResponseCache {
+    path: PathBuf,
+    entries: HashMap<(String, String), HashMap<Column, String>>,
+}
+
+impl ResponseCache {
+    /*
+        Opens the cache file, starting empty if it does not exist yet.
+
+        Runtime: O(n)
+     */
+    pub fn open(path: &str) -> Result<ResponseCache, CellError> {
+        let mut entries: HashMap<(String, String), HashMap<Column, String>> = HashMap::new();
+
+        if Path::new(path).exists() {
+            let mut reader = csv::Reader::from_reader(File::open(path)?);
+            for result in reader.records() {
+                let record = result?;
+                let key = (record.get(0).unwrap_or_default().to_string(), record.get(1).unwrap_or_default().to_string());
+                let entry = entries.entry(key).or_default();
+                if let Some(column) = Column::from_name(record.get(2).unwrap_or_default()) {
+                    entry.insert(column, record.get(3).unwrap_or_default().to_string());
+                }
+            }
+        }
+
+        Ok(ResponseCache { path: PathBuf::from(path), entries })
+    }
+
+    pub fn get(&self, oem: &str, model: &str) -> Option<&HashMap<Column, String>> {
+        self.entries.get(&(oem.to_string(), model.to_string()))
+    }
+
+    pub fn insert(&mut self, oem: &str, model: &str, values: HashMap<Column, String>) {
+        self.entries.insert((oem.to_string(), model.to_string()), values);
+    }
+
+    /*
+        Writes every cached response back to the cache file. A phone the source knew
+        nothing about is kept as a row with an empty column so it is not requested again.
+
+        Runtime: O(n)
+     */
+    pub fn save(&self) -> Result<(), CellError> {
+        let mut writer = csv::Writer::from_path(&self.path)?;
+        writer.write_record(["oem", "model", "column", "value"])?;
+
+        for ((oem, model), values) in &self.entries {
+            if values.is_empty() {
+                writer.write_record([oem.as_str(), model.as_str(), "", ""])?;
+            }
+            for (column, value) in values {
+                writer.write_record([oem.as_str(), model.as_str(), column.name(), value.as_str()])?;
+            }
+        }
+
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+// Fills missing fields of selected cells from a lookup source.
+pub struct Enricher<S: LookupSource> {
+    source: S,
+    limiter: RateLimiter,
+    cache: ResponseCache,
+}
+
+impl<S: LookupSource> Enricher<S> {
+    pub fn new(source: S, limiter: RateLimiter, cache: ResponseCache) -> Enricher<S> {
+        Enricher { source, limiter, cache }
+    }
+
+    pub fn source(&self) -> &S {
+        &self.source
+    }
+
+    /*
+        For every cell the `select` predicate accepts and that has missing fields, look the
+        phone up (cache first, then the rate-limited source) and fill in only the missing
+        fields. The cache is saved at the end, and also when a lookup fails, so the responses
+        received before it are kept, before its error is returned. Returns the number of
+        fields filled. Lookups are made one at a time on the calling thread; see
+        enrich_concurrently.
+
+        Runtime: O(n) lookups
+     */
+    pub fn enrich<F>(&mut self, cells: &mut [Cell], select: F) -> Result<usize, CellError>
+    where
+        F: Fn(&Cell) -> bool,
+    {
+        for (oem, model) in self.uncached(cells, &select) {
+            self.limiter.wait();
+            match self.source.lookup(&oem, &model) {
+                Ok(values) => self.cache.insert(&oem, &model, values),
+                Err(error) => {
+                    self.cache.save()?;
+                    return Err(error);
+                }
+            }
+        }
+
+        self.cache.save()?;
+        Ok(self.fill(cells, &select))
+    }
+
+    /*
+        Like enrich, with the lookups spread over `workers` threads so a slow source is
+        asked about several phones at once. The rate limiter is shared: a worker waits its
+        turn before each call, so calls still start at least `min_interval` apart, and each
+        phone is asked for once. After the first failed lookup no new ones start; the
+        responses already received are cached and saved, and its error is returned.
+
+        Runtime: O(n / w) lookup latencies for w workers, or n lookups at the rate limit
+     */
+    pub fn enrich_concurrently<F>(&mut self, cells: &mut [Cell], select: F, workers: usize) -> Result<usize, CellError>
+    where
+        S: Sync,
+        F: Fn(&Cell) -> bool,
+    {
+        let phones = self.uncached(cells, &select);
+        let next = AtomicUsize::new(0);
+        let failed = AtomicBool::new(false);
+        let limiter = Mutex::new(&mut self.limiter);
+        let source = &self.source;
+
+        let results: Vec<Vec<LookupResult>> = thread::scope(|scope| {
+            let workers: Vec<_> = (0..workers.clamp(1, phones.len().max(1)))
+                .map(|_| {
+                    scope.spawn(|| {
+                        let mut results = Vec::new();
+                        while !failed.load(Ordering::Relaxed) {
+                            let Some((oem, model)) = phones.get(next.fetch_add(1, Ordering::Relaxed)) else {
+                                break;
+                            };
+                            limiter.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).wait();
+                            let result = source.lookup(oem, model);
+                            failed.fetch_or(result.is_err(), Ordering::Relaxed);
+                            results.push((oem.clone(), model.clone(), result));
+                        }
+                        results
+                    })
+                })
+                .collect();
+            workers.into_iter().map(|worker| worker.join().expect("an enrichment worker panicked")).collect()
+        });
+
+        let mut error = None;
+        for (oem, model, result) in results.into_iter().flatten() {
+            match result {
+                Ok(values) => self.cache.insert(&oem, &model, values),
+                Err(failed) => error = error.or(Some(failed)),
+            }
+        }
+        self.cache.save()?;
+        if let Some(error) = error {
+            return Err(error);
+        }
+        Ok(self.fill(cells, &select))
+    }
+
+    // The (oem, model) of the selected cells with missing fields that the cache has no
+    // response for, each once, in cell order.
+    fn uncached<F: Fn(&Cell) -> bool>(&self, cells: &[Cell], select: &F) -> Vec<(String, String)> {
+        let mut phones = Vec::new();
+        let mut seen = HashSet::new();
+
+        for cell in cells.iter().filter(|cell| select(cell)) {
+            if let (Some(oem), Some(model)) = (&cell.oem, &cell.model) {
+                let phone = (oem.to_string(), model.to_string());
+                if missing(cell).next().is_some() && self.cache.get(oem, model).is_none() && seen.insert(phone.clone()) {
+                    phones.push(phone);
+                }
+            }
+        }
+
+        phones
+    }
+
+    // Fills the missing fields of the selected cells from the cache, returning how many.
+    fn fill<F: Fn(&Cell) -> bool>(&self, cells: &mut [Cell], select: &F) -> usize {
+        let mut filled = 0;
+
+        for cell in cells.iter_mut().filter(|cell| select(cell)) {
+            let (Some(oem), Some(model)) = (cell.oem.clone(), cell.model.clone()) else {
+                continue;
+            };
+            let Some(values) = self.cache.get(&oem, &model) else {
+                continue;
+            };
+            for column in missing(cell).collect::<Vec<_>>() {
+                if let Some(value) = values.get(&column) {
+                    if cell.set_text(column, value).is_ok() {
+                        filled += 1;
+                    }
+                }
+            }
+        }
+
+        filled
+    }
+}
+
+// A worker's response for one phone, or the error the source gave.
+type LookupResult = (String, String, Result<HashMap<Column, String>, CellError>);
+
+// The columns of a cell without a value.
+fn missing(cell: &Cell) -> impl Iterator<Item = Column> + '_ {
+    Column::ALL.into_iter().filter(|&column| cell.text(column).is_none())
+}
@@ -0,0 +1,124 @@
+use crate::column::Column;
+use crate::parse::ParseReport;
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::time::Duration;
+
+#[derive(Debug)]
+/// What can go wrong reading, writing or changing cells.
+pub enum CellError {
+    /// A file could not be opened, read or written.
+    Io(io::Error),
+    /// A CSV file is malformed; the csv error says at which record and line.
+    Csv(csv::Error),
+    /// A JSON file of cells is malformed or could not be written.
+    Json(serde_json::Error),
+    /// A value is not valid for its column, e.g. a body_weight that is not a number. The row
+    /// is given when the value came from a file.
+    Parse { row: Option<usize>, column: Column, value: String },
+    /// A row index past the end of the cells.
+    IndexOutOfBounds { index: usize, len: usize },
+    /// A CSV file has no header for one of the columns.
+    MissingColumn { column: Column },
+    /// A field was asked for by a name that is not one of the columns.
+    UnknownColumn { name: String },
+    /// A value is not valid for a field of a record other than a cell, or for a setting or
+    /// a command, e.g. a filter condition without an operator.
+    InvalidField { row: Option<usize>, field: &'static str, value: String },
+    /// A CSV file has no header for one of the fields of a record other than a cell.
+    MissingField { field: &'static str },
+    /// A cell was built without a column every cell needs.
+    Required { column: Column },
+    /// A strict load found numeric values that are not numbers; the report lists them all.
+    Invalid(ParseReport),
+    /// A query was cancelled before it finished.
+    Cancelled,
+    /// A query ran past its timeout.
+    TimedOut { after: Duration },
+    /// Something was asked for by a name there is none of, e.g. a preset or a dataset.
+    Unknown { kind: &'static str, name: String },
+    /// A TOML file, such as the config or a schema, is malformed.
+    Toml { path: String, error: toml::de::Error },
+    /// A config or schema could not be written as TOML.
+    TomlWrite(toml::ser::Error),
+    /// An import mapped no header to these columns.
+    Unmapped { columns: Vec<Column> },
+    /// Something was run without a setting it needs, e.g. a pipeline without a source.
+    Unset { what: &'static str },
+    /// A source outside the crate failed, e.g. the lookup source of an enrichment.
+    External(Box<dyn Error + Send + Sync>),
+}
+
+impl fmt::Display for CellError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CellError::Io(error) => write!(f, "{}", error),
+            CellError::Csv(error) => write!(f, "invalid CSV: {}", error),
+            CellError::Json(error) => write!(f, "invalid JSON: {}", error),
+            CellError::Parse { row: Some(row), column, value } => write!(f, "row {}: invalid {} '{}'", row, column, value),
+            CellError::Parse { row: None, column, value } => write!(f, "invalid {} '{}'", column, value),
+            CellError::IndexOutOfBounds { index, len } => write!(f, "row {} is out of bounds ({} rows)", index, len),
+            CellError::MissingColumn { column } => write!(f, "the CSV file has no '{}' column", column),
+            CellError::InvalidField { row: Some(row), field, value } => write!(f, "row {}: invalid {} '{}'", row, field, value),
+            CellError::InvalidField { row: None, field, value } => write!(f, "invalid {} '{}'", field, value),
+            CellError::MissingField { field } => write!(f, "the CSV file has no '{}' column", field),
+            CellError::UnknownColumn { name } => write!(f, "unknown column '{}'", name),
+            CellError::Required { column } => write!(f, "a cell needs a {}", column),
+            CellError::Invalid(report) => write!(f, "{}", report.to_string().trim_end()),
+            CellError::Cancelled => write!(f, "the query was cancelled"),
+            CellError::TimedOut { after } => write!(f, "the query timed out after {:?}", after),
+            CellError::Unknown { kind, name } => write!(f, "unknown {} '{}'", kind, name),
+            CellError::Toml { path, error } => write!(f, "{}: {}", path, error.to_string().trim_end()),
+            CellError::TomlWrite(error) => write!(f, "could not write TOML: {}", error),
+            CellError::Unmapped { columns } => write!(f, "no header was mapped to {}", columns.iter().map(Column::name).collect::<Vec<_>>().join(", ")),
+            CellError::Unset { what } => write!(f, "no {} was set", what),
+            CellError::External(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl CellError {
+    // The error of a source outside the crate, e.g. `CellError::external("the API is down")`.
+    pub fn external(error: impl Into<Box<dyn Error + Send + Sync>>) -> CellError {
+        CellError::External(error.into())
+    }
+}
+
+impl Error for CellError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            CellError::Io(error) => Some(error),
+            CellError::Csv(error) => Some(error),
+            CellError::Json(error) => Some(error),
+            CellError::Toml { error, .. } => Some(error),
+            CellError::TomlWrite(error) => Some(error),
+            CellError::External(error) => Some(&**error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for CellError {
+    fn from(error: io::Error) -> CellError {
+        CellError::Io(error)
+    }
+}
+
+impl From<csv::Error> for CellError {
+    fn from(error: csv::Error) -> CellError {
+        CellError::Csv(error)
+    }
+}
+
+impl From<serde_json::Error> for CellError {
+    fn from(error: serde_json::Error) -> CellError {
+        CellError::Json(error)
+    }
+}
+
+impl From<toml::ser::Error> for CellError {
+    fn from(error: toml::ser::Error) -> CellError {
+        CellError::TomlWrite(error)
+    }
+}
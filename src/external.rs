@@ -0,0 +1,404 @@
+use crate::error::CellError;
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BinaryHeap, HashMap};
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::mem::size_of;
+use std::path::PathBuf;
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering as AtomicOrdering};
+
+// Most runs merged at once, so a tight budget that spills many runs stays well under the
+// limit of open files. More runs are first merged in passes of this many into longer runs.
+pub const MERGE_FAN_IN: usize = 64;
+
+// Numbers the spill files of this process so that several spills never share a name.
+static NEXT_RUN: AtomicUsize = AtomicUsize::new(0);
+
+// A new spill file path in the system temp directory.
+fn run_path(kind: &str) -> PathBuf {
+    let number = NEXT_RUN.fetch_add(1, AtomicOrdering::Relaxed);
+    std::env::temp_dir().join(format!("cells-{}-{}-{}.run", kind, process::id(), number))
+}
+
+/*
+    Parse a memory size such as 512K, 64M or 2G (powers of 1024), or a plain number of
+    bytes. Zero, and sizes that don't fit in a usize, are errors.
+
+    Runtime: O(1)
+ */
+pub fn parse_bytes(text: &str) -> Result<usize, CellError> {
+    let text = text.trim();
+    let (number, multiplier) = match text.chars().last().map(|c| c.to_ascii_uppercase()) {
+        Some('K') => (&text[..text.len() - 1], 1 << 10),
+        Some('M') => (&text[..text.len() - 1], 1 << 20),
+        Some('G') => (&text[..text.len() - 1], 1 << 30),
+        _ => (text, 1),
+    };
+
+    let invalid = || CellError::InvalidField { row: None, field: "memory size (e.g. 64M)", value: text.to_string() };
+    let bytes: usize = number.trim().parse().map_err(|_| invalid())?;
+    match bytes.checked_mul(multiplier) {
+        Some(0) | None => Err(invalid()),
+        Some(bytes) => Ok(bytes),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+// An f32 ordered with total_cmp, so it can sit in a BinaryHeap.
+struct Ordered(f32);
+
+impl Eq for Ordered {}
+
+impl PartialOrd for Ordered {
+    fn partial_cmp(&self, other: &Ordered) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Ordered {
+    fn cmp(&self, other: &Ordered) -> Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+// Reads the next little-endian f32 of a run, or None at its end.
+fn read_value(reader: &mut BufReader<File>) -> Result<Option<f32>, CellError> {
+    let mut bytes = [0; 4];
+    match reader.read_exact(&mut bytes) {
+        Ok(()) => Ok(Some(f32::from_le_bytes(bytes))),
+        Err(error) if error.kind() == std::io::ErrorKind::UnexpectedEof => Ok(None),
+        Err(error) => Err(error.into()),
+    }
+}
+
+/*
+    K-way merge of sorted runs of f32s, calling `visit` with each value in order until it
+    returns false. Opens every run given, so callers keep to MERGE_FAN_IN.
+
+    Runtime: O(n log r)
+ */
+fn merge_values(runs: &[PathBuf], mut visit: impl FnMut(f32) -> Result<bool, CellError>) -> Result<(), CellError> {
+    let mut readers: Vec<BufReader<File>> = runs.iter().map(|path| File::open(path).map(BufReader::new)).collect::<Result<_, _>>()?;
+    let mut heap = BinaryHeap::new();
+    for (run, reader) in readers.iter_mut().enumerate() {
+        if let Some(value) = read_value(reader)? {
+            heap.push(Reverse((Ordered(value), run)));
+        }
+    }
+
+    while let Some(Reverse((Ordered(value), run))) = heap.pop() {
+        if !visit(value)? {
+            break;
+        }
+        if let Some(value) = read_value(&mut readers[run])? {
+            heap.push(Reverse((Ordered(value), run)));
+        }
+    }
+
+    Ok(())
+}
+
+/*
+    Merge the oldest runs, MERGE_FAN_IN at a time, into longer runs until at most
+    MERGE_FAN_IN are left, removing the merged files. `merge` writes the merge of some runs
+    to a new file.
+
+    Runtime: O(n log r) over all passes
+ */
+fn compact(runs: &mut Vec<PathBuf>, kind: &str, merge: impl Fn(&[PathBuf], &PathBuf) -> Result<(), CellError>) -> Result<(), CellError> {
+    while runs.len() > MERGE_FAN_IN {
+        let path = run_path(kind);
+        let merged: Vec<PathBuf> = runs.drain(..MERGE_FAN_IN).collect();
+        // Pushed first, so Drop removes it even if the merge fails part way.
+        runs.push(path.clone());
+        let result = merge(&merged, &path);
+        for path in &merged {
+            let _ = fs::remove_file(path);
+        }
+        result?;
+    }
+
+    Ok(())
+}
+
+/*
+    Quantiles of a stream of numbers using at most about `max_memory` bytes: values are kept
+    in memory until the budget is full, then sorted and spilled to a temp file. The quantiles
+    are read off a k-way merge of the spilled runs, merged MERGE_FAN_IN at a time when there
+    are more. Spill files are removed on drop.
+ */
+pub struct ExternalSorter {
+    max_values: usize,
+    buffer: Vec<f32>,
+    runs: Vec<PathBuf>,
+    count: u64,
+}
+
+impl ExternalSorter {
+    pub fn new(max_memory: usize) -> ExternalSorter {
+        ExternalSorter { max_values: (max_memory / size_of::<f32>()).max(1), buffer: Vec::new(), runs: Vec::new(), count: 0 }
+    }
+
+    // Number of values pushed so far.
+    pub fn len(&self) -> u64 {
+        self.count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    // Number of runs spilled to disk so far.
+    pub fn spilled_runs(&self) -> usize {
+        self.runs.len()
+    }
+
+    pub fn push(&mut self, value: f32) -> Result<(), CellError> {
+        self.buffer.push(value);
+        self.count += 1;
+
+        if self.buffer.len() >= self.max_values {
+            self.spill()?;
+        }
+        Ok(())
+    }
+
+    fn spill(&mut self) -> Result<(), CellError> {
+        self.buffer.sort_by(f32::total_cmp);
+
+        let path = run_path("sort");
+        let mut writer = BufWriter::new(File::create(&path)?);
+        for value in &self.buffer {
+            writer.write_all(&value.to_le_bytes())?;
+        }
+        writer.flush()?;
+
+        self.runs.push(path);
+        self.buffer.clear();
+        Ok(())
+    }
+
+    /*
+        The q-quantiles (0 <= q <= 1) in the order asked for, all found in one merge pass.
+        Each interpolates between the two nearest ranks, so q = 0.5 gives the usual median.
+        None if no values were pushed.
+
+        Runtime: O(n log r) once values are spilled, O(n log n) otherwise
+     */
+    pub fn quantiles(&mut self, qs: &[f32]) -> Result<Vec<Option<f32>>, CellError> {
+        if self.count == 0 {
+            return Ok(vec![None; qs.len()]);
+        }
+
+        // Positions of the two ranks every quantile is interpolated from.
+        let last = (self.count - 1) as f64;
+        let positions: Vec<(u64, u64, f32)> = qs
+            .iter()
+            .map(|&q| {
+                let rank = q.clamp(0.0, 1.0) as f64 * last;
+                (rank.floor() as u64, rank.ceil() as u64, (rank - rank.floor()) as f32)
+            })
+            .collect();
+        let mut wanted: Vec<u64> = positions.iter().flat_map(|&(low, high, _)| [low, high]).collect();
+        wanted.sort();
+        wanted.dedup();
+
+        let mut found: HashMap<u64, f32> = HashMap::new();
+        if self.runs.is_empty() {
+            self.buffer.sort_by(f32::total_cmp);
+            for &index in &wanted {
+                found.insert(index, self.buffer[index as usize]);
+            }
+        } else {
+            if !self.buffer.is_empty() {
+                self.spill()?;
+            }
+            self.merge(&wanted, &mut found)?;
+        }
+
+        Ok(positions.iter().map(|&(low, high, fraction)| Some(found[&low] + (found[&high] - found[&low]) * fraction)).collect())
+    }
+
+    // Walks the merged runs in order, remembering the values at the wanted positions.
+    fn merge(&mut self, wanted: &[u64], found: &mut HashMap<u64, f32>) -> Result<(), CellError> {
+        compact(&mut self.runs, "sort", |runs, path| {
+            let mut writer = BufWriter::new(File::create(path)?);
+            merge_values(runs, |value| {
+                writer.write_all(&value.to_le_bytes())?;
+                Ok(true)
+            })?;
+            writer.flush()?;
+            Ok(())
+        })?;
+
+        let mut position = 0;
+        let mut next = wanted.iter().peekable();
+        merge_values(&self.runs, |value| {
+            if next.peek() == Some(&&position) {
+                found.insert(position, value);
+                next.next();
+            }
+            position += 1;
+            Ok(next.peek().is_some())
+        })
+    }
+}
+
+impl Drop for ExternalSorter {
+    fn drop(&mut self) {
+        for path in &self.runs {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+// Rough bytes one group-by entry takes in the in-memory map, on top of its key.
+const ENTRY_OVERHEAD: usize = size_of::<String>() + size_of::<usize>() + 16;
+
+/*
+    Counts per key using at most about `max_memory` bytes: counts are kept in a map until it
+    is full, then written sorted by key to a temp file and the map starts over. finish()
+    merges the runs, MERGE_FAN_IN at a time when there are more, adding up the counts of a
+    key that was spilled more than once.
+ */
+pub struct SpillGroupBy {
+    max_memory: usize,
+    memory: usize,
+    counts: HashMap<String, usize>,
+    runs: Vec<PathBuf>,
+}
+
+impl SpillGroupBy {
+    pub fn new(max_memory: usize) -> SpillGroupBy {
+        SpillGroupBy { max_memory, memory: 0, counts: HashMap::new(), runs: Vec::new() }
+    }
+
+    // Number of runs spilled to disk so far.
+    pub fn spilled_runs(&self) -> usize {
+        self.runs.len()
+    }
+
+    pub fn add(&mut self, key: &str) -> Result<(), CellError> {
+        match self.counts.get_mut(key) {
+            Some(count) => *count += 1,
+            None => {
+                self.counts.insert(key.to_string(), 1);
+                self.memory += key.len() + ENTRY_OVERHEAD;
+            }
+        }
+
+        if self.memory >= self.max_memory {
+            self.spill()?;
+        }
+        Ok(())
+    }
+
+    fn spill(&mut self) -> Result<(), CellError> {
+        let mut entries: Vec<(String, usize)> = self.counts.drain().collect();
+        entries.sort();
+
+        let path = run_path("group");
+        let mut writer = csv::WriterBuilder::new().has_headers(false).from_path(&path)?;
+        for (key, count) in entries {
+            writer.write_record([key, count.to_string()])?;
+        }
+        writer.flush()?;
+
+        self.runs.push(path);
+        self.memory = 0;
+        Ok(())
+    }
+
+    /*
+        Calls `emit` once per key with its total count, in key order.
+
+        Runtime: O(n log r) once counts are spilled, O(k log k) otherwise
+     */
+    pub fn finish(mut self, mut emit: impl FnMut(&str, usize)) -> Result<(), CellError> {
+        if self.runs.is_empty() {
+            let mut entries: Vec<(&String, &usize)> = self.counts.iter().collect();
+            entries.sort();
+            for (key, &count) in entries {
+                emit(key, count);
+            }
+            return Ok(());
+        }
+        if !self.counts.is_empty() {
+            self.spill()?;
+        }
+
+        compact(&mut self.runs, "group", |runs, path| {
+            let mut writer = csv::WriterBuilder::new().has_headers(false).from_path(path)?;
+            merge_entries(runs, |key, count| Ok(writer.write_record([key, &count.to_string()])?))?;
+            writer.flush()?;
+            Ok(())
+        })?;
+        merge_entries(&self.runs, |key, count| {
+            emit(key, count);
+            Ok(())
+        })
+    }
+}
+
+/*
+    K-way merge of group-by runs sorted by key, calling `emit` once per key with the sum of
+    its counts in every run, in key order. Opens every run given, so callers keep to
+    MERGE_FAN_IN.
+
+    Runtime: O(n log r)
+ */
+fn merge_entries(runs: &[PathBuf], mut emit: impl FnMut(&str, usize) -> Result<(), CellError>) -> Result<(), CellError> {
+    let mut readers: Vec<csv::StringRecordsIntoIter<File>> = runs
+        .iter()
+        .map(|path| csv::ReaderBuilder::new().has_headers(false).from_path(path).map(|reader| reader.into_records()))
+        .collect::<Result<_, _>>()?;
+    let mut heap = BinaryHeap::new();
+    for (run, reader) in readers.iter_mut().enumerate() {
+        if let Some(entry) = next_entry(reader)? {
+            heap.push(Reverse((entry, run)));
+        }
+    }
+
+    let mut current: Option<(String, usize)> = None;
+    while let Some(Reverse(((key, count), run))) = heap.pop() {
+        current = match current {
+            Some((current_key, total)) if current_key == key => Some((current_key, total + count)),
+            Some((current_key, total)) => {
+                emit(&current_key, total)?;
+                Some((key, count))
+            }
+            None => Some((key, count)),
+        };
+
+        if let Some(entry) = next_entry(&mut readers[run])? {
+            heap.push(Reverse((entry, run)));
+        }
+    }
+    if let Some((key, total)) = current {
+        emit(&key, total)?;
+    }
+
+    Ok(())
+}
+
+// Reads the next (key, count) of a group-by run.
+fn next_entry(reader: &mut csv::StringRecordsIntoIter<File>) -> Result<Option<(String, usize)>, CellError> {
+    match reader.next() {
+        Some(record) => {
+            let record = record?;
+            let count = record[1].parse().map_err(|_| CellError::InvalidField { row: None, field: "run count", value: record[1].to_string() })?;
+            Ok(Some((record[0].to_string(), count)))
+        }
+        None => Ok(None),
+    }
+}
+
+impl Drop for SpillGroupBy {
+    fn drop(&mut self) {
+        for path in &self.runs {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
@@ -0,0 +1,200 @@
+use crate::column::Column;
+use crate::error::CellError;
+use crate::Cell;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+// A comparison operator of a filter condition.
+pub enum Op {
+    Eq,
+    Ne,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+    // Case-insensitive "contains".
+    Contains,
+}
+
+impl Op {
+    // Operators in the order they are looked for, so ">=" is found before ">".
+    const SYMBOLS: [(&'static str, Op); 7] = [
+        ("!=", Op::Ne),
+        (">=", Op::Ge),
+        ("<=", Op::Le),
+        ("=", Op::Eq),
+        (">", Op::Gt),
+        ("<", Op::Lt),
+        ("~", Op::Contains),
+    ];
+}
+
+// Prefix of a filter name that refers to an extra column, e.g. `extras.battery`.
+pub const EXTRAS_PREFIX: &str = "extras.";
+
+// Name of the field holding the OS family parsed from platform_os, e.g. `os_family = Android`.
+pub const OS_FAMILY: &str = "os_family";
+
+// Name of the field holding a cell's tags from the tag sidecar, e.g. `tag = verified`.
+pub const TAG: &str = "tag";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+// What a condition looks at: one of the columns, an extra column by header name, the OS
+// family of platform_os ("Other" for the systems of feature phones), or the cell's tags.
+pub enum Field {
+    Column(Column),
+    Extra(String),
+    OsFamily,
+    Tag,
+}
+
+impl Field {
+    // The field a filter name refers to, if any.
+    pub fn from_name(name: &str) -> Option<Field> {
+        match name.strip_prefix(EXTRAS_PREFIX) {
+            Some(header) if !header.trim().is_empty() => Some(Field::Extra(header.trim().to_string())),
+            Some(_) => None,
+            None if name == OS_FAMILY => Some(Field::OsFamily),
+            None if name == TAG => Some(Field::Tag),
+            None => Column::from_name(name).map(Field::Column),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+// One `column op value` condition.
+pub struct Condition {
+    pub field: Field,
+    pub op: Op,
+    pub value: String,
+}
+
+impl Condition {
+    /*
+        Checks the condition against a cell. Values that both parse as numbers are compared
+        numerically, anything else as case-insensitive text. A missing value never matches.
+        A cell matches a tag condition when one of its tags does, except that `tag != x`
+        means none of its tags is x.
+
+        Runtime: O(1), O(t) for the tags
+     */
+    pub fn matches(&self, cell: &Cell) -> bool {
+        let actual = match &self.field {
+            Field::Column(column) => cell.text(*column),
+            Field::Extra(header) => cell.extra(header).map(str::to_string),
+            Field::OsFamily => cell.os().map(|os| os.family.name().to_string()),
+            Field::Tag if self.op == Op::Ne => return !cell.tags.iter().any(|tag| tag.eq_ignore_ascii_case(&self.value)),
+            Field::Tag => return cell.tags.iter().any(|tag| self.compare(tag)),
+        };
+        actual.is_some_and(|actual| self.compare(&actual))
+    }
+
+    // Compares one value of the field with the condition's value.
+    fn compare(&self, actual: &str) -> bool {
+        if self.op == Op::Contains {
+            return actual.to_lowercase().contains(&self.value.to_lowercase());
+        }
+
+        let ordering = match (actual.trim().parse::<f64>(), self.value.parse::<f64>()) {
+            (Ok(actual), Ok(expected)) => actual.partial_cmp(&expected),
+            _ => Some(actual.trim().to_lowercase().cmp(&self.value.to_lowercase())),
+        };
+        let Some(ordering) = ordering else {
+            return false;
+        };
+
+        match self.op {
+            Op::Eq => ordering.is_eq(),
+            Op::Ne => ordering.is_ne(),
+            Op::Gt => ordering.is_gt(),
+            Op::Ge => ordering.is_ge(),
+            Op::Lt => ordering.is_lt(),
+            Op::Le => ordering.is_le(),
+            Op::Contains => unreachable!(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+/*
+    A filter expression such as `launch_announced >= 2015 && oem = Samsung`: conditions joined
+    by `&&`, all of which must hold. Values may be wrapped in double quotes. Extra columns
+    are named with an `extras.` prefix, e.g. `extras.battery ~ mAh`, and `tag = verified`
+    keeps the cells tagged verified.
+ */
+pub struct Filter {
+    pub conditions: Vec<Condition>,
+}
+
+impl Filter {
+    /*
+        Parse a filter expression. An empty expression keeps every cell.
+
+        Runtime: O(n) in the length of the expression
+     */
+    pub fn parse(expression: &str) -> Result<Filter, CellError> {
+        let mut conditions = Vec::new();
+
+        for part in expression.split("&&").map(|part| part.trim()).filter(|part| !part.is_empty()) {
+            let (index, symbol, op) = Op::SYMBOLS
+                .iter()
+                .filter_map(|&(symbol, op)| part.find(symbol).map(|index| (index, symbol, op)))
+                .min_by_key(|&(index, _, _)| index)
+                .ok_or_else(|| CellError::InvalidField { row: None, field: "filter condition", value: part.to_string() })?;
+
+            let name = part[..index].trim();
+            let field = Field::from_name(name).ok_or_else(|| CellError::UnknownColumn { name: name.to_string() })?;
+            let value = part[index + symbol.len()..].trim().trim_matches('"').to_string();
+
+            conditions.push(Condition { field, op, value });
+        }
+
+        Ok(Filter { conditions })
+    }
+
+    pub fn matches(&self, cell: &Cell) -> bool {
+        self.conditions.iter().all(|condition| condition.matches(cell))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+// A named filter expression new users can start from, e.g. `--preset modern-smartphone`.
+pub struct Preset {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub expression: &'static str,
+}
+
+// The built-in presets, as `--list-presets` prints them.
+pub const PRESETS: [Preset; 5] = [
+    Preset {
+        name: "modern-smartphone",
+        description: "Announced in 2015 or later, with a smartphone OS and a display of 5 inches or more",
+        expression: "launch_announced >= 2015 && os_family != Other && display_size >= 5",
+    },
+    Preset { name: "feature-phone", description: "Runs no smartphone OS: a feature phone's or a wearable's own system", expression: "os_family = Other" },
+    Preset { name: "compact", description: "A display of 5 inches or less and a weight of 150 g or less", expression: "display_size <= 5 && body_weight <= 150" },
+    Preset { name: "phablet", description: "A display of 6.5 inches or more", expression: "display_size >= 6.5" },
+    Preset { name: "android", description: "Runs Android", expression: "os_family = Android" },
+];
+
+impl Preset {
+    pub fn find(name: &str) -> Option<&'static Preset> {
+        PRESETS.iter().find(|preset| preset.name == name)
+    }
+}
+
+/*
+    The filter expression with the named presets added to it. Presets compose like any other
+    conditions, so a row must match the expression and every preset.
+
+    Runtime: O(p) for the number of presets
+ */
+pub fn with_presets(expression: &str, presets: &[String]) -> Result<String, CellError> {
+    let mut parts: Vec<&str> = vec![expression.trim()];
+    for name in presets {
+        let preset = Preset::find(name).ok_or_else(|| CellError::Unknown { kind: "preset", name: name.to_string() })?;
+        parts.push(preset.expression);
+    }
+
+    Ok(parts.into_iter().filter(|part| !part.is_empty()).collect::<Vec<_>>().join(" && "))
+}
@@ -0,0 +1,162 @@
+use crate::column::Column;
+use crate::error::CellError;
+use crate::random::Rng;
+use std::io::Write;
+
+/*
+    The tricky rows the tests rely on, as the raw CSV values of cells_test.csv: an empty
+    display size and "V1" sensors, '-' for missing dimensions and weight, year strings with
+    the month and release date in them, a "Not officially announced yet" year, and both the
+    "Discontinued" and "Cancelled" statuses.
+ */
+pub const EDGE_CASES: [[&str; 12]; 4] = [
+    [
+        "Benefon",
+        "Vega",
+        "1999",
+        "Discontinued",
+        "145 x 56 x 23 mm (5.71 x 2.20 x 0.91 in)",
+        "190 g (6.70 oz)",
+        "Mini-SIM",
+        "Monochrome graphic",
+        "",
+        "6 lines",
+        "V1",
+        "",
+    ],
+    [
+        "Garmin-Asus",
+        "nuvifone M10",
+        "2010, January. Released 2010, March",
+        "Discontinued",
+        "-",
+        "-",
+        "Mini-SIM",
+        "TFT resistive touchscreen, 65K colors",
+        "3.5 inches, 34.9 cm",
+        "480 x 800 pixels, 5:3 ratio (~267 ppi density)",
+        "Accelerometer",
+        "Microsoft Windows Mobile 6.5.3 Professional",
+    ],
+    [
+        "Gigabyte",
+        "GSmart G1305 Boston",
+        "2010, April. Released 2010, April",
+        "Discontinued",
+        "116 x 56.8 x 12.4 mm (4.57 x 2.24 x 0.49 in)",
+        "118 g (4.16 oz)",
+        "Mini-SIM",
+        "TFT capacitive touchscreen, 256K colors",
+        "3.2 inches, 30.5 cm (~46.3% screen-to-body ratio)",
+        "320 x 480 pixels, 3:2 ratio (~180 ppi density)",
+        "Accelerometer",
+        "Android 1.6 (Donut)",
+    ],
+    [
+        "Gigabyte",
+        "GSmart",
+        "Not officially announced yet",
+        "Cancelled",
+        "103 x 54 x 13.4 mm (4.06 x 2.13 x 0.53 in)",
+        "-",
+        "Mini-SIM",
+        "TFT capacitive touchscreen, 256K colors",
+        "2.8 inches, 24.3 cm (~43.7% screen-to-body ratio)",
+        "240 x 320 pixels, 4:3 ratio (~143 ppi density)",
+        "Accelerometer",
+        "Android 1.6 (Donut)",
+    ],
+];
+
+const OEMS: [&str; 8] = ["Nokia", "Samsung", "Motorola", "Sony Ericsson", "Apple", "Xiaomi", "Huawei", "Alcatel"];
+const MONTHS: [&str; 12] = ["January", "February", "March", "April", "May", "June", "July", "August", "September", "October", "November", "December"];
+const SIMS: [&str; 4] = ["Mini-SIM", "Nano-SIM", "Nano-SIM, eSIM", "Dual SIM (Nano-SIM, dual stand-by)"];
+const DISPLAY_TYPES: [&str; 4] = ["Monochrome graphic", "TFT, 65K colors", "IPS LCD", "Super AMOLED, HDR10"];
+const SENSORS: [&str; 4] = ["V1", "Accelerometer", "Fingerprint, accelerometer, proximity, compass", "Face ID, accelerometer, gyro, barometer"];
+const OS: [&str; 4] = ["Android 4.4 (KitKat)", "Android 11, One UI 3.0", "iOS 9", "Symbian 9.2, S60 rel. 3.1"];
+
+// One of the values, picked by the generator.
+fn pick<'a>(rng: &mut Rng, values: &[&'a str]) -> &'a str {
+    values[rng.below(values.len())]
+}
+
+/*
+    A made-up row in the format of the GSMArena export, with a value missing now and then
+    the way the real data has them.
+
+    Runtime: O(1)
+ */
+fn generated_row(rng: &mut Rng, index: usize) -> Vec<String> {
+    let year = 1999 + rng.below(25);
+    let month = pick(rng, &MONTHS);
+    let (width, height, depth) = (45.0 + rng.below(35) as f32, 100.0 + rng.below(65) as f32, 6.0 + rng.below(150) as f32 / 10.0);
+    let weight = 70 + rng.below(180);
+    let inches = 1.5 + rng.below(55) as f32 / 10.0;
+    let (columns, rows) = (240 + 40 * rng.below(20), 320 + 80 * rng.below(25));
+    let os = pick(rng, &OS).to_string();
+    let missing = |rng: &mut Rng, value: String| if rng.below(10) == 0 { "-".to_string() } else { value };
+
+    let status = match rng.below(4) {
+        0 => "Discontinued".to_string(),
+        1 => "Cancelled".to_string(),
+        _ => format!("Available. Released {}, {}", year, month),
+    };
+    let dimensions = format!(
+        "{} x {} x {} mm ({:.2} x {:.2} x {:.2} in)",
+        height,
+        width,
+        depth,
+        height / 25.4,
+        width / 25.4,
+        depth / 25.4
+    );
+
+    vec![
+        pick(rng, &OEMS).to_string(),
+        format!("Model {}", index + 1),
+        format!("{}, {}", year, month),
+        status,
+        missing(rng, dimensions),
+        missing(rng, format!("{} g ({:.2} oz)", weight, weight as f32 / 28.35)),
+        pick(rng, &SIMS).to_string(),
+        pick(rng, &DISPLAY_TYPES).to_string(),
+        missing(rng, format!("{:.1} inches, {:.1} cm2", inches, inches * inches * 2.7)),
+        format!("{} x {} pixels", columns, rows),
+        pick(rng, &SENSORS).to_string(),
+        missing(rng, os),
+    ]
+}
+
+/*
+    The raw records of a test CSV: with `edge_cases`, the EDGE_CASES rows first, then rows
+    generated from the seed, `rows` in all. The same arguments always give the same rows.
+
+    Runtime: O(r)
+ */
+pub fn generate(rows: usize, edge_cases: bool, seed: u64) -> Vec<Vec<String>> {
+    let mut records: Vec<Vec<String>> = Vec::with_capacity(rows);
+    if edge_cases {
+        records.extend(EDGE_CASES.iter().take(rows).map(|row| row.iter().map(|value| value.to_string()).collect()));
+    }
+
+    let mut rng = Rng::new(seed);
+    while records.len() < rows {
+        let index = records.len();
+        records.push(generated_row(&mut rng, index));
+    }
+
+    records
+}
+
+// Writes records under the usual header row. Lines end in \r\n like cells_test.csv, which
+// the tests need to keep checking line numbers on such files.
+pub fn write(records: &[Vec<String>], output: impl Write) -> Result<(), CellError> {
+    let mut writer = csv::WriterBuilder::new().terminator(csv::Terminator::CRLF).from_writer(output);
+    writer.write_record(Column::ALL.iter().map(|column| column.name()))?;
+    for record in records {
+        writer.write_record(record)?;
+    }
+    writer.flush()?;
+
+    Ok(())
+}
@@ -0,0 +1,110 @@
+use crate::column::Column;
+use crate::Cell;
+use serde::Deserialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+// How a number is brought to the configured decimal places.
+pub enum Rounding {
+    // Halves away from zero: 2.125 -> 2.13, -2.125 -> -2.13.
+    #[default]
+    HalfUp,
+    // Halves to the even digit: 2.125 -> 2.12, 2.135 -> 2.14.
+    HalfEven,
+    // Towards negative infinity.
+    Floor,
+    // Towards positive infinity.
+    Ceil,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+/*
+    How every measured number is written: in the report, the tables and the viewer, the LaTeX
+    summary and JSON exports. Read from the [number_format] section of the config, e.g.
+
+        [number_format]
+        decimals = 1
+        rounding = "half_even"
+        trim_zeros = false
+
+    By default numbers get at most two decimals with trailing zeros removed, so 150.5 stays
+    "150.5" and 190 stays "190".
+ */
+pub struct NumberFormat {
+    pub decimals: usize,
+    pub rounding: Rounding,
+    // Drop trailing zeros after the decimal point (and the point itself if nothing is left).
+    pub trim_zeros: bool,
+}
+
+impl Default for NumberFormat {
+    fn default() -> NumberFormat {
+        NumberFormat { decimals: 2, rounding: Rounding::HalfUp, trim_zeros: true }
+    }
+}
+
+impl NumberFormat {
+    /*
+        The value rounded to the configured decimal places.
+
+        Runtime: O(1)
+     */
+    pub fn round(&self, value: f64) -> f64 {
+        let scale = 10f64.powi(self.decimals as i32);
+        let scaled = value * scale;
+        let rounded = match self.rounding {
+            Rounding::HalfUp => scaled.round(),
+            Rounding::HalfEven => scaled.round_ties_even(),
+            Rounding::Floor => scaled.floor(),
+            Rounding::Ceil => scaled.ceil(),
+        };
+
+        // Adding 0.0 turns a -0.0 into 0.0, so small negative values don't print as "-0".
+        rounded / scale + 0.0
+    }
+
+    /*
+        The value written with the configured decimals, rounding and trailing zeros.
+
+        Runtime: O(d)
+     */
+    pub fn format(&self, value: f64) -> String {
+        let text = format!("{:.*}", self.decimals, self.round(value));
+        if self.trim_zeros && text.contains('.') {
+            text.trim_end_matches('0').trim_end_matches('.').to_string()
+        } else {
+            text
+        }
+    }
+
+    // A share between 0 and 1 as a percentage, e.g. 0.425 -> "42.5%".
+    pub fn percent(&self, share: f64) -> String {
+        format!("{}%", self.format(share * 100.0))
+    }
+
+    /*
+        The value of a column as text, like Cell::text but with body_weight and display_size
+        written by this format. Years are whole numbers and are left as they are.
+
+        Runtime: O(1)
+     */
+    pub fn text(&self, cell: &Cell, column: Column) -> Option<String> {
+        match column {
+            Column::BodyWeight | Column::DisplaySize => cell.number(column).map(|value| self.format(value as f64)),
+            _ => cell.text(column),
+        }
+    }
+
+    /*
+        A copy of the cell with body_weight and display_size rounded, for exports that keep
+        numbers as numbers (JSON).
+
+        Runtime: O(1)
+     */
+    pub fn round_cell(&self, cell: &Cell) -> Cell {
+        let round = |value: Option<f32>| value.map(|value| self.round(value as f64) as f32);
+
+        Cell { body_weight: round(cell.body_weight), display_size: round(cell.display_size), ..cell.clone() }
+    }
+}
@@ -0,0 +1,71 @@
+use crate::column::Column;
+use crate::Cell;
+use serde::Deserialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(default)]
+/*
+    How the float columns are bucketed when cells are grouped or counted by them, so that
+    6.0 and 6.01 inches count as the same display size. Each value is rounded to the nearest
+    multiple of its column's step; without a step the exact value is used. Read from the
+    [float_grouping] section of the config, e.g.
+
+        [float_grouping]
+        display_size = 0.1
+        body_weight = 5.0
+
+    By default display sizes are grouped to 0.1 inch and weights are exact.
+ */
+pub struct FloatGrouping {
+    pub display_size: Option<f32>,
+    pub body_weight: Option<f32>,
+}
+
+impl FloatGrouping {
+    pub const DEFAULT: FloatGrouping = FloatGrouping { display_size: Some(0.1), body_weight: None };
+
+    // Every float grouped by its exact value.
+    pub const EXACT: FloatGrouping = FloatGrouping { display_size: None, body_weight: None };
+
+    // The bucket width of a column, if its values are bucketed. A step of 0 means exact.
+    pub fn step(&self, column: Column) -> Option<f32> {
+        let step = match column {
+            Column::DisplaySize => self.display_size,
+            Column::BodyWeight => self.body_weight,
+            _ => None,
+        };
+        step.filter(|step| *step > 0.0)
+    }
+
+    /*
+        The value a cell is grouped under for a column: the text of the column, with float
+        values replaced by their bucket written to the step's decimals (6.01 -> "6", 6.14 ->
+        "6.1" for a 0.1 step).
+
+        Runtime: O(1)
+     */
+    pub fn key(&self, cell: &Cell, column: Column) -> Option<String> {
+        match (self.step(column), cell.number(column)) {
+            (Some(step), Some(value)) => Some(FloatGrouping::bucket(value, step)),
+            _ => cell.text(column),
+        }
+    }
+
+    // A value rounded to the nearest multiple of the step, without trailing zeros.
+    fn bucket(value: f32, step: f32) -> String {
+        let decimals = step.to_string().split('.').nth(1).map_or(0, str::len);
+        let bucket = format!("{:.*}", decimals, ((value / step).round() * step) as f64 + 0.0);
+
+        if bucket.contains('.') {
+            bucket.trim_end_matches('0').trim_end_matches('.').to_string()
+        } else {
+            bucket
+        }
+    }
+}
+
+impl Default for FloatGrouping {
+    fn default() -> FloatGrouping {
+        FloatGrouping::DEFAULT
+    }
+}
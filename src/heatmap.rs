@@ -0,0 +1,114 @@
+use crossterm::style::{Color, Stylize};
+
+// Colour of the lowest and highest non-zero counts; cells in between are blended.
+const LOW: (u8, u8, u8) = (255, 237, 160);
+const HIGH: (u8, u8, u8) = (189, 0, 38);
+// Colour of years without launches.
+const EMPTY: (u8, u8, u8) = (240, 240, 240);
+
+// Size of one SVG cell and the space left for the OEM labels and the year axis.
+const CELL_SIZE: usize = 14;
+const LABEL_WIDTH: usize = 120;
+const AXIS_HEIGHT: usize = 40;
+
+// Blends LOW into HIGH by a count's share of the largest count.
+pub fn color(count: usize, largest: usize) -> (u8, u8, u8) {
+    if count == 0 || largest == 0 {
+        return EMPTY;
+    }
+
+    let t = count as f32 / largest as f32;
+    let blend = |low: u8, high: u8| (low as f32 + (high as f32 - low as f32) * t).round() as u8;
+    (blend(LOW.0, HIGH.0), blend(LOW.1, HIGH.1), blend(LOW.2, HIGH.2))
+}
+
+fn largest(rows: &[(String, Vec<usize>)]) -> usize {
+    rows.iter().flat_map(|(_, counts)| counts.iter().copied()).max().unwrap_or(0)
+}
+
+/*
+    Draw an OEM x year pivot (as returned by Cell::oem_year_pivot) as a grid of coloured
+    cells for a terminal with true-colour support. Each year is three characters wide and
+    labelled by its last two digits.
+
+    Runtime: O(o * y)
+ */
+pub fn render_terminal(years: &[u32], rows: &[(String, Vec<usize>)]) -> String {
+    let largest = largest(rows);
+    let label_width = rows.iter().map(|(oem, _)| oem.chars().count()).max().unwrap_or(0);
+
+    let mut grid = format!("{:<width$}", "", width = label_width);
+    for year in years {
+        grid.push_str(&format!(" {:02}", year % 100));
+    }
+    grid.push('\n');
+
+    for (oem, counts) in rows {
+        grid.push_str(&format!("{:<width$}", oem, width = label_width));
+        for &count in counts {
+            let (r, g, b) = color(count, largest);
+            grid.push(' ');
+            grid.push_str(&format!("{}", "  ".on(Color::Rgb { r, g, b })));
+        }
+        grid.push('\n');
+    }
+
+    grid.push_str(&format!("lightest = 1 launch, darkest = {} launches\n", largest));
+    grid
+}
+
+// Escapes text for use inside SVG elements and attributes.
+pub fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+/*
+    Draw an OEM x year pivot as a standalone SVG image. Every cell has a tooltip with the
+    OEM, year and count.
+
+    Runtime: O(o * y)
+ */
+pub fn render_svg(years: &[u32], rows: &[(String, Vec<usize>)]) -> String {
+    let largest = largest(rows);
+    let width = LABEL_WIDTH + years.len() * CELL_SIZE;
+    let height = AXIS_HEIGHT + rows.len() * CELL_SIZE;
+
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" font-family=\"sans-serif\" font-size=\"10\">\n",
+        width, height
+    );
+
+    for (column, year) in years.iter().enumerate() {
+        let x = LABEL_WIDTH + column * CELL_SIZE + CELL_SIZE / 2;
+        svg.push_str(&format!(
+            "<text x=\"{x}\" y=\"{y}\" transform=\"rotate(-90 {x} {y})\">{year}</text>\n",
+            x = x,
+            y = AXIS_HEIGHT - 4,
+            year = year
+        ));
+    }
+
+    for (line, (oem, counts)) in rows.iter().enumerate() {
+        let y = AXIS_HEIGHT + line * CELL_SIZE;
+        svg.push_str(&format!("<text x=\"{}\" y=\"{}\" text-anchor=\"end\">{}</text>\n", LABEL_WIDTH - 4, y + CELL_SIZE - 3, escape(oem)));
+
+        for (column, (&count, year)) in counts.iter().zip(years).enumerate() {
+            let (r, g, b) = color(count, largest);
+            svg.push_str(&format!(
+                "<rect x=\"{}\" y=\"{}\" width=\"{size}\" height=\"{size}\" fill=\"#{:02x}{:02x}{:02x}\"><title>{} {}: {}</title></rect>\n",
+                LABEL_WIDTH + column * CELL_SIZE,
+                y,
+                r,
+                g,
+                b,
+                escape(oem),
+                year,
+                count,
+                size = CELL_SIZE
+            ));
+        }
+    }
+
+    svg.push_str("</svg>\n");
+    svg
+}
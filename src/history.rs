@@ -0,0 +1,142 @@
+use crate::column::Column;
+use crate::dataset::Dataset;
+use crate::error::CellError;
+use crate::parse::FieldCounts;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// History file `cells history` uses when no --history flag is given.
+pub const DEFAULT_HISTORY_FILE: &str = "cells_history.jsonl";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+// The headline metrics of the dataset at one run, one line of the history file.
+pub struct RunMetrics {
+    // Seconds since the Unix epoch.
+    pub recorded_at: u64,
+    pub file: String,
+    pub rows: usize,
+    pub mean_weight: Option<f64>,
+    pub mean_display_size: Option<f64>,
+    // The share of rows without a value, 0 to 1, by column name.
+    pub null_rates: BTreeMap<String, f64>,
+    // How the parser took each column's values, by column name. Empty in runs recorded
+    // before it was kept.
+    #[serde(default)]
+    pub parse: BTreeMap<String, FieldCounts>,
+}
+
+impl RunMetrics {
+    /*
+        The metrics of a loaded dataset, recorded now.
+
+        Runtime: O(c log n)
+     */
+    pub fn of(dataset: &Dataset, file: &str) -> RunMetrics {
+        let recorded_at = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |time| time.as_secs());
+        let rows = dataset.len();
+        let null_rates = Column::ALL
+            .iter()
+            .map(|&column| (column.name().to_string(), if rows == 0 { 0.0 } else { dataset.column_stats(column).nulls as f64 / rows as f64 }))
+            .collect();
+
+        RunMetrics {
+            recorded_at,
+            file: file.to_string(),
+            rows,
+            mean_weight: dataset.column_stats(Column::BodyWeight).mean(),
+            mean_display_size: dataset.column_stats(Column::DisplaySize).mean(),
+            null_rates,
+            parse: dataset.parse_report().field_counts().into_iter().map(|(column, counts)| (column.name().to_string(), counts)).collect(),
+        }
+    }
+
+    // The day the run was recorded, as YYYY-MM-DD in UTC.
+    pub fn date(&self) -> String {
+        // Days to a civil date, after Howard Hinnant's days_from_civil inverse.
+        let days = (self.recorded_at / 86_400) as i64 + 719_468;
+        let (era, day_of_era) = (days.div_euclid(146_097), days.rem_euclid(146_097));
+        let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let month_index = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+        let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+        let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+        format!("{:04}-{:02}-{:02}", year, month, day)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+/*
+    The metrics of successive runs, oldest first, kept in a JSON-lines file so each run only
+    appends a line. Comparing the runs shows how the dataset itself has drifted across
+    versions of the file.
+ */
+pub struct History {
+    pub runs: Vec<RunMetrics>,
+}
+
+// How one column's null rate moved between two runs.
+pub type NullDrift = (String, f64, f64);
+
+impl History {
+    /*
+        Read a history file. A file that doesn't exist yet is an empty history.
+
+        Runtime: O(r) for the number of runs
+     */
+    pub fn load(path: &str) -> Result<History, CellError> {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(error) if error.kind() == ErrorKind::NotFound => return Ok(History::default()),
+            Err(error) => return Err(error.into()),
+        };
+        let runs = text.lines().filter(|line| !line.trim().is_empty()).map(serde_json::from_str).collect::<Result<_, _>>()?;
+
+        Ok(History { runs })
+    }
+
+    // Appends one run to a history file, creating it if needed. Runtime: O(1)
+    pub fn append(path: &str, run: &RunMetrics) -> Result<(), CellError> {
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+        writeln!(file, "{}", serde_json::to_string(run)?)?;
+
+        Ok(())
+    }
+
+    /*
+        The columns whose null rate moved by more than `threshold` (a share, e.g. 0.01) from
+        the first run to the last, as (column, first rate, last rate) by column order.
+
+        Runtime: O(c)
+     */
+    pub fn null_drift(&self, threshold: f64) -> Vec<NullDrift> {
+        let (Some(first), Some(last)) = (self.runs.first(), self.runs.last()) else {
+            return Vec::new();
+        };
+
+        Column::ALL
+            .iter()
+            .filter_map(|column| {
+                let (before, after) = (*first.null_rates.get(column.name())?, *last.null_rates.get(column.name())?);
+                ((after - before).abs() > threshold).then(|| (column.name().to_string(), before, after))
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for History {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let number = |value: Option<f64>| value.map_or("-".to_string(), |value| format!("{:.2}", value));
+
+        writeln!(f, "{:<4} {:<10} {:>7} {:>12} {:>13}  file", "run", "date", "rows", "mean weight", "mean display")?;
+        for (index, run) in self.runs.iter().enumerate() {
+            writeln!(f, "{:<4} {:<10} {:>7} {:>12} {:>13}  {}", index + 1, run.date(), run.rows, number(run.mean_weight), number(run.mean_display_size), run.file)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,103 @@
+use crate::column::Column;
+use crate::Cell;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+// Text columns with few distinct values, e.g. one OEM name over thousands of rows. Their
+// values are shared strings, one allocation per distinct value once interned.
+pub const INTERNED: [Column; 5] = [Column::Oem, Column::LaunchStatus, Column::BodySim, Column::DisplayType, Column::PlatformOs];
+
+#[derive(Debug, Clone, Default)]
+/*
+    A pool of shared strings. Interning a value returns the pooled copy, so every cell with
+    the same OEM points at the same string and equal values can be grouped by pointer.
+ */
+pub struct Interner {
+    pool: HashSet<Arc<str>>,
+}
+
+impl Interner {
+    pub fn new() -> Interner {
+        Interner::default()
+    }
+
+    /*
+        The pooled copy of a value, added to the pool if it is new.
+
+        Runtime: O(1) expected
+     */
+    pub fn intern(&mut self, value: &str) -> Arc<str> {
+        if let Some(shared) = self.pool.get(value) {
+            return shared.clone();
+        }
+
+        let shared: Arc<str> = value.into();
+        self.pool.insert(shared.clone());
+        shared
+    }
+
+    // Replaces a shared string by its pooled copy, or pools it as it is if it is new.
+    pub fn share(&mut self, value: &mut Arc<str>) {
+        match self.pool.get(&**value) {
+            Some(shared) => *value = shared.clone(),
+            None => {
+                self.pool.insert(value.clone());
+            }
+        }
+    }
+
+    // Shares every interned column of a cell through this pool, e.g. for cells parsed with
+    // another pool.
+    pub fn intern_cell(&mut self, cell: &mut Cell) {
+        let fields = [&mut cell.oem, &mut cell.launch_status, &mut cell.body_sim, &mut cell.display_type, &mut cell.platform_os];
+        for value in fields.into_iter().flatten() {
+            self.share(value);
+        }
+    }
+
+    // How many distinct values are pooled.
+    pub fn len(&self) -> usize {
+        self.pool.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pool.is_empty()
+    }
+}
+
+/*
+    Count shared strings as (value, count), in no particular order. Values from one pool are
+    counted by pointer without hashing their text; equal values that are not shared, e.g.
+    of cells built by hand, are added together afterwards.
+
+    Runtime: O(n + v) for v distinct strings
+ */
+pub fn count<'a>(values: impl IntoIterator<Item = &'a Arc<str>>) -> Vec<(&'a str, usize)> {
+    group(values.into_iter().map(|value| (value, ())), |count: &mut usize, ()| *count += 1, |count, other| *count += other)
+}
+
+/*
+    Group items by a shared string as (value, group), in no particular order, `add` putting
+    each item into the group of its value. As in count, values from one pool are grouped by
+    pointer, and the groups of equal values that are not shared are then joined by `merge`.
+
+    Runtime: O(n + v) for v distinct strings
+ */
+pub fn group<'a, T, G: Default>(items: impl IntoIterator<Item = (&'a Arc<str>, T)>, mut add: impl FnMut(&mut G, T), mut merge: impl FnMut(&mut G, G)) -> Vec<(&'a str, G)> {
+    let mut by_pointer: HashMap<*const u8, (&'a str, G)> = HashMap::new();
+    for (value, item) in items {
+        add(&mut by_pointer.entry(value.as_ptr()).or_insert_with(|| (value, G::default())).1, item);
+    }
+
+    let mut by_text: HashMap<&str, G> = HashMap::new();
+    for (text, group) in by_pointer.into_values() {
+        match by_text.get_mut(text) {
+            Some(other) => merge(other, group),
+            None => {
+                by_text.insert(text, group);
+            }
+        }
+    }
+
+    by_text.into_iter().collect()
+}
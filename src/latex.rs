@@ -0,0 +1,85 @@
+use crate::column::Column;
+use crate::format::NumberFormat;
+use crate::view::DatasetView;
+use crate::Cell;
+
+// Escapes the characters LaTeX treats specially so values print as written.
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        match c {
+            '&' | '%' | '$' | '#' | '_' | '{' | '}' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '~' => escaped.push_str("\\textasciitilde{}"),
+            '^' => escaped.push_str("\\textasciicircum{}"),
+            '\\' => escaped.push_str("\\textbackslash{}"),
+            _ => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+/*
+    Render rows as a booktabs table (\toprule, \midrule, \bottomrule) inside a table float.
+    Columns whose values are all numbers are right-aligned, the rest left-aligned. Needs
+    \usepackage{booktabs} in the document.
+
+    Runtime: O(n * c)
+ */
+pub fn render_table(headers: &[String], rows: &[Vec<String>], caption: Option<&str>) -> String {
+    let alignment: String = (0..headers.len())
+        .map(|index| {
+            let mut values = rows.iter().filter_map(|row| row.get(index)).filter(|value| !value.is_empty() && value.as_str() != "-").peekable();
+            let numeric = values.peek().is_some() && values.all(|value| value.trim().parse::<f64>().is_ok());
+            if numeric { 'r' } else { 'l' }
+        })
+        .collect();
+    let line = |values: &[String]| values.iter().map(|value| escape(value)).collect::<Vec<String>>().join(" & ") + " \\\\\n";
+
+    let mut latex = String::from("\\begin{table}[ht]\n\\centering\n");
+    latex.push_str(&format!("\\begin{{tabular}}{{{}}}\n\\toprule\n", alignment));
+    latex.push_str(&line(headers));
+    latex.push_str("\\midrule\n");
+    for row in rows {
+        latex.push_str(&line(row));
+    }
+    latex.push_str("\\bottomrule\n\\end{tabular}\n");
+    if let Some(caption) = caption {
+        latex.push_str(&format!("\\caption{{{}}}\n", escape(caption)));
+    }
+    latex.push_str("\\end{table}\n");
+
+    latex
+}
+
+/*
+    The summary statistics of some cells as a two-column LaTeX table, with the busiest launch
+    year counted after `after_year`.
+
+    Runtime: O(n log n)
+ */
+pub fn summary_table(cells: &DatasetView, format: &NumberFormat, after_year: u32, caption: Option<&str>) -> String {
+    let headers = vec!["Statistic".to_string(), "Value".to_string()];
+    let rows: Vec<Vec<String>> = Cell::summary_stats(cells, format, after_year).into_iter().map(|(label, value)| vec![label, value]).collect();
+
+    render_table(&headers, &rows, caption)
+}
+
+/*
+    The given columns of some cells as a LaTeX table. Missing values are left blank.
+
+    Runtime: O(n * c)
+ */
+pub fn cells_table(cells: &[Cell], columns: &[Column], caption: Option<&str>) -> String {
+    let headers: Vec<String> = columns.iter().map(|column| column.name().to_string()).collect();
+    let rows: Vec<Vec<String>> = cells
+        .iter()
+        .map(|cell| columns.iter().map(|&column| cell.text(column).unwrap_or_default()).collect())
+        .collect();
+
+    render_table(&headers, &rows, caption)
+}
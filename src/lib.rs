@@ -0,0 +1,993 @@
+//! Reading, cleaning and analysing a CSV dataset of mobile phones.
+//!
+//! [`Cell`] is one row of the dataset. [`Cell::read_csv`] loads a file and the associated
+//! functions of `Cell` and the [`analysis`] module compute statistics over a
+//! [`view::DatasetView`], a possibly filtered selection of the loaded cells.
+//! The `cells` binary is a command line front end to this library.
+//!
+//! Optional parts are behind Cargo features, all on by default: `cli` (the `cells` binary
+//! and its argument parsing), `tui` (the terminal viewer), `charts` (heatmaps and world maps)
+//! and `parallel` (parsing on every core). Without `parallel`, [`Cell::read_csv_parallel`]
+//! parses the file on one thread.
+
+pub mod aliases;
+pub mod analysis;
+pub mod bounds;
+pub mod breakdown;
+pub mod builder;
+pub mod cancel;
+pub mod chart;
+#[cfg(feature = "cli")]
+pub mod cli;
+pub mod column;
+pub mod config;
+pub mod country;
+pub mod date;
+pub mod dimensions;
+pub mod dataset;
+pub mod display;
+pub mod edits;
+pub mod enrich;
+pub mod error;
+pub mod external;
+pub mod filter;
+pub mod fixture;
+pub mod format;
+pub mod grouping;
+#[cfg(feature = "charts")]
+pub mod heatmap;
+pub mod history;
+pub mod intern;
+pub mod latex;
+pub mod memory;
+pub mod metric;
+mod nfc_tables;
+pub mod normalize;
+pub mod os;
+pub mod output;
+#[cfg(feature = "parquet")]
+pub mod parquet;
+pub mod parse;
+pub mod partition;
+pub mod patch;
+pub mod pipeline;
+pub mod provenance;
+pub mod query;
+pub mod random;
+pub mod reader;
+pub mod record;
+pub mod repl;
+pub mod report;
+pub mod resolution;
+pub mod schema;
+pub mod sensor;
+pub mod sim;
+pub mod sketch;
+pub mod status;
+pub mod summary;
+pub mod table;
+pub mod tags;
+pub mod text;
+// The tests cover the binary's commands too, so they need the full build. The original
+// tests are kept as they were written, glob import and cast included.
+#[cfg(all(test, feature = "cli"))]
+#[allow(unused_imports, clippy::unnecessary_cast)]
+mod test;
+pub mod timing;
+#[cfg(feature = "tui")]
+pub mod tui;
+pub mod units;
+pub mod view;
+#[cfg(feature = "tui")]
+pub mod viewer;
+pub mod watch;
+pub mod weight;
+pub mod wizard;
+pub mod workspace;
+#[cfg(feature = "charts")]
+pub mod worldmap;
+
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use column::{Column, HeaderIndex};
+use country::CountryMap;
+use dimensions::Dimensions;
+use error::CellError;
+use format::NumberFormat;
+use grouping::FloatGrouping;
+use display::DisplayFamily;
+use normalize::Normalizer;
+use os::{OsInfo, Version};
+use output::{FileSink, OutputSink};
+use parse::{EmptyInput, ParseReport};
+use provenance::{Provenance, SOURCE_FILE, SOURCE_LINE};
+use reader::CellReader;
+use resolution::{AspectRatio, Resolution, ResolutionClass};
+use schema::Schema;
+use sensor::Sensor;
+use date::LaunchDate;
+use sim::SimInfo;
+use status::LaunchStatus;
+use view::DatasetView;
+use weight::Weight;
+use std::fs::File;
+use std::io::BufReader;
+use std::sync::Arc;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+/// One phone of the dataset, a row of cells.csv. Values that are blank or '-' in the file are None.
+///
+/// Fields serialize under the CSV header names. Fields missing from a serialized cell are None.
+/// Values of CSV columns that are not one of the twelve are kept in `extras`, by header name,
+/// and a cell read from a file knows its `source` file and line. `sensors` are parsed from
+/// features_sensors when a cell is read from CSV, built, edited or patched; a field set by
+/// hand needs [`Cell::parse_sensors`]. `launch_date` is launch_announced with the month or
+/// quarter the file gives, which the year cleaning stage drops from launch_announced; it is
+/// kept to the same year by the same setters, or by [`Cell::sync_launch_date`]. Likewise
+/// `release_date` is when a released phone went on sale, from the file's "Released 2019,
+/// September", kept to the status by [`Cell::sync_release_date`]. A foldable that lists a
+/// second display after its main one keeps it in `cover_display_size` and
+/// `cover_display_resolution`; display_size and display_resolution are the main display.
+/// `tags` are curation notes such as "verified" from the file's tag sidecar (see
+/// [`tags::Tags`]); they are not a column.
+pub struct Cell {
+    pub oem: Option<Arc<str>>,
+    pub model: Option<Box<str>>,
+    pub launch_announced: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub launch_date: Option<LaunchDate>,
+    pub launch_status: Option<Arc<str>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub release_date: Option<LaunchDate>,
+    pub body_dimensions: Option<Box<str>>,
+    pub body_weight: Option<f32>,
+    pub body_sim: Option<Arc<str>>,
+    pub display_type: Option<Arc<str>>,
+    pub display_size: Option<f32>,
+    pub display_resolution: Option<Box<str>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cover_display_size: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cover_display_resolution: Option<Box<str>>,
+    pub features_sensors: Option<Box<str>>,
+    pub platform_os: Option<Arc<str>>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub sensors: Vec<Sensor>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub extras: BTreeMap<String, String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<Provenance>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+}
+
+/// A model name and its body weight in grams.
+pub type ModelWeight = (String, f32);
+
+/// Launch analyses look at the years after this one unless told otherwise.
+pub const DEFAULT_AFTER_YEAR: u32 = 1999;
+
+/// An OEM x year pivot has a column for every year in between when its years span no more
+/// than this; a wider span (e.g. a junk year) only gets the years with launches.
+pub const PIVOT_MAX_SPAN: u32 = 100;
+
+/// Names of operating systems that make a phone count as a smartphone.
+const SMARTPHONE_OS: [&str; 14] = [
+    "android", "ios", "ipados", "windows", "microsoft", "symbian", "blackberry", "webos",
+    "tizen", "bada", "sailfish", "firefox", "maemo", "meego",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// Whether a phone runs a named smartphone OS or is a feature phone.
+pub enum DeviceClass {
+    Smartphone,
+    FeaturePhone,
+}
+
+impl fmt::Display for DeviceClass {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DeviceClass::Smartphone => write!(f, "Smartphone"),
+            DeviceClass::FeaturePhone => write!(f, "Feature phone"),
+        }
+    }
+}
+
+impl Default for Cell {
+    fn default() -> Cell {
+        Cell::new()
+    }
+}
+
+// Implements cell.
+impl Cell {
+    /**
+     Initializes all the variable values
+     Runtime: O(1)
+     */
+    pub fn new() -> Cell {
+        Cell {
+            oem: None,
+            model: None,
+            launch_announced: None,
+            launch_date: None,
+            launch_status: None,
+            release_date: None,
+            body_dimensions: None,
+            body_weight: None,
+            body_sim: None,
+            display_type: None,
+            display_size: None,
+            display_resolution: None,
+            cover_display_size: None,
+            cover_display_resolution: None,
+            features_sensors: None,
+            platform_os: None,
+            sensors: Vec::new(),
+            extras: BTreeMap::new(),
+            source: None,
+            tags: Vec::new(),
+        }
+    }
+
+    /**
+        Read the CSV file using the csv library.
+        Return a vector of each line of the CSV file.
+        Each cell corresponds to a value from the struct variables.
+
+        Runtime: O(n)
+     */
+    pub fn read_csv(filename: &str) -> Result<Vec<Cell>, CellError> {
+        Self::read_csv_with(filename, &mut Normalizer::default())
+    }
+
+    /**
+        Read the CSV file, cleaning every field with the given normalization stages.
+        The normalizer keeps count of what each stage changed.
+
+        Runtime: O(n)
+     */
+    pub fn read_csv_with(filename: &str, normalizer: &mut Normalizer) -> Result<Vec<Cell>, CellError> {
+        Self::read_csv_with_schema(filename, &Schema::default(), normalizer)
+    }
+
+    /**
+        Read a CSV file whose headers are named or ordered differently, finding each column
+        through the schema before cleaning the fields.
+
+        Runtime: O(n)
+     */
+    pub fn read_csv_with_schema(filename: &str, schema: &Schema, normalizer: &mut Normalizer) -> Result<Vec<Cell>, CellError> {
+        let mut reader = CellReader::open_with(filename, schema, std::mem::take(normalizer))?;
+        let cells = reader.by_ref().collect::<Result<Vec<Cell>, CellError>>();
+
+        *normalizer = reader.into_normalizer();
+        cells
+    }
+
+    /**
+        Read the CSV file like read_csv_with, but clean and parse the records on every core.
+        The records are read in chunks of CHUNK_ROWS, a batch of one chunk per core at a
+        time, and each batch is parsed in parallel while the next one is read, so at most
+        two batches of raw records are held. The cells are put back together in file order.
+        Each chunk gets its own copy of the normalizer, whose counts are added back and
+        whose interned strings are pooled at the end. Numeric fields that are not numbers
+        are added to the report, as is a file without rows.
+
+        Runtime: O(n / p) for p cores, plus O(n) to read the records
+     */
+    pub fn read_csv_parallel(filename: &str, schema: &Schema, normalizer: &mut Normalizer, report: &mut ParseReport) -> Result<Vec<Cell>, CellError> {
+        const CHUNK_ROWS: usize = 4096;
+        #[cfg(feature = "parallel")]
+        let batch_chunks = rayon::current_num_threads();
+        #[cfg(not(feature = "parallel"))]
+        let batch_chunks = 1;
+
+        let mut reader = csv::Reader::from_reader(BufReader::new(File::open(filename)?));
+        let header_record = reader.headers()?.clone();
+        let headers = HeaderIndex::from_headers_with(&header_record, schema)?.in_file(filename);
+        let mut records = reader.records();
+        let mut read_batch = || -> Result<Vec<Vec<csv::StringRecord>>, csv::Error> {
+            let mut batch = Vec::new();
+            while batch.len() < batch_chunks {
+                let chunk = records.by_ref().take(CHUNK_ROWS).collect::<Result<Vec<_>, _>>()?;
+                if chunk.is_empty() {
+                    break;
+                }
+                batch.push(chunk);
+            }
+            Ok(batch)
+        };
+
+        // The chunks fork this copy, as the normalizer itself takes their counts meanwhile.
+        let template = normalizer.fork();
+        let parse = |chunk: &Vec<csv::StringRecord>| {
+            let mut fork = template.fork();
+            let mut issues = ParseReport::default();
+            let cells: Vec<Cell> = chunk.iter().map(|record| Self::parse_record(record, &headers, &mut fork, &mut issues)).collect();
+            (cells, fork, issues)
+        };
+
+        let mut cells = Vec::new();
+        let mut batch = read_batch()?;
+        while !batch.is_empty() {
+            #[cfg(feature = "parallel")]
+            let (parsed, next) = rayon::join(|| batch.par_iter().map(parse).collect::<Vec<_>>(), &mut read_batch);
+            #[cfg(not(feature = "parallel"))]
+            let (parsed, next) = (batch.iter().map(parse).collect::<Vec<_>>(), read_batch());
+
+            // Each chunk interned with its own pool; sharing them through this one makes
+            // equal values the same string across chunks.
+            for (chunk, fork, issues) in parsed {
+                for mut cell in chunk {
+                    normalizer.interner().intern_cell(&mut cell);
+                    cells.push(cell);
+                }
+                normalizer.merge(&fork);
+                report.extend(issues);
+            }
+            batch = next?;
+        }
+        if cells.is_empty() {
+            report.empty_input = Some(EmptyInput::of(&header_record));
+        }
+
+        Ok(cells)
+    }
+
+    /**
+        Write cells as a CSV file with the usual headers, so it can be read back with
+        read_csv. Missing values are left blank. When any cell knows its source, source_file
+        and source_line columns are added so the rows can still be traced once read back.
+
+        Runtime: O(n)
+     */
+    pub fn write_csv<'a>(cells: impl IntoIterator<Item = &'a Cell>, filename: &str) -> Result<(), CellError> {
+        Cell::write_csv_to(cells, &mut FileSink::create(filename)?)
+    }
+
+    // Writes cells as CSV like write_csv, to any sink, e.g. a buffer or stdout.
+    pub fn write_csv_to<'a>(cells: impl IntoIterator<Item = &'a Cell>, sink: &mut dyn OutputSink) -> Result<(), CellError> {
+        let cells: Vec<&Cell> = cells.into_iter().collect();
+        let with_source = cells.iter().any(|cell| cell.source.is_some());
+        let mut writer = csv::Writer::from_writer(sink);
+        let mut headers: Vec<&str> = Column::ALL.iter().map(|column| column.name()).collect();
+        if with_source {
+            headers.extend([SOURCE_FILE, SOURCE_LINE]);
+        }
+        writer.write_record(headers)?;
+
+        for cell in cells {
+            let mut record: Vec<String> = Column::ALL.iter().map(|&column| cell.text(column).unwrap_or_default()).collect();
+            if with_source {
+                let source = cell.source.as_ref();
+                record.push(source.map(|source| source.file.to_string()).unwrap_or_default());
+                record.push(source.map(|source| source.line.to_string()).unwrap_or_default());
+            }
+            writer.write_record(record)?;
+        }
+        writer.flush()?;
+
+        Ok(())
+    }
+
+    /**
+        Write cells as a JSON array, e.g. to cache a cleaned dataset.
+
+        Runtime: O(n)
+     */
+    pub fn write_json(cells: &[Cell], filename: &str) -> Result<(), CellError> {
+        Cell::write_json_to(cells, &mut FileSink::create(filename)?)
+    }
+
+    // Writes cells as JSON like write_json, to any sink.
+    pub fn write_json_to(cells: &[Cell], sink: &mut dyn OutputSink) -> Result<(), CellError> {
+        serde_json::to_writer(&mut *sink, cells)?;
+        sink.flush()?;
+
+        Ok(())
+    }
+
+    /**
+        Read cells written by write_json. The values are used as they are, without cleaning;
+        cells written without a launch_date or release_date get the bare years.
+
+        Runtime: O(n)
+     */
+    pub fn read_json(filename: &str) -> Result<Vec<Cell>, CellError> {
+        let reader = BufReader::new(File::open(filename)?);
+        let mut cells: Vec<Cell> = serde_json::from_reader(reader)?;
+        for cell in &mut cells {
+            cell.sync_launch_date();
+            cell.sync_release_date();
+        }
+
+        Ok(cells)
+    }
+
+    /**
+        Build a cell from one CSV record, finding the fields with the file's header index.
+        Numeric columns whose cleaned value is not a number become None.
+
+        Runtime: O(1)
+     */
+    pub fn from_record(record: &csv::StringRecord, headers: &HeaderIndex, normalizer: &mut Normalizer) -> Cell {
+        Self::parse_record(record, headers, normalizer, &mut ParseReport::default())
+    }
+
+    /**
+        Like from_record, but every numeric field that is not a number is also added to the
+        report with its line number, as is every number outside the normalizer's bounds.
+
+        Runtime: O(1)
+     */
+    pub fn parse_record(record: &csv::StringRecord, headers: &HeaderIndex, normalizer: &mut Normalizer, report: &mut ParseReport) -> Cell {
+        // The header is line 1. Counted from the record number, as the csv crate's own line
+        // count runs one short on files with \r\n line endings.
+        let line = record.position().map_or(0, |position| position.record() as usize + 1);
+        let [oem, model, launch_announced, launch_status, body_dimensions, body_weight, body_sim, display_type, display_size, display_resolution, features_sensors, platform_os] =
+            std::array::from_fn(|index| {
+                let (column, raw) = (Column::ALL[index], headers.get(record, Column::ALL[index]));
+                let cleaned = normalizer.normalize(column, raw);
+                report.count_field(column, raw, cleaned.as_deref());
+                cleaned
+            });
+        let mut cell = Cell::new();
+
+        // Low-cardinality columns share one string per distinct value.
+        let interner = normalizer.interner();
+        let mut shared = |value: Option<String>| value.map(|value| interner.intern(&value));
+        cell.oem = shared(oem);
+        cell.model = model.map(Into::into);
+        cell.launch_status = shared(launch_status);
+        cell.body_dimensions = body_dimensions.map(Into::into);
+        cell.body_sim = shared(body_sim);
+        cell.display_type = shared(display_type);
+        cell.display_resolution = display_resolution.map(Into::into);
+        cell.features_sensors = features_sensors.map(Into::into);
+        cell.platform_os = shared(platform_os);
+        cell.parse_sensors();
+        cell.extras = headers.extras(record);
+        cell.source = headers.provenance(record, line);
+
+        let (announced, status) = (headers.get(record, Column::LaunchAnnounced), headers.get(record, Column::LaunchStatus));
+        cell.launch_announced = Self::parse_number(launch_announced, announced, line, Column::LaunchAnnounced, report);
+        cell.launch_date = LaunchDate::parse(announced);
+        cell.sync_launch_date();
+        // The release may also follow the announcement, as in "2010, January. Released 2010, March".
+        cell.release_date = LaunchDate::released(status).or_else(|| LaunchDate::released(announced));
+        cell.sync_release_date();
+        cell.body_weight = Self::parse_number(body_weight, headers.get(record, Column::BodyWeight), line, Column::BodyWeight, report);
+        cell.display_size = Self::parse_number(display_size, headers.get(record, Column::DisplaySize), line, Column::DisplaySize, report);
+
+        // Foldables list a cover display after the main one, which the cleaning drops.
+        cell.cover_display_size = display::cover_display_size(headers.get(record, Column::DisplaySize));
+        cell.cover_display_resolution = display::cover_display_resolution(headers.get(record, Column::DisplayResolution)).map(Into::into);
+
+        // The cleaned weight is the first number, which is in ounces when the text has no
+        // grams; the two units of one weight should agree.
+        let weight = Weight::parse(headers.get(record, Column::BodyWeight));
+        if let (true, Some(_), Some(grams)) = (weight.grams.is_empty(), cell.body_weight, weight.ounces_in_grams()) {
+            cell.body_weight = Some(grams);
+        }
+        if weight.mismatch() {
+            report.record_weight_mismatch(line, &weight);
+        }
+
+        let bounds = normalizer.bounds();
+        cell.body_weight = bounds.check(line, Column::BodyWeight, cell.body_weight, report);
+        cell.display_size = bounds.check(line, Column::DisplaySize, cell.display_size, report);
+
+        cell
+    }
+
+    // Parses the cleaned value of a numeric field, reporting the raw text if the cleaning
+    // found no number in it or it isn't a finite one. Blank and '-' values are missing
+    // rather than malformed, so they aren't reported.
+    fn parse_number<T: std::str::FromStr>(value: Option<String>, raw: &str, line: usize, column: Column, report: &mut ParseReport) -> Option<T> {
+        let finite = |value: &String| value.parse::<f64>().is_ok_and(f64::is_finite);
+        let number = value.as_deref().and_then(Self::check_empty).filter(finite).and_then(|value| value.parse().ok());
+        if number.is_none() && !record::is_missing(raw) {
+            report.record(line, column, raw.trim());
+        }
+
+        number
+    }
+
+    /**
+        Keep launch_date to launch_announced: a date of another year, or none at all, becomes
+        the bare year, and a cell without a year has no date.
+
+        Runtime: O(1)
+     */
+    pub fn sync_launch_date(&mut self) {
+        self.launch_date = self.launch_announced.map(|year| self.launch_date.filter(|date| date.year == year).unwrap_or(LaunchDate::of_year(year)));
+    }
+
+    /**
+        Keep release_date to the launch status: a date of another year than the status's
+        release year, or none at all, becomes that bare year, and a phone that is not
+        released has no release date.
+
+        Runtime: O(1)
+     */
+    pub fn sync_release_date(&mut self) {
+        let year = self.status().and_then(|status| status.released_year());
+        self.release_date = year.map(|year| self.release_date.filter(|date| date.year == year).unwrap_or(LaunchDate::of_year(year)));
+    }
+
+    /// Checks if the value passed in is '-' or blank. If yes, replace it with the value None
+    fn check_empty(value: &str) -> Option<String> {
+        (!record::is_missing(value)).then(|| value.to_string())
+    }
+
+    /**
+        Classify the phone from its platform_os. A phone without an OS, or with an OS
+        that is not in SMARTPHONE_OS, is a feature phone.
+
+        Runtime: O(1)
+     */
+    pub fn device_class(&self) -> DeviceClass {
+        match &self.platform_os {
+            Some(os) => {
+                let os = os.to_lowercase();
+                if SMARTPHONE_OS.iter().any(|name| os.starts_with(name)) {
+                    DeviceClass::Smartphone
+                } else {
+                    DeviceClass::FeaturePhone
+                }
+            }
+            None => DeviceClass::FeaturePhone,
+        }
+    }
+
+    /// The parsed launch_status, if it is known and one of the statuses LaunchStatus knows.
+    pub fn status(&self) -> Option<LaunchStatus> {
+        self.launch_status.as_deref().and_then(LaunchStatus::parse)
+    }
+
+    /**
+        Group the launch status into Available, Discontinued, Cancelled or Coming soon. The
+        year cleaning stage leaves just the release year of available phones, so a bare year
+        counts as Available, as does a status LaunchStatus doesn't know.
+
+        Runtime: O(1)
+     */
+    pub fn status_category(&self) -> Option<&'static str> {
+        self.launch_status.as_ref()?;
+        Some(self.status().map_or("Available", |status| status.category()))
+    }
+
+    /// The display technology family of the phone's display_type.
+    pub fn display_family(&self) -> Option<DisplayFamily> {
+        self.display_type.as_deref().map(DisplayFamily::from_display_type)
+    }
+
+    /// The parsed body_dimensions, if they give a height, width and depth in millimetres.
+    pub fn dimensions(&self) -> Option<Dimensions> {
+        self.body_dimensions.as_deref().and_then(Dimensions::parse)
+    }
+
+    /// Estimated share of the front taken by the screen, from display size, resolution and dimensions.
+    pub fn screen_to_body_ratio(&self) -> Option<f32> {
+        let aspect_ratio = self.display_resolution.as_deref().and_then(dimensions::aspect_ratio)?;
+        self.dimensions()?.screen_to_body_ratio(self.display_size?, aspect_ratio)
+    }
+
+    /// The pixels of the display, if display_resolution gives them.
+    pub fn resolution(&self) -> Option<Resolution> {
+        self.display_resolution.as_deref().and_then(Resolution::parse)
+    }
+
+    /// The shape of the display, e.g. 16:9, if display_resolution gives the pixels.
+    pub fn aspect_ratio(&self) -> Option<AspectRatio> {
+        self.resolution().map(|resolution| AspectRatio::of(&resolution))
+    }
+
+    /// Pixel density of the display, from its resolution and display_size, if both are known
+    /// and give a plausible density.
+    pub fn ppi(&self) -> Option<f32> {
+        self.resolution()?.ppi(self.display_size?)
+    }
+
+    /// The resolution class of the display, if display_resolution gives the pixels.
+    pub fn resolution_class(&self) -> Option<ResolutionClass> {
+        self.display_resolution.as_deref().and_then(ResolutionClass::from_display_resolution)
+    }
+
+    /// Parses `sensors` again from features_sensors, e.g. after setting the field by hand.
+    pub fn parse_sensors(&mut self) {
+        self.sensors = self.features_sensors.as_deref().map_or_else(Vec::new, Sensor::parse_list);
+    }
+
+    /// The number of sensors features_sensors lists, if it is known.
+    pub fn sensor_count(&self) -> Option<usize> {
+        self.features_sensors.as_deref().map(|sensors| Sensor::parse_list(sensors).len())
+    }
+
+    /// The family and version of the OS the phone shipped with, if platform_os is known.
+    pub fn os(&self) -> Option<OsInfo> {
+        self.platform_os.as_deref().and_then(OsInfo::parse)
+    }
+
+    /// The newest OS version platform_os says the phone is upgradable to, if it names one.
+    pub fn os_max_version(&self) -> Option<Version> {
+        self.os()?.max_version
+    }
+
+    /// The SIM slots described by body_sim, if it is known.
+    pub fn sim(&self) -> Option<SimInfo> {
+        self.body_sim.as_deref().map(SimInfo::parse)
+    }
+
+    /// Looks up the country of origin of the phone's OEM.
+    pub fn country<'a>(&self, countries: &'a CountryMap) -> Option<&'a str> {
+        self.oem.as_deref().and_then(|oem| countries.country_of(oem))
+    }
+
+    /**
+        Function used to check what share of the phones launched each year were smartphones.
+        Returns (year, share) pairs sorted by year, where share is between 0 and 1.
+
+        Runtime: O(n)
+     */
+    pub fn smartphone_share_per_year(cells: &DatasetView) -> Vec<(u32, f32)> {
+        let mut year_counts: HashMap<u32, (usize, usize)> = HashMap::new();
+
+        for cell in cells {
+            if let Some(year) = cell.launch_announced {
+                let entry = year_counts.entry(year).or_insert((0, 0));
+                if cell.device_class() == DeviceClass::Smartphone {
+                    entry.0 += 1;
+                }
+                entry.1 += 1;
+            }
+        }
+
+        let mut shares: Vec<(u32, f32)> = year_counts
+            .into_iter()
+            .map(|(year, (smartphones, total))| (year, smartphones as f32 / total as f32))
+            .collect();
+        shares.sort_by_key(|&(year, _)| year);
+
+        shares
+    }
+
+    /**
+        Function used to count the phones announced each year, sorted by year.
+
+        Runtime: O(n log n)
+     */
+    pub fn launches_per_year(cells: &DatasetView) -> Vec<(u32, usize)> {
+        let mut year_counts: HashMap<u32, usize> = HashMap::new();
+
+        for cell in cells {
+            if let Some(year) = cell.launch_announced {
+                *year_counts.entry(year).or_insert(0) += 1;
+            }
+        }
+
+        let mut counts: Vec<(u32, usize)> = year_counts.into_iter().collect();
+        counts.sort();
+
+        counts
+    }
+
+    /**
+        Function used to count the phones of each OEM, most phones first and ties by name. The
+        OEMs are counted by their interned name (see intern::count).
+
+        Runtime: O(n + o log o)
+     */
+    pub fn oem_counts(cells: &DatasetView) -> Vec<(String, usize)> {
+        let oem_counts = intern::count(cells.iter().filter_map(|cell| cell.interned(Column::Oem)));
+
+        let mut counts: Vec<(String, usize)> = oem_counts.into_iter().map(|(oem, count)| (oem.to_string(), count)).collect();
+        counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+
+        counts
+    }
+
+    /**
+        Function used to count the launches of every OEM in every year. Returns the years, every
+        one from the first to the last announcement unless they span more than PIVOT_MAX_SPAN
+        (then only those with launches), and per OEM (in oem_counts order) one count per year.
+        The counts are kept per year launched, so a stray year costs no more than another, and
+        grouped by interned OEM name (see intern::group).
+
+        Runtime: O(n log n + o * y)
+     */
+    pub fn oem_year_pivot(cells: &DatasetView) -> (Vec<u32>, Vec<(String, Vec<usize>)>) {
+        let launches = cells.iter().filter_map(|cell| Some((cell.interned(Column::Oem)?, cell.launch_announced?)));
+        let add = |counts: &mut BTreeMap<u32, usize>, year: u32| *counts.entry(year).or_insert(0) += 1;
+        let merge = |counts: &mut BTreeMap<u32, usize>, other: BTreeMap<u32, usize>| other.into_iter().for_each(|(year, count)| *counts.entry(year).or_insert(0) += count);
+        let mut pivot: HashMap<&str, BTreeMap<u32, usize>> = intern::group(launches, add, merge).into_iter().collect();
+        let launched: BTreeSet<u32> = pivot.values().flat_map(|counts| counts.keys().copied()).collect();
+        let years: Vec<u32> = match (launched.first(), launched.last()) {
+            (Some(&first), Some(&last)) if last - first < PIVOT_MAX_SPAN => (first..=last).collect(),
+            _ => launched.into_iter().collect(),
+        };
+
+        let rows = Cell::oem_counts(cells)
+            .into_iter()
+            .filter_map(|(oem, _)| {
+                let counts = pivot.remove(oem.as_str())?;
+                Some((oem, years.iter().map(|year| counts.get(year).copied().unwrap_or(0)).collect()))
+            })
+            .collect();
+
+        (years, rows)
+    }
+
+    /**
+        Function used to rank the years after `after_year` by how many phones were announced
+        in them. Returns (year, count) with the most launches first and ties by year.
+
+        Runtime: O(n log n)
+     */
+    pub fn years_ranked_by_launches_after(cells: &DatasetView, after_year: u32) -> Vec<(u32, usize)> {
+        let mut ranked: Vec<(u32, usize)> = Cell::launches_per_year(cells).into_iter().filter(|&(year, _)| year > after_year).collect();
+        ranked.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+
+        ranked
+    }
+
+    /**
+        Function used to check which year after `after_year` released the most amount of phones.
+        Ties go to the earliest year.
+
+        Runtime: O(n log n)
+     */
+    #[allow(non_snake_case)]
+    pub fn year_most_phones_launched__after_year(cells: &DatasetView, after_year: u32) -> Option<u32> {
+        Cell::years_ranked_by_launches_after(cells, after_year).first().map(|&(year, _)| year)
+    }
+
+    /**
+        Function used to find the lightest and heaviest model of every OEM. Returns
+        (oem, (lightest model, weight), (heaviest model, weight)) sorted by OEM; on equal
+        weights the first phone in the file wins. Phones without a weight are skipped, and the
+        OEMs are grouped by their interned name (see intern::group).
+
+        Runtime: O(n log n)
+     */
+    pub fn weight_extremes_per_oem(cells: &DatasetView) -> Vec<(String, ModelWeight, ModelWeight)> {
+        let weight = |index: usize| cells[index].body_weight.unwrap_or_default();
+        // Indexes of the lightest and heaviest of two pairs of cells.
+        let extremes = |(light, heavy): (usize, usize), (other_light, other_heavy): (usize, usize)| {
+            let light = if (weight(other_light), other_light) < (weight(light), light) { other_light } else { light };
+            let heavy = if (weight(other_heavy), Reverse(other_heavy)) > (weight(heavy), Reverse(heavy)) { other_heavy } else { heavy };
+            (light, heavy)
+        };
+        let add = |pair: &mut Option<(usize, usize)>, index: usize| *pair = Some(pair.map_or((index, index), |pair| extremes(pair, (index, index))));
+        let merge = |pair: &mut Option<(usize, usize)>, other: Option<(usize, usize)>| *pair = pair.zip(other).map(|(pair, other)| extremes(pair, other)).or(*pair).or(other);
+
+        let weighed = cells.iter().enumerate().filter(|(_, cell)| cell.model.is_some() && cell.body_weight.is_some());
+        let extremes = intern::group(weighed.filter_map(|(index, cell)| Some((cell.interned(Column::Oem)?, index))), add, merge);
+
+        let model_weight = |index: usize| (cells[index].model.as_deref().unwrap_or_default().to_string(), weight(index));
+        let mut rows: Vec<(String, ModelWeight, ModelWeight)> = extremes
+            .into_iter()
+            .filter_map(|(oem, pair)| pair.map(|(light, heavy)| (oem.to_string(), model_weight(light), model_weight(heavy))))
+            .collect();
+        rows.sort_by(|a, b| a.0.cmp(&b.0));
+
+        rows
+    }
+
+    /**
+        Function used to check which phone oem had the highest average weight. The OEMs are
+        grouped by their interned name (see intern::group).
+
+        Runtime: O(n)
+     */
+    pub fn highest_avg_body_weight_oem(cells: &DatasetView) -> Option<String> {
+        let weights = cells.iter().filter_map(|cell| Some((cell.interned(Column::Oem)?, cell.body_weight?)));
+        let add = |total: &mut (f32, usize), weight: f32| *total = (total.0 + weight, total.1 + 1);
+        let merge = |total: &mut (f32, usize), other: (f32, usize)| *total = (total.0 + other.0, total.1 + other.1);
+        let oem_weights = intern::group(weights, add, merge);
+
+        if oem_weights.is_empty() {
+            return None;
+        }
+
+        let (oem, (_, _)) = oem_weights
+            .into_iter()
+            .max_by(|(_, (avg1, count1)), (_, (avg2, count2))| {
+                (avg1 / *count1 as f32)
+                    .partial_cmp(&(avg2 / *count2 as f32))
+                    .unwrap_or(Ordering::Equal)
+            })?;
+
+        Some(oem.to_string())
+    }
+
+    /**
+        Function used which phones had a single sensor, counting the parsed sensors, so a
+        note such as "Fingerprint (under display, optical)" is one sensor.
+
+        Runtime: O(n)
+     */
+    pub fn count_phones_with_single_sensor(cells: &DatasetView) -> usize {
+        cells.iter().filter(|cell| cell.sensors.len() == 1).count()
+    }
+
+    /**
+        Whether the phone folds: its display_type says "Foldable", its body_dimensions give a
+        folded size, or it has a cover display.
+
+        Runtime: O(1)
+     */
+    pub fn is_foldable(&self) -> bool {
+        self.display_type.as_deref().is_some_and(|display_type| display_type.to_lowercase().contains("foldable"))
+            || self.body_dimensions.as_deref().is_some_and(|dimensions| dimensions.contains("Folded:"))
+            || self.cover_display_size.is_some()
+    }
+
+    /**
+        How many phones fold, and how many of those list a cover display.
+
+        Runtime: O(n)
+     */
+    pub fn count_foldables(cells: &DatasetView) -> (usize, usize) {
+        cells.iter().filter(|cell| cell.is_foldable()).fold((0, 0), |(foldables, covers), cell| (foldables + 1, covers + usize::from(cell.cover_display_size.is_some())))
+    }
+
+    /**
+        Function used to check which phones were announced one year, but released on a different year.
+        Only released phones have a release year, so discontinued, cancelled and coming
+        phones are left out.
+
+        Runtime: O(n)
+     */
+    pub fn phones_announced_in_one_year_released_in_another(cells: &DatasetView) -> Vec<(String, String)> {
+        let mut mismatched_years = Vec::new();
+
+        for cell in cells {
+            if let (Some(announced_year), Some(released_year)) = (cell.launch_announced, cell.status().and_then(|status| status.released_year())) {
+                if announced_year != released_year {
+                    if let (Some(oem), Some(model)) = (&cell.oem, &cell.model) {
+                        mismatched_years.push((oem.to_string(), model.to_string()));
+                    }
+                }
+            }
+        }
+
+        mismatched_years
+    }
+
+    /**
+        The phones that never went on sale, cancelled or still coming, as (OEM, model) in
+        file order.
+
+        Runtime: O(n)
+     */
+    pub fn never_released(cells: &DatasetView) -> Vec<(String, String)> {
+        cells
+            .iter()
+            .filter(|cell| cell.status().is_some_and(|status| status.never_released()))
+            .map(|cell| (cell.oem.as_deref().unwrap_or_default().to_string(), cell.model.as_deref().unwrap_or_default().to_string()))
+            .collect()
+    }
+
+    /**
+        Function used to check which oem was the most common in the file.
+
+        Runtime: O(n)
+     */
+    pub fn most_common_oem(cells: &DatasetView) -> Option<String> {
+        analysis::mode(cells, Column::Oem, 1, &FloatGrouping::DEFAULT).into_iter().next().map(|(oem, _)| oem)
+    }
+
+    /**
+        Function used to check what the most common display size is, with sizes grouped to
+        0.1 inch so that 6.0 and 6.01 count as the same size.
+
+        Runtime: O(n)
+     */
+    pub fn most_common_display_size(cells: &DatasetView) -> Option<String> {
+        analysis::mode(cells, Column::DisplaySize, 1, &FloatGrouping::DEFAULT).into_iter().next().map(|(size, _)| size)
+    }
+
+    /**
+        Function used to the mean (average) body weight.
+
+        Runtime: O(n)
+     */
+    pub fn mean_body_weight(cells: &DatasetView) -> Option<f32> {
+        let mut sum = 0.0;
+        let mut count = 0;
+
+        for cell in cells {
+            if let Some(weight) = cell.body_weight {
+                sum += weight;
+                count += 1;
+            }
+        }
+
+        if count > 0 {
+            Some(sum / count as f32)
+        } else {
+            None
+        }
+    }
+
+    /**
+        Function used to check the median body weight throughout the file.
+
+        Runtime: O(n)
+     */
+    pub fn median_body_weight(cells: &DatasetView) -> Option<f32> {
+        let mut weights: Vec<f32> = cells.iter().filter_map(|cell| cell.body_weight).collect();
+        weights.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+
+        let len = weights.len();
+        if len == 0 {
+            None
+        } else if len.is_multiple_of(2) {
+            Some((weights[len / 2 - 1] + weights[len / 2]) / 2.0)
+        } else {
+            Some(weights[len / 2])
+        }
+    }
+
+    /**
+        The headline statistics as (label, value) pairs, for exporters, with the weights written
+        by `format` and the busiest launch year counted after `after_year`. Missing results are
+        shown as "-".
+
+        Runtime: O(n log n)
+     */
+    pub fn summary_stats(cells: &DatasetView, format: &NumberFormat, after_year: u32) -> Vec<(String, String)> {
+        let or_dash = |value: Option<String>| value.unwrap_or_else(|| "-".to_string());
+
+        vec![
+            ("Phones".to_string(), cells.len().to_string()),
+            ("Most common OEM".to_string(), or_dash(Cell::most_common_oem(cells))),
+            ("Most common display size".to_string(), or_dash(Cell::most_common_display_size(cells))),
+            ("Mean body weight (g)".to_string(), or_dash(Cell::mean_body_weight(cells).map(|mean| format.format(mean as f64)))),
+            ("Median body weight (g)".to_string(), or_dash(Cell::median_body_weight(cells).map(|median| format.format(median as f64)))),
+            ("Heaviest OEM on average".to_string(), or_dash(Cell::highest_avg_body_weight_oem(cells))),
+            ("Phones with a single sensor".to_string(), Cell::count_phones_with_single_sensor(cells).to_string()),
+            (
+                format!("Year with most launches after {}", after_year),
+                or_dash(Cell::year_most_phones_launched__after_year(cells, after_year).map(|year| year.to_string())),
+            ),
+        ]
+    }
+
+    /**
+        Insert a new Cell into the vector at `index`, which may be the end. This does not
+        affect the file itself. No cell is displaced, so the result is always None; it has the
+        same type as modify_cell and delete_cell so callers can log or undo all three alike.
+
+        Runtime: O(n)
+     */
+    pub fn insert_cell(cells: &mut Vec<Cell>, index: usize, new_cell: Cell) -> Result<Option<Cell>, CellError> {
+        record::insert(cells, index, new_cell)
+    }
+
+    /**
+        Replace an existing Cell within the vector, returning the cell it replaced.
+
+        Runtime: O(1)
+     */
+    #[allow(clippy::ptr_arg)]
+    pub fn modify_cell(cells: &mut Vec<Cell>, index: usize, modified_cell: Cell) -> Result<Option<Cell>, CellError> {
+        record::modify(cells, index, modified_cell)
+    }
+
+    /**
+        Delete an existing Cell within the vector, returning the removed cell.
+
+        Runtime: O(n)
+     */
+    pub fn delete_cell(cells: &mut Vec<Cell>, index: usize) -> Result<Option<Cell>, CellError> {
+        record::delete(cells, index)
+    }
+}
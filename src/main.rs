@@ -1,428 +1,222 @@
-mod test;
-
-use std::cmp::Ordering;
-use std::collections::HashMap;
-use csv;
-use regex::Regex;
+use alternate_language_assignment::cli::{self, Cli, Command, ReportAllArgs, ReportArgs};
+use alternate_language_assignment::config::{Config, DEFAULT_CONFIG_FILE};
+use alternate_language_assignment::country::CountryMap;
+use alternate_language_assignment::dataset::Dataset;
+use alternate_language_assignment::filter::{self, Filter};
+use alternate_language_assignment::output;
+use alternate_language_assignment::parse::ParseMode;
+use alternate_language_assignment::random::Rng;
+use alternate_language_assignment::report::{self, Analyses, Context, Report, ReportFormat, Sample, Style};
+use alternate_language_assignment::timing::Timings;
+use alternate_language_assignment::tui;
+use clap::Parser;
 use std::error::Error;
-use std::fs::File;
+use std::time::Instant;
 
-#[derive(Debug)]
-// Create a struct called Cell; create the variables and their respective types.
-pub struct Cell {
-    oem: Option<String>,
-    model: Option<String>,
-    launch_announced: Option<u32>,
-    launch_status: Option<String>,
-    body_dimensions: Option<String>,
-    body_weight: Option<f32>,
-    body_sim: Option<String>,
-    display_type: Option<String>,
-    display_size: Option<f32>,
-    display_resolution: Option<String>,
-    features_sensors: Option<String>,
-    platform_os: Option<String>,
+fn main() {
+    // Errors are printed with Display, so reports such as a strict parse failure stay readable.
+    if let Err(error) = run(&Cli::parse()) {
+        eprintln!("Error: {}", error);
+        std::process::exit(1);
+    }
 }
 
-// Implements cell.
-impl Cell {
-    /*
-     Initializes all the variable values
-     Runtime: O(1)
-     */
-    fn new() -> Cell {
-        Cell {
-            oem: None,
-            model: None,
-            launch_announced: None,
-            launch_status: None,
-            body_dimensions: None,
-            body_weight: None,
-            body_sim: None,
-            display_type: None,
-            display_size: None,
-            display_resolution: None,
-            features_sensors: None,
-            platform_os: None,
-        }
+fn run(args: &Cli) -> Result<(), Box<dyn Error>> {
+    let mut config = Config::load_or_default(DEFAULT_CONFIG_FILE)?;
+    if args.schema.is_some() {
+        config.schema = args.schema.clone();
+    }
+    if args.strict {
+        config.parse_mode = ParseMode::Strict;
+    }
+    if args.raw_text {
+        config.raw_text = true;
+    }
+    if let Some(decimals) = args.decimals {
+        config.number_format.decimals = decimals;
+    }
+    if args.min_sample.is_some() {
+        config.min_sample_size = args.min_sample;
+    }
+    let file = args.file.as_str();
+
+    match &args.command {
+        None => report(args, &config, &ReportArgs::default(), ReportFormat::Text),
+        Some(Command::Report(report_args)) if report_args.list => {
+            Analyses::default().names().for_each(|name| println!("{}", name));
+            Ok(())
+        }
+        Some(Command::Report(report_args)) if report_args.list_presets => {
+            cli::list_presets();
+            Ok(())
+        }
+        Some(Command::Report(report_args)) => report(args, &config, report_args, if report_args.json { ReportFormat::Json } else { ReportFormat::Text }),
+        Some(Command::ReportAll(all_args)) => report_all(args, &config, all_args),
+        Some(Command::Stats(stats_args)) => cli::stats(file, stats_args, &config),
+        Some(Command::Query(query_args)) => cli::query(file, query_args, &config),
+        Some(Command::Show(show_args)) => cli::show(file, show_args, &config),
+        Some(Command::Crud { command }) => cli::edit(file, command, &config),
+        Some(Command::Tag { command }) => cli::tag(file, command, &config),
+        Some(Command::Breakdown(breakdown_args)) => cli::breakdown(file, breakdown_args, &config),
+        Some(Command::Etl(etl_args)) => cli::etl(file, etl_args, &config),
+        Some(Command::Head(look_args)) => cli::quick_look("head", file, look_args, None, &config),
+        Some(Command::Tail(look_args)) => cli::quick_look("tail", file, look_args, None, &config),
+        Some(Command::Sample(sample_args)) => cli::quick_look("sample", file, &sample_args.look, sample_args.seed, &config),
+        Some(Command::Heatmap(heatmap_args)) => cli::heatmap(file, heatmap_args, &config),
+        Some(Command::Latex(latex_args)) => cli::latex(file, latex_args, args.after_year, &config),
+        Some(Command::Map(map_args)) => cli::map(file, map_args, &config),
+        Some(Command::Describe) => cli::describe(file, &config),
+        Some(Command::Mode(mode_args)) => cli::mode(file, mode_args, &config),
+        Some(Command::History(history_args)) => cli::history(file, history_args, &config),
+        Some(Command::Histogram(histogram_args)) => cli::histogram(file, histogram_args, &config),
+        Some(Command::Regression(regression_args)) => cli::regression(file, regression_args, &config),
+        Some(Command::Metrics) => {
+            cli::metrics();
+            Ok(())
+        }
+        Some(Command::CompareGroups(compare_args)) => cli::compare_groups(file, compare_args, &config),
+        Some(Command::Partition(partition_args)) => cli::partition(file, partition_args, &config),
+        Some(Command::Watch(watch_args)) => cli::watch(file, watch_args, &config),
+        Some(Command::Repl) => cli::repl(file, args.after_year, &config),
+        Some(Command::Import(import_args)) => cli::import(import_args, &config, DEFAULT_CONFIG_FILE),
+        Some(Command::MakeFixture(fixture_args)) => cli::make_fixture(fixture_args),
+        Some(Command::View(columns_args)) => cli::view(file, columns_args, &config),
+        Some(Command::Tui) => Ok(tui::run(file, &config)?),
     }
+}
 
-    /*
-        Read the CSV file using the csv library.
-        Return a vector of each line of the CSV file.
-        Each cell corresponds to a value from the struct variables.
-
-        Runtime: O(n)
-     */
-    fn read_csv(filename: &str) -> Result<Vec<Cell>, Box<dyn Error>> {
-        let file = File::open(filename)?;
-        let mut reader = csv::Reader::from_reader(file);
-        let mut cells = Vec::new();
-
-        let regex_year = Regex::new(r"\b(\d{4})\b").unwrap();
-        let regex_numeric = Regex::new(r"\d+(\.\d+)?").unwrap();
-
-        for result in reader.records() {
-            let record = result?;
-            let mut cell = Cell::new();
-
-            cell.oem = Some(record.get(0).unwrap_or_default().to_string());
-            cell.model = Some(record.get(1).unwrap_or_default().to_string());
-
-            if let Some(capture) = regex_year.captures(record.get(2).unwrap_or_default()) {
-                cell.launch_announced = Some(capture[0].parse::<u32>().unwrap());
-            } else {
-                cell.launch_announced = None;
-            }
-
-            let status = record.get(3).unwrap_or_default().to_string();
-
-            if let Some(capture) = regex_year.captures(&status) {
-                cell.launch_status = Some(capture[1].to_string());
-            } else {
-                cell.launch_status = Some(status);
-            }
-
-            if let Some(weight_str) = record.get(5) {
-                if let Some(capture) = regex_numeric.captures(weight_str) {
-                    if let Ok(weight) = capture[0].parse::<f32>() {
-                        cell.body_weight = Some(weight);
-                    }
-                }
-            }
-
-            cell.body_dimensions = Self::check_empty(record.get(4).unwrap_or_default());
-            cell.body_sim = Self::check_empty(record.get(6).unwrap_or_default());
-            cell.display_type = Self::check_empty(record.get(7).unwrap_or_default());
-
-            if let Some(size_str) = record.get(8) {
-                if let Some(capture) = regex_numeric.captures(size_str) {
-                    if let Ok(size) = capture[0].parse::<f32>() {
-                        cell.display_size = Some(size);
-                    }
-                }
-            }
-
-            cell.display_resolution = Self::check_empty(record.get(9).unwrap_or_default());
-            cell.features_sensors = Self::check_empty(record.get(10).unwrap_or_default());
-            cell.platform_os = Self::check_empty(record.get(11).unwrap_or_default());
-
-            cells.push(cell);
-        }
-
-        Ok(cells)
-    }
+/*
+    cells report-all --format markdown
+
+    Run every registered analysis into one Report and render it in the --format, as
+    `cells report` does for all of them.
+
+    Runtime: O(n log n)
+ */
+fn report_all(args: &Cli, config: &Config, all_args: &ReportAllArgs) -> Result<(), Box<dyn Error>> {
+    let report_args = ReportArgs {
+        filter: all_args.filter.clone(),
+        preset: all_args.preset.clone(),
+        units: all_args.units.clone(),
+        out: all_args.out.clone(),
+        ..ReportArgs::default()
+    };
+    report(args, config, &report_args, all_args.format)
+}
 
-    // Checks if the value passed in is '-' or blank. If yes, replace it with the value None
-    fn check_empty(value: &str) -> Option<String> {
-        if value.trim().is_empty() || value.trim() == "-" {
-            None
+/*
+    Print the analyses named by --only, or all of them, of the --file rows matching --filter
+    and every --preset.
+    This is what the program does when run without a command. Each analysis returns a typed
+    result that prints as text as it finishes; in the other formats the results are
+    rendered together at the end, JSON as one object keyed by analysis name. They go to
+    stdout or the --out file.
+    With --timings the load and each analysis are timed and summarized at the end, and with
+    --mem-stats the estimated memory of the loaded dataset is printed after loading; both go
+    to stderr unless the format is text. Years are ranked by launches after --after-year
+    (1999 by default).
+    With --quick the analyses run on a random 10% of the matching rows, for fast iteration
+    on a very large file (it is still loaded in full); counts are scaled up to all the rows
+    and the report is labelled approximate.
+    With --units the weights and lengths print in each profile in turn (the config's units
+    by default), all from the one run of the analyses. JSON gives the dataset's units and is
+    written once.
+
+    Runtime: O(n log n)
+ */
+fn report(args: &Cli, config: &Config, report_args: &ReportArgs, format: ReportFormat) -> Result<(), Box<dyn Error>> {
+    let text = format == ReportFormat::Text;
+    let only = &report_args.only;
+    let expression = filter::with_presets(&report_args.filter, &report_args.preset)?;
+    let filter = Filter::parse(&expression)?;
+    let analyses = Analyses::default();
+    if let Some(unknown) = only.iter().find(|name| !analyses.contains(name)) {
+        return Err(format!("unknown analysis '{}' (see `cells report --list`)", unknown).into());
+    }
+    let names: Vec<&str> = analyses.names().filter(|&name| only.is_empty() || only.iter().any(|wanted| wanted == name)).collect();
+    let mut timings = Timings::new(args.timings);
+
+    let start = Instant::now();
+    let (dataset, normalizer) = Dataset::load(&args.file, config)?;
+    timings.record("load", start.elapsed(), dataset.len());
+
+    if args.mem_stats {
+        let usage = format!("Memory used by {} loaded rows:\n{}", dataset.len(), dataset.memory_usage());
+        if !text {
+            eprint!("{}", usage);
         } else {
-            Some(value.to_string())
-        }
-    }
-
-    /*
-        Function used to check which year after the year 1999 released the most amount of phones.
-
-        Runtime: O(n)
-     */
-    fn year_most_phones_launched__after_year(cells: &[Cell]) -> Option<u32> {
-        let mut year_counts: HashMap<u32, usize> = HashMap::new();
-
-        for cell in cells {
-            if let Some(year) = cell.launch_announced {
-                if year > 1999 {
-                    let count = year_counts.entry(year).or_insert(0);
-                    *count += 1;
-                }
-            }
-        }
-
-        year_counts.into_iter().max_by_key(|&(_, count)| count).map(|(year, _)| year)
-    }
-
-    /*
-        Function used to check which phone oem had the highest average weight.
-
-        Runtime: O(n)
-     */
-    fn highest_avg_body_weight_oem(cells: &[Cell]) -> Option<String> {
-        let mut oem_weights: HashMap<String, (f32, usize)> = HashMap::new();
-
-        for cell in cells {
-            if let Some(oem) = &cell.oem {
-                if let Some(weight) = cell.body_weight {
-                    let entry = oem_weights.entry(oem.clone()).or_insert((0.0, 0));
-                    entry.0 += weight;
-                    entry.1 += 1;
-                }
-            }
-        }
-
-        if oem_weights.is_empty() {
-            return None;
-        }
-
-        let (oem, (_, _)) = oem_weights
-            .into_iter()
-            .max_by(|(_, (avg1, count1)), (_, (avg2, count2))| {
-                (avg1 / *count1 as f32)
-                    .partial_cmp(&(avg2 / *count2 as f32))
-                    .unwrap_or(Ordering::Equal)
-            })?;
-
-        Some(oem)
-    }
-
-    /*
-        Function used which phones had a single sensor. This is determined by splitting the cell by ','
-
-        Runtime: O(n)
-     */
-    fn count_phones_with_single_sensor(cells: &[Cell]) -> usize {
-        let mut count = 0;
-
-        for cell in cells {
-            if let Some(sensors_str) = &cell.features_sensors {
-                let sensors = sensors_str.split(',').map(|s| s.trim()).collect::<Vec<&str>>();
-                if sensors.len() == 1 {
-                    count += 1;
-                }
-            }
-        }
-
-        count
-    }
-
-    /*
-        Function used to check which phones were announced one year, but released on a different year.
-
-        Runtime: O(n)
-     */
-    fn phones_announced_in_one_year_released_in_another(cells: &[Cell]) -> Vec<(String, String)> {
-        let mut mismatched_years = Vec::new();
-
-        for cell in cells {
-            if let (Some(announced_year), Some(released_year)) = (cell.launch_announced, &cell.launch_status) {
-                if announced_year != released_year.parse().unwrap_or_default() {
-                    if let (Some(oem), Some(model)) = (&cell.oem, &cell.model) {
-                        mismatched_years.push((oem.clone(), model.clone()));
-                    }
-                }
-            }
-        }
-
-        mismatched_years
-    }
-
-    /*
-        Function used to check which oem was the most common in the file.
-
-        Runtime: O(n)
-     */
-    fn most_common_oem(cells: &[Cell]) -> Option<String> {
-        let mut oem_counts: HashMap<String, usize> = HashMap::new();
-
-        for cell in cells {
-            if let Some(oem) = &cell.oem {
-                let count = oem_counts.entry(oem.clone()).or_insert(0);
-                *count += 1;
-            }
-        }
-
-        oem_counts.into_iter().max_by_key(|&(_, count)| count).map(|(oem, _)| oem)
-    }
-
-    /*
-        Function used to check what the most common display size is.
-
-        Runtime: O(n)
-     */
-    fn most_common_display_size(cells: &[Cell]) -> Option<String> {
-        let mut oem_counts: HashMap<String, usize> = HashMap::new();
-
-        for cell in cells {
-            if let Some(display_size) = &cell.display_size {
-                let count = oem_counts.entry(display_size.clone().to_string()).or_insert(0);
-                *count += 1;
-            }
-        }
-
-        oem_counts.into_iter().max_by_key(|&(_, count)| count).map(|(oem, _)| oem)
-    }
-
-    /*
-        Function used to the mean (average) body weight.
-
-        Runtime: O(n)
-     */
-    fn mean_body_weight(cells: &[Cell]) -> Option<f32> {
-        let mut sum = 0.0;
-        let mut count = 0;
+            print!("{}", usage);
+        }
+    }
+
+    // The analyses only see the matching rows; a view filters them without copying.
+    let matching = dataset.view().matching(&filter);
+    let total = matching.len();
+    let view = if args.quick { matching.sample(report::QUICK_SAMPLE, &mut Rng::from_time()) } else { matching };
+    let sample = args.quick.then(|| Sample { rows: view.len(), of: total });
+    let countries = CountryMap::from_config(config);
+    let units = if report_args.units.is_empty() { vec![config.units] } else { report_args.units.clone() };
+    let context = Context {
+        cells: &view,
+        normalizer: &normalizer,
+        parse_report: dataset.parse_report(),
+        countries: &countries,
+        float_grouping: config.float_grouping,
+        after_year: args.after_year,
+        style: Style { units: units[0], ..Style::from_config(config) },
+    };
 
-        for cell in cells {
-            if let Some(weight) = cell.body_weight {
-                sum += weight;
-                count += 1;
+    // Without rows the report is one line rather than a "None" per analysis.
+    let mut out = output::open(report_args.out.as_deref())?;
+    let nothing = view.is_empty().then(|| cli::nothing_to_analyse(&args.file, dataset.parse_report(), &expression));
+    if let (Some(message), true) = (&nothing, text) {
+        out.write_text(&format!("{}\n", message))?;
+        print!("{}", timings.report());
+        return Ok(());
+    }
+
+    if let (Some(sample), true) = (&sample, text) {
+        out.write_text(&sample.to_string())?;
+    }
+    if text && units.len() > 1 {
+        out.write_text(&format!("Units: {}\n", units[0]))?;
+    }
+    let mut started = Instant::now();
+    let mut results = Report::of_each(&analyses, &names, &context, |name, section| {
+        timings.record(name, started.elapsed(), view.len());
+        if let Some(sample) = &sample {
+            section.extrapolate(sample.factor());
+        }
+        // Text is written as each analysis finishes, so a long report shows progress.
+        if text {
+            out.write_text(&section.to_string())?;
+        }
+        started = Instant::now();
+        Ok::<_, std::io::Error>(())
+    })?;
+    results.sample = sample;
+    // Every other profile prints the same results again, without rerunning the analyses.
+    if text {
+        for &profile in &units[1..] {
+            out.write_text(&format!("\nUnits: {}\n{}", profile, results.with_units(profile)))?;
+        }
+    }
+    out.flush()?;
+
+    if !text {
+        results.write_as(out.as_mut(), format)?;
+        if format == ReportFormat::Markdown {
+            for &profile in &units[1..] {
+                out.write_text(&format!("# Units: {}\n\n", profile))?;
+                results.with_units(profile).write_as(out.as_mut(), format)?;
             }
         }
-
-        if count > 0 {
-            Some(sum / count as f32)
-        } else {
-            None
-        }
-    }
-
-    /*
-        Function used to check the median body weight throughout the file.
-
-        Runtime: O(n)
-     */
-    fn median_body_weight(cells: &[Cell]) -> Option<f32> {
-        let mut weights: Vec<f32> = cells.iter().filter_map(|cell| cell.body_weight).collect();
-        weights.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
-
-        let len = weights.len();
-        if len == 0 {
-            None
-        } else if len % 2 == 0 {
-            Some((weights[len / 2 - 1] + weights[len / 2]) / 2.0)
-        } else {
-            Some(weights[len / 2])
-        }
-    }
-
-    /*
-        Create a new Cell in the vector. This does not affect the file itself.
-
-        Runtime: O(n)
-     */
-    fn insert_cell(cells: &mut Vec<Cell>, index: usize, new_cell: Cell) {
-        if index <= cells.len() {
-            cells.insert(index, new_cell);
-        } else {
-            println!("Index out of bounds.");
-        }
-    }
-
-    /*
-        Modify an existing Cell within the vector.
-
-        Runtime: O(1)
-     */
-    fn modify_cell(cells: &mut Vec<Cell>, index: usize, modified_cell: Cell) {
-        if let Some(cell) = cells.get_mut(index) {
-            *cell = modified_cell;
-        } else {
-            println!("Index out of bounds.");
-        }
-    }
-
-    /*
-        Delete an existing Cell within the vector.
-
-        Runtime: O(n)
-     */
-    fn delete_cell(cells: &mut Vec<Cell>, index: usize) {
-        if index < cells.len() {
-            cells.remove(index);
-        } else {
-            println!("Index out of bounds.");
-        }
-    }
-}
-
-fn main() -> Result<(), Box<dyn Error>> {
-    let mut cells = Cell::read_csv("cells.csv")?;
-    let most_appearances = Cell::most_common_oem(&cells);
-    let most_common_display_size = Cell::most_common_display_size(&cells);
-    let highest_body_weight = Cell::highest_avg_body_weight_oem(&cells);
-
-    let phones_with_mismatched_years = Cell::phones_announced_in_one_year_released_in_another(&cells);
-
-    if phones_with_mismatched_years.is_empty() {
-        println!("No phones were announced in one year and released in another.");
-    } else {
-        println!("Phones announced in one year and released in another:");
-        for (oem, model) in phones_with_mismatched_years {
-            println!("OEM: {}, Model: {}", oem, model);
+        if let Some(message) = nothing {
+            eprintln!("{}", message);
         }
-    }
-
-    let phones_with_single_sensor = Cell::count_phones_with_single_sensor(&cells);
-    println!("Phones with only one feature sensor: {}", phones_with_single_sensor);
-
-    if let Some(oem) = most_appearances {
-        println!("Most Common OEM: {}", oem);
+        eprint!("{}", timings.report());
     } else {
-        println!("No data found.");
-    }
-
-    if let Some(display_size) = most_common_display_size {
-        println!("Most Common Display Size: {}", display_size);
-    } else {
-        println!("None");
-    }
-
-    if let Some(mean) = Cell::mean_body_weight(&cells) {
-        println!("Mean Body Weight: {:.2}", mean);
-    } else {
-        println!("None");
-    }
-
-    if let Some(median) = Cell::median_body_weight(&cells) {
-        println!("Median Body Weight: {:.2}", median);
-    } else {
-        println!("None");
-    }
-
-    let new_cell = Cell {
-        oem: Some("New OEM".to_string()),
-        model: Some("New Model".to_string()),
-        launch_announced: Some(2024),
-        launch_status: Some("New".to_string()),
-        body_dimensions: Some("New Dimensions".to_string()),
-        body_weight: Some(150.0),
-        body_sim: Some("New SIM".to_string()),
-        display_type: Some("New Type".to_string()),
-        display_size: Some(6.0),
-        display_resolution: Some("New Resolution".to_string()),
-        features_sensors: Some("New Sensors".to_string()),
-        platform_os: Some("New OS".to_string()),
-    };
-
-    // let modified = Cell {
-    //     oem: Some("New OEM".to_string()),
-    //     model: Some("New Model".to_string()),
-    //     launch_announced: Some(2024),
-    //     launch_status: Some("New".to_string()),
-    //     body_dimensions: Some("New Dimensions".to_string()),
-    //     body_weight: Some(150.0),
-    //     body_sim: Some("New SIM".to_string()),
-    //     display_type: Some("New Type".to_string()),
-    //     display_size: Some(6.0),
-    //     display_resolution: Some("New Resolution".to_string()),
-    //     features_sensors: Some("New Sensors".to_string()),
-    //     platform_os: Some("New OS".to_string()),
-    // };
-    //
-    // Cell::insert_cell(&mut cells, 1, new_cell);
-    //
-    // Cell::modify_cell(&mut cells, 0, modified);
-    //
-    // Cell::delete_cell(&mut cells, 2);
-
-    if let Some(year) = Cell::year_most_phones_launched__after_year(&cells) {
-        println!("Year with most phones launched after 1999: {}", year);
-    } else {
-        println!("None");
-    }
-
-    println!("Highest Average Body Weight OEM: {}", highest_body_weight.unwrap());
-    for cell in cells {
-        println!("{:?}\n", cell);
+        print!("{}", timings.report());
     }
 
     Ok(())
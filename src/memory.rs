@@ -0,0 +1,113 @@
+use crate::column::Column;
+use crate::date::LaunchDate;
+use crate::sensor::Sensor;
+use crate::Cell;
+use std::collections::HashSet;
+use std::fmt;
+use std::mem::size_of;
+use std::sync::Arc;
+
+#[derive(Debug, Clone, PartialEq)]
+// Bytes one column takes: inside the Cell structs, and on the heap for string contents.
+pub struct ColumnBytes {
+    pub column: Column,
+    pub inline: usize,
+    pub heap: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/*
+    Estimated memory used by loaded cells, for `--mem-stats`. Heap sizes are the length of
+    each boxed string, which has no spare capacity, so allocator bookkeeping isn't included.
+    A shared (interned) string is counted once per column, with its reference counts.
+    `overhead` is struct padding plus spare capacity of the cell vector. `extras` is the heap
+    of the extra columns' names and values, not counting the map nodes, and of the tags.
+ */
+pub struct MemoryUsage {
+    pub columns: Vec<ColumnBytes>,
+    pub extras: usize,
+    pub overhead: usize,
+    pub total: usize,
+}
+
+// (inline, heap) bytes of one field of a cell. The heap of a shared string is only counted
+// the first time it is seen.
+fn field_bytes(cell: &Cell, column: Column, seen: &mut HashSet<*const u8>) -> (usize, usize) {
+    let string = |value: &Option<Box<str>>| (size_of::<Option<Box<str>>>(), value.as_ref().map_or(0, |value| value.len()));
+    let mut shared = |value: &Option<Arc<str>>| {
+        let heap = value.as_ref().filter(|value| seen.insert(value.as_ptr())).map_or(0, |value| 2 * size_of::<usize>() + value.len());
+        (size_of::<Option<Arc<str>>>(), heap)
+    };
+
+    match column {
+        Column::Oem => shared(&cell.oem),
+        Column::Model => string(&cell.model),
+        // With the launch_date kept beside it.
+        Column::LaunchAnnounced => (size_of::<Option<u32>>() + size_of::<Option<LaunchDate>>(), 0),
+        // With the release_date kept beside it.
+        Column::LaunchStatus => {
+            let (inline, heap) = shared(&cell.launch_status);
+            (inline + size_of::<Option<LaunchDate>>(), heap)
+        }
+        Column::BodyDimensions => string(&cell.body_dimensions),
+        Column::BodyWeight => (size_of::<Option<f32>>(), 0),
+        Column::BodySim => shared(&cell.body_sim),
+        Column::DisplayType => shared(&cell.display_type),
+        // With the cover display of a foldable kept beside each.
+        Column::DisplaySize => (2 * size_of::<Option<f32>>(), 0),
+        Column::DisplayResolution => {
+            let ((inline, heap), (cover_inline, cover_heap)) = (string(&cell.display_resolution), string(&cell.cover_display_resolution));
+            (inline + cover_inline, heap + cover_heap)
+        }
+        // With the sensors parsed from it, and the text of the ones that are not known.
+        Column::FeaturesSensors => {
+            let (inline, heap) = string(&cell.features_sensors);
+            let others: usize = cell.sensors.iter().map(|sensor| if let Sensor::Other(entry) = sensor { entry.len() } else { 0 }).sum();
+            (inline + size_of::<Vec<Sensor>>(), heap + cell.sensors.capacity() * size_of::<Sensor>() + others)
+        }
+        Column::PlatformOs => shared(&cell.platform_os),
+    }
+}
+
+impl MemoryUsage {
+    /*
+        Estimate the memory of cells held in a vector with the given capacity.
+
+        Runtime: O(n * c)
+     */
+    pub fn of(cells: &[Cell], capacity: usize) -> MemoryUsage {
+        let columns: Vec<ColumnBytes> = Column::ALL
+            .iter()
+            .map(|&column| {
+                let mut seen = HashSet::new();
+                let (inline, heap) = cells
+                    .iter()
+                    .map(|cell| field_bytes(cell, column, &mut seen))
+                    .fold((0, 0), |(inline, heap), (field_inline, field_heap)| (inline + field_inline, heap + field_heap));
+                ColumnBytes { column, inline, heap }
+            })
+            .collect();
+
+        let structs = capacity.max(cells.len()) * size_of::<Cell>();
+        let inline: usize = columns.iter().map(|bytes| bytes.inline).sum();
+        let heap: usize = columns.iter().map(|bytes| bytes.heap).sum();
+        let extras: usize = cells.iter().flat_map(|cell| &cell.extras).map(|(header, value)| header.capacity() + value.capacity()).sum();
+        let extras = extras + cells.iter().map(|cell| cell.tags.capacity() * size_of::<String>() + cell.tags.iter().map(String::capacity).sum::<usize>()).sum::<usize>();
+
+        MemoryUsage { columns, extras, overhead: structs - inline, total: structs + heap + extras }
+    }
+}
+
+impl fmt::Display for MemoryUsage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{:<20} {:>10} {:>10} {:>10}", "column", "inline", "heap", "total")?;
+        for bytes in &self.columns {
+            writeln!(f, "{:<20} {:>10} {:>10} {:>10}", bytes.column.name(), bytes.inline, bytes.heap, bytes.inline + bytes.heap)?;
+        }
+        if self.extras > 0 {
+            writeln!(f, "{:<20} {:>10} {:>10} {:>10}", "(extras)", 0, self.extras, self.extras)?;
+        }
+        writeln!(f, "{:<20} {:>10} {:>10} {:>10}", "(padding, spare)", self.overhead, 0, self.overhead)?;
+        writeln!(f, "{:<20} {:>32}", "total bytes", self.total)
+    }
+}
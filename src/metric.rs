@@ -0,0 +1,86 @@
+use crate::error::CellError;
+use crate::Cell;
+use std::fmt;
+use std::sync::Arc;
+
+// Reads one number from a cell, None when the cell has no value for it.
+pub type NumericAccessor = Arc<dyn Fn(&Cell) -> Option<f32> + Send + Sync>;
+
+#[derive(Clone)]
+/*
+    The numeric values commands can compute over by name: the numeric columns and values
+    derived from the others, such as ppi and pixel_count. Callers can register their own, so
+    `cells stats --column`, `cells histogram` and `cells regression` accept any name known
+    at runtime, not only the columns.
+ */
+pub struct Metrics {
+    accessors: Vec<(String, NumericAccessor)>,
+}
+
+impl Default for Metrics {
+    // The numeric columns and the built-in derived values.
+    fn default() -> Metrics {
+        let mut metrics = Metrics::empty();
+        metrics.register("launch_announced", |cell| cell.launch_announced.map(|year| year as f32));
+        metrics.register("body_weight", |cell| cell.body_weight);
+        metrics.register("display_size", |cell| cell.display_size);
+        metrics.register("ppi", Cell::ppi);
+        metrics.register("pixel_count", |cell| cell.resolution().map(|resolution| resolution.pixels() as f32));
+        metrics.register("thickness", |cell| cell.dimensions().map(|dimensions| dimensions.depth));
+        metrics.register("screen_to_body_ratio", Cell::screen_to_body_ratio);
+        metrics.register("sensor_count", |cell| cell.features_sensors.as_ref().map(|_| cell.sensors.len() as f32));
+        metrics.register("os_version", |cell| cell.os()?.version.map(|version| version.value()));
+        metrics
+    }
+}
+
+impl Metrics {
+    // A registry without any names.
+    pub fn empty() -> Metrics {
+        Metrics { accessors: Vec::new() }
+    }
+
+    // Registers a value under a name, replacing any value registered under it before.
+    pub fn register(&mut self, name: &str, accessor: impl Fn(&Cell) -> Option<f32> + Send + Sync + 'static) {
+        let accessor: NumericAccessor = Arc::new(accessor);
+        match self.accessors.iter_mut().find(|(known, _)| known == name) {
+            Some((_, existing)) => *existing = accessor,
+            None => self.accessors.push((name.to_string(), accessor)),
+        }
+    }
+
+    /*
+        The accessor registered under a name, ignoring case and surrounding spaces.
+
+        Runtime: O(m) for the number of registered names
+     */
+    pub fn get(&self, name: &str) -> Result<&NumericAccessor, CellError> {
+        let name = name.trim();
+        self.accessors
+            .iter()
+            .find(|(known, _)| known.eq_ignore_ascii_case(name))
+            .map(|(_, accessor)| accessor)
+            .ok_or_else(|| CellError::UnknownColumn { name: name.to_string() })
+    }
+
+    // The registered names, in the order they were registered.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.accessors.iter().map(|(name, _)| name.as_str())
+    }
+
+    /*
+        The values of a name over the cells, skipping cells without one.
+
+        Runtime: O(n)
+     */
+    pub fn values<'a>(&self, name: &str, cells: impl IntoIterator<Item = &'a Cell>) -> Result<Vec<f32>, CellError> {
+        let accessor = self.get(name)?;
+        Ok(cells.into_iter().filter_map(|cell| accessor(cell)).collect())
+    }
+}
+
+impl fmt::Debug for Metrics {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.names()).finish()
+    }
+}
@@ -0,0 +1,326 @@
+use crate::aliases::OemAliases;
+use crate::bounds::Bounds;
+use crate::column::Column;
+use crate::config::Config;
+use crate::intern::Interner;
+use crate::record;
+use crate::text;
+use regex::Regex;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+// One named cleaning step applied to the raw text of a CSV field.
+pub enum Stage {
+    // Replaces a blank or '-' value with None.
+    Empty,
+    // Replaces a value containing a 4-digit year with just that year, and a launch_announced
+    // without one with None.
+    Year,
+    // Replaces a value containing a number with just the first number, and one without any
+    // with None.
+    Numeric,
+}
+
+impl Stage {
+    // The stages in the order read_csv has always applied them.
+    pub const DEFAULT: [Stage; 3] = [Stage::Empty, Stage::Year, Stage::Numeric];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Stage::Empty => "empty",
+            Stage::Year => "year",
+            Stage::Numeric => "numeric",
+        }
+    }
+
+    // The columns this stage cleans.
+    pub fn columns(&self) -> &'static [Column] {
+        match self {
+            Stage::Empty => &[
+                Column::BodyDimensions,
+                Column::BodySim,
+                Column::DisplayType,
+                Column::DisplayResolution,
+                Column::FeaturesSensors,
+                Column::PlatformOs,
+            ],
+            Stage::Year => &[Column::LaunchAnnounced, Column::LaunchStatus],
+            Stage::Numeric => &[Column::BodyWeight, Column::DisplaySize],
+        }
+    }
+}
+
+impl fmt::Display for Stage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+// The [normalization] section of the config.
+pub struct NormalizationConfig {
+    // Stages to run, in order. Leaving it out runs Stage::DEFAULT.
+    pub stages: Option<Vec<Stage>>,
+    // Rules for the raw text of single columns, e.g. [normalization.fields.body_weight].
+    pub fields: HashMap<Column, FieldRules>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+/*
+    How to adapt the raw values of one column of an unusual source file before the stages
+    clean them, e.g.
+
+        [normalization.fields.body_weight]
+        strip = ["grams"]
+
+        [normalization.fields.launch_status]
+        map = { Yes = "Available", No = "Discontinued" }
+ */
+pub struct FieldRules {
+    // Text removed wherever it appears in a value.
+    pub strip: Vec<String>,
+    // Whole values, after stripping and trimming, replaced by another.
+    pub map: HashMap<String, String>,
+}
+
+impl FieldRules {
+    // The rules as a field normalizer.
+    pub fn normalizer(&self) -> FieldNormalizer {
+        let rules = self.clone();
+        Arc::new(move |raw: &str| {
+            let stripped = rules.strip.iter().fold(raw.to_string(), |value, text| value.replace(text.as_str(), ""));
+            let stripped = stripped.trim();
+            Some(rules.map.get(stripped).cloned().unwrap_or_else(|| stripped.to_string()))
+        })
+    }
+}
+
+/// A custom cleaning function for the raw text of one column, run before the stages. It
+/// returns the text to clean further, or None for a missing value.
+pub type FieldNormalizer = Arc<dyn Fn(&str) -> Option<String> + Send + Sync>;
+
+/*
+    Runs the enabled stages in order over each raw field and counts, per stage, how many
+    values the stage changed. It also carries the plausibility bounds the parsed numbers are
+    checked against, the pool the low-cardinality text columns are interned in, and the OEM
+    aliases that give every spelling of an OEM one name once the stages ran. Unless turned
+    off, the text of every field is cleaned first (see text::clean), so stray tabs,
+    non-breaking spaces and curly quotes don't split a group. Field normalizers registered
+    for a column run on its raw text next, in the order they were registered, so a file
+    with unusual values can be read without changing the stages.
+ */
+pub struct Normalizer {
+    stages: Vec<Stage>,
+    counts: Vec<usize>,
+    bounds: Bounds,
+    regex_year: Regex,
+    regex_numeric: Regex,
+    interner: Interner,
+    fields: Vec<(Column, FieldNormalizer)>,
+    oem_aliases: Arc<OemAliases>,
+    clean_text: bool,
+    // Values the text cleaning changed.
+    text_cleaned: usize,
+}
+
+impl Normalizer {
+    pub fn new(stages: Vec<Stage>) -> Normalizer {
+        let counts = vec![0; stages.len()];
+
+        Normalizer {
+            stages,
+            counts,
+            bounds: Bounds::default(),
+            regex_year: Regex::new(r"\b(\d{4})\b").unwrap(),
+            regex_numeric: Regex::new(r"\d+(\.\d+)?").unwrap(),
+            interner: Interner::new(),
+            fields: Vec::new(),
+            oem_aliases: Arc::new(OemAliases::new()),
+            clean_text: true,
+            text_cleaned: 0,
+        }
+    }
+
+    // Uses the stages listed in the config, or the defaults, the config's field rules, in
+    // column order, its bounds, its OEM aliases and whether to clean the text.
+    pub fn from_config(config: &Config) -> Normalizer {
+        let mut normalizer = match &config.normalization.stages {
+            Some(stages) => Normalizer::new(stages.clone()),
+            None => Normalizer::default(),
+        };
+        for column in Column::ALL {
+            if let Some(rules) = config.normalization.fields.get(&column) {
+                normalizer = normalizer.with_field_normalizer(column, rules.normalizer());
+            }
+        }
+
+        normalizer.with_bounds(config.bounds).with_oem_aliases(OemAliases::from_config(config)).with_text_cleaning(!config.raw_text)
+    }
+
+    // Runs a custom function on the raw text of a column before the stages, e.g.
+    // `normalizer.with_field_normalizer(Column::BodyWeight, Arc::new(|raw| Some(raw.replace("grams", ""))))`.
+    pub fn with_field_normalizer(mut self, column: Column, normalizer: FieldNormalizer) -> Normalizer {
+        self.fields.push((column, normalizer));
+        self
+    }
+
+    // Checks the parsed numbers against these bounds; by default nothing is checked.
+    pub fn with_bounds(mut self, bounds: Bounds) -> Normalizer {
+        self.bounds = bounds;
+        self
+    }
+
+    // Renames OEMs with these aliases; by default only the built-in ones.
+    pub fn with_oem_aliases(mut self, aliases: OemAliases) -> Normalizer {
+        self.oem_aliases = Arc::new(aliases);
+        self
+    }
+
+    // Whether to clean the text of every field before anything else; on by default.
+    pub fn with_text_cleaning(mut self, clean_text: bool) -> Normalizer {
+        self.clean_text = clean_text;
+        self
+    }
+
+    pub fn bounds(&self) -> &Bounds {
+        &self.bounds
+    }
+
+    // The pool the parsed OEMs, statuses, SIMs, display types and platforms are shared from.
+    pub fn interner(&mut self) -> &mut Interner {
+        &mut self.interner
+    }
+
+    /*
+        Clean the raw text of one field: the text cleaning, if on, then the column's field
+        normalizers, then the stages. Each stage only touches the columns it applies to. An
+        OEM then gets its canonical name.
+
+        Runtime: O(n + k + f) for n characters, k stages and f field normalizers
+     */
+    pub fn normalize(&mut self, column: Column, raw: &str) -> Option<String> {
+        let mut value = Some(raw.to_string());
+        if self.clean_text {
+            let cleaned = text::clean(raw);
+            self.text_cleaned += usize::from(cleaned != raw);
+            value = Some(cleaned);
+        }
+        for (_, normalizer) in self.fields.iter().filter(|(field, _)| *field == column) {
+            value = value.and_then(|text| normalizer(&text));
+        }
+
+        for (index, stage) in self.stages.iter().enumerate() {
+            if !stage.columns().contains(&column) {
+                continue;
+            }
+            let Some(text) = &value else {
+                continue;
+            };
+
+            let cleaned = match stage {
+                Stage::Empty => {
+                    if text.trim().is_empty() || text.trim() == "-" {
+                        None
+                    } else {
+                        value.clone()
+                    }
+                }
+                // A status without a year is kept as text, and a blank or '-' stays missing;
+                // any other text without a number has none, rather than whatever str::parse
+                // would make of it ("NaN", "inf", a nine-digit year).
+                Stage::Year => match self.regex_year.captures(text) {
+                    Some(capture) => Some(capture[1].to_string()),
+                    None if column == Column::LaunchStatus || record::is_missing(text) => value.clone(),
+                    None => None,
+                },
+                Stage::Numeric => match self.regex_numeric.captures(text) {
+                    Some(capture) => Some(capture[0].to_string()),
+                    None if record::is_missing(text) => value.clone(),
+                    None => None,
+                },
+            };
+
+            if cleaned != value {
+                self.counts[index] += 1;
+            }
+            value = cleaned;
+        }
+
+        match column {
+            Column::Oem => value.map(|oem| self.oem_aliases.canonical(&oem).to_string()),
+            _ => value,
+        }
+    }
+
+    // The oem and model of a phone as parsing names them: with the text cleaning, the
+    // columns' field normalizers and, for the oem, its canonical name. No stage cleans either.
+    pub fn phone(&self, oem: &str, model: &str) -> (String, String) {
+        let name = |column: Column, raw: &str| {
+            let text = if self.clean_text { text::clean(raw) } else { raw.to_string() };
+            let mut fields = self.fields.iter().filter(|(field, _)| *field == column);
+            fields.try_fold(text, |text, (_, normalizer)| normalizer(&text)).unwrap_or_default()
+        };
+        (self.oem_aliases.canonical(&name(Column::Oem, oem)).to_string(), name(Column::Model, model))
+    }
+
+    // A normalizer with the same stages, field normalizers, OEM aliases and text cleaning,
+    // and its own counts and pool, e.g. for another thread.
+    pub fn fork(&self) -> Normalizer {
+        Normalizer {
+            stages: self.stages.clone(),
+            counts: vec![0; self.stages.len()],
+            bounds: self.bounds,
+            regex_year: self.regex_year.clone(),
+            regex_numeric: self.regex_numeric.clone(),
+            interner: Interner::new(),
+            fields: self.fields.clone(),
+            oem_aliases: Arc::clone(&self.oem_aliases),
+            clean_text: self.clean_text,
+            text_cleaned: 0,
+        }
+    }
+
+    // Adds the counts of a forked normalizer to this one's.
+    pub fn merge(&mut self, fork: &Normalizer) {
+        for (count, other) in self.counts.iter_mut().zip(&fork.counts) {
+            *count += other;
+        }
+        self.text_cleaned += fork.text_cleaned;
+    }
+
+    // How many values the text cleaning changed so far.
+    pub fn text_cleaned(&self) -> usize {
+        self.text_cleaned
+    }
+
+    // How many values each stage changed so far, in stage order.
+    pub fn report(&self) -> Vec<(Stage, usize)> {
+        self.stages.iter().copied().zip(self.counts.iter().copied()).collect()
+    }
+}
+
+impl Default for Normalizer {
+    fn default() -> Normalizer {
+        Normalizer::new(Stage::DEFAULT.to_vec())
+    }
+}
+
+// The field normalizers are closures, so only the settings and counts are shown.
+impl fmt::Debug for Normalizer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Normalizer")
+            .field("stages", &self.stages)
+            .field("counts", &self.counts)
+            .field("bounds", &self.bounds)
+            .field("fields", &self.fields.len())
+            .field("clean_text", &self.clean_text)
+            .field("text_cleaned", &self.text_cleaned)
+            .finish_non_exhaustive()
+    }
+}
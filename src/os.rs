@@ -0,0 +1,167 @@
+use serde::Serialize;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+// The operating system a platform_os names, without its version or vendor skin.
+pub enum OsFamily {
+    Android,
+    Ios,
+    Windows,
+    Symbian,
+    BlackBerry,
+    WebOs,
+    Tizen,
+    Bada,
+    Linux,
+    // Anything else, e.g. "Huawei wearable platform" or a feature phone's own system.
+    Other,
+}
+
+// How platform_os values of each family start, in lowercase, after any "Microsoft ".
+const FAMILIES: [(&str, OsFamily); 10] = [
+    ("android", OsFamily::Android),
+    ("ios", OsFamily::Ios),
+    ("ipados", OsFamily::Ios),
+    ("windows", OsFamily::Windows),
+    ("symbian", OsFamily::Symbian),
+    ("blackberry", OsFamily::BlackBerry),
+    ("webos", OsFamily::WebOs),
+    ("tizen", OsFamily::Tizen),
+    ("bada", OsFamily::Bada),
+    ("linux", OsFamily::Linux),
+];
+
+impl OsFamily {
+    pub fn name(&self) -> &'static str {
+        match self {
+            OsFamily::Android => "Android",
+            OsFamily::Ios => "iOS",
+            OsFamily::Windows => "Windows",
+            OsFamily::Symbian => "Symbian",
+            OsFamily::BlackBerry => "BlackBerry",
+            OsFamily::WebOs => "webOS",
+            OsFamily::Tizen => "Tizen",
+            OsFamily::Bada => "Bada",
+            OsFamily::Linux => "Linux",
+            OsFamily::Other => "Other",
+        }
+    }
+}
+
+impl fmt::Display for OsFamily {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+// A dotted OS version such as 4.2.2. Missing parts are 0, and versions compare part by part.
+pub struct Version {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+}
+
+impl Version {
+    /*
+        Parse "9", "9.0" or "4.2.2". Numbers above 100 are years, as in "Smartphone 2003",
+        rather than versions and give None.
+
+        Runtime: O(n)
+     */
+    pub fn parse(text: &str) -> Option<Version> {
+        let mut parts = text.split('.').map(|part| part.parse::<u32>());
+        let major = parts.next()?.ok().filter(|major| *major <= 100)?;
+        let minor = parts.next().unwrap_or(Ok(0)).ok()?;
+        let patch = parts.next().unwrap_or(Ok(0)).ok()?;
+
+        Some(Version { major, minor, patch })
+    }
+
+    // The version as a decimal, e.g. 4.4 for 4.4.2, to average versions by. Minor versions
+    // are taken to stay below 10, as Android's do.
+    pub fn value(&self) -> f32 {
+        self.major as f32 + self.minor.min(9) as f32 / 10.0
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (self.minor, self.patch) {
+            (0, 0) => write!(f, "{}", self.major),
+            (minor, 0) => write!(f, "{}.{}", self.major, minor),
+            (minor, patch) => write!(f, "{}.{}.{}", self.major, minor, patch),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+// The OS a phone shipped with, from its platform_os, and the newest version it is said to
+// be upgradable to.
+pub struct OsInfo {
+    pub family: OsFamily,
+    pub version: Option<Version>,
+    pub max_version: Option<Version>,
+}
+
+impl OsInfo {
+    /*
+        Parse a platform_os such as "Android 11, upgradable to Android 13", "iOS 14.1" or
+        "Microsoft Windows Mobile 6.5.3 Professional". Only the part before the first comma
+        describes the shipped OS; its first number is the version, so vendor skins ("EMUI
+        9.1") are ignored. The max version is the highest one a later "upgradable to", "up
+        to" or "planned upgrade to" clause names; a "not upgradable to" clause, or one that
+        is not above the shipped version, doesn't count. Blank text gives None.
+
+        Runtime: O(n)
+     */
+    pub fn parse(platform_os: &str) -> Option<OsInfo> {
+        let mut clauses = platform_os.split(',');
+        let shipped = clauses.next()?.trim();
+        if shipped.is_empty() {
+            return None;
+        }
+
+        let lower = shipped.to_lowercase();
+        let name = lower.strip_prefix("microsoft ").unwrap_or(&lower);
+        let family = FAMILIES.iter().find(|(prefix, _)| name.starts_with(prefix)).map_or(OsFamily::Other, |(_, family)| *family);
+        let version = first_version(shipped);
+        let max_version = clauses
+            .map(|clause| clause.trim().to_lowercase())
+            .filter(|clause| !clause.starts_with("not "))
+            .filter_map(|clause| ["upgradable to", "upgrade to", "up to"].iter().find_map(|words| clause.find(words).map(|at| first_version(&clause[at..]))))
+            .flatten()
+            .filter(|max| version.is_none_or(|version| *max > version))
+            .max();
+
+        Some(OsInfo { family, version, max_version })
+    }
+
+    /*
+        How many upgrades the phone is said to get: the major versions between the shipped
+        and the max version, an upgrade within one major version counting as one. 0 without
+        a max version, and None when the shipped version is unknown.
+
+        Runtime: O(1)
+     */
+    pub fn upgrades(&self) -> Option<u32> {
+        let shipped = self.version?;
+        Some(self.max_version.map_or(0, |max| (max.major - shipped.major).max(1)))
+    }
+}
+
+// The first word of the text that parses as a version.
+fn first_version(text: &str) -> Option<Version> {
+    text.split(|c: char| c.is_whitespace() || c == '(' || c == ')')
+        .find(|word| word.starts_with(|c: char| c.is_ascii_digit()))
+        .and_then(Version::parse)
+}
+
+impl fmt::Display for OsInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.version {
+            Some(version) => write!(f, "{} {}", self.family, version),
+            None => write!(f, "{}", self.family),
+        }
+    }
+}
@@ -0,0 +1,103 @@
+use crate::error::CellError;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+
+/// Where rendered reports and exports go: stdout, a file, or a [`Buffer`] a library caller
+/// reads back. Renderers write to a sink rather than printing, so the same output can be
+/// captured or redirected without changing them.
+pub trait OutputSink {
+    /// Writes part of the output.
+    fn write_bytes(&mut self, bytes: &[u8]) -> io::Result<()>;
+
+    /// Writes out anything buffered so far.
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Writes rendered text.
+    fn write_text(&mut self, text: &str) -> io::Result<()> {
+        self.write_bytes(text.as_bytes())
+    }
+}
+
+// A sink is also a writer, e.g. for csv::Writer and serde_json.
+impl io::Write for dyn OutputSink + '_ {
+    fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+        self.write_bytes(bytes)?;
+        Ok(bytes.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        OutputSink::flush(self)
+    }
+}
+
+/// Standard output.
+pub struct Stdout;
+
+impl OutputSink for Stdout {
+    fn write_bytes(&mut self, bytes: &[u8]) -> io::Result<()> {
+        io::stdout().lock().write_all(bytes)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        io::stdout().flush()
+    }
+}
+
+/// A file, created or truncated when the sink is.
+pub struct FileSink {
+    writer: BufWriter<File>,
+}
+
+impl FileSink {
+    pub fn create(path: &str) -> Result<FileSink, CellError> {
+        Ok(FileSink { writer: BufWriter::new(File::create(path)?) })
+    }
+}
+
+impl OutputSink for FileSink {
+    fn write_bytes(&mut self, bytes: &[u8]) -> io::Result<()> {
+        self.writer.write_all(bytes)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+/// Output kept in memory, to read back as text.
+pub struct Buffer {
+    bytes: Vec<u8>,
+}
+
+impl Buffer {
+    pub fn new() -> Buffer {
+        Buffer::default()
+    }
+
+    /// The output so far. Renderers only write UTF-8, so nothing is replaced.
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.bytes).into_owned()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+}
+
+impl OutputSink for Buffer {
+    fn write_bytes(&mut self, bytes: &[u8]) -> io::Result<()> {
+        self.bytes.extend_from_slice(bytes);
+        Ok(())
+    }
+}
+
+/// The file at `path`, or stdout when there is none or it is "-".
+pub fn open(path: Option<&str>) -> Result<Box<dyn OutputSink>, CellError> {
+    match path {
+        None | Some("-") => Ok(Box::new(Stdout)),
+        Some(path) => Ok(Box::new(FileSink::create(path)?)),
+    }
+}
@@ -0,0 +1,354 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+
+// Rows held in memory before they are written out as one row group.
+pub const ROW_GROUP_ROWS: usize = 10_000;
+
+const MAGIC: &[u8] = b"PAR1";
+
+// Thrift compact protocol field types.
+const I32: u8 = 5;
+const I64: u8 = 6;
+const BINARY: u8 = 8;
+const LIST: u8 = 9;
+const STRUCT: u8 = 12;
+
+// Parquet enum values, as numbered in parquet.thrift.
+const PLAIN: i32 = 0;
+const RLE: i32 = 3;
+const UNCOMPRESSED: i32 = 0;
+const DATA_PAGE: i32 = 0;
+const OPTIONAL: i32 = 1;
+const UTF8: i32 = 0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+// The type of the values of one column.
+pub enum ParquetType {
+    Int32,
+    Float,
+    // Text, stored as UTF-8 byte arrays.
+    Utf8,
+}
+
+impl ParquetType {
+    // The physical type number of parquet.thrift.
+    fn physical(self) -> i32 {
+        match self {
+            ParquetType::Int32 => 1,
+            ParquetType::Float => 4,
+            ParquetType::Utf8 => 6,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+// One value of a row; a missing value is None.
+pub enum ParquetValue {
+    Int32(i32),
+    Float(f32),
+    Utf8(String),
+}
+
+// The values of one column of the row group being filled.
+struct ColumnBuffer {
+    name: String,
+    kind: ParquetType,
+    // Whether each row has a value: the definition levels.
+    defined: Vec<bool>,
+    // The values, PLAIN encoded.
+    values: Vec<u8>,
+}
+
+// Where a column of a written row group is in the file.
+struct ChunkMeta {
+    offset: u64,
+    size: u64,
+    values: usize,
+}
+
+// A written row group.
+struct RowGroupMeta {
+    rows: usize,
+    chunks: Vec<ChunkMeta>,
+}
+
+/*
+    Writes rows to a Parquet file as they come, holding at most ROW_GROUP_ROWS of them. Every
+    column is optional and flat; values are PLAIN encoded in one uncompressed data page per
+    column and row group, with the definition levels in the RLE hybrid encoding, and the
+    metadata is written with the Thrift compact protocol, as parquet.thrift describes. That
+    is all a reader needs, so no Parquet library is needed to write one.
+ */
+pub struct ParquetWriter<W: Write> {
+    out: W,
+    // Bytes written so far, where the next page starts.
+    offset: u64,
+    columns: Vec<ColumnBuffer>,
+    rows: usize,
+    row_groups: Vec<RowGroupMeta>,
+}
+
+impl ParquetWriter<BufWriter<File>> {
+    // Creates the file, replacing one that exists.
+    pub fn create(path: &str, columns: &[(String, ParquetType)]) -> io::Result<ParquetWriter<BufWriter<File>>> {
+        ParquetWriter::new(BufWriter::new(File::create(path)?), columns)
+    }
+}
+
+impl<W: Write> ParquetWriter<W> {
+    pub fn new(mut out: W, columns: &[(String, ParquetType)]) -> io::Result<ParquetWriter<W>> {
+        out.write_all(MAGIC)?;
+        let columns = columns.iter().map(|(name, kind)| ColumnBuffer { name: name.clone(), kind: *kind, defined: Vec::new(), values: Vec::new() }).collect();
+
+        Ok(ParquetWriter { out, offset: MAGIC.len() as u64, columns, rows: 0, row_groups: Vec::new() })
+    }
+
+    /*
+        Add a row, one value per column in column order. A value of another type than its
+        column is an InvalidInput error.
+
+        Runtime: O(c) for c columns, plus writing a row group every ROW_GROUP_ROWS rows
+     */
+    pub fn write_row(&mut self, row: &[Option<ParquetValue>]) -> io::Result<()> {
+        if row.len() != self.columns.len() {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("a row of {} values for {} columns", row.len(), self.columns.len())));
+        }
+        for (column, value) in self.columns.iter_mut().zip(row) {
+            match (column.kind, value) {
+                (_, None) => {}
+                (ParquetType::Int32, Some(ParquetValue::Int32(number))) => column.values.extend_from_slice(&number.to_le_bytes()),
+                (ParquetType::Float, Some(ParquetValue::Float(number))) => column.values.extend_from_slice(&number.to_le_bytes()),
+                (ParquetType::Utf8, Some(ParquetValue::Utf8(text))) => {
+                    column.values.extend_from_slice(&(text.len() as u32).to_le_bytes());
+                    column.values.extend_from_slice(text.as_bytes());
+                }
+                (kind, Some(value)) => return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("{:?} in the {:?} column '{}'", value, kind, column.name))),
+            }
+            column.defined.push(value.is_some());
+        }
+
+        self.rows += 1;
+        if self.rows == ROW_GROUP_ROWS {
+            self.flush_row_group()?;
+        }
+        Ok(())
+    }
+
+    /*
+        Write the rows still held and the footer, and return the output.
+
+        Runtime: O(r * c) for r row groups and c columns
+     */
+    pub fn finish(mut self) -> io::Result<W> {
+        self.flush_row_group()?;
+        let footer = self.file_metadata();
+        self.out.write_all(&footer)?;
+        self.out.write_all(&(footer.len() as u32).to_le_bytes())?;
+        self.out.write_all(MAGIC)?;
+        self.out.flush()?;
+        Ok(self.out)
+    }
+
+    // Writes the held rows as a row group of one data page per column.
+    fn flush_row_group(&mut self) -> io::Result<()> {
+        if self.rows == 0 {
+            return Ok(());
+        }
+
+        let mut chunks = Vec::new();
+        for column in &mut self.columns {
+            let levels = definition_levels(&column.defined);
+            let mut data = Vec::with_capacity(4 + levels.len() + column.values.len());
+            data.extend_from_slice(&(levels.len() as u32).to_le_bytes());
+            data.extend_from_slice(&levels);
+            data.append(&mut column.values);
+
+            let mut header = Compact::new();
+            header.i32(1, DATA_PAGE);
+            header.i32(2, data.len() as i32);
+            header.i32(3, data.len() as i32);
+            header.begin_struct(5);
+            header.i32(1, column.defined.len() as i32);
+            header.i32(2, PLAIN);
+            header.i32(3, RLE);
+            header.i32(4, RLE);
+            header.end_struct();
+            let header = header.finish();
+
+            self.out.write_all(&header)?;
+            self.out.write_all(&data)?;
+            let size = (header.len() + data.len()) as u64;
+            chunks.push(ChunkMeta { offset: self.offset, size, values: column.defined.len() });
+            self.offset += size;
+            column.defined.clear();
+        }
+
+        self.row_groups.push(RowGroupMeta { rows: self.rows, chunks });
+        self.rows = 0;
+        Ok(())
+    }
+
+    // The FileMetaData struct of the footer.
+    fn file_metadata(&self) -> Vec<u8> {
+        let mut meta = Compact::new();
+        meta.i32(1, 1);
+
+        meta.list(2, STRUCT, self.columns.len() + 1);
+        meta.begin_element();
+        meta.binary(4, b"schema");
+        meta.i32(5, self.columns.len() as i32);
+        meta.end_struct();
+        for column in &self.columns {
+            meta.begin_element();
+            meta.i32(1, column.kind.physical());
+            meta.i32(3, OPTIONAL);
+            meta.binary(4, column.name.as_bytes());
+            if column.kind == ParquetType::Utf8 {
+                meta.i32(6, UTF8);
+            }
+            meta.end_struct();
+        }
+
+        meta.i64(3, self.row_groups.iter().map(|group| group.rows as i64).sum());
+        meta.list(4, STRUCT, self.row_groups.len());
+        for group in &self.row_groups {
+            meta.begin_element();
+            meta.list(1, STRUCT, group.chunks.len());
+            for (column, chunk) in self.columns.iter().zip(&group.chunks) {
+                meta.begin_element();
+                meta.i64(2, chunk.offset as i64);
+                meta.begin_struct(3);
+                meta.i32(1, column.kind.physical());
+                meta.list(2, I32, 2);
+                meta.list_i32(PLAIN);
+                meta.list_i32(RLE);
+                meta.list(3, BINARY, 1);
+                meta.list_binary(column.name.as_bytes());
+                meta.i32(4, UNCOMPRESSED);
+                meta.i64(5, chunk.values as i64);
+                meta.i64(6, chunk.size as i64);
+                meta.i64(7, chunk.size as i64);
+                meta.i64(9, chunk.offset as i64);
+                meta.end_struct();
+                meta.end_struct();
+            }
+            meta.i64(2, group.chunks.iter().map(|chunk| chunk.size as i64).sum());
+            meta.i64(3, group.rows as i64);
+            meta.end_struct();
+        }
+        meta.binary(6, concat!("cells version ", env!("CARGO_PKG_VERSION")).as_bytes());
+
+        meta.finish()
+    }
+}
+
+/*
+    Definition levels of bit width 1 in the RLE/bit-packing hybrid encoding: one RLE run per
+    stretch of rows that all have a value or all don't, each a varint header (the run length
+    shifted left once) and the level in one byte.
+
+    Runtime: O(n)
+ */
+fn definition_levels(defined: &[bool]) -> Vec<u8> {
+    let mut levels = Vec::new();
+    for run in defined.chunk_by(|a, b| a == b) {
+        varint(&mut levels, (run.len() as u64) << 1);
+        levels.push(u8::from(run[0]));
+    }
+    levels
+}
+
+// An unsigned LEB128 varint, as Thrift and the hybrid encoding write lengths.
+fn varint(bytes: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        bytes.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+}
+
+// A struct being written with the Thrift compact protocol.
+struct Compact {
+    bytes: Vec<u8>,
+    // The last field id of each struct being written, innermost last.
+    last_field: Vec<i16>,
+}
+
+impl Compact {
+    fn new() -> Compact {
+        Compact { bytes: Vec::new(), last_field: vec![0] }
+    }
+
+    // Field ids are written as the difference from the previous field's when it fits.
+    fn field(&mut self, id: i16, kind: u8) {
+        let last = self.last_field.last_mut().expect("a struct is open");
+        match id - *last {
+            delta @ 1..=15 => self.bytes.push((delta as u8) << 4 | kind),
+            _ => {
+                self.bytes.push(kind);
+                varint(&mut self.bytes, zigzag(id as i64));
+            }
+        }
+        *last = id;
+    }
+
+    fn i32(&mut self, id: i16, value: i32) {
+        self.field(id, I32);
+        varint(&mut self.bytes, zigzag(value as i64));
+    }
+
+    fn i64(&mut self, id: i16, value: i64) {
+        self.field(id, I64);
+        varint(&mut self.bytes, zigzag(value));
+    }
+
+    fn binary(&mut self, id: i16, value: &[u8]) {
+        self.field(id, BINARY);
+        self.list_binary(value);
+    }
+
+    fn begin_struct(&mut self, id: i16) {
+        self.field(id, STRUCT);
+        self.last_field.push(0);
+    }
+
+    // Starts a struct that is an element of a list.
+    fn begin_element(&mut self) {
+        self.last_field.push(0);
+    }
+
+    fn end_struct(&mut self) {
+        self.bytes.push(0);
+        self.last_field.pop();
+    }
+
+    // A list field of `len` elements of one type, which are written next.
+    fn list(&mut self, id: i16, kind: u8, len: usize) {
+        self.field(id, LIST);
+        if len < 15 {
+            self.bytes.push((len as u8) << 4 | kind);
+        } else {
+            self.bytes.push(0xf0 | kind);
+            varint(&mut self.bytes, len as u64);
+        }
+    }
+
+    fn list_i32(&mut self, value: i32) {
+        varint(&mut self.bytes, zigzag(value as i64));
+    }
+
+    fn list_binary(&mut self, value: &[u8]) {
+        varint(&mut self.bytes, value.len() as u64);
+        self.bytes.extend_from_slice(value);
+    }
+
+    // The bytes of the outermost struct, ended.
+    fn finish(mut self) -> Vec<u8> {
+        self.end_struct();
+        self.bytes
+    }
+}
+
+fn zigzag(value: i64) -> u64 {
+    ((value << 1) ^ (value >> 63)) as u64
+}
@@ -0,0 +1,255 @@
+use crate::bounds::{BoundsAction, Range};
+use crate::column::Column;
+use crate::weight::{Weight, GRAMS_PER_OUNCE};
+use crate::record;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+// What happens to a numeric field that doesn't parse after cleaning.
+pub enum ParseMode {
+    // It becomes None, and is recorded in the parse report as a warning.
+    #[default]
+    Lenient,
+    // Loading fails with a report of every such field.
+    Strict,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+// A field that could not be parsed: where it is and its cleaned text.
+pub struct ParseIssue {
+    pub line: usize,
+    pub column: Column,
+    pub value: String,
+}
+
+impl fmt::Display for ParseIssue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: invalid {} '{}'", self.line, self.column, self.value)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+// A parsed number outside its column's plausible range, and what was done with it.
+pub struct RangeIssue {
+    pub line: usize,
+    pub column: Column,
+    pub value: f32,
+    pub range: Range,
+    pub action: BoundsAction,
+}
+
+impl fmt::Display for RangeIssue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {} {} is outside {} ({})", self.line, self.column, self.value, self.range, self.action)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+// How the parser took the values of one column.
+pub struct FieldCounts {
+    // Kept as written, apart from surrounding spaces.
+    pub verbatim: usize,
+    // Cut down by a cleaning stage, changed by a field rule or renamed by an OEM alias, e.g.
+    // "190 g (6.70 oz)" to "190".
+    pub extracted: usize,
+    // Blank, '-' or dropped by a field rule, so missing.
+    pub none: usize,
+    // Taken, verbatim or extracted, but not a number, so left empty or failing a strict load.
+    pub failed: usize,
+}
+
+impl FieldCounts {
+    // Adds the counts of another part of the file.
+    pub fn add(&mut self, other: &FieldCounts) {
+        self.verbatim += other.verbatim;
+        self.extracted += other.extracted;
+        self.none += other.none;
+        self.failed += other.failed;
+    }
+}
+
+impl fmt::Display for FieldCounts {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} verbatim, {} extracted, {} none, {} failed", self.verbatim, self.extracted, self.none, self.failed)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+// A body_weight whose grams and ounces disagree, e.g. a typo in one of them.
+pub struct WeightIssue {
+    pub line: usize,
+    pub grams: Vec<f32>,
+    pub ounces: f32,
+}
+
+impl fmt::Display for WeightIssue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let grams: Vec<String> = self.grams.iter().map(|grams| format!("{} g", grams)).collect();
+        write!(f, "line {}: body_weight {} disagrees with {:.2} oz ({:.0} g)", self.line, grams.join(" / "), self.ounces, self.ounces * GRAMS_PER_OUNCE)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+// Why a load found no rows: the file has no header row at all, or only the header row.
+pub enum EmptyInput {
+    NoHeader,
+    HeaderOnly,
+}
+
+impl EmptyInput {
+    // Which kind of empty a file without records is, from the header row it read.
+    pub fn of(headers: &csv::StringRecord) -> EmptyInput {
+        if headers.is_empty() {
+            EmptyInput::NoHeader
+        } else {
+            EmptyInput::HeaderOnly
+        }
+    }
+}
+
+impl fmt::Display for EmptyInput {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EmptyInput::NoHeader => write!(f, "the file is empty"),
+            EmptyInput::HeaderOnly => write!(f, "the file has a header but no rows"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+/*
+    Every field of a load that could not be parsed, in file order, every number outside
+    the configured bounds, and every weight whose two units disagree. `fields` counts how each
+    column's values were taken. `empty_input` is set when the file had no rows at all. len,
+    is_empty and counts are about the fields that could not be parsed; a strict load only
+    fails on those.
+ */
+pub struct ParseReport {
+    pub issues: Vec<ParseIssue>,
+    pub out_of_range: Vec<RangeIssue>,
+    pub weight_mismatches: Vec<WeightIssue>,
+    pub fields: Vec<(Column, FieldCounts)>,
+    pub empty_input: Option<EmptyInput>,
+}
+
+
+impl ParseReport {
+    pub fn record(&mut self, line: usize, column: Column, value: &str) {
+        self.issues.push(ParseIssue { line, column, value: value.to_string() });
+        self.field(column).failed += 1;
+    }
+
+    /*
+        Count how one value was taken, from its raw text and what the normalizer made of it.
+
+        Runtime: O(n) in the length of the value
+     */
+    pub fn count_field(&mut self, column: Column, raw: &str, cleaned: Option<&str>) {
+        let counts = self.field(column);
+        match cleaned {
+            Some(cleaned) if !record::is_missing(cleaned) && cleaned.trim() == raw.trim() => counts.verbatim += 1,
+            Some(cleaned) if !record::is_missing(cleaned) => counts.extracted += 1,
+            _ => counts.none += 1,
+        }
+    }
+
+    // The counts of a column, added the first time one of its values is counted.
+    fn field(&mut self, column: Column) -> &mut FieldCounts {
+        let index = match self.fields.iter().position(|(other, _)| *other == column) {
+            Some(index) => index,
+            None => {
+                self.fields.push((column, FieldCounts::default()));
+                self.fields.len() - 1
+            }
+        };
+        &mut self.fields[index].1
+    }
+
+    // How each column's values were taken, in column order.
+    pub fn field_counts(&self) -> Vec<(Column, FieldCounts)> {
+        Column::ALL
+            .into_iter()
+            .map(|column| (column, self.fields.iter().find(|(other, _)| *other == column).map_or_else(FieldCounts::default, |(_, counts)| *counts)))
+            .collect()
+    }
+
+    pub fn record_out_of_range(&mut self, line: usize, column: Column, value: f32, range: Range, action: BoundsAction) {
+        self.out_of_range.push(RangeIssue { line, column, value, range, action });
+    }
+
+    pub fn record_weight_mismatch(&mut self, line: usize, weight: &Weight) {
+        if let Some(ounces) = weight.ounces {
+            self.weight_mismatches.push(WeightIssue { line, grams: weight.grams.clone(), ounces });
+        }
+    }
+
+    // Appends the issues of a later part of the file.
+    pub fn extend(&mut self, other: ParseReport) {
+        self.issues.extend(other.issues);
+        self.out_of_range.extend(other.out_of_range);
+        self.weight_mismatches.extend(other.weight_mismatches);
+        for (column, counts) in &other.fields {
+            self.field(*column).add(counts);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.issues.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    // How many fields of each column failed, for a short summary.
+    pub fn counts(&self) -> Vec<(Column, usize)> {
+        Column::ALL
+            .into_iter()
+            .map(|column| (column, self.issues.iter().filter(|issue| issue.column == column).count()))
+            .filter(|&(_, count)| count > 0)
+            .collect()
+    }
+
+    // The short summary printed after a lenient load: whether the file was empty, one line
+    // for the fields that were not numbers, then every number outside the bounds and every
+    // weight whose units disagree.
+    pub fn warnings(&self) -> Vec<String> {
+        let mut warnings: Vec<String> = self.empty_input.iter().map(|empty| format!("Warning: {}", empty)).collect();
+        if !self.is_empty() {
+            let counts: Vec<String> = self.counts().iter().map(|(column, count)| format!("{} {}", column, count)).collect();
+            warnings.push(format!("{} values were not numbers and were left empty ({}); --strict lists them", self.len(), counts.join(", ")));
+        }
+        warnings.extend(self.out_of_range.iter().map(|issue| issue.to_string()));
+        warnings.extend(self.weight_mismatches.iter().map(|issue| issue.to_string()));
+
+        warnings
+    }
+}
+
+impl fmt::Display for ParseReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let values = |count: usize| if count == 1 { "value" } else { "values" };
+        if !self.issues.is_empty() || (self.out_of_range.is_empty() && self.weight_mismatches.is_empty()) {
+            writeln!(f, "{} {} could not be parsed:", self.len(), values(self.len()))?;
+            for issue in &self.issues {
+                writeln!(f, "    {}", issue)?;
+            }
+        }
+        if !self.out_of_range.is_empty() {
+            writeln!(f, "{} {} outside the plausible range:", self.out_of_range.len(), values(self.out_of_range.len()))?;
+            for issue in &self.out_of_range {
+                writeln!(f, "    {}", issue)?;
+            }
+        }
+        if !self.weight_mismatches.is_empty() {
+            let weights = if self.weight_mismatches.len() == 1 { "weight" } else { "weights" };
+            writeln!(f, "{} {} in grams and ounces that disagree:", self.weight_mismatches.len(), weights)?;
+            for issue in &self.weight_mismatches {
+                writeln!(f, "    {}", issue)?;
+            }
+        }
+        Ok(())
+    }
+}
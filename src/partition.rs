@@ -0,0 +1,128 @@
+use crate::column::Column;
+use crate::error::CellError;
+use crate::format::NumberFormat;
+use crate::grouping::FloatGrouping;
+use crate::view::DatasetView;
+use crate::Cell;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+// Name of the index written next to the partition files.
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+// Partition key of the rows without a value in the partition column.
+pub const UNKNOWN_KEY: &str = "unknown";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+// File format of the partitions.
+pub enum PartitionFormat {
+    Csv,
+    Json,
+}
+
+impl PartitionFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            PartitionFormat::Csv => "csv",
+            PartitionFormat::Json => "json",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+// One file of a partitioned export: the key its rows share, its name in the directory, and
+// how many rows it holds.
+pub struct Partition {
+    pub key: String,
+    pub file: String,
+    pub rows: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+// The index of a partitioned export, written as manifest.json.
+pub struct Manifest {
+    pub column: Column,
+    pub rows: usize,
+    pub partitions: Vec<Partition>,
+}
+
+/*
+    The name of a partition file, e.g. "launch_announced=2020.csv". Characters other than
+    letters, digits, '-' and '.' are replaced by '_' so any key is a safe file name.
+
+    Runtime: O(k)
+ */
+pub fn partition_file(column: Column, key: &str, format: PartitionFormat) -> String {
+    let key: String = key.chars().map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '.' { c } else { '_' }).collect();
+
+    format!("{}={}.{}", column, key, format.extension())
+}
+
+/*
+    Group the rows by the value of a column, in key order. Numeric keys such as years sort
+    by number, and float values are bucketed as FloatGrouping::DEFAULT does; rows without a
+    value go under UNKNOWN_KEY, last.
+
+    Runtime: O(n log n)
+ */
+pub fn partition_by<'a>(cells: &DatasetView<'a>, column: Column) -> Vec<(String, Vec<&'a Cell>)> {
+    let mut groups: BTreeMap<String, Vec<&Cell>> = BTreeMap::new();
+    let mut unknown = Vec::new();
+
+    for cell in cells {
+        match FloatGrouping::DEFAULT.key(cell, column) {
+            Some(key) => groups.entry(key).or_default().push(cell),
+            None => unknown.push(cell),
+        }
+    }
+
+    let mut partitions: Vec<(String, Vec<&Cell>)> = groups.into_iter().collect();
+    partitions.sort_by(|a, b| match (a.0.parse::<f64>(), b.0.parse::<f64>()) {
+        (Ok(x), Ok(y)) => x.total_cmp(&y),
+        _ => a.0.cmp(&b.0),
+    });
+    if !unknown.is_empty() {
+        partitions.push((UNKNOWN_KEY.to_string(), unknown));
+    }
+
+    partitions
+}
+
+/*
+    Write one file per value of a column into a directory (created if needed), plus a
+    manifest.json listing every partition with its key, file and row count. Keys that would
+    get the same file name are told apart by a -2, -3, ... suffix. JSON files get their numbers
+    rounded by `numbers`.
+
+    Runtime: O(n log n)
+ */
+pub fn export_partitioned(cells: &DatasetView, column: Column, dir: &str, format: PartitionFormat, numbers: &NumberFormat) -> Result<Manifest, CellError> {
+    fs::create_dir_all(dir)?;
+    let mut manifest = Manifest { column, rows: cells.len(), partitions: Vec::new() };
+
+    for (key, rows) in partition_by(cells, column) {
+        let mut file = partition_file(column, &key, format);
+        // Keys that only differ in replaced characters would share a file name.
+        let mut copy = 1;
+        while manifest.partitions.iter().any(|partition| partition.file == file) {
+            copy += 1;
+            file = partition_file(column, &format!("{}-{}", key, copy), format);
+        }
+        let path = Path::new(dir).join(&file);
+        let path = path.to_str().ok_or_else(|| CellError::InvalidField { row: None, field: "partition path", value: path.to_string_lossy().into_owned() })?;
+
+        match format {
+            PartitionFormat::Csv => Cell::write_csv(rows.iter().copied(), path)?,
+            PartitionFormat::Json => Cell::write_json(&rows.iter().map(|&cell| numbers.round_cell(cell)).collect::<Vec<Cell>>(), path)?,
+        }
+        manifest.partitions.push(Partition { key, file, rows: rows.len() });
+    }
+
+    let index = fs::File::create(Path::new(dir).join(MANIFEST_FILE))?;
+    serde_json::to_writer_pretty(index, &manifest)?;
+
+    Ok(manifest)
+}
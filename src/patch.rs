@@ -0,0 +1,84 @@
+use crate::error::CellError;
+use crate::Cell;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+/*
+    A change to some fields of a cell. Fields that are Some replace the cell's value; fields
+    that are None leave it as it is, so
+
+        CellPatch { launch_status: Some("Discontinued".to_string()), ..CellPatch::default() }
+
+    changes only the launch status.
+ */
+pub struct CellPatch {
+    pub oem: Option<Arc<str>>,
+    pub model: Option<Box<str>>,
+    pub launch_announced: Option<u32>,
+    pub launch_status: Option<Arc<str>>,
+    pub body_dimensions: Option<Box<str>>,
+    pub body_weight: Option<f32>,
+    pub body_sim: Option<Arc<str>>,
+    pub display_type: Option<Arc<str>>,
+    pub display_size: Option<f32>,
+    pub display_resolution: Option<Box<str>>,
+    pub features_sensors: Option<Box<str>>,
+    pub platform_os: Option<Arc<str>>,
+}
+
+impl CellPatch {
+    // Whether the patch changes nothing.
+    pub fn is_empty(&self) -> bool {
+        *self == CellPatch::default()
+    }
+}
+
+impl Cell {
+    /*
+        Set the fields the patch has a value for, leaving the others unchanged.
+
+        Runtime: O(1)
+     */
+    pub fn apply_patch(&mut self, patch: &CellPatch) {
+        fn set<T: Clone>(field: &mut Option<T>, value: &Option<T>) {
+            if value.is_some() {
+                field.clone_from(value);
+            }
+        }
+
+        set(&mut self.oem, &patch.oem);
+        set(&mut self.model, &patch.model);
+        set(&mut self.launch_announced, &patch.launch_announced);
+        set(&mut self.launch_status, &patch.launch_status);
+        set(&mut self.body_dimensions, &patch.body_dimensions);
+        set(&mut self.body_weight, &patch.body_weight);
+        set(&mut self.body_sim, &patch.body_sim);
+        set(&mut self.display_type, &patch.display_type);
+        set(&mut self.display_size, &patch.display_size);
+        set(&mut self.display_resolution, &patch.display_resolution);
+        set(&mut self.features_sensors, &patch.features_sensors);
+        set(&mut self.platform_os, &patch.platform_os);
+        if patch.features_sensors.is_some() {
+            self.parse_sensors();
+        }
+        self.sync_launch_date();
+        self.sync_release_date();
+    }
+
+    /**
+        Apply a patch to an existing Cell within the vector, returning the cell as it was
+        before the patch.
+
+        Runtime: O(1)
+     */
+    pub fn modify_cell_with_patch(cells: &mut [Cell], index: usize, patch: &CellPatch) -> Result<Option<Cell>, CellError> {
+        let len = cells.len();
+        let cell = cells.get_mut(index).ok_or(CellError::IndexOutOfBounds { index, len })?;
+        let old = cell.clone();
+
+        cell.apply_patch(patch);
+        Ok(Some(old))
+    }
+}
@@ -0,0 +1,487 @@
+use crate::analysis::{low_confidence, MIN_SAMPLE_SIZE};
+use crate::column::Column;
+use crate::country::CountryMap;
+use crate::error::CellError;
+use crate::format::NumberFormat;
+use crate::grouping::FloatGrouping;
+use crate::normalize::Normalizer;
+#[cfg(feature = "parquet")]
+use crate::parquet::{ParquetType, ParquetValue, ParquetWriter};
+use crate::reader::CellReader;
+use crate::schema::Schema;
+use crate::Cell;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+// A function computing a derived value from a cell, writing numbers with the pipeline's format.
+pub type DeriveFn = Box<dyn Fn(&Cell, &NumberFormat) -> Option<String>>;
+
+// A predicate deciding whether a cell is kept.
+pub type FilterFn = Box<dyn Fn(&Cell) -> bool>;
+
+// A named column computed from each cell, written next to the cell's own columns.
+pub struct Derive {
+    name: String,
+    compute: DeriveFn,
+}
+
+impl Derive {
+    pub fn new<F>(name: &str, compute: F) -> Derive
+    where
+        F: Fn(&Cell, &NumberFormat) -> Option<String> + 'static,
+    {
+        Derive { name: name.to_string(), compute: Box::new(compute) }
+    }
+
+    // Adds a "device_class" column (Smartphone / Feature phone).
+    pub fn device_class() -> Derive {
+        Derive::new("device_class", |cell, _| Some(cell.device_class().to_string()))
+    }
+
+    // Adds a "country" column from the OEM's country of origin.
+    pub fn country(countries: CountryMap) -> Derive {
+        Derive::new("country", move |cell, _| cell.country(&countries).map(|country| country.to_string()))
+    }
+
+    // Adds a "screen_to_body_ratio" column, the estimated share of the front taken by the screen.
+    pub fn screen_to_body_ratio() -> Derive {
+        Derive::new("screen_to_body_ratio", |cell, format| cell.screen_to_body_ratio().map(|ratio| format.format(ratio as f64)))
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+// A result computed by looking at each kept cell once.
+pub trait Aggregate {
+    fn name(&self) -> String;
+    fn update(&mut self, cell: &Cell);
+    // The result as (key, value) pairs, with numbers written by `format`.
+    fn finish(&self, format: &NumberFormat) -> Vec<(String, String)>;
+}
+
+// Counts the kept cells per value of a column, float values per bucket.
+pub struct CountBy {
+    column: Column,
+    grouping: FloatGrouping,
+    counts: BTreeMap<String, usize>,
+}
+
+impl CountBy {
+    pub fn new(column: Column) -> CountBy {
+        CountBy { column, grouping: FloatGrouping::DEFAULT, counts: BTreeMap::new() }
+    }
+
+    // Buckets float values by `grouping` instead of the default.
+    pub fn grouping(mut self, grouping: FloatGrouping) -> CountBy {
+        self.grouping = grouping;
+        self
+    }
+}
+
+impl Aggregate for CountBy {
+    fn name(&self) -> String {
+        format!("count_by_{}", self.column)
+    }
+
+    fn update(&mut self, cell: &Cell) {
+        if let Some(value) = self.grouping.key(cell, self.column) {
+            *self.counts.entry(value).or_insert(0) += 1;
+        }
+    }
+
+    fn finish(&self, _: &NumberFormat) -> Vec<(String, String)> {
+        self.counts.iter().map(|(value, count)| (value.clone(), count.to_string())).collect()
+    }
+}
+
+// Mean of a numeric column over the kept cells that have a value.
+pub struct MeanOf {
+    column: Column,
+    sum: f64,
+    count: usize,
+    min_sample: usize,
+}
+
+impl MeanOf {
+    pub fn new(column: Column) -> MeanOf {
+        MeanOf { column, sum: 0.0, count: 0, min_sample: MIN_SAMPLE_SIZE }
+    }
+
+    // Marks means of fewer than `min_sample` values as low confidence instead of the default.
+    pub fn min_sample(mut self, min_sample: usize) -> MeanOf {
+        self.min_sample = min_sample;
+        self
+    }
+
+    // Whether the mean comes from too few values to rely on.
+    pub fn low_confidence(&self) -> bool {
+        low_confidence(self.count, self.min_sample).is_some()
+    }
+
+    // The mean so far, if any cell had a value.
+    pub fn mean(&self) -> Option<f64> {
+        if self.count > 0 {
+            Some(self.sum / self.count as f64)
+        } else {
+            None
+        }
+    }
+
+    // Number of cells that had a value.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+}
+
+impl Aggregate for MeanOf {
+    fn name(&self) -> String {
+        format!("mean_{}", self.column)
+    }
+
+    fn update(&mut self, cell: &Cell) {
+        if let Some(value) = cell.text(self.column).and_then(|value| value.parse::<f64>().ok()) {
+            self.sum += value;
+            self.count += 1;
+        }
+    }
+
+    fn finish(&self, format: &NumberFormat) -> Vec<(String, String)> {
+        let mean = self.mean().map(|mean| format.format(mean)).unwrap_or_default();
+        vec![
+            ("mean".to_string(), mean),
+            ("count".to_string(), self.count.to_string()),
+            ("low_confidence".to_string(), self.low_confidence().to_string()),
+        ]
+    }
+}
+
+/*
+    Runs a separate aggregate for each value of a column, e.g. the mean display size of every
+    OEM. Float columns are grouped by bucket. Cells without a value in the column are skipped.
+ */
+pub struct GroupBy<A: Aggregate> {
+    column: Column,
+    grouping: FloatGrouping,
+    make: Box<dyn Fn() -> A>,
+    groups: BTreeMap<String, A>,
+}
+
+impl<A: Aggregate> GroupBy<A> {
+    // Groups by `column`, starting each group with the aggregate `make` returns.
+    pub fn new(column: Column, make: impl Fn() -> A + 'static) -> GroupBy<A> {
+        GroupBy { column, grouping: FloatGrouping::DEFAULT, make: Box::new(make), groups: BTreeMap::new() }
+    }
+
+    // Buckets float values by `grouping` instead of the default.
+    pub fn grouping(mut self, grouping: FloatGrouping) -> GroupBy<A> {
+        self.grouping = grouping;
+        self
+    }
+
+    // The aggregate of every group, in order of the group value.
+    pub fn groups(&self) -> impl Iterator<Item = (&String, &A)> {
+        self.groups.iter()
+    }
+}
+
+impl<A: Aggregate> Aggregate for GroupBy<A> {
+    fn name(&self) -> String {
+        format!("{}_by_{}", (self.make)().name(), self.column)
+    }
+
+    fn update(&mut self, cell: &Cell) {
+        if let Some(value) = self.grouping.key(cell, self.column) {
+            let make = &self.make;
+            self.groups.entry(value.trim().to_string()).or_insert_with(make).update(cell);
+        }
+    }
+
+    // Each group's results with keys prefixed by the group value, e.g. ("Nokia.mean", "2.4").
+    fn finish(&self, format: &NumberFormat) -> Vec<(String, String)> {
+        self.groups
+            .iter()
+            .flat_map(|(group, aggregate)| aggregate.finish(format).into_iter().map(move |(key, value)| (format!("{}.{}", group, key), value)))
+            .collect()
+    }
+}
+
+// Where the pipeline writes its output.
+pub enum Sink {
+    Csv(String),
+    Json(String),
+    // Typed columns: launch_announced as integers, body_weight and display_size as floats.
+    #[cfg(feature = "parquet")]
+    Parquet(String),
+}
+
+// Counts reported after a pipeline run.
+#[derive(Debug, PartialEq)]
+pub struct PipelineSummary {
+    pub rows_read: usize,
+    pub rows_kept: usize,
+}
+
+#[derive(Serialize)]
+// One output row for the JSON sink: the cell's columns plus the derived columns.
+struct Row<'a> {
+    #[serde(flatten)]
+    cell: &'a Cell,
+    #[serde(flatten)]
+    derived: BTreeMap<&'a str, Option<String>>,
+}
+
+/*
+    A load-transform-export job declared as one expression and run once:
+
+        Pipeline::new().source("cells.csv").filter(f).derive(d).aggregate(a).sink(Sink::Json(path)).run()
+
+    Rows are read, filtered, derived and written one at a time. With aggregates, only the
+    aggregate results are written, once every row has been seen. Derived and aggregated
+    numbers are written with the pipeline's number format.
+ */
+pub struct Pipeline {
+    source: Option<String>,
+    schema: Schema,
+    normalizer: Normalizer,
+    filters: Vec<FilterFn>,
+    derives: Vec<Derive>,
+    aggregates: Vec<Box<dyn Aggregate>>,
+    number_format: NumberFormat,
+    sink: Option<Sink>,
+}
+
+impl Pipeline {
+    pub fn new() -> Pipeline {
+        Pipeline {
+            source: None,
+            schema: Schema::default(),
+            normalizer: Normalizer::default(),
+            filters: Vec::new(),
+            derives: Vec::new(),
+            aggregates: Vec::new(),
+            number_format: NumberFormat::default(),
+            sink: None,
+        }
+    }
+
+    pub fn source(mut self, csv: &str) -> Pipeline {
+        self.source = Some(csv.to_string());
+        self
+    }
+
+    // Finds the source's columns through a schema instead of by their usual headers.
+    pub fn schema(mut self, schema: Schema) -> Pipeline {
+        self.schema = schema;
+        self
+    }
+
+    // Cleans the source with these normalization stages instead of the defaults.
+    pub fn normalizer(mut self, normalizer: Normalizer) -> Pipeline {
+        self.normalizer = normalizer;
+        self
+    }
+
+    // Keeps only the cells the predicate accepts. Several filters must all accept.
+    pub fn filter<F>(mut self, predicate: F) -> Pipeline
+    where
+        F: Fn(&Cell) -> bool + 'static,
+    {
+        self.filters.push(Box::new(predicate));
+        self
+    }
+
+    pub fn derive(mut self, derive: Derive) -> Pipeline {
+        self.derives.push(derive);
+        self
+    }
+
+    pub fn aggregate<A>(mut self, aggregate: A) -> Pipeline
+    where
+        A: Aggregate + 'static,
+    {
+        self.aggregates.push(Box::new(aggregate));
+        self
+    }
+
+    // Writes derived and aggregated numbers with this format instead of the default.
+    pub fn number_format(mut self, format: NumberFormat) -> Pipeline {
+        self.number_format = format;
+        self
+    }
+
+    pub fn sink(mut self, sink: Sink) -> Pipeline {
+        self.sink = Some(sink);
+        self
+    }
+
+    /*
+        Run the job in a single pass over the source file.
+
+        Runtime: O(n)
+     */
+    pub fn run(mut self) -> Result<PipelineSummary, CellError> {
+        let source = self.source.take().ok_or(CellError::Unset { what: "pipeline source" })?;
+        let sink = self.sink.take().ok_or(CellError::Unset { what: "pipeline sink" })?;
+        let normalizer = std::mem::take(&mut self.normalizer);
+        let reader = CellReader::open_with(&source, &self.schema, normalizer)?;
+        let mut output = SinkWriter::open(&sink, &self.derives, self.aggregates.is_empty())?;
+        let mut summary = PipelineSummary { rows_read: 0, rows_kept: 0 };
+
+        for cell in reader {
+            let cell = cell?;
+            summary.rows_read += 1;
+
+            if !self.filters.iter().all(|predicate| predicate(&cell)) {
+                continue;
+            }
+            summary.rows_kept += 1;
+
+            if self.aggregates.is_empty() {
+                let derived: Vec<Option<String>> = self.derives.iter().map(|derive| (derive.compute)(&cell, &self.number_format)).collect();
+                output.write_row(&cell, &self.derives, derived)?;
+            } else {
+                for aggregate in &mut self.aggregates {
+                    aggregate.update(&cell);
+                }
+            }
+        }
+
+        output.finish(&self.aggregates, &self.number_format)?;
+
+        Ok(summary)
+    }
+}
+
+impl Default for Pipeline {
+    fn default() -> Pipeline {
+        Pipeline::new()
+    }
+}
+
+// The open output file of a running pipeline.
+enum SinkWriter {
+    Csv(Box<csv::Writer<File>>),
+    Json { writer: BufWriter<File>, rows: usize },
+    #[cfg(feature = "parquet")]
+    Parquet(Box<ParquetWriter<BufWriter<File>>>),
+}
+
+// The type of a column of the Parquet sink.
+#[cfg(feature = "parquet")]
+fn parquet_type(column: Column) -> ParquetType {
+    match column {
+        Column::LaunchAnnounced => ParquetType::Int32,
+        Column::BodyWeight | Column::DisplaySize => ParquetType::Float,
+        _ => ParquetType::Utf8,
+    }
+}
+
+impl SinkWriter {
+    fn open(sink: &Sink, derives: &[Derive], writes_rows: bool) -> Result<SinkWriter, CellError> {
+        match sink {
+            Sink::Csv(path) => {
+                let mut writer = csv::Writer::from_path(path)?;
+                if writes_rows {
+                    let mut headers: Vec<&str> = Column::ALL.iter().map(|column| column.name()).collect();
+                    headers.extend(derives.iter().map(|derive| derive.name()));
+                    writer.write_record(&headers)?;
+                } else {
+                    writer.write_record(["aggregate", "key", "value"])?;
+                }
+                Ok(SinkWriter::Csv(Box::new(writer)))
+            }
+            Sink::Json(path) => {
+                let mut writer = BufWriter::new(File::create(path)?);
+                if writes_rows {
+                    write!(writer, "[")?;
+                }
+                Ok(SinkWriter::Json { writer, rows: 0 })
+            }
+            #[cfg(feature = "parquet")]
+            Sink::Parquet(path) => {
+                let columns: Vec<(String, ParquetType)> = if writes_rows {
+                    let columns = Column::ALL.iter().map(|&column| (column.name().to_string(), parquet_type(column)));
+                    columns.chain(derives.iter().map(|derive| (derive.name().to_string(), ParquetType::Utf8))).collect()
+                } else {
+                    ["aggregate", "key", "value"].iter().map(|name| (name.to_string(), ParquetType::Utf8)).collect()
+                };
+                Ok(SinkWriter::Parquet(Box::new(ParquetWriter::create(path, &columns)?)))
+            }
+        }
+    }
+
+    fn write_row(&mut self, cell: &Cell, derives: &[Derive], derived: Vec<Option<String>>) -> Result<(), CellError> {
+        match self {
+            SinkWriter::Csv(writer) => {
+                let mut record: Vec<String> = Column::ALL.iter().map(|&column| cell.text(column).unwrap_or_default()).collect();
+                record.extend(derived.into_iter().map(|value| value.unwrap_or_default()));
+                writer.write_record(&record)?;
+            }
+            SinkWriter::Json { writer, rows } => {
+                if *rows > 0 {
+                    write!(writer, ",")?;
+                }
+                let derived = derives.iter().map(|derive| derive.name()).zip(derived).collect();
+                serde_json::to_writer(&mut *writer, &Row { cell, derived })?;
+                *rows += 1;
+            }
+            #[cfg(feature = "parquet")]
+            SinkWriter::Parquet(writer) => {
+                let mut row: Vec<Option<ParquetValue>> = Column::ALL
+                    .iter()
+                    .map(|&column| match column {
+                        Column::LaunchAnnounced => cell.launch_announced.map(|year| ParquetValue::Int32(year as i32)),
+                        Column::BodyWeight => cell.body_weight.map(ParquetValue::Float),
+                        Column::DisplaySize => cell.display_size.map(ParquetValue::Float),
+                        _ => cell.text(column).map(ParquetValue::Utf8),
+                    })
+                    .collect();
+                row.extend(derived.into_iter().map(|value| value.map(ParquetValue::Utf8)));
+                writer.write_row(&row)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn finish(self, aggregates: &[Box<dyn Aggregate>], format: &NumberFormat) -> Result<(), CellError> {
+        match self {
+            SinkWriter::Csv(mut writer) => {
+                for aggregate in aggregates {
+                    for (key, value) in aggregate.finish(format) {
+                        writer.write_record([aggregate.name(), key, value])?;
+                    }
+                }
+                writer.flush()?;
+            }
+            SinkWriter::Json { mut writer, .. } => {
+                if aggregates.is_empty() {
+                    writeln!(writer, "]")?;
+                } else {
+                    let results: BTreeMap<String, BTreeMap<String, String>> = aggregates
+                        .iter()
+                        .map(|aggregate| (aggregate.name(), aggregate.finish(format).into_iter().collect()))
+                        .collect();
+                    serde_json::to_writer_pretty(&mut writer, &results)?;
+                    writeln!(writer)?;
+                }
+                writer.flush()?;
+            }
+            #[cfg(feature = "parquet")]
+            SinkWriter::Parquet(mut writer) => {
+                for aggregate in aggregates {
+                    for (key, value) in aggregate.finish(format) {
+                        writer.write_row(&[Some(ParquetValue::Utf8(aggregate.name())), Some(ParquetValue::Utf8(key)), Some(ParquetValue::Utf8(value))])?;
+                    }
+                }
+                writer.finish()?;
+            }
+        }
+
+        Ok(())
+    }
+}
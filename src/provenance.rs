@@ -0,0 +1,28 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::Arc;
+
+// Headers under which exports carry the provenance of each row, so a file written from
+// merged sources still points back to them when it is read again.
+pub const SOURCE_FILE: &str = "source_file";
+pub const SOURCE_LINE: &str = "source_line";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+/// Where a cell was read from: the source file and its line there, the header being line 1.
+/// The file name is shared by every cell of a file.
+pub struct Provenance {
+    pub file: Arc<str>,
+    pub line: usize,
+}
+
+impl Provenance {
+    pub fn new(file: &str, line: usize) -> Provenance {
+        Provenance { file: file.into(), line }
+    }
+}
+
+impl fmt::Display for Provenance {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.file, self.line)
+    }
+}
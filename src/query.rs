@@ -0,0 +1,163 @@
+use crate::cancel::{self, Cancel};
+use crate::column::Column;
+use crate::error::CellError;
+use crate::filter::Filter;
+use crate::view::DatasetView;
+use crate::Cell;
+use serde::Deserialize;
+use std::cmp::Ordering;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+// The column rows are ordered by, and in which direction.
+pub struct SortKey {
+    pub column: Column,
+    pub descending: bool,
+}
+
+impl SortKey {
+    /*
+        Parse a sort such as "display_size" (ascending) or "display_size:desc".
+
+        Runtime: O(1)
+     */
+    pub fn parse(text: &str) -> Result<SortKey, CellError> {
+        let (name, direction) = text.split_once(':').unwrap_or((text, "asc"));
+        let descending = match direction.trim() {
+            "asc" => false,
+            "desc" => true,
+            other => return Err(CellError::Unknown { kind: "sort direction", name: other.to_string() }),
+        };
+        let column = Column::from_name(name).ok_or_else(|| CellError::UnknownColumn { name: name.trim().to_string() })?;
+
+        Ok(SortKey { column, descending })
+    }
+
+    /*
+        Compare two cells by the key's column: numerically if both values are numbers,
+        otherwise as case-insensitive text. Missing values sort last in either direction.
+
+        Runtime: O(1)
+     */
+    pub fn compare(&self, a: &Cell, b: &Cell) -> Ordering {
+        let ordering = match (a.text(self.column), b.text(self.column)) {
+            (Some(a), Some(b)) => match (a.trim().parse::<f64>(), b.trim().parse::<f64>()) {
+                (Ok(a), Ok(b)) => a.total_cmp(&b),
+                _ => a.to_lowercase().cmp(&b.to_lowercase()),
+            },
+            (Some(_), None) => return Ordering::Less,
+            (None, Some(_)) => return Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        };
+
+        if self.descending { ordering.reverse() } else { ordering }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default)]
+/*
+    A filter, sort and projection kept under a name in the config, e.g.
+
+        [queries.flagships-2020]
+        filter = "launch_announced = 2020 && display_size >= 6.5"
+        sort = "body_weight:desc"
+        columns = ["oem", "model", "body_weight"]
+        limit = 20
+
+    Every field is optional; an empty query lists every row with every column.
+ */
+pub struct SavedQuery {
+    pub filter: String,
+    pub sort: Option<String>,
+    pub columns: Vec<Column>,
+    pub limit: Option<usize>,
+}
+
+impl SavedQuery {
+    // The columns to show, all of them when none are named.
+    pub fn columns(&self) -> Vec<Column> {
+        if self.columns.is_empty() { Column::ALL.to_vec() } else { self.columns.clone() }
+    }
+
+    /*
+        The rows of a view matching the query's filter, in its sort order. Sorting is stable,
+        so rows with equal values keep their file order. The limit is left to the caller, which
+        usually also reports how many rows matched.
+
+        Runtime: O(n log n)
+     */
+    pub fn select<'a>(&self, view: &DatasetView<'a>) -> Result<Vec<&'a Cell>, CellError> {
+        let mut rows: Vec<&Cell> = view.matching(&Filter::parse(&self.filter)?).iter().collect();
+
+        if let Some(key) = self.sort_key()? {
+            rows.sort_by(|a, b| key.compare(a, b));
+        }
+
+        Ok(rows)
+    }
+
+    /*
+        Like select, but stops with CellError::Cancelled or TimedOut once `cancel` says so,
+        while filtering or sorting. A cancelled sort stops comparing, so it ends quickly.
+
+        Runtime: O(n log n)
+     */
+    pub fn select_until<'a>(&self, view: &DatasetView<'a>, cancel: &Cancel) -> Result<Vec<&'a Cell>, CellError> {
+        let mut rows: Vec<&Cell> = view.try_matching(&Filter::parse(&self.filter)?, cancel)?.iter().collect();
+
+        if let Some(key) = self.sort_key()? {
+            sort_until(&mut rows, cancel, |a, b| key.compare(a, b));
+        }
+        cancel.check()?;
+
+        Ok(rows)
+    }
+
+    /*
+        Like select, but returns the positions of the rows in the file, e.g. to copy their raw
+        records.
+
+        Runtime: O(n log n)
+     */
+    pub fn select_indices(&self, cells: &[Cell]) -> Result<Vec<usize>, CellError> {
+        self.select_indices_until(cells, &Cancel::new())
+    }
+
+    // Like select_indices, stopping once `cancel` says so as select_until does.
+    pub fn select_indices_until(&self, cells: &[Cell], cancel: &Cancel) -> Result<Vec<usize>, CellError> {
+        let filter = Filter::parse(&self.filter)?;
+        let mut indices = Vec::new();
+        for (index, cell) in cells.iter().enumerate() {
+            if index % cancel::CHECK_EVERY == 0 {
+                cancel.check()?;
+            }
+            if filter.matches(cell) {
+                indices.push(index);
+            }
+        }
+
+        if let Some(key) = self.sort_key()? {
+            sort_until(&mut indices, cancel, |&a, &b| key.compare(&cells[a], &cells[b]));
+        }
+        cancel.check()?;
+
+        Ok(indices)
+    }
+
+    fn sort_key(&self) -> Result<Option<SortKey>, CellError> {
+        self.sort.as_deref().map(SortKey::parse).transpose()
+    }
+}
+
+// A stable sort that stops comparing once `cancel` says so, so a cancelled sort ends quickly;
+// the caller checks the token afterwards.
+fn sort_until<T>(items: &mut [T], cancel: &Cancel, compare: impl Fn(&T, &T) -> Ordering) {
+    let (mut compared, mut stopped) = (0, false);
+    items.sort_by(|a, b| {
+        compared += 1;
+        if compared % cancel::CHECK_EVERY == 0 {
+            stopped = stopped || cancel.is_cancelled();
+        }
+        if stopped { Ordering::Equal } else { compare(a, b) }
+    });
+}
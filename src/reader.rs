@@ -0,0 +1,92 @@
+use crate::column::HeaderIndex;
+use crate::error::CellError;
+use crate::normalize::Normalizer;
+use crate::parse::{EmptyInput, ParseReport};
+use crate::schema::Schema;
+use crate::Cell;
+use std::fs::File;
+use std::io::Read;
+
+/*
+    Reads cells from a CSV source one record at a time, so a file can be analysed in a single
+    pass without holding every row in memory:
+
+        for cell in CellReader::open("cells.csv")? {
+            let cell = cell?;
+            ...
+        }
+
+    The reader owns its normalizer; into_normalizer() gives it back with its counts. Numeric
+    values that are not numbers become None and are listed in report(), which also says when
+    the file turned out to have no rows.
+ */
+pub struct CellReader<R: Read = File> {
+    records: csv::StringRecordsIntoIter<R>,
+    headers: HeaderIndex,
+    normalizer: Normalizer,
+    report: ParseReport,
+    // Why the file has no rows, reported if the first read finds none.
+    empty: EmptyInput,
+    rows: usize,
+}
+
+impl CellReader<File> {
+    // Opens a CSV file with the usual headers and the default cleaning stages.
+    pub fn open(filename: &str) -> Result<CellReader<File>, CellError> {
+        CellReader::open_with(filename, &Schema::default(), Normalizer::default())
+    }
+
+    // Opens a CSV file, finding its columns through a schema and cleaning with a normalizer.
+    // Each cell records the file and line it was read from.
+    pub fn open_with(filename: &str, schema: &Schema, normalizer: Normalizer) -> Result<CellReader<File>, CellError> {
+        let mut reader = CellReader::from_reader(File::open(filename)?, schema, normalizer)?;
+        reader.headers = reader.headers.in_file(filename);
+        Ok(reader)
+    }
+}
+
+impl<R: Read> CellReader<R> {
+    /*
+        Reads the header row of any CSV source; the records are read as the reader is iterated.
+
+        Runtime: O(c)
+     */
+    pub fn from_reader(source: R, schema: &Schema, normalizer: Normalizer) -> Result<CellReader<R>, CellError> {
+        let mut reader = csv::Reader::from_reader(source);
+        let headers = HeaderIndex::from_headers_with(reader.headers()?, schema)?;
+        let empty = EmptyInput::of(reader.headers()?);
+
+        Ok(CellReader { records: reader.into_records(), headers, normalizer, report: ParseReport::default(), empty, rows: 0 })
+    }
+
+    // The values read so far that could not be parsed.
+    pub fn report(&self) -> &ParseReport {
+        &self.report
+    }
+
+    // The normalizer, e.g. to report what its stages changed once the file is read.
+    pub fn into_normalizer(self) -> Normalizer {
+        self.normalizer
+    }
+}
+
+impl<R: Read> Iterator for CellReader<R> {
+    type Item = Result<Cell, CellError>;
+
+    // Runtime: O(1)
+    fn next(&mut self) -> Option<Result<Cell, CellError>> {
+        let Some(record) = self.records.next() else {
+            if self.rows == 0 {
+                self.report.empty_input = Some(self.empty);
+            }
+            return None;
+        };
+        self.rows += 1;
+        let record = match record {
+            Ok(record) => record,
+            Err(error) => return Some(Err(error.into())),
+        };
+
+        Some(Ok(Cell::parse_record(&record, &self.headers, &mut self.normalizer, &mut self.report)))
+    }
+}
@@ -0,0 +1,150 @@
+use crate::column::Column;
+use crate::error::CellError;
+use crate::Cell;
+use std::fmt;
+use std::fs::File;
+use std::io::BufReader;
+
+/// One row of a CSV dataset, read, summarized and edited by field. [`Cell`] is the phone
+/// row; another dataset, e.g. of laptops, implements this for its own row type to reuse the
+/// reading, [`crate::summary::DatasetStats`] and the CRUD functions of this module.
+pub trait Record {
+    /// Names one field of the record, e.g. [`Column`] for cells.
+    type Field: Copy + PartialEq + fmt::Display + 'static;
+
+    /// Every field, in CSV order.
+    fn fields() -> &'static [Self::Field];
+
+    /// The CSV header name of a field.
+    fn field_name(field: Self::Field) -> &'static str;
+
+    /// Whether a field holds numbers, so its values are summed and ranged.
+    fn is_numeric(field: Self::Field) -> bool;
+
+    /// The value of a field as text, or None if it is missing.
+    fn text(&self, field: Self::Field) -> Option<String>;
+
+    /// The value of a numeric field, or None for a missing value or a text field.
+    fn number(&self, field: Self::Field) -> Option<f32>;
+
+    /// Sets a field from its raw text. A value for which is_missing holds clears the field;
+    /// a numeric field that does not parse is an error and leaves the record unchanged.
+    fn set_text(&mut self, field: Self::Field, value: &str) -> Result<(), CellError>;
+
+    /// The field with the given header name, ignoring case and surrounding spaces.
+    fn field_by_name(name: &str) -> Option<Self::Field> {
+        let name = name.trim();
+        Self::fields().iter().copied().find(|&field| Self::field_name(field).eq_ignore_ascii_case(name))
+    }
+
+    /// Whether every field is missing, as for a blank row.
+    fn is_empty(&self) -> bool {
+        Self::fields().iter().all(|&field| self.text(field).is_none())
+    }
+}
+
+/// Whether a raw value stands for a missing one: blank or '-'.
+pub fn is_missing(value: &str) -> bool {
+    let value = value.trim();
+    value.is_empty() || value == "-"
+}
+
+impl Record for Cell {
+    type Field = Column;
+
+    fn fields() -> &'static [Column] {
+        &Column::ALL
+    }
+
+    fn field_name(column: Column) -> &'static str {
+        column.name()
+    }
+
+    fn is_numeric(column: Column) -> bool {
+        matches!(column, Column::LaunchAnnounced | Column::BodyWeight | Column::DisplaySize)
+    }
+
+    fn text(&self, column: Column) -> Option<String> {
+        Cell::text(self, column)
+    }
+
+    fn number(&self, column: Column) -> Option<f32> {
+        Cell::number(self, column)
+    }
+
+    fn set_text(&mut self, column: Column, value: &str) -> Result<(), CellError> {
+        Cell::set_text(self, column, value)
+    }
+}
+
+/*
+    Read every row of a CSV file as a record, finding the fields by header name in any order.
+    Values are set as they are, without the cleaning Cell::read_csv does. Fails on a field
+    without a header, or a numeric value that does not parse, with its row.
+
+    Runtime: O(n * c)
+ */
+pub fn read_records<R: Record + Default>(filename: &str) -> Result<Vec<R>, CellError> {
+    let mut reader = csv::Reader::from_reader(BufReader::new(File::open(filename)?));
+    let headers = reader.headers()?.clone();
+    let positions = R::fields()
+        .iter()
+        .map(|&field| {
+            let position = headers.iter().position(|header| R::field_by_name(header) == Some(field));
+            position.map(|position| (field, position)).ok_or(CellError::MissingField { field: R::field_name(field) })
+        })
+        .collect::<Result<Vec<(R::Field, usize)>, CellError>>()?;
+
+    let mut records = Vec::new();
+    for (row, result) in reader.records().enumerate() {
+        let line = result?;
+        let mut record = R::default();
+        for &(field, position) in &positions {
+            let value = line.get(position).unwrap_or_default();
+            record.set_text(field, value).map_err(|_| CellError::InvalidField { row: Some(row + 1), field: R::field_name(field), value: value.to_string() })?;
+        }
+        records.push(record);
+    }
+
+    Ok(records)
+}
+
+/*
+    Insert a record at `index`, which may be the end. Nothing is displaced, so the result is
+    None, the same type as modify and delete return.
+
+    Runtime: O(n)
+ */
+pub fn insert<R>(records: &mut Vec<R>, index: usize, record: R) -> Result<Option<R>, CellError> {
+    if index > records.len() {
+        return Err(CellError::IndexOutOfBounds { index, len: records.len() });
+    }
+
+    records.insert(index, record);
+    Ok(None)
+}
+
+/*
+    Replace the record at `index`, returning the one it replaced.
+
+    Runtime: O(1)
+ */
+pub fn modify<R>(records: &mut [R], index: usize, record: R) -> Result<Option<R>, CellError> {
+    let len = records.len();
+    let old = records.get_mut(index).ok_or(CellError::IndexOutOfBounds { index, len })?;
+
+    Ok(Some(std::mem::replace(old, record)))
+}
+
+/*
+    Remove the record at `index`, returning it.
+
+    Runtime: O(n)
+ */
+pub fn delete<R>(records: &mut Vec<R>, index: usize) -> Result<Option<R>, CellError> {
+    if index >= records.len() {
+        return Err(CellError::IndexOutOfBounds { index, len: records.len() });
+    }
+
+    Ok(Some(records.remove(index)))
+}
@@ -0,0 +1,220 @@
+use crate::cancel::Cancel;
+use crate::column::Column;
+use crate::config::Config;
+use crate::country::CountryMap;
+use crate::dataset::{JoinMatch, MatchConfidence};
+use crate::error::CellError;
+use crate::filter::Filter;
+use crate::report::{Context, Report, Style, ANALYSES};
+use crate::table;
+use crate::workspace::Workspace;
+use crate::Cell;
+use std::io::{BufRead, Write};
+
+// Rows `head` prints unless given a number.
+pub const HEAD_ROWS: usize = 10;
+
+const HELP: &str = "\
+load <file> [as <name>]         load a CSV file, named after the file unless given a name
+datasets                        list the loaded datasets
+drop <name>                     unload a dataset
+count <name> [filter]           count the rows, or those matching a filter expression
+head <name> [n]                 print the first rows as a table
+queries                         list the queries saved in the config
+query <name> <saved>            run a saved query on a dataset
+report <name> [analysis ...]    run the analyses of the report, all of them by default
+diff <left> <right>             compare the rows of two datasets by oem and model
+join <left> <right> [fuzzy]     match the rows of one dataset with those of another
+union <left> <right> as <name>  put the rows of two datasets together, without duplicates
+help                            print this
+quit                            leave (also exit, or end of input)
+Ctrl-C stops a running count, query or report.
+";
+
+/*
+    An interactive session over a workspace of named datasets, e.g.
+
+        load cells_2023.csv as old
+        load cells.csv as new
+        diff old new
+
+    Each command names the datasets it works on. Errors are printed and the session goes
+    on. Counts, queries and reports check a cancellation token, which each command starts
+    with cleared, so a signal handler that cancels it stops the running command only.
+ */
+pub struct Repl<'a> {
+    pub workspace: Workspace,
+    config: &'a Config,
+    after_year: u32,
+    cancel: Cancel,
+}
+
+impl<'a> Repl<'a> {
+    pub fn new(config: &'a Config, after_year: u32) -> Repl<'a> {
+        Repl { workspace: Workspace::new(), config, after_year, cancel: Cancel::new() }
+    }
+
+    // Stops the running command when this token, or a clone of it, is cancelled.
+    pub fn with_cancel(mut self, cancel: Cancel) -> Repl<'a> {
+        self.cancel = cancel;
+        self
+    }
+
+    /*
+        Run one command line, returning what it prints, or None when it ends the session.
+        A blank line prints nothing.
+
+        Runtime: that of the command
+     */
+    pub fn execute(&mut self, line: &str) -> Result<Option<String>, CellError> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let Some((&command, args)) = words.split_first() else {
+            return Ok(Some(String::new()));
+        };
+        self.cancel.reset();
+
+        let output = match (command, args) {
+            ("quit" | "exit", []) => return Ok(None),
+            ("help", []) => HELP.to_string(),
+            ("load", [file]) => self.load(file, &Workspace::default_name(file))?,
+            ("load", [file, "as", name]) => self.load(file, name)?,
+            ("datasets", []) => self.datasets(),
+            ("drop", [name]) => match self.workspace.remove(name) {
+                Some(_) => format!("Dropped {}\n", name),
+                None => return Err(CellError::Unknown { kind: "dataset", name: name.to_string() }),
+            },
+            ("count", [name, filter @ ..]) => self.count(name, &filter.join(" "))?,
+            ("head", [name]) => self.head(name, HEAD_ROWS)?,
+            ("head", [name, n]) => self.head(name, n.parse().map_err(|_| CellError::InvalidField { row: None, field: "number of rows", value: n.to_string() })?)?,
+            ("queries", []) => self.queries(),
+            ("query", [name, saved]) => self.query(name, saved)?,
+            ("report", [name, analyses @ ..]) => self.report(name, analyses)?,
+            ("diff", [left, right]) => self.workspace.diff(left, right)?.to_string(),
+            ("join", [left, right]) => self.join(left, right, JoinMatch::Exact)?,
+            ("join", [left, right, "fuzzy"]) => self.join(left, right, JoinMatch::Fuzzy)?,
+            ("union", [left, right, "as", name]) => {
+                let duplicates = self.workspace.union(left, right, name, self.config)?;
+                format!("{}: {} rows ({} duplicates left out)\n", name, self.workspace.get(name)?.dataset.len(), duplicates)
+            }
+            _ => return Err(CellError::Unknown { kind: "command", name: line.trim().to_string() }),
+        };
+
+        Ok(Some(output))
+    }
+
+    /*
+        Read commands from the input until quit or the end of the input, writing a prompt
+        before each and then its output or error.
+
+        Runtime: that of the commands
+     */
+    pub fn run(&mut self, input: &mut impl BufRead, output: &mut impl Write) -> Result<(), CellError> {
+        loop {
+            write!(output, "cells> ")?;
+            output.flush()?;
+
+            let mut line = String::new();
+            if input.read_line(&mut line)? == 0 {
+                return Ok(());
+            }
+            match self.execute(&line) {
+                Ok(Some(text)) => write!(output, "{}", text)?,
+                Ok(None) => return Ok(()),
+                Err(error) => writeln!(output, "Error: {}", error)?,
+            }
+        }
+    }
+
+    fn load(&mut self, file: &str, name: &str) -> Result<String, CellError> {
+        let replaced = self.workspace.get(name).is_ok();
+        let rows = self.workspace.load(file, name, self.config)?.dataset.len();
+        Ok(format!("Loaded {} rows from {} as {}{}\n", rows, file, name, if replaced { " (replacing it)" } else { "" }))
+    }
+
+    fn datasets(&self) -> String {
+        if self.workspace.is_empty() {
+            return "No datasets loaded (see `help`)\n".to_string();
+        }
+        self.workspace
+            .datasets()
+            .map(|(name, loaded)| format!("{}: {} rows ({})\n", name, loaded.dataset.len(), loaded.file.as_deref().unwrap_or("made in this session")))
+            .collect()
+    }
+
+    fn count(&self, name: &str, filter: &str) -> Result<String, CellError> {
+        let dataset = &self.workspace.get(name)?.dataset;
+        let matching = dataset.view().try_matching(&Filter::parse(filter)?, &self.cancel)?.len();
+        Ok(format!("{} of {} rows\n", matching, dataset.len()))
+    }
+
+    fn head(&self, name: &str, n: usize) -> Result<String, CellError> {
+        let rows: Vec<&Cell> = self.workspace.get(name)?.dataset.cells().iter().take(n).collect();
+        Ok(table::render_table(&rows, &Column::ALL, &self.config.number_format))
+    }
+
+    fn queries(&self) -> String {
+        if self.config.queries.is_empty() {
+            return "No queries saved in the config\n".to_string();
+        }
+        self.config
+            .queries
+            .iter()
+            .map(|(name, saved)| format!("{}: {}\n", name, if saved.filter.is_empty() { "(every row)" } else { &saved.filter }))
+            .collect()
+    }
+
+    // Prints the rows a saved query selects as `cells query run` does.
+    fn query(&self, name: &str, saved: &str) -> Result<String, CellError> {
+        let Some(query) = self.config.queries.get(saved) else {
+            return Err(CellError::Unknown { kind: "saved query", name: saved.to_string() });
+        };
+        let dataset = &self.workspace.get(name)?.dataset;
+        let matching = query.select_until(&dataset.view(), &self.cancel)?;
+        let shown = &matching[..query.limit.unwrap_or(matching.len()).min(matching.len())];
+
+        Ok(format!("{}{} of {} rows match\n", table::render_table(shown, &query.columns(), &self.config.number_format), matching.len(), dataset.len()))
+    }
+
+    fn report(&self, name: &str, analyses: &[&str]) -> Result<String, CellError> {
+        if let Some(unknown) = analyses.iter().find(|name| !ANALYSES.contains(name)) {
+            return Err(CellError::Unknown { kind: "analysis", name: unknown.to_string() });
+        }
+
+        let loaded = self.workspace.get(name)?;
+        let view = loaded.dataset.view();
+        let countries = CountryMap::from_config(self.config);
+        let context = Context {
+            cells: &view,
+            normalizer: &loaded.normalizer,
+            parse_report: loaded.dataset.parse_report(),
+            countries: &countries,
+            float_grouping: self.config.float_grouping,
+            after_year: self.after_year,
+            style: Style::from_config(self.config),
+        };
+
+        let names = if analyses.is_empty() { &ANALYSES[..] } else { analyses };
+        Ok(Report::of_until(names, &context, &self.cancel)?.to_string())
+    }
+
+    fn join(&self, left: &str, right: &str, matching: JoinMatch) -> Result<String, CellError> {
+        let rows = self.workspace.join(left, right, matching)?;
+        let unmatched = rows.iter().filter(|row| row.confidence.is_none()).count();
+        let counts: Vec<String> = [MatchConfidence::Exact, MatchConfidence::SameYear, MatchConfidence::NearYear]
+            .into_iter()
+            .map(|confidence| (confidence, rows.iter().filter(|row| row.confidence == Some(confidence)).count()))
+            .filter(|&(_, count)| count > 0)
+            .map(|(confidence, count)| format!("{} {}", count, confidence))
+            .collect();
+
+        Ok(format!(
+            "Joined {} with {} on oem and model: {} matches ({}), {} rows of {} unmatched\n",
+            left,
+            right,
+            rows.len() - unmatched,
+            if counts.is_empty() { "none".to_string() } else { counts.join("; ") },
+            unmatched,
+            left
+        ))
+    }
+}
@@ -0,0 +1,1580 @@
+use crate::analysis;
+use crate::cancel::Cancel;
+use crate::chart;
+use crate::column::Column;
+use crate::config::Config;
+use crate::error::CellError;
+use crate::country::{self, CountryMap};
+use crate::format::NumberFormat;
+use crate::grouping::FloatGrouping;
+use crate::normalize::Normalizer;
+use crate::os::OsFamily;
+use crate::output::OutputSink;
+use crate::parse::{FieldCounts, ParseReport};
+use crate::resolution::{AspectRatio, ResolutionClass};
+use crate::units::UnitProfile;
+use crate::view::DatasetView;
+use crate::Cell;
+use serde::ser::{SerializeMap, Serializer};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::fmt;
+use std::sync::Arc;
+
+/// The analyses of the report in the order they are printed, as named by `cells report --only`.
+pub const ANALYSES: [&str; 34] = [
+    "normalization",
+    "average_launch_delay_by_oem",
+    "never_released",
+    "count_phones_with_single_sensor",
+    "count_foldables",
+    "most_common_oem",
+    "most_common_display_size",
+    "mean_body_weight",
+    "median_body_weight",
+    "years_ranked_by_launches_after",
+    "highest_avg_body_weight_oem",
+    "display_families_per_year",
+    "resolution_classes_per_year",
+    "aspect_ratios_per_year",
+    "profile_per_year",
+    "thinnest_phones",
+    "highest_ppi_per_year",
+    "average_thickness_per_year",
+    "screen_to_body_ratio_per_year",
+    "sim_share_per_year",
+    "sim_share_per_oem",
+    "avg_display_size_per_oem",
+    "weight_extremes_per_oem",
+    "smartphone_share_per_year",
+    "android_version_per_year",
+    "os_upgrades_per_oem_year",
+    "launches_per_year",
+    "launches_per_quarter",
+    "launches_per_decade",
+    "oem_cadence",
+    "oem_counts",
+    "oem_year_pivot",
+    "launches_per_country_per_year",
+    "records",
+];
+
+/// The share of rows `--quick` runs the analyses on.
+pub const QUICK_SAMPLE: f64 = 0.1;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// How results print: the number format, the units of weights and lengths, how few values
+/// make a result low confidence, and whether the phones behind an estimate are a sample's.
+pub struct Style {
+    pub numbers: NumberFormat,
+    pub units: UnitProfile,
+    pub min_sample: usize,
+    pub sampled: bool,
+}
+
+impl Default for Style {
+    fn default() -> Style {
+        Style { numbers: NumberFormat::default(), units: UnitProfile::default(), min_sample: analysis::MIN_SAMPLE_SIZE, sampled: false }
+    }
+}
+
+impl Style {
+    pub fn from_config(config: &Config) -> Style {
+        Style { numbers: config.number_format, units: config.units, min_sample: config.min_sample_size(), sampled: false }
+    }
+
+    fn number(&self, value: f32) -> String {
+        self.numbers.format(value as f64)
+    }
+
+    // A weight in grams, a display size in inches or a body size in millimetres with its
+    // unit, e.g. "6.7 oz".
+    fn weight(&self, grams: f32) -> String {
+        let (value, unit) = self.units.weight(grams);
+        format!("{} {}", self.number(value), unit)
+    }
+
+    fn display_size(&self, inches: f32) -> String {
+        let (value, unit) = self.units.display_size(inches);
+        format!("{} {}", self.number(value), unit)
+    }
+
+    fn length(&self, millimetres: f32) -> String {
+        let (value, unit) = self.units.length(millimetres);
+        format!("{} {}", self.number(value), unit)
+    }
+
+    fn percent(&self, share: f32) -> String {
+        self.numbers.percent(share as f64)
+    }
+
+    fn low_confidence(&self, n: usize) -> bool {
+        analysis::low_confidence(n, self.min_sample).is_some()
+    }
+
+    // "12 phones" behind an estimate, or "12 sampled phones" if the section was computed on a
+    // sample, as those are not scaled up.
+    fn phones(&self, n: usize) -> String {
+        format!("{} {}phones", n, if self.sampled { "sampled " } else { "" })
+    }
+
+    // " [low confidence (n=3)]" after a result from too few rows, otherwise nothing.
+    fn caution(&self, n: usize) -> String {
+        analysis::low_confidence(n, self.min_sample).map_or(String::new(), |note| format!(" [{}]", note))
+    }
+}
+
+/// What the analyses of a report read besides the rows.
+pub struct Context<'a> {
+    pub cells: &'a DatasetView<'a>,
+    pub normalizer: &'a Normalizer,
+    pub parse_report: &'a ParseReport,
+    pub countries: &'a CountryMap,
+    pub float_grouping: FloatGrouping,
+    pub after_year: u32,
+    pub style: Style,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+/// A group of rows and how many of them there are, e.g. an OEM or a year.
+pub struct Count<K> {
+    pub key: K,
+    pub count: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+/// A phone by OEM and model.
+pub struct Phone {
+    pub oem: String,
+    pub model: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+/// An average of a group and how many values it was computed from.
+pub struct Average<K> {
+    pub key: K,
+    pub value: f32,
+    pub count: usize,
+    pub low_confidence: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+/// Values changed by each normalization stage and by the text cleaning before them, and the
+/// problems found parsing the numbers.
+pub struct Normalization {
+    pub stages: Vec<Count<String>>,
+    pub text_cleaned: usize,
+    pub fields: Vec<FieldParse>,
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+/// How the parser took the values of one column.
+pub struct FieldParse {
+    pub column: String,
+    #[serde(flatten)]
+    pub counts: FieldCounts,
+}
+
+impl fmt::Display for Normalization {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Normalization stages:")?;
+        if self.text_cleaned > 0 {
+            writeln!(f, "text: {} values cleaned", self.text_cleaned)?;
+        }
+        for stage in &self.stages {
+            writeln!(f, "{}: {} values changed", stage.key, stage.count)?;
+        }
+        if self.fields.iter().any(|field| field.counts != FieldCounts::default()) {
+            writeln!(f, "Values per field:")?;
+            for field in &self.fields {
+                writeln!(f, "{}: {}", field.column, field.counts)?;
+            }
+        }
+        for warning in &self.warnings {
+            writeln!(f, "{}", warning)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+/// One OEM's mean months from announcement to release.
+pub struct OemDelay {
+    pub oem: String,
+    pub months: f32,
+    pub phones: usize,
+    pub by_month: usize,
+    pub low_confidence: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+/// How long each OEM takes from announcing its phones to releasing them, longest first.
+pub struct LaunchDelays {
+    pub oems: Vec<OemDelay>,
+    #[serde(skip)]
+    pub style: Style,
+}
+
+impl fmt::Display for LaunchDelays {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.oems.is_empty() {
+            return writeln!(f, "No released phone has both an announcement and a release date.");
+        }
+        writeln!(f, "Average months from announcement to release per OEM:")?;
+        for oem in &self.oems {
+            writeln!(f, "{}: {} months ({}, {} by month){}", oem.oem, self.style.number(oem.months), self.style.phones(oem.phones), oem.by_month, self.style.caution(oem.phones))?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+/// Phones that were cancelled or are still coming.
+pub struct NeverReleased {
+    pub phones: Vec<Phone>,
+}
+
+impl fmt::Display for NeverReleased {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.phones.is_empty() {
+            return writeln!(f, "Every phone was released.");
+        }
+        writeln!(f, "Phones never released ({}):", self.phones.len())?;
+        for phone in &self.phones {
+            writeln!(f, "OEM: {}, Model: {}", phone.oem, phone.model)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+/// How many phones list exactly one feature sensor.
+pub struct SingleSensorPhones {
+    pub phones: usize,
+}
+
+impl fmt::Display for SingleSensorPhones {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Phones with only one feature sensor: {}", self.phones)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+/// How many phones fold, and how many of those list a second, cover display.
+pub struct Foldables {
+    pub phones: usize,
+    pub with_cover_display: usize,
+}
+
+impl fmt::Display for Foldables {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Foldable phones: {} ({} with a cover display)", self.phones, self.with_cover_display)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+/// The OEM with the most phones.
+pub struct MostCommonOem {
+    pub oem: Option<String>,
+}
+
+impl fmt::Display for MostCommonOem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.oem {
+            Some(oem) => writeln!(f, "Most Common OEM: {}", oem),
+            None => writeln!(f, "No data found."),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+/// The most common display size bucket, e.g. "6.1".
+pub struct MostCommonDisplaySize {
+    pub display_size: Option<String>,
+}
+
+impl fmt::Display for MostCommonDisplaySize {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.display_size {
+            Some(size) => writeln!(f, "Most Common Display Size: {}", size),
+            None => writeln!(f, "None"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+/// Which average of the body weights.
+pub enum Statistic {
+    Mean,
+    Median,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+/// The mean or median body weight in grams, of `count` phones with a weight.
+pub struct BodyWeight {
+    pub statistic: Statistic,
+    pub grams: Option<f32>,
+    pub count: usize,
+    pub low_confidence: bool,
+    #[serde(skip)]
+    pub style: Style,
+}
+
+impl fmt::Display for BodyWeight {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let label = match self.statistic {
+            Statistic::Mean => "Mean",
+            Statistic::Median => "Median",
+        };
+        match self.grams {
+            Some(grams) => writeln!(f, "{} Body Weight: {}{}", label, self.style.weight(grams), self.style.caution(self.count)),
+            None => writeln!(f, "None"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+/// The years after `after_year` by launches, most first.
+pub struct YearsRanked {
+    pub after_year: u32,
+    pub years: Vec<Count<u32>>,
+}
+
+impl fmt::Display for YearsRanked {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let Some(first) = self.years.first() else {
+            return writeln!(f, "None");
+        };
+        writeln!(f, "Year with most phones launched after {}: {}", self.after_year, first.key)?;
+        writeln!(f, "Years after {} by launches:", self.after_year)?;
+        for year in &self.years {
+            writeln!(f, "{}: {}", year.key, year.count)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+/// The OEM with the highest average body weight.
+pub struct HeaviestOem {
+    pub oem: Option<String>,
+}
+
+impl fmt::Display for HeaviestOem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Highest Average Body Weight OEM: {}", self.oem.as_deref().unwrap_or("None"))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+/// The display families of the phones announced in one year.
+pub struct YearFamilies {
+    pub year: u32,
+    pub families: Vec<Count<String>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+/// Display families per announcement year.
+pub struct DisplayFamilies {
+    pub years: Vec<YearFamilies>,
+}
+
+impl fmt::Display for DisplayFamilies {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Display families per year:")?;
+        for year in &self.years {
+            let counts: Vec<String> = year.families.iter().map(|family| format!("{} {}", family.key, family.count)).collect();
+            writeln!(f, "{}: {}", year.year, counts.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+/// The phones of each resolution class announced in one year, lowest class first.
+pub struct YearClasses {
+    pub year: u32,
+    pub classes: Vec<Count<String>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+/// Resolution classes per announcement year, each year drawn as a bar of its class shares.
+pub struct ResolutionClasses {
+    pub years: Vec<YearClasses>,
+}
+
+impl fmt::Display for ResolutionClasses {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // Each class is drawn with the first character of its short name.
+        let symbol = |name: &str| name.chars().next().unwrap_or('?');
+        let legend: Vec<String> = ResolutionClass::ALL.iter().map(|class| format!("{} {}", symbol(class.short_name()), class.short_name())).collect();
+        writeln!(f, "Resolution classes per year ({}):", legend.join(", "))?;
+        for year in &self.years {
+            let parts: Vec<(char, usize)> = year.classes.iter().map(|class| (symbol(&class.key), class.count)).collect();
+            let phones: usize = year.classes.iter().map(|class| class.count).sum();
+            writeln!(f, "{} |{}| {} phones", year.year, chart::stacked_bar(&parts, chart::BAR_WIDTH), phones)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+/// The phones of each aspect ratio announced in one year, squarest first.
+pub struct YearRatios {
+    pub year: u32,
+    pub phones: usize,
+    pub ratios: Vec<Count<String>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+/// Aspect ratios per announcement year, then the share of 16:9 phones charted per year.
+pub struct AspectRatios {
+    pub years: Vec<YearRatios>,
+    #[serde(skip)]
+    pub style: Style,
+}
+
+impl fmt::Display for AspectRatios {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let wide = AspectRatio { long: 16.0, short: 9.0 }.to_string();
+        writeln!(f, "Aspect ratios per year:")?;
+        for year in &self.years {
+            let shares: Vec<String> = year.ratios.iter().map(|ratio| format!("{} {}", ratio.key, self.style.percent(ratio.count as f32 / year.phones as f32))).collect();
+            writeln!(f, "{}: {}", year.year, shares.join(", "))?;
+        }
+
+        writeln!(f, "Share of {} phones per year:", wide)?;
+        for year in &self.years {
+            let count = year.ratios.iter().find(|ratio| ratio.key == wide).map_or(0, |ratio| ratio.count);
+            let share = count as f32 / year.phones as f32;
+            let length = (share * chart::BAR_WIDTH as f32).round() as usize;
+            writeln!(f, "{} | {:<width$} {}", year.year, "#".repeat(length), self.style.percent(share), width = chart::BAR_WIDTH)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+/// The median specs of the phones announced in one year.
+pub struct Profile {
+    pub year: u32,
+    pub phones: usize,
+    pub body_weight: Option<f32>,
+    pub display_size: Option<f32>,
+    pub resolution: Option<String>,
+    pub sensors: Option<f32>,
+    pub low_confidence: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+/// The typical phone of every year.
+pub struct Profiles {
+    pub years: Vec<Profile>,
+    #[serde(skip)]
+    pub style: Style,
+}
+
+impl fmt::Display for Profiles {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Typical phone per year (medians):")?;
+        let number = |value: Option<f32>, format: &dyn Fn(f32) -> String| value.map_or("-".to_string(), format);
+        for profile in &self.years {
+            writeln!(
+                f,
+                "{}: {}, {}, {} resolution, {} sensors ({}){}",
+                profile.year,
+                number(profile.body_weight, &|grams| self.style.weight(grams)),
+                number(profile.display_size, &|inches| self.style.display_size(inches)),
+                profile.resolution.as_deref().unwrap_or("-"),
+                number(profile.sensors, &|sensors| self.style.number(sensors)),
+                self.style.phones(profile.phones),
+                self.style.caution(profile.phones)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+/// A phone and its body depth in mm.
+pub struct PhoneDepth {
+    pub oem: String,
+    pub model: String,
+    pub depth: f32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+/// The thinnest phones, thinnest first.
+pub struct ThinnestPhones {
+    pub phones: Vec<PhoneDepth>,
+    #[serde(skip)]
+    pub style: Style,
+}
+
+impl fmt::Display for ThinnestPhones {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Thinnest phones:")?;
+        for phone in &self.phones {
+            writeln!(f, "{} {}: {}", phone.oem, phone.model, self.style.length(phone.depth))?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+/// The phone with the densest display of one year.
+pub struct YearPpi {
+    pub year: u32,
+    pub phone: Phone,
+    pub ppi: f32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+/// The densest display of each announcement year, in pixels per inch.
+pub struct HighestPpi {
+    pub years: Vec<YearPpi>,
+    #[serde(skip)]
+    pub style: Style,
+}
+
+impl fmt::Display for HighestPpi {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Highest pixel density per year:")?;
+        for year in &self.years {
+            writeln!(f, "{}: {} {} ({} ppi)", year.year, year.phone.oem, year.phone.model, self.style.number(year.ppi))?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+/// Average body depth in mm per announcement year.
+pub struct ThicknessPerYear {
+    pub years: Vec<Average<u32>>,
+    #[serde(skip)]
+    pub style: Style,
+}
+
+impl fmt::Display for ThicknessPerYear {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Average thickness per year:")?;
+        for year in &self.years {
+            writeln!(f, "{}: {} ({}){}", year.key, self.style.length(year.value), self.style.phones(year.count), self.style.caution(year.count))?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+/// Average share of the front taken by the screen per announcement year.
+pub struct ScreenToBodyPerYear {
+    pub years: Vec<Average<u32>>,
+    #[serde(skip)]
+    pub style: Style,
+}
+
+impl fmt::Display for ScreenToBodyPerYear {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Average screen-to-body ratio per year:")?;
+        for year in &self.years {
+            writeln!(f, "{}: {} ({}){}", year.key, self.style.percent(year.value), self.style.phones(year.count), self.style.caution(year.count))?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+/// The shares of a group's phones with a known SIM that take two SIMs or an eSIM.
+pub struct GroupSimShare {
+    pub group: String,
+    pub phones: usize,
+    pub dual_sim: f32,
+    pub esim: f32,
+    pub low_confidence: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+/// What the groups of SIM shares are.
+pub enum SimGrouping {
+    Year,
+    Oem,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+/// Dual-SIM and eSIM shares per year or per OEM.
+pub struct SimShares {
+    pub by: SimGrouping,
+    pub groups: Vec<GroupSimShare>,
+    #[serde(skip)]
+    pub style: Style,
+}
+
+impl fmt::Display for SimShares {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.by {
+            SimGrouping::Year => writeln!(f, "Dual-SIM and eSIM share per year:")?,
+            SimGrouping::Oem => writeln!(f, "Dual-SIM and eSIM share per OEM:")?,
+        }
+        for share in &self.groups {
+            writeln!(
+                f,
+                "{}: {} dual SIM, {} eSIM ({}){}",
+                share.group,
+                self.style.percent(share.dual_sim),
+                self.style.percent(share.esim),
+                self.style.phones(share.phones),
+                self.style.caution(share.phones)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+/// Average display size in inches per OEM, largest first.
+pub struct DisplaySizePerOem {
+    pub oems: Vec<Average<String>>,
+    #[serde(skip)]
+    pub style: Style,
+}
+
+impl fmt::Display for DisplaySizePerOem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Average display size per OEM:")?;
+        for oem in &self.oems {
+            writeln!(f, "{}: {} ({}){}", oem.key, self.style.display_size(oem.value), self.style.phones(oem.count), self.style.caution(oem.count))?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+/// A model and its body weight in grams.
+pub struct ModelWeight {
+    pub model: String,
+    pub grams: f32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+/// The lightest and heaviest phone of an OEM.
+pub struct OemExtremes {
+    pub oem: String,
+    pub lightest: ModelWeight,
+    pub heaviest: ModelWeight,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+/// The lightest and heaviest phone of every OEM.
+pub struct WeightExtremes {
+    pub oems: Vec<OemExtremes>,
+    #[serde(skip)]
+    pub style: Style,
+}
+
+impl fmt::Display for WeightExtremes {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Lightest and heaviest phone per OEM:")?;
+        for oem in &self.oems {
+            writeln!(
+                f,
+                "{}: lightest {} ({}), heaviest {} ({})",
+                oem.oem,
+                oem.lightest.model,
+                self.style.weight(oem.lightest.grams),
+                oem.heaviest.model,
+                self.style.weight(oem.heaviest.grams)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+/// The share of each year's launches that are smartphones, `count` being the launches.
+pub struct SmartphoneShare {
+    pub years: Vec<Average<u32>>,
+    #[serde(skip)]
+    pub style: Style,
+}
+
+impl fmt::Display for SmartphoneShare {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Smartphone share of launches per year:")?;
+        for year in &self.years {
+            writeln!(f, "{}: {}{}", year.key, self.style.percent(year.value), self.style.caution(year.count))?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+/// The mean Android version phones shipped with per announcement year.
+pub struct AndroidVersionPerYear {
+    pub years: Vec<Average<u32>>,
+    #[serde(skip)]
+    pub style: Style,
+}
+
+impl fmt::Display for AndroidVersionPerYear {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Average shipped Android version per year:")?;
+        for year in &self.years {
+            writeln!(f, "{}: {} ({}){}", year.key, self.style.number(year.value), self.style.phones(year.count), self.style.caution(year.count))?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+/// The mean OS upgrades promised per launch of one OEM, by announcement year.
+pub struct OemUpgrades {
+    pub oem: String,
+    pub years: Vec<Average<u32>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+/// The mean OS upgrades promised per launch, by OEM and announcement year.
+pub struct OsUpgrades {
+    pub oems: Vec<OemUpgrades>,
+    #[serde(skip)]
+    pub style: Style,
+}
+
+impl fmt::Display for OsUpgrades {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "OS upgrades per launch by OEM and year:")?;
+        for oem in &self.oems {
+            writeln!(f, "{}:", oem.oem)?;
+            for year in &oem.years {
+                writeln!(f, "  {}: {} ({}){}", year.key, self.style.number(year.value), self.style.phones(year.count), self.style.caution(year.count))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+/// Launches per announcement year, charted.
+pub struct LaunchesPerYear {
+    pub years: Vec<Count<u32>>,
+}
+
+impl fmt::Display for LaunchesPerYear {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let bars: Vec<(String, usize)> = self.years.iter().map(|year| (year.key.to_string(), year.count)).collect();
+        writeln!(f, "Launches per year:")?;
+        write!(f, "{}", chart::bar_chart(&bars, None, chart::BAR_WIDTH))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+/// Launches per announcement quarter, e.g. "2019 Q3", charted.
+pub struct LaunchesPerQuarter {
+    pub quarters: Vec<Count<String>>,
+}
+
+impl fmt::Display for LaunchesPerQuarter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.quarters.is_empty() {
+            return writeln!(f, "No launch_announced gives a month or quarter.");
+        }
+        let bars: Vec<(String, usize)> = self.quarters.iter().map(|quarter| (quarter.key.clone(), quarter.count)).collect();
+        writeln!(f, "Launches per quarter:")?;
+        write!(f, "{}", chart::bar_chart(&bars, None, chart::BAR_WIDTH))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+/// The launches of a decade, e.g. "1990s", and its leading OEM.
+pub struct Decade {
+    pub decade: String,
+    pub launches: usize,
+    pub leader: Option<Count<String>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+/// Launches per decade, charted, with each decade's leader.
+pub struct LaunchesPerDecade {
+    pub decades: Vec<Decade>,
+}
+
+impl fmt::Display for LaunchesPerDecade {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let bars: Vec<(String, usize)> = self.decades.iter().map(|decade| (decade.decade.clone(), decade.launches)).collect();
+        writeln!(f, "Launches per decade:")?;
+        write!(f, "{}", chart::bar_chart(&bars, None, chart::BAR_WIDTH))?;
+        for decade in &self.decades {
+            if let Some(leader) = &decade.leader {
+                writeln!(f, "{} leader: {} ({} phones)", decade.decade, leader.key, leader.count)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+/// A run of years without launches, first to last.
+pub struct Gap {
+    pub from: u32,
+    pub to: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+/// One OEM's launch cadence.
+pub struct Cadence {
+    pub oem: String,
+    pub launches: usize,
+    pub first_year: u32,
+    pub last_year: u32,
+    pub active_years: usize,
+    pub launches_per_active_year: f64,
+    pub gaps: Vec<Gap>,
+    pub dormant_since: Option<u32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+/// Every OEM's launches per active year and gaps, most launches first, and the OEMs that went
+/// dormant. The text lists the busiest OEMs and the latest to go dormant.
+pub struct OemCadences {
+    pub oems: Vec<Cadence>,
+    #[serde(skip)]
+    pub style: Style,
+}
+
+impl fmt::Display for OemCadences {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        const SHOWN: usize = 10;
+
+        writeln!(f, "OEM launch cadence:")?;
+        let width = self.oems.iter().take(SHOWN).map(|oem| oem.oem.chars().count()).max().unwrap_or(0);
+        for oem in self.oems.iter().take(SHOWN) {
+            let gaps: Vec<String> = oem.gaps.iter().map(|gap| if gap.from == gap.to { gap.from.to_string() } else { format!("{}-{}", gap.from, gap.to) }).collect();
+            write!(f, "{:<width$} {}-{}, {} launches a year", oem.oem, oem.first_year, oem.last_year, self.style.numbers.format(oem.launches_per_active_year), width = width)?;
+            if !gaps.is_empty() {
+                write!(f, ", no launches in {}", gaps.join(", "))?;
+            }
+            writeln!(f)?;
+        }
+
+        let mut dormant: Vec<&Cadence> = self.oems.iter().filter(|oem| oem.dormant_since.is_some()).collect();
+        dormant.sort_by(|a, b| b.dormant_since.cmp(&a.dormant_since).then_with(|| a.oem.cmp(&b.oem)));
+        if !dormant.is_empty() {
+            let latest: Vec<String> = dormant.iter().take(SHOWN).map(|oem| format!("{} (since {})", oem.oem, oem.dormant_since.unwrap_or_default())).collect();
+            writeln!(f, "Dormant OEMs: {}, latest {}", dormant.len(), latest.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+/// The OEMs with the most phones, charted against all `rows`.
+pub struct TopOems {
+    pub rows: usize,
+    pub oems: Vec<Count<String>>,
+}
+
+impl fmt::Display for TopOems {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let bars: Vec<(String, usize)> = self.oems.iter().map(|oem| (oem.key.clone(), oem.count)).collect();
+        writeln!(f, "Top OEMs by phones:")?;
+        write!(f, "{}", chart::bar_chart(&bars, Some(self.rows), chart::BAR_WIDTH))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+/// An OEM's launches in each year of the pivot.
+pub struct OemYears {
+    pub oem: String,
+    pub launches: Vec<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+/// Launches per OEM and year, drawn as a sparkline per OEM.
+pub struct OemYearPivot {
+    pub years: Vec<u32>,
+    pub oems: Vec<OemYears>,
+}
+
+impl fmt::Display for OemYearPivot {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let (Some(first), Some(last)) = (self.years.first(), self.years.last()) {
+            // Too wide a span only has the years with launches (see Cell::oem_year_pivot).
+            let gaps = if self.years.len() as u32 == last - first + 1 { "" } else { ", only the years with launches" };
+            writeln!(f, "OEM launches per year ({}-{}{}):", first, last, gaps)?;
+        }
+        let width = self.oems.iter().map(|oem| oem.oem.chars().count()).max().unwrap_or(0);
+        for oem in &self.oems {
+            writeln!(f, "{:<width$} {} {}", oem.oem, chart::sparkline(&oem.launches), oem.launches.iter().sum::<usize>(), width = width)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+/// The launches of one country in one year.
+pub struct CountryYear {
+    pub country: String,
+    pub year: u32,
+    pub launches: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+/// Launches per OEM home country and year.
+pub struct CountryLaunches {
+    pub launches: Vec<CountryYear>,
+}
+
+impl fmt::Display for CountryLaunches {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Launches per country per year:")?;
+        for launches in &self.launches {
+            writeln!(f, "{} {}: {}", launches.country, launches.year, launches.launches)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+/// The analysed rows themselves.
+pub struct Records {
+    pub cells: Vec<Cell>,
+}
+
+impl fmt::Display for Records {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for cell in &self.cells {
+            writeln!(f, "{:?}\n", cell)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+/// The result of an analysis of no rows at all; null in JSON.
+pub struct NoData;
+
+impl fmt::Display for NoData {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "No data.")
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(transparent)]
+/// The result of an analysis registered by a library user (see `Analyses::register`): the
+/// text it prints, ending with a newline, and the value JSON gives for it.
+pub struct Custom {
+    #[serde(skip)]
+    pub text: String,
+    pub value: serde_json::Value,
+}
+
+impl fmt::Display for Custom {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(untagged)]
+/// The result of one analysis of the report.
+pub enum Section {
+    NoData(NoData),
+    Custom(Custom),
+    Normalization(Normalization),
+    LaunchDelays(LaunchDelays),
+    NeverReleased(NeverReleased),
+    SingleSensorPhones(SingleSensorPhones),
+    Foldables(Foldables),
+    MostCommonOem(MostCommonOem),
+    MostCommonDisplaySize(MostCommonDisplaySize),
+    BodyWeight(BodyWeight),
+    YearsRanked(YearsRanked),
+    HeaviestOem(HeaviestOem),
+    DisplayFamilies(DisplayFamilies),
+    ResolutionClasses(ResolutionClasses),
+    AspectRatios(AspectRatios),
+    Profiles(Profiles),
+    ThinnestPhones(ThinnestPhones),
+    HighestPpi(HighestPpi),
+    ThicknessPerYear(ThicknessPerYear),
+    ScreenToBodyPerYear(ScreenToBodyPerYear),
+    SimShares(SimShares),
+    DisplaySizePerOem(DisplaySizePerOem),
+    WeightExtremes(WeightExtremes),
+    SmartphoneShare(SmartphoneShare),
+    AndroidVersionPerYear(AndroidVersionPerYear),
+    OsUpgrades(OsUpgrades),
+    LaunchesPerYear(LaunchesPerYear),
+    LaunchesPerQuarter(LaunchesPerQuarter),
+    LaunchesPerDecade(LaunchesPerDecade),
+    OemCadences(OemCadences),
+    TopOems(TopOems),
+    OemYearPivot(OemYearPivot),
+    CountryLaunches(CountryLaunches),
+    Records(Records),
+}
+
+impl Section {
+    /**
+        Scale the counts of rows in a section computed on a sample up to the whole dataset,
+        `factor` being the rows of the dataset over the rows of the sample. Every count of
+        phones in the dataset is scaled; means, medians, shares and extremes are estimates
+        from the sample as they are. The number of phones behind each estimate stays the
+        sample's, as its confidence depends on that, and prints as "sampled phones".
+
+        Runtime: O(s) for the size of the section
+     */
+    pub fn extrapolate(&mut self, factor: f64) {
+        let scale = |count: &mut usize| *count = (*count as f64 * factor).round() as usize;
+
+        match self {
+            Section::LaunchDelays(result) => result.style.sampled = true,
+            Section::BodyWeight(result) => result.style.sampled = true,
+            Section::Profiles(result) => result.style.sampled = true,
+            Section::ThicknessPerYear(result) => result.style.sampled = true,
+            Section::ScreenToBodyPerYear(result) => result.style.sampled = true,
+            Section::SimShares(result) => result.style.sampled = true,
+            Section::DisplaySizePerOem(result) => result.style.sampled = true,
+            Section::SmartphoneShare(result) => result.style.sampled = true,
+            Section::AndroidVersionPerYear(result) => result.style.sampled = true,
+            Section::OsUpgrades(result) => result.style.sampled = true,
+            Section::DisplayFamilies(result) => result.years.iter_mut().flat_map(|year| year.families.iter_mut()).for_each(|family| scale(&mut family.count)),
+            Section::ResolutionClasses(result) => result.years.iter_mut().flat_map(|year| year.classes.iter_mut()).for_each(|class| scale(&mut class.count)),
+            Section::AspectRatios(result) => {
+                for year in &mut result.years {
+                    scale(&mut year.phones);
+                    year.ratios.iter_mut().for_each(|ratio| scale(&mut ratio.count));
+                }
+            }
+            Section::SingleSensorPhones(result) => scale(&mut result.phones),
+            Section::Foldables(result) => {
+                scale(&mut result.phones);
+                scale(&mut result.with_cover_display);
+            }
+            Section::YearsRanked(result) => result.years.iter_mut().for_each(|year| scale(&mut year.count)),
+            Section::LaunchesPerYear(result) => result.years.iter_mut().for_each(|year| scale(&mut year.count)),
+            Section::LaunchesPerQuarter(result) => result.quarters.iter_mut().for_each(|quarter| scale(&mut quarter.count)),
+            Section::LaunchesPerDecade(result) => {
+                for decade in &mut result.decades {
+                    scale(&mut decade.launches);
+                    if let Some(leader) = &mut decade.leader {
+                        scale(&mut leader.count);
+                    }
+                }
+            }
+            Section::OemCadences(result) => {
+                for oem in &mut result.oems {
+                    scale(&mut oem.launches);
+                    oem.launches_per_active_year *= factor;
+                }
+            }
+            Section::TopOems(result) => {
+                scale(&mut result.rows);
+                result.oems.iter_mut().for_each(|oem| scale(&mut oem.count));
+            }
+            Section::OemYearPivot(result) => result.oems.iter_mut().flat_map(|oem| oem.launches.iter_mut()).for_each(scale),
+            Section::CountryLaunches(result) => result.launches.iter_mut().for_each(|launches| scale(&mut launches.launches)),
+            _ => {}
+        }
+    }
+
+    /**
+        Print the weights and lengths of the section in another unit profile. The results
+        stay in the dataset's units, so this only changes the text; JSON always gives grams,
+        inches and millimetres.
+
+        Runtime: O(1)
+     */
+    pub fn set_units(&mut self, units: UnitProfile) {
+        match self {
+            Section::BodyWeight(result) => result.style.units = units,
+            Section::Profiles(result) => result.style.units = units,
+            Section::ThinnestPhones(result) => result.style.units = units,
+            Section::ThicknessPerYear(result) => result.style.units = units,
+            Section::DisplaySizePerOem(result) => result.style.units = units,
+            Section::WeightExtremes(result) => result.style.units = units,
+            _ => {}
+        }
+    }
+}
+
+impl fmt::Display for Section {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Section::NoData(result) => result.fmt(f),
+            Section::Custom(result) => result.fmt(f),
+            Section::Normalization(result) => result.fmt(f),
+            Section::LaunchDelays(result) => result.fmt(f),
+            Section::NeverReleased(result) => result.fmt(f),
+            Section::SingleSensorPhones(result) => result.fmt(f),
+            Section::Foldables(result) => result.fmt(f),
+            Section::MostCommonOem(result) => result.fmt(f),
+            Section::MostCommonDisplaySize(result) => result.fmt(f),
+            Section::BodyWeight(result) => result.fmt(f),
+            Section::YearsRanked(result) => result.fmt(f),
+            Section::HeaviestOem(result) => result.fmt(f),
+            Section::DisplayFamilies(result) => result.fmt(f),
+            Section::ResolutionClasses(result) => result.fmt(f),
+            Section::AspectRatios(result) => result.fmt(f),
+            Section::Profiles(result) => result.fmt(f),
+            Section::ThinnestPhones(result) => result.fmt(f),
+            Section::HighestPpi(result) => result.fmt(f),
+            Section::ThicknessPerYear(result) => result.fmt(f),
+            Section::ScreenToBodyPerYear(result) => result.fmt(f),
+            Section::SimShares(result) => result.fmt(f),
+            Section::DisplaySizePerOem(result) => result.fmt(f),
+            Section::WeightExtremes(result) => result.fmt(f),
+            Section::SmartphoneShare(result) => result.fmt(f),
+            Section::AndroidVersionPerYear(result) => result.fmt(f),
+            Section::OsUpgrades(result) => result.fmt(f),
+            Section::LaunchesPerYear(result) => result.fmt(f),
+            Section::LaunchesPerQuarter(result) => result.fmt(f),
+            Section::LaunchesPerDecade(result) => result.fmt(f),
+            Section::OemCadences(result) => result.fmt(f),
+            Section::TopOems(result) => result.fmt(f),
+            Section::OemYearPivot(result) => result.fmt(f),
+            Section::CountryLaunches(result) => result.fmt(f),
+            Section::Records(result) => result.fmt(f),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+/// How a whole report is rendered.
+pub enum ReportFormat {
+    /// The sections as they print, one after the other.
+    #[default]
+    Text,
+    /// One pretty-printed JSON object keyed by analysis name.
+    Json,
+    /// A heading per analysis with its text below it.
+    Markdown,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+/// The random sample of rows an approximate report was computed on.
+pub struct Sample {
+    pub rows: usize,
+    pub of: usize,
+}
+
+impl Sample {
+    // How many rows of the dataset each row of the sample stands for.
+    pub fn factor(&self) -> f64 {
+        if self.rows == 0 { 1.0 } else { self.of as f64 / self.rows as f64 }
+    }
+}
+
+impl fmt::Display for Sample {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "APPROXIMATE: computed on a random sample of {} of {} rows. Counts are scaled up by {:.1}, means and shares are", self.rows, self.of, self.factor())?;
+        writeln!(f, "estimates from the sample's phones (\"sampled phones\"), and lists of phones come from the sample only.")
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+/// The results of a report by analysis name, in the order they were run. Serializes as one
+/// JSON object keyed by name and prints as the sections one after the other. A report
+/// computed on a sample says so at the top, and under a "sample" key in JSON.
+pub struct Report {
+    pub sections: Vec<(String, Section)>,
+    pub sample: Option<Sample>,
+}
+
+impl Report {
+    /**
+        Run every analysis of ANALYSES, in order, into one report.
+
+        Runtime: O(a * n log n) for a analyses
+     */
+    pub fn all(context: &Context) -> Report {
+        Report::all_with(&Analyses::default(), context)
+    }
+
+    // Runs every analysis of a registry, in the order they were registered.
+    pub fn all_with(analyses: &Analyses, context: &Context) -> Report {
+        Report::of_with(analyses, &analyses.names().collect::<Vec<_>>(), context)
+    }
+
+    // Runs the named analyses in the order given, leaving out names that are not analyses.
+    pub fn of(names: &[&str], context: &Context) -> Report {
+        Report::of_with(&Analyses::default(), names, context)
+    }
+
+    // Runs the named analyses of a registry in the order given, leaving out names it
+    // doesn't have.
+    pub fn of_with(analyses: &Analyses, names: &[&str], context: &Context) -> Report {
+        let Ok(report) = Report::of_each(analyses, names, context, |_, _| Ok::<_, Infallible>(()));
+        report
+    }
+
+    /*
+        Like of, but stops with CellError::Cancelled or TimedOut before the next analysis
+        once `cancel` says so.
+
+        Runtime: O(a * n log n) for a analyses
+     */
+    pub fn of_until(names: &[&str], context: &Context, cancel: &Cancel) -> Result<Report, CellError> {
+        cancel.check()?;
+        Report::of_each(&Analyses::default(), names, context, |_, _| cancel.check())
+    }
+
+    /**
+        Like of_with, handing each section to `each` as soon as its analysis finished and
+        before it is added, e.g. to time it, scale it or print it while the next one runs.
+        The first error of `each` stops the report and is returned.
+
+        Runtime: O(a * n log n) for a analyses
+     */
+    pub fn of_each<E>(analyses: &Analyses, names: &[&str], context: &Context, mut each: impl FnMut(&str, &mut Section) -> Result<(), E>) -> Result<Report, E> {
+        let mut report = Report::default();
+        for name in names {
+            if let Some(mut section) = analyses.run(name, context) {
+                each(name, &mut section)?;
+                report.push(name, section);
+            }
+        }
+        Ok(report)
+    }
+
+    pub fn push(&mut self, name: &str, section: Section) {
+        self.sections.push((name.to_string(), section));
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Section> {
+        self.sections.iter().find(|(section, _)| section == name).map(|(_, section)| section)
+    }
+
+    /**
+        The same results printed in another unit profile, so one pass over the data can give
+        e.g. both a metric and an imperial report.
+
+        Runtime: O(s) for the sections
+     */
+    pub fn with_units(&self, units: UnitProfile) -> Report {
+        let mut report = self.clone();
+        report.sections.iter_mut().for_each(|(_, section)| section.set_units(units));
+        report
+    }
+
+    // Writes the report to a sink, as text or as one pretty-printed JSON object.
+    pub fn write_to(&self, sink: &mut dyn OutputSink, json: bool) -> Result<(), CellError> {
+        self.write_as(sink, if json { ReportFormat::Json } else { ReportFormat::Text })
+    }
+
+    // Writes the report to a sink in the given format.
+    pub fn write_as(&self, sink: &mut dyn OutputSink, format: ReportFormat) -> Result<(), CellError> {
+        match format {
+            ReportFormat::Text => sink.write_text(&self.to_string())?,
+            ReportFormat::Json => {
+                serde_json::to_writer_pretty(&mut *sink, self)?;
+                sink.write_text("\n")?;
+            }
+            ReportFormat::Markdown => {
+                if let Some(sample) = &self.sample {
+                    sink.write_text(&format!("> {}\n\n", sample.to_string().trim_end().replace('\n', " ")))?;
+                }
+                for (name, section) in &self.sections {
+                    sink.write_text(&format!("## {}\n\n```text\n{}```\n\n", name, section))?;
+                }
+            }
+        }
+        sink.flush()?;
+
+        Ok(())
+    }
+}
+
+impl Serialize for Report {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.sections.len() + usize::from(self.sample.is_some())))?;
+        if let Some(sample) = &self.sample {
+            map.serialize_entry("sample", sample)?;
+        }
+        for (name, section) in &self.sections {
+            map.serialize_entry(name, section)?;
+        }
+        map.end()
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(sample) = &self.sample {
+            sample.fmt(f)?;
+        }
+        self.sections.iter().try_for_each(|(_, section)| section.fmt(f))
+    }
+}
+
+// Computes the section of one analysis.
+pub type AnalysisFn = Arc<dyn Fn(&Context) -> Section + Send + Sync>;
+
+#[derive(Clone)]
+/// The analyses a report can run by name: by default the built-in ones of ANALYSES, in
+/// order. A library user can register their own, e.g. one returning a `Section::Custom`,
+/// and run them with `Report::all_with` like the built-in ones.
+pub struct Analyses {
+    analyses: Vec<(String, AnalysisFn)>,
+}
+
+impl Default for Analyses {
+    fn default() -> Analyses {
+        let mut analyses = Analyses::empty();
+        for name in ANALYSES {
+            analyses.register(name, move |context| analyse(name, context).expect("every name in ANALYSES is an analysis"));
+        }
+        analyses
+    }
+}
+
+impl Analyses {
+    // A registry without any analyses.
+    pub fn empty() -> Analyses {
+        Analyses { analyses: Vec::new() }
+    }
+
+    // Registers an analysis under a name, replacing any registered under it before.
+    pub fn register(&mut self, name: &str, analysis: impl Fn(&Context) -> Section + Send + Sync + 'static) {
+        let analysis: AnalysisFn = Arc::new(analysis);
+        match self.analyses.iter_mut().find(|(known, _)| known == name) {
+            Some((_, existing)) => *existing = analysis,
+            None => self.analyses.push((name.to_string(), analysis)),
+        }
+    }
+
+    // The registered names, in the order they were registered.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.analyses.iter().map(|(name, _)| name.as_str())
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.analyses.iter().any(|(known, _)| known == name)
+    }
+
+    /**
+        Run the analysis registered under a name; None if there is none. With no rows every
+        analysis but normalization is NoData, as with `analyse`.
+
+        Runtime: that of the analysis
+     */
+    pub fn run(&self, name: &str, context: &Context) -> Option<Section> {
+        let (_, analysis) = self.analyses.iter().find(|(known, _)| known == name)?;
+        if context.cells.is_empty() && name != "normalization" {
+            return Some(Section::NoData(NoData));
+        }
+        Some(analysis(context))
+    }
+}
+
+/**
+    Run one analysis of the report by its name in ANALYSES. None for an unknown name. With
+    no rows every analysis but normalization, which is about the whole file, is NoData.
+
+    Runtime: O(n log n)
+ */
+pub fn analyse(name: &str, context: &Context) -> Option<Section> {
+    let cells = context.cells;
+    if cells.is_empty() && name != "normalization" {
+        return ANALYSES.contains(&name).then_some(Section::NoData(NoData));
+    }
+    let style = context.style;
+    let weights = || cells.iter().filter(|cell| cell.body_weight.is_some()).count();
+    let average = |key, value: f32, count: usize| Average { key, value, count, low_confidence: style.low_confidence(count) };
+    let sim_shares = |by, groups: Vec<(String, analysis::SimShare)>| SimShares {
+        by,
+        groups: groups
+            .into_iter()
+            .map(|(group, share)| GroupSimShare {
+                group,
+                phones: share.phones,
+                dual_sim: share.dual_sim_share(),
+                esim: share.esim_share(),
+                low_confidence: style.low_confidence(share.phones),
+            })
+            .collect(),
+        style,
+    };
+    let counts = |items: Vec<(String, usize)>| items.into_iter().map(|(key, count)| Count { key, count }).collect();
+
+    let section = match name {
+        "normalization" => Section::Normalization(Normalization {
+            stages: context.normalizer.report().into_iter().map(|(stage, count)| Count { key: stage.to_string(), count }).collect(),
+            text_cleaned: context.normalizer.text_cleaned(),
+            fields: context.parse_report.field_counts().into_iter().map(|(column, counts)| FieldParse { column: column.to_string(), counts }).collect(),
+            warnings: context.parse_report.warnings(),
+        }),
+        "average_launch_delay_by_oem" => Section::LaunchDelays(LaunchDelays {
+            oems: analysis::average_launch_delay_by_oem(cells)
+                .into_iter()
+                .map(|delay| OemDelay { oem: delay.oem, months: delay.mean_months, phones: delay.phones, by_month: delay.by_month, low_confidence: style.low_confidence(delay.phones) })
+                .collect(),
+            style,
+        }),
+        "never_released" => Section::NeverReleased(NeverReleased { phones: Cell::never_released(cells).into_iter().map(|(oem, model)| Phone { oem, model }).collect() }),
+        "count_phones_with_single_sensor" => Section::SingleSensorPhones(SingleSensorPhones { phones: Cell::count_phones_with_single_sensor(cells) }),
+        "count_foldables" => {
+            let (phones, with_cover_display) = Cell::count_foldables(cells);
+            Section::Foldables(Foldables { phones, with_cover_display })
+        }
+        "most_common_oem" => Section::MostCommonOem(MostCommonOem { oem: Cell::most_common_oem(cells) }),
+        "most_common_display_size" => Section::MostCommonDisplaySize(MostCommonDisplaySize {
+            display_size: analysis::mode(cells, Column::DisplaySize, 1, &context.float_grouping).into_iter().next().map(|(size, _)| size),
+        }),
+        "mean_body_weight" | "median_body_weight" => {
+            let (statistic, grams) = if name == "mean_body_weight" {
+                (Statistic::Mean, Cell::mean_body_weight(cells))
+            } else {
+                (Statistic::Median, Cell::median_body_weight(cells))
+            };
+            let count = weights();
+            Section::BodyWeight(BodyWeight { statistic, grams, count, low_confidence: style.low_confidence(count), style })
+        }
+        "years_ranked_by_launches_after" => Section::YearsRanked(YearsRanked {
+            after_year: context.after_year,
+            years: Cell::years_ranked_by_launches_after(cells, context.after_year).into_iter().map(|(key, count)| Count { key, count }).collect(),
+        }),
+        "highest_avg_body_weight_oem" => Section::HeaviestOem(HeaviestOem { oem: Cell::highest_avg_body_weight_oem(cells) }),
+        "display_families_per_year" => Section::DisplayFamilies(DisplayFamilies {
+            years: analysis::display_families_per_year(cells)
+                .into_iter()
+                .map(|(year, families)| YearFamilies { year, families: families.into_iter().map(|(family, count)| Count { key: family.to_string(), count }).collect() })
+                .collect(),
+        }),
+        "resolution_classes_per_year" => Section::ResolutionClasses(ResolutionClasses {
+            years: analysis::resolution_classes_per_year(cells)
+                .into_iter()
+                .map(|(year, classes)| YearClasses { year, classes: classes.into_iter().map(|(class, count)| Count { key: class.short_name().to_string(), count }).collect() })
+                .collect(),
+        }),
+        "aspect_ratios_per_year" => Section::AspectRatios(AspectRatios {
+            years: analysis::aspect_ratios_per_year(cells)
+                .into_iter()
+                .map(|(year, ratios)| YearRatios {
+                    year,
+                    phones: ratios.iter().map(|(_, count)| count).sum(),
+                    ratios: ratios.into_iter().map(|(ratio, count)| Count { key: ratio.to_string(), count }).collect(),
+                })
+                .collect(),
+            style,
+        }),
+        "profile_per_year" => Section::Profiles(Profiles {
+            years: analysis::profile_per_year(cells)
+                .into_iter()
+                .map(|profile| Profile {
+                    year: profile.year,
+                    phones: profile.phones,
+                    body_weight: profile.body_weight,
+                    display_size: profile.display_size,
+                    resolution: profile.resolution.map(|class| class.to_string()),
+                    sensors: profile.sensors,
+                    low_confidence: style.low_confidence(profile.phones),
+                })
+                .collect(),
+            style,
+        }),
+        "thinnest_phones" => Section::ThinnestPhones(ThinnestPhones {
+            phones: analysis::thinnest_phones(cells, 10).into_iter().map(|(oem, model, depth)| PhoneDepth { oem, model, depth }).collect(),
+            style,
+        }),
+        "highest_ppi_per_year" => Section::HighestPpi(HighestPpi {
+            years: analysis::highest_ppi_per_year(cells).into_iter().map(|(year, oem, model, ppi)| YearPpi { year, phone: Phone { oem, model }, ppi }).collect(),
+            style,
+        }),
+        "average_thickness_per_year" => Section::ThicknessPerYear(ThicknessPerYear {
+            years: analysis::average_thickness_per_year(cells).into_iter().map(|(year, depth, count)| average(year, depth, count)).collect(),
+            style,
+        }),
+        "screen_to_body_ratio_per_year" => Section::ScreenToBodyPerYear(ScreenToBodyPerYear {
+            years: analysis::screen_to_body_ratio_per_year(cells).into_iter().map(|(year, ratio, count)| average(year, ratio, count)).collect(),
+            style,
+        }),
+        "sim_share_per_year" => {
+            Section::SimShares(sim_shares(SimGrouping::Year, analysis::sim_share_per_year(cells).into_iter().map(|(year, share)| (year.to_string(), share)).collect()))
+        }
+        "sim_share_per_oem" => Section::SimShares(sim_shares(SimGrouping::Oem, analysis::sim_share_per_oem(cells))),
+        "avg_display_size_per_oem" => Section::DisplaySizePerOem(DisplaySizePerOem {
+            oems: analysis::avg_display_size_per_oem(cells)
+                .into_iter()
+                .map(|(oem, mean, count)| Average { key: oem, value: mean, count, low_confidence: style.low_confidence(count) })
+                .collect(),
+            style,
+        }),
+        "weight_extremes_per_oem" => Section::WeightExtremes(WeightExtremes {
+            oems: Cell::weight_extremes_per_oem(cells)
+                .into_iter()
+                .map(|(oem, (light, light_grams), (heavy, heavy_grams))| OemExtremes {
+                    oem,
+                    lightest: ModelWeight { model: light, grams: light_grams },
+                    heaviest: ModelWeight { model: heavy, grams: heavy_grams },
+                })
+                .collect(),
+            style,
+        }),
+        "smartphone_share_per_year" => {
+            let launches: HashMap<u32, usize> = Cell::launches_per_year(cells).into_iter().collect();
+            Section::SmartphoneShare(SmartphoneShare {
+                years: Cell::smartphone_share_per_year(cells).into_iter().map(|(year, share)| average(year, share, launches.get(&year).copied().unwrap_or(0))).collect(),
+                style,
+            })
+        }
+        "android_version_per_year" => Section::AndroidVersionPerYear(AndroidVersionPerYear {
+            years: analysis::average_os_version_per_year(cells, OsFamily::Android).into_iter().map(|(year, version, count)| average(year, version, count)).collect(),
+            style,
+        }),
+        "os_upgrades_per_oem_year" => Section::OsUpgrades(OsUpgrades {
+            oems: analysis::os_upgrades_per_oem_year(cells)
+                .into_iter()
+                .map(|(oem, years)| OemUpgrades { oem, years: years.into_iter().map(|(year, upgrades, count)| average(year, upgrades, count)).collect() })
+                .collect(),
+            style,
+        }),
+        "launches_per_year" => Section::LaunchesPerYear(LaunchesPerYear { years: Cell::launches_per_year(cells).into_iter().map(|(key, count)| Count { key, count }).collect() }),
+        "launches_per_quarter" => Section::LaunchesPerQuarter(LaunchesPerQuarter {
+            quarters: analysis::launches_per_quarter(cells).into_iter().map(|(year, quarter, count)| Count { key: format!("{} Q{}", year, quarter), count }).collect(),
+        }),
+        "launches_per_decade" => Section::LaunchesPerDecade(LaunchesPerDecade {
+            decades: analysis::launches_per_decade(cells)
+                .into_iter()
+                .map(|decade| Decade { decade: decade.label(), launches: decade.launches, leader: decade.leader.map(|(key, count)| Count { key, count }) })
+                .collect(),
+        }),
+        "oem_cadence" => Section::OemCadences(OemCadences {
+            oems: analysis::oem_cadence(cells)
+                .into_iter()
+                .map(|oem| Cadence {
+                    launches_per_active_year: oem.launches_per_active_year(),
+                    gaps: oem.gaps.iter().map(|&(from, to)| Gap { from, to }).collect(),
+                    oem: oem.oem,
+                    launches: oem.launches,
+                    first_year: oem.first_year,
+                    last_year: oem.last_year,
+                    active_years: oem.active_years,
+                    dormant_since: oem.dormant_since,
+                })
+                .collect(),
+            style,
+        }),
+        "oem_counts" => Section::TopOems(TopOems { rows: cells.len(), oems: counts(Cell::oem_counts(cells).into_iter().take(10).collect()) }),
+        "oem_year_pivot" => {
+            let (years, pivot) = Cell::oem_year_pivot(cells);
+            Section::OemYearPivot(OemYearPivot { years, oems: pivot.into_iter().map(|(oem, launches)| OemYears { oem, launches }).collect() })
+        }
+        "launches_per_country_per_year" => Section::CountryLaunches(CountryLaunches {
+            launches: country::launches_per_country_per_year(cells, context.countries)
+                .into_iter()
+                .map(|(country, year, launches)| CountryYear { country, year, launches })
+                .collect(),
+        }),
+        "records" => Section::Records(Records { cells: cells.iter().cloned().collect() }),
+        _ => return None,
+    };
+
+    Some(section)
+}
@@ -0,0 +1,236 @@
+use serde::Serialize;
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::RangeInclusive;
+
+// Pixel densities a phone display plausibly has, from the coarsest monochrome screens to 4K
+// on a phone. A density outside them means the resolution or the size is wrong.
+pub const PLAUSIBLE_PPI: RangeInclusive<f32> = 30.0..=1000.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+/*
+    The pixels of a display, in the order display_resolution gives them (usually the short
+    side first). Resolutions compare by their total pixels, then by width.
+ */
+pub struct Resolution {
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Resolution {
+    /*
+        Parse the first "width x height" in pixels of a display_resolution, such as "1080 x
+        2340 pixels, 19.5:9 ratio (~403 ppi density)", "240 x 320 pixels, 8 lines", "8 lines,
+        128 x 160 pixels" or "720 x 1280 (~294 ppi density)". Older text displays measured
+        only in lines or characters ("4 lines", "16 x 2 chars") have no pixels and give None.
+
+        Runtime: O(n)
+     */
+    pub fn parse(display_resolution: &str) -> Option<Resolution> {
+        let digits = |c: char| c.is_ascii_digit();
+
+        display_resolution.match_indices('x').find_map(|(at, _)| {
+            let before = display_resolution[..at].trim_end();
+            let after = display_resolution[at + 1..].trim_start();
+            let unit = after.trim_start_matches(digits);
+            let width = before[before.trim_end_matches(digits).len()..].parse().ok()?;
+            let height = after[..after.len() - unit.len()].parse().ok()?;
+
+            let unit = unit.trim_start();
+            let in_pixels = unit.is_empty() || unit.starts_with("pixels") || unit.starts_with('(');
+            (in_pixels && width > 0 && height > 0).then_some(Resolution { width, height })
+        })
+    }
+
+    // Width times height.
+    pub fn pixels(&self) -> u64 {
+        self.width as u64 * self.height as u64
+    }
+
+    pub fn short_side(&self) -> u32 {
+        self.width.min(self.height)
+    }
+
+    pub fn long_side(&self) -> u32 {
+        self.width.max(self.height)
+    }
+
+    pub fn class(&self) -> ResolutionClass {
+        ResolutionClass::from_pixels(self.width, self.height)
+    }
+
+    /*
+        Pixels per inch on a display with this diagonal, in inches: the diagonal in pixels
+        over the diagonal in inches. None for a diagonal that is not a positive number, or a
+        density outside PLAUSIBLE_PPI.
+
+        Runtime: O(1)
+     */
+    pub fn ppi(&self, diagonal_inches: f32) -> Option<f32> {
+        if !diagonal_inches.is_finite() || diagonal_inches <= 0.0 {
+            return None;
+        }
+        let ppi = (self.width as f32).hypot(self.height as f32) / diagonal_inches;
+
+        PLAUSIBLE_PPI.contains(&ppi).then_some(ppi)
+    }
+}
+
+impl Ord for Resolution {
+    fn cmp(&self, other: &Resolution) -> Ordering {
+        (self.pixels(), self.width, self.height).cmp(&(other.pixels(), other.width, other.height))
+    }
+}
+
+impl PartialOrd for Resolution {
+    fn partial_cmp(&self, other: &Resolution) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for Resolution {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} x {}", self.width, self.height)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+/*
+    The shape of a display as long side : short side, e.g. 19.5:9 or 4:3. Ratios near one of
+    the common phone shapes are snapped to it; others are written over 9 to the nearest half,
+    e.g. 17.5:9. Ordered from squarest to tallest.
+ */
+pub struct AspectRatio {
+    pub long: f32,
+    pub short: f32,
+}
+
+impl AspectRatio {
+    // Shapes phones have been sold with, squarest first.
+    pub const COMMON: [AspectRatio; 12] = [
+        AspectRatio { long: 1.0, short: 1.0 },
+        AspectRatio { long: 5.0, short: 4.0 },
+        AspectRatio { long: 4.0, short: 3.0 },
+        AspectRatio { long: 3.0, short: 2.0 },
+        AspectRatio { long: 5.0, short: 3.0 },
+        AspectRatio { long: 16.0, short: 9.0 },
+        AspectRatio { long: 18.0, short: 9.0 },
+        AspectRatio { long: 18.5, short: 9.0 },
+        AspectRatio { long: 19.0, short: 9.0 },
+        AspectRatio { long: 19.5, short: 9.0 },
+        AspectRatio { long: 20.0, short: 9.0 },
+        AspectRatio { long: 21.0, short: 9.0 },
+    ];
+
+    // How far, relative to it, a ratio may be from a common shape to count as that shape.
+    const TOLERANCE: f32 = 0.015;
+
+    /*
+        The aspect ratio of a resolution, whichever side is written first.
+
+        Runtime: O(1)
+     */
+    pub fn of(resolution: &Resolution) -> AspectRatio {
+        let ratio = resolution.long_side() as f32 / resolution.short_side() as f32;
+        let nearest = AspectRatio::COMMON.iter().min_by(|a, b| (a.value() - ratio).abs().total_cmp(&(b.value() - ratio).abs()));
+
+        match nearest {
+            Some(common) if (common.value() - ratio).abs() <= common.value() * AspectRatio::TOLERANCE => *common,
+            _ => AspectRatio { long: (ratio * 18.0).round() / 2.0, short: 9.0 },
+        }
+    }
+
+    // Long side over short side, e.g. 1.78 for 16:9.
+    pub fn value(&self) -> f32 {
+        self.long / self.short
+    }
+}
+
+impl PartialOrd for AspectRatio {
+    fn partial_cmp(&self, other: &AspectRatio) -> Option<Ordering> {
+        Some(self.value().total_cmp(&other.value()))
+    }
+}
+
+impl fmt::Display for AspectRatio {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.long, self.short)
+    }
+}
+
+/*
+    The width and height in pixels of a display_resolution, in the order they are written.
+    See Resolution::parse.
+
+    Runtime: O(n)
+ */
+pub fn pixels(display_resolution: &str) -> Option<(u32, u32)> {
+    Resolution::parse(display_resolution).map(|resolution| (resolution.width, resolution.height))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+// How sharp a display is, by the pixels along its short side. Ordered lowest first.
+pub enum ResolutionClass {
+    // Below 240 pixels, the small screens of feature phones.
+    Low,
+    // 240 to 719 pixels, QVGA up to qHD.
+    Sd,
+    // 720 to 1079 pixels.
+    Hd,
+    // 1080 to 1439 pixels.
+    FullHd,
+    // 1440 to 2159 pixels.
+    QuadHd,
+    // 2160 pixels or more.
+    UltraHd,
+}
+
+impl ResolutionClass {
+    pub const ALL: [ResolutionClass; 6] =
+        [ResolutionClass::Low, ResolutionClass::Sd, ResolutionClass::Hd, ResolutionClass::FullHd, ResolutionClass::QuadHd, ResolutionClass::UltraHd];
+
+    pub fn from_pixels(width: u32, height: u32) -> ResolutionClass {
+        match width.min(height) {
+            0..=239 => ResolutionClass::Low,
+            240..=719 => ResolutionClass::Sd,
+            720..=1079 => ResolutionClass::Hd,
+            1080..=1439 => ResolutionClass::FullHd,
+            1440..=2159 => ResolutionClass::QuadHd,
+            _ => ResolutionClass::UltraHd,
+        }
+    }
+
+    // The class of a display_resolution, if it gives the pixels.
+    pub fn from_display_resolution(display_resolution: &str) -> Option<ResolutionClass> {
+        Resolution::parse(display_resolution).map(|resolution| resolution.class())
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ResolutionClass::Low => "Low",
+            ResolutionClass::Sd => "SD",
+            ResolutionClass::Hd => "HD",
+            ResolutionClass::FullHd => "Full HD",
+            ResolutionClass::QuadHd => "Quad HD",
+            ResolutionClass::UltraHd => "Ultra HD",
+        }
+    }
+
+    // The short marketing name: HD, FHD, QHD, 4K, and SD and Low below them.
+    pub fn short_name(&self) -> &'static str {
+        match self {
+            ResolutionClass::Low => "Low",
+            ResolutionClass::Sd => "SD",
+            ResolutionClass::Hd => "HD",
+            ResolutionClass::FullHd => "FHD",
+            ResolutionClass::QuadHd => "QHD",
+            ResolutionClass::UltraHd => "4K",
+        }
+    }
+}
+
+impl fmt::Display for ResolutionClass {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
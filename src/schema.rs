@@ -0,0 +1,115 @@
+use crate::column::Column;
+use crate::config::Config;
+use crate::error::CellError;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+// What a source column holds: one of the cell's columns, or nothing to read.
+pub enum Mapping {
+    Field(Column),
+    Ignore,
+}
+
+impl FromStr for Mapping {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Mapping, String> {
+        match name.trim() {
+            "ignore" => Ok(Mapping::Ignore),
+            name => name.parse().map(Mapping::Field).map_err(|error| format!("{} (or 'ignore')", error)),
+        }
+    }
+}
+
+impl fmt::Display for Mapping {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Mapping::Field(column) => write!(f, "{}", column),
+            Mapping::Ignore => write!(f, "ignore"),
+        }
+    }
+}
+
+impl From<Mapping> for String {
+    fn from(mapping: Mapping) -> String {
+        mapping.to_string()
+    }
+}
+
+impl TryFrom<String> for Mapping {
+    type Error = String;
+
+    fn try_from(name: String) -> Result<Mapping, String> {
+        name.parse()
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+/*
+    How the headers of a dataset variant map to the cell's columns, read from a TOML file:
+
+        [columns]
+        "Brand" = "oem"
+        "Phone Name" = "model"
+        "Price" = "ignore"
+
+    Headers not listed keep their usual meaning, so only the differences need to be given.
+ */
+pub struct Schema {
+    columns: BTreeMap<String, Mapping>,
+}
+
+impl Schema {
+    /*
+        Read and parse a schema file.
+
+        Runtime: O(n)
+     */
+    pub fn load(path: &str) -> Result<Schema, CellError> {
+        let text = fs::read_to_string(path)?;
+        let schema = toml::from_str(&text).map_err(|error| CellError::Toml { path: path.to_string(), error })?;
+
+        Ok(schema)
+    }
+
+    // Writes the schema in the format load reads.
+    pub fn save(&self, path: &str) -> Result<(), CellError> {
+        fs::write(path, toml::to_string(self)?)?;
+        Ok(())
+    }
+
+    // The schema file named in the config (or by --schema), or the usual headers if none is.
+    pub fn from_config(config: &Config) -> Result<Schema, CellError> {
+        match &config.schema {
+            Some(path) => Schema::load(path),
+            None => Ok(Schema::default()),
+        }
+    }
+
+    // Maps a source header to a column or to nothing.
+    pub fn insert(&mut self, header: &str, mapping: Mapping) {
+        self.columns.insert(header.to_string(), mapping);
+    }
+
+    /*
+        What a header holds: its mapping if the schema lists it (ignoring case and surrounding
+        spaces), otherwise the column of the same name, if any.
+
+        Runtime: O(m)
+     */
+    pub fn mapping(&self, header: &str) -> Option<Mapping> {
+        let header = header.trim().to_lowercase();
+
+        self.columns
+            .iter()
+            .find(|(name, _)| name.trim().to_lowercase() == header)
+            .map(|(_, mapping)| *mapping)
+            .or_else(|| Column::from_name(&header).map(Mapping::Field))
+    }
+}
@@ -0,0 +1,142 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+// Where a fingerprint reader sits on the body.
+pub enum FingerprintPosition {
+    Front,
+    Rear,
+    Side,
+    UnderDisplay,
+    // A fingerprint reader without a position, e.g. "Fingerprint".
+    Unknown,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+// One entry of features_sensors. Entries that are not one of the known sensors are kept as
+// they are written.
+pub enum Sensor {
+    Accelerometer,
+    Gyro,
+    Proximity,
+    Compass,
+    Barometer,
+    Fingerprint { position: FingerprintPosition },
+    FaceId,
+    IrisScanner,
+    HeartRate,
+    SpO2,
+    ColorSpectrum,
+    Other(Box<str>),
+}
+
+// How entries of the known sensors other than fingerprint readers start, in lowercase.
+const NAMES: [(&str, Sensor); 12] = [
+    ("accelerometer", Sensor::Accelerometer),
+    ("gyro", Sensor::Gyro),
+    ("proximity", Sensor::Proximity),
+    ("compass", Sensor::Compass),
+    ("barometer", Sensor::Barometer),
+    ("baroceptor", Sensor::Barometer),
+    ("face id", Sensor::FaceId),
+    ("infrared face recognition", Sensor::FaceId),
+    ("iris", Sensor::IrisScanner),
+    ("heart rate", Sensor::HeartRate),
+    ("spo2", Sensor::SpO2),
+    ("color spectrum", Sensor::ColorSpectrum),
+];
+
+impl Sensor {
+    /*
+        Parse one entry such as "Fingerprint (rear-mounted)" or "compass - India only",
+        ignoring case and trailing notes.
+
+        Runtime: O(1)
+     */
+    pub fn parse(entry: &str) -> Sensor {
+        let entry = entry.trim();
+        let lower = entry.to_lowercase();
+
+        if lower.contains("fingerprint") {
+            let position = if lower.contains("under display") {
+                FingerprintPosition::UnderDisplay
+            } else if lower.contains("rear") {
+                FingerprintPosition::Rear
+            } else if lower.contains("side") {
+                FingerprintPosition::Side
+            } else if lower.contains("front") {
+                FingerprintPosition::Front
+            } else {
+                FingerprintPosition::Unknown
+            };
+            return Sensor::Fingerprint { position };
+        }
+
+        match NAMES.iter().find(|(name, _)| lower.starts_with(name)) {
+            Some((_, sensor)) => sensor.clone(),
+            None => Sensor::Other(entry.into()),
+        }
+    }
+
+    /*
+        Parse a features_sensors value such as "Fingerprint (under display, optical),
+        accelerometer, proximity" into its sensors. Entries are split on commas outside
+        parentheses, and "V1", which the dataset has for phones without a sensor list, is
+        none.
+
+        Runtime: O(s) for the length of the text
+     */
+    pub fn parse_list(sensors: &str) -> Vec<Sensor> {
+        if sensors.trim() == "V1" {
+            return Vec::new();
+        }
+
+        let (mut entries, mut start, mut depth) = (Vec::new(), 0, 0usize);
+        for (index, character) in sensors.char_indices() {
+            match character {
+                '(' => depth += 1,
+                ')' => depth = depth.saturating_sub(1),
+                ',' if depth == 0 => {
+                    entries.push(&sensors[start..index]);
+                    start = index + 1;
+                }
+                _ => {}
+            }
+        }
+        entries.push(&sensors[start..]);
+
+        entries.into_iter().filter(|entry| !entry.trim().is_empty()).map(Sensor::parse).collect()
+    }
+}
+
+impl fmt::Display for FingerprintPosition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FingerprintPosition::Front => write!(f, "front-mounted"),
+            FingerprintPosition::Rear => write!(f, "rear-mounted"),
+            FingerprintPosition::Side => write!(f, "side-mounted"),
+            FingerprintPosition::UnderDisplay => write!(f, "under display"),
+            FingerprintPosition::Unknown => write!(f, "unknown position"),
+        }
+    }
+}
+
+impl fmt::Display for Sensor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Sensor::Accelerometer => write!(f, "Accelerometer"),
+            Sensor::Gyro => write!(f, "Gyro"),
+            Sensor::Proximity => write!(f, "Proximity"),
+            Sensor::Compass => write!(f, "Compass"),
+            Sensor::Barometer => write!(f, "Barometer"),
+            Sensor::Fingerprint { position: FingerprintPosition::Unknown } => write!(f, "Fingerprint"),
+            Sensor::Fingerprint { position } => write!(f, "Fingerprint ({})", position),
+            Sensor::FaceId => write!(f, "Face ID"),
+            Sensor::IrisScanner => write!(f, "Iris scanner"),
+            Sensor::HeartRate => write!(f, "Heart rate"),
+            Sensor::SpO2 => write!(f, "SpO2"),
+            Sensor::ColorSpectrum => write!(f, "Color spectrum"),
+            Sensor::Other(entry) => write!(f, "{}", entry),
+        }
+    }
+}
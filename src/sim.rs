@@ -0,0 +1,72 @@
+use serde::Serialize;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+// The size of a physical SIM card, largest first.
+pub enum SimForm {
+    Mini,
+    Micro,
+    Nano,
+}
+
+impl SimForm {
+    pub const ALL: [SimForm; 3] = [SimForm::Mini, SimForm::Micro, SimForm::Nano];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            SimForm::Mini => "Mini-SIM",
+            SimForm::Micro => "Micro-SIM",
+            SimForm::Nano => "Nano-SIM",
+        }
+    }
+}
+
+impl fmt::Display for SimForm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
+// What the body_sim text says about the SIM slots of a phone.
+pub struct SimInfo {
+    // The card sizes it takes, largest first.
+    pub forms: Vec<SimForm>,
+    // Physical SIM slots in its largest variant: 2 for dual SIM, 0 for eSIM only or "No",
+    // None when the text doesn't say, e.g. "Yes".
+    pub slots: Option<u8>,
+    // Takes two SIMs, in at least one of its variants (includes hybrid slots).
+    pub dual_sim: bool,
+    // The second SIM slot is shared with a memory card.
+    pub hybrid: bool,
+    // Supports an embedded SIM.
+    pub esim: bool,
+}
+
+impl SimInfo {
+    /*
+        Parse a body_sim such as "Single SIM (Nano-SIM) or Hybrid Dual SIM (Nano-SIM, dual
+        stand-by)", "Nano-SIM, eSIM or Dual SIM" or "Nano-SIM card & eSIM". Case and the
+        spelling of the sizes ("MIni-SIM", "Micro-SIM/ Nano-SIM") don't matter.
+
+        Runtime: O(1)
+     */
+    pub fn parse(body_sim: &str) -> SimInfo {
+        let lower = body_sim.to_lowercase();
+        let forms: Vec<SimForm> = SimForm::ALL.into_iter().filter(|form| lower.contains(&form.name().to_lowercase())).collect();
+        let dual_sim = lower.contains("dual sim");
+        let esim = lower.contains("esim");
+
+        let slots = if dual_sim {
+            Some(2)
+        } else if !forms.is_empty() || lower.contains("single sim") {
+            Some(1)
+        } else if esim || lower.trim() == "no" {
+            Some(0)
+        } else {
+            None
+        };
+
+        SimInfo { forms, slots, dual_sim, hybrid: lower.contains("hybrid"), esim }
+    }
+}
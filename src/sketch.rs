@@ -0,0 +1,241 @@
+use std::collections::hash_map::DefaultHasher;
+use std::f64::consts::PI;
+use std::hash::{Hash, Hasher};
+
+// Centroids kept by a t-digest with the default compression; more is more accurate.
+pub const DEFAULT_COMPRESSION: f64 = 100.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+// A cluster of nearby values: their mean and how many there are.
+struct Centroid {
+    mean: f64,
+    weight: f64,
+}
+
+/*
+    Approximate quantiles of a stream in bounded memory (a merging t-digest). Values are
+    buffered and periodically merged into at most about `compression` centroids, which are
+    kept small near the tails so extreme percentiles stay accurate.
+ */
+#[derive(Debug, Clone)]
+pub struct TDigest {
+    compression: f64,
+    centroids: Vec<Centroid>,
+    buffer: Vec<f64>,
+    count: u64,
+    min: f64,
+    max: f64,
+}
+
+impl TDigest {
+    pub fn new(compression: f64) -> TDigest {
+        TDigest { compression, centroids: Vec::new(), buffer: Vec::new(), count: 0, min: f64::INFINITY, max: f64::NEG_INFINITY }
+    }
+
+    pub fn len(&self) -> u64 {
+        self.count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    // Number of centroids after merging the buffered values.
+    pub fn centroids(&mut self) -> usize {
+        self.merge();
+        self.centroids.len()
+    }
+
+    pub fn push(&mut self, value: f32) {
+        let value = value as f64;
+        self.buffer.push(value);
+        self.count += 1;
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+
+        if self.buffer.len() as f64 >= self.compression * 5.0 {
+            self.merge();
+        }
+    }
+
+    // Position on the k1 scale; neighbouring centroids are merged while they span less than 1.
+    fn scale(&self, q: f64) -> f64 {
+        self.compression / (2.0 * PI) * (2.0 * q - 1.0).clamp(-1.0, 1.0).asin()
+    }
+
+    /*
+        Fold the buffered values into the centroids.
+
+        Runtime: O(b log b + c)
+     */
+    fn merge(&mut self) {
+        if self.buffer.is_empty() {
+            return;
+        }
+
+        let mut all: Vec<Centroid> = self.buffer.drain(..).map(|mean| Centroid { mean, weight: 1.0 }).collect();
+        all.append(&mut self.centroids);
+        all.sort_by(|a, b| a.mean.total_cmp(&b.mean));
+
+        let total: f64 = all.iter().map(|centroid| centroid.weight).sum();
+        let mut merged: Vec<Centroid> = Vec::new();
+        let mut before = 0.0;
+        let mut current = all[0];
+
+        for &next in &all[1..] {
+            let limit = self.scale(before / total) + 1.0;
+            if self.scale((before + current.weight + next.weight) / total) <= limit {
+                let weight = current.weight + next.weight;
+                current.mean += (next.mean - current.mean) * next.weight / weight;
+                current.weight = weight;
+            } else {
+                before += current.weight;
+                merged.push(current);
+                current = next;
+            }
+        }
+        merged.push(current);
+
+        self.centroids = merged;
+    }
+
+    /*
+        The approximate q-quantile (0 <= q <= 1), interpolated between centroid means and the
+        smallest and largest values seen. Exact while every centroid holds one value. None if
+        nothing was pushed.
+
+        Runtime: O(c)
+     */
+    pub fn quantile(&mut self, q: f32) -> Option<f32> {
+        self.merge();
+        let first = self.centroids.first()?;
+        let last = self.centroids.last()?;
+
+        let total = self.count as f64;
+        let target = q.clamp(0.0, 1.0) as f64 * total;
+
+        // Each centroid sits at the middle of the ranks it covers.
+        if target < first.weight / 2.0 {
+            let fraction = if first.weight > 1.0 { target / (first.weight / 2.0) } else { 0.0 };
+            return Some((self.min + (first.mean - self.min) * fraction) as f32);
+        }
+        if target > total - last.weight / 2.0 {
+            let fraction = if last.weight > 1.0 { (total - target) / (last.weight / 2.0) } else { 0.0 };
+            return Some((self.max - (self.max - last.mean) * fraction) as f32);
+        }
+
+        let mut before = 0.0;
+        for pair in self.centroids.windows(2) {
+            let left = before + pair[0].weight / 2.0;
+            let right = before + pair[0].weight + pair[1].weight / 2.0;
+            if target <= right {
+                let fraction = if right > left { (target - left) / (right - left) } else { 0.0 };
+                return Some((pair[0].mean + (pair[1].mean - pair[0].mean) * fraction) as f32);
+            }
+            before += pair[0].weight;
+        }
+
+        Some(last.mean as f32)
+    }
+}
+
+// Registers of a HyperLogLog are indexed by this many bits of the hash (2^14 = 16384 registers).
+const HLL_PRECISION: u32 = 14;
+
+/*
+    Approximate number of distinct values in a stream (HyperLogLog). Uses a fixed 16 KiB no
+    matter how many values are added, with a typical error of about 1%.
+ */
+#[derive(Debug, Clone)]
+pub struct HyperLogLog {
+    registers: Vec<u8>,
+}
+
+impl Default for HyperLogLog {
+    fn default() -> HyperLogLog {
+        HyperLogLog::new()
+    }
+}
+
+impl HyperLogLog {
+    pub fn new() -> HyperLogLog {
+        HyperLogLog { registers: vec![0; 1 << HLL_PRECISION] }
+    }
+
+    pub fn add(&mut self, value: impl Hash) {
+        let mut hasher = DefaultHasher::new();
+        value.hash(&mut hasher);
+        let hash = hasher.finish();
+
+        let index = (hash >> (64 - HLL_PRECISION)) as usize;
+        // Position of the first 1 bit in the remaining bits, counting from 1.
+        let rest = hash << HLL_PRECISION;
+        let rank = (rest.leading_zeros() + 1).min(64 - HLL_PRECISION + 1) as u8;
+        self.registers[index] = self.registers[index].max(rank);
+    }
+
+    /*
+        The estimated number of distinct values added, using linear counting while many
+        registers are still empty.
+
+        Runtime: O(m)
+     */
+    pub fn count(&self) -> u64 {
+        let m = self.registers.len() as f64;
+        let alpha = 0.7213 / (1.0 + 1.079 / m);
+        let sum: f64 = self.registers.iter().map(|&rank| 2f64.powi(-(rank as i32))).sum();
+        let estimate = alpha * m * m / sum;
+
+        let empty = self.registers.iter().filter(|&&rank| rank == 0).count();
+        if estimate <= 2.5 * m && empty > 0 {
+            (m * (m / empty as f64).ln()).round() as u64
+        } else {
+            estimate.round() as u64
+        }
+    }
+}
+
+/*
+    A set that can answer "definitely not present" or "maybe present" in a few bits per
+    item (a bloom filter). Sized for an expected number of items and false-positive rate.
+ */
+#[derive(Debug, Clone)]
+pub struct BloomFilter {
+    bits: Vec<u64>,
+    bit_count: u64,
+    hashes: u32,
+}
+
+impl BloomFilter {
+    pub fn new(expected_items: usize, false_positive_rate: f64) -> BloomFilter {
+        let items = expected_items.max(1) as f64;
+        let rate = false_positive_rate.clamp(1e-9, 0.5);
+        let bit_count = (-items * rate.ln() / (2f64.ln() * 2f64.ln())).ceil().max(64.0) as u64;
+        let hashes = ((bit_count as f64 / items) * 2f64.ln()).round().clamp(1.0, 16.0) as u32;
+
+        BloomFilter { bits: vec![0; bit_count.div_ceil(64) as usize], bit_count, hashes }
+    }
+
+    // The bits an item sets, from two hashes combined (double hashing).
+    fn positions(&self, item: impl Hash) -> impl Iterator<Item = u64> {
+        let mut hasher = DefaultHasher::new();
+        item.hash(&mut hasher);
+        let first = hasher.finish();
+        0x9e37_79b9_7f4a_7c15u64.hash(&mut hasher);
+        let second = hasher.finish() | 1;
+        let bit_count = self.bit_count;
+
+        (0..self.hashes as u64).map(move |index| first.wrapping_add(index.wrapping_mul(second)) % bit_count)
+    }
+
+    pub fn insert(&mut self, item: impl Hash) {
+        for position in self.positions(item).collect::<Vec<u64>>() {
+            self.bits[(position / 64) as usize] |= 1 << (position % 64);
+        }
+    }
+
+    // False means the item was never inserted; true means it probably was.
+    pub fn may_contain(&self, item: impl Hash) -> bool {
+        self.positions(item).all(|position| self.bits[(position / 64) as usize] & (1 << (position % 64)) != 0)
+    }
+}
@@ -0,0 +1,81 @@
+use crate::date::quarter;
+use serde::Serialize;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+// Where a phone stands, from its launch_status.
+pub enum LaunchStatus {
+    // On sale, or sold until it was discontinued without the status saying so. The quarter,
+    // 1 to 4, is known when the status names a month or a quarter.
+    Released { year: u32, quarter: Option<u8> },
+    // Announced with an expected release, in a year if the status gives one.
+    ComingSoon { year: Option<u32> },
+    Discontinued,
+    Cancelled,
+}
+
+impl LaunchStatus {
+    /*
+        Parse a launch_status such as "Available. Released 2019, September", "Coming soon.
+        Exp. release 2020, July", "Discontinued" or "Cancelled". The year cleaning stage
+        leaves only the year of an available phone, so a bare "2019" is a release in 2019
+        with no quarter. Text that is none of these gives None.
+
+        Runtime: O(n)
+     */
+    pub fn parse(launch_status: &str) -> Option<LaunchStatus> {
+        let lower = launch_status.to_lowercase();
+
+        if lower.contains("discontinued") {
+            return Some(LaunchStatus::Discontinued);
+        }
+        if lower.contains("cancel") {
+            return Some(LaunchStatus::Cancelled);
+        }
+
+        let year = lower
+            .split(|c: char| !c.is_ascii_digit())
+            .find(|digits| digits.len() == 4)
+            .and_then(|digits| digits.parse().ok());
+        if lower.contains("coming") {
+            return Some(LaunchStatus::ComingSoon { year });
+        }
+
+        Some(LaunchStatus::Released { year: year?, quarter: quarter(&lower) })
+    }
+
+    // The year the phone went on sale, if it did.
+    pub fn released_year(&self) -> Option<u32> {
+        match self {
+            LaunchStatus::Released { year, .. } => Some(*year),
+            _ => None,
+        }
+    }
+
+    // Whether the phone never went on sale: it was cancelled or is still coming.
+    pub fn never_released(&self) -> bool {
+        matches!(self, LaunchStatus::Cancelled | LaunchStatus::ComingSoon { .. })
+    }
+
+    // The group breakdowns split statuses into.
+    pub fn category(&self) -> &'static str {
+        match self {
+            LaunchStatus::Released { .. } => "Available",
+            LaunchStatus::ComingSoon { .. } => "Coming soon",
+            LaunchStatus::Discontinued => "Discontinued",
+            LaunchStatus::Cancelled => "Cancelled",
+        }
+    }
+}
+
+impl fmt::Display for LaunchStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LaunchStatus::Released { year, quarter: Some(quarter) } => write!(f, "Released {} Q{}", year, quarter),
+            LaunchStatus::Released { year, quarter: None } => write!(f, "Released {}", year),
+            LaunchStatus::ComingSoon { year: Some(year) } => write!(f, "Coming soon ({})", year),
+            LaunchStatus::ComingSoon { year: None } => write!(f, "Coming soon"),
+            status => write!(f, "{}", status.category()),
+        }
+    }
+}
@@ -0,0 +1,183 @@
+use crate::column::Column;
+use crate::record::Record;
+use crate::Cell;
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::marker::PhantomData;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+// An f32 ordered by total_cmp, so numeric values can be kept in a BTreeMap.
+struct Value(f32);
+
+impl Eq for Value {}
+
+impl PartialOrd for Value {
+    fn partial_cmp(&self, other: &Value) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Value {
+    fn cmp(&self, other: &Value) -> Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+// The summary of one column: how many values it has and, for numeric columns, their range.
+pub struct ColumnSummary<F = Column> {
+    pub column: F,
+    pub count: usize,
+    pub nulls: usize,
+    // Sum, min and max are None for text columns and for numeric columns with no values.
+    pub sum: Option<f64>,
+    pub min: Option<f32>,
+    pub max: Option<f32>,
+}
+
+impl<F> ColumnSummary<F> {
+    pub fn mean(&self) -> Option<f64> {
+        self.sum.filter(|_| self.count > 0).map(|sum| sum / self.count as f64)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+// The summaries of one column for two groups of records, to set side by side.
+pub struct ColumnComparison<F = Column> {
+    pub left: ColumnSummary<F>,
+    pub right: ColumnSummary<F>,
+}
+
+impl<F> ColumnComparison<F> {
+    // Right minus left for a statistic of the summaries, if both groups have it, e.g.
+    // `comparison.delta(ColumnSummary::mean)`.
+    pub fn delta(&self, statistic: impl Fn(&ColumnSummary<F>) -> Option<f64>) -> Option<f64> {
+        Some(statistic(&self.right)? - statistic(&self.left)?)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+// Running totals of one column. The numeric values are counted so a removed minimum or
+// maximum can be replaced by the next one without rescanning.
+struct ColumnStats {
+    count: usize,
+    nulls: usize,
+    sum: f64,
+    values: BTreeMap<Value, usize>,
+}
+
+impl ColumnStats {
+    fn add<R: Record>(&mut self, record: &R, field: R::Field) {
+        if record.text(field).is_none() {
+            self.nulls += 1;
+            return;
+        }
+        self.count += 1;
+
+        if let Some(value) = record.number(field) {
+            self.sum += value as f64;
+            *self.values.entry(Value(value)).or_insert(0) += 1;
+        }
+    }
+
+    fn remove<R: Record>(&mut self, record: &R, field: R::Field) {
+        if record.text(field).is_none() {
+            self.nulls -= 1;
+            return;
+        }
+        self.count -= 1;
+
+        if let Some(value) = record.number(field) {
+            self.sum -= value as f64;
+            if let Some(copies) = self.values.get_mut(&Value(value)) {
+                *copies -= 1;
+                if *copies == 0 {
+                    self.values.remove(&Value(value));
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+/*
+    Per-column count, null count, sum, min and max of a set of records, cells unless told
+    otherwise, kept up to date as records are added and removed so reading a summary never
+    rescans them.
+ */
+pub struct DatasetStats<R: Record = Cell> {
+    columns: Vec<ColumnStats>,
+    record: PhantomData<fn(&R)>,
+}
+
+impl<R: Record> Default for DatasetStats<R> {
+    fn default() -> DatasetStats<R> {
+        DatasetStats { columns: vec![ColumnStats::default(); R::fields().len()], record: PhantomData }
+    }
+}
+
+impl<R: Record> DatasetStats<R> {
+    /*
+        Summarize every column of the records.
+
+        Runtime: O(n log n)
+     */
+    pub fn of(records: &[R]) -> DatasetStats<R> {
+        DatasetStats::of_refs(records)
+    }
+
+    // Summarizes records that are borrowed, e.g. the cells of a view. Runtime: O(n log n)
+    pub fn of_refs<'a>(records: impl IntoIterator<Item = &'a R>) -> DatasetStats<R>
+    where
+        R: 'a,
+    {
+        let mut stats = DatasetStats::default();
+        for record in records {
+            stats.add(record);
+        }
+        stats
+    }
+
+    // Runtime: O(log n)
+    pub fn add(&mut self, record: &R) {
+        for (stats, &field) in self.columns.iter_mut().zip(R::fields()) {
+            stats.add(record, field);
+        }
+    }
+
+    // Takes back a record that was added before. Runtime: O(log n)
+    pub fn remove(&mut self, record: &R) {
+        for (stats, &field) in self.columns.iter_mut().zip(R::fields()) {
+            stats.remove(record, field);
+        }
+    }
+
+    /*
+        The current summary of a column.
+
+        Runtime: O(c + log n)
+     */
+    pub fn summary(&self, field: R::Field) -> ColumnSummary<R::Field> {
+        let index = R::fields().iter().position(|&other| other == field).expect("every field is in Record::fields");
+        let stats = &self.columns[index];
+        let has_values = R::is_numeric(field) && !stats.values.is_empty();
+
+        ColumnSummary {
+            column: field,
+            count: stats.count,
+            nulls: stats.nulls,
+            sum: has_values.then_some(stats.sum),
+            min: stats.values.keys().next().map(|value| value.0),
+            max: stats.values.keys().next_back().map(|value| value.0),
+        }
+    }
+
+    /*
+        Every field's summary here (left) next to its summary in other (right), in field order.
+
+        Runtime: O(c * (c + log n))
+     */
+    pub fn compare(&self, other: &DatasetStats<R>) -> Vec<ColumnComparison<R::Field>> {
+        R::fields().iter().map(|&field| ColumnComparison { left: self.summary(field), right: other.summary(field) }).collect()
+    }
+}
@@ -0,0 +1,83 @@
+use crate::column::Column;
+use crate::format::NumberFormat;
+use crate::Cell;
+
+// Widest a column is drawn before its values are cut off.
+pub const MAX_COLUMN_WIDTH: usize = 24;
+
+// Shortens a value to `width` characters, marking the cut with "…".
+pub fn truncate(value: &str, width: usize) -> String {
+    if value.chars().count() <= width {
+        value.to_string()
+    } else {
+        let mut cut: String = value.chars().take(width.saturating_sub(1)).collect();
+        cut.push('…');
+        cut
+    }
+}
+
+/*
+    Render cells as a text table with a header row. Each column is as wide as its widest
+    value, up to MAX_COLUMN_WIDTH. Numbers are written by `format`; missing values are left blank.
+
+    Runtime: O(n * c)
+ */
+pub fn render_table(cells: &[&Cell], columns: &[Column], format: &NumberFormat) -> String {
+    let headers: Vec<String> = columns.iter().map(|column| column.name().to_string()).collect();
+    let rows: Vec<Vec<String>> = cells
+        .iter()
+        .map(|cell| columns.iter().map(|&column| format.text(cell, column).unwrap_or_default()).collect())
+        .collect();
+
+    render_rows(&headers, &rows)
+}
+
+/*
+    Render rows of text under the given headers, in the same layout as render_table.
+
+    Runtime: O(n * c)
+ */
+pub fn render_rows(headers: &[String], rows: &[Vec<String>]) -> String {
+    let widths = column_widths(headers, rows);
+
+    let mut table = String::new();
+    push_row(&mut table, headers, &widths);
+
+    let rule: Vec<String> = widths.iter().map(|&width| "-".repeat(width)).collect();
+    push_row(&mut table, &rule, &widths);
+
+    for row in rows {
+        push_row(&mut table, row, &widths);
+    }
+
+    table
+}
+
+// Width of each column: its widest value or header, up to MAX_COLUMN_WIDTH.
+pub fn column_widths(headers: &[String], rows: &[Vec<String>]) -> Vec<usize> {
+    headers
+        .iter()
+        .enumerate()
+        .map(|(index, header)| {
+            let widest = rows.iter().filter_map(|row| row.get(index)).map(|value| value.chars().count()).max().unwrap_or(0);
+            widest.max(header.chars().count()).min(MAX_COLUMN_WIDTH)
+        })
+        .collect()
+}
+
+// Appends one padded line of the table.
+fn push_row(table: &mut String, values: &[String], widths: &[usize]) {
+    table.push_str(&format_row(values, widths));
+    table.push('\n');
+}
+
+// One line of the table: each value padded or cut to its column width.
+pub fn format_row(values: &[String], widths: &[usize]) -> String {
+    let cells: Vec<String> = values
+        .iter()
+        .zip(widths)
+        .map(|(value, &width)| format!("{:<width$}", truncate(value, width), width = width))
+        .collect();
+
+    cells.join(" | ").trim_end().to_string()
+}
@@ -0,0 +1,154 @@
+use crate::error::CellError;
+use crate::normalize::Normalizer;
+use crate::Cell;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::Path;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+// The tags of one phone as the sidecar stores them.
+struct Entry {
+    oem: String,
+    model: String,
+    tags: BTreeSet<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+/*
+    Free-form tags such as "verified" or "suspect-weight" attached to phones, kept in a JSON
+    sidecar next to the CSV file (cells.csv -> cells.csv.tags.json) so curation survives
+    rewrites of the file. A phone is found by its oem and model as the dataset's normalizer
+    names them, ignoring case, so tags follow a row when rows are inserted, deleted or
+    reordered, and a row of "HTC Corporation" is found under the HTC it is parsed as.
+ */
+pub struct Tags {
+    // (lowercased oem, lowercased model) -> the normalized names as first tagged and the tags
+    phones: BTreeMap<(String, String), Entry>,
+}
+
+// A phone's key in the sidecar, from its normalized oem and model.
+fn key(oem: &str, model: &str) -> (String, String) {
+    (oem.trim().to_lowercase(), model.trim().to_lowercase())
+}
+
+impl Tags {
+    // Path of the sidecar that holds the tags of a CSV file.
+    pub fn path(csv_file: &str) -> String {
+        format!("{}.tags.json", csv_file)
+    }
+
+    // Loads the tags of a CSV file, with the names normalized as the file is parsed; none if
+    // there is no sidecar.
+    pub fn load(csv_file: &str, normalizer: &Normalizer) -> Result<Tags, CellError> {
+        let path = Tags::path(csv_file);
+        if !Path::new(&path).exists() {
+            return Ok(Tags::default());
+        }
+
+        let entries: Vec<Entry> = serde_json::from_str(&fs::read_to_string(&path)?)?;
+        let mut tags = Tags::default();
+        for entry in entries {
+            for tag in &entry.tags {
+                tags.add(normalizer, &entry.oem, &entry.model, tag);
+            }
+        }
+        Ok(tags)
+    }
+
+    // Writes the sidecar, or removes it once no phone has a tag.
+    pub fn save(&self, csv_file: &str) -> Result<(), CellError> {
+        let path = Tags::path(csv_file);
+        if self.phones.is_empty() {
+            if Path::new(&path).exists() {
+                fs::remove_file(path)?;
+            }
+            return Ok(());
+        }
+
+        let entries: Vec<&Entry> = self.phones.values().collect();
+        fs::write(path, serde_json::to_string_pretty(&entries)?)?;
+        Ok(())
+    }
+
+    // Tags a phone by its oem and model in the file. False if it already had the tag.
+    pub fn add(&mut self, normalizer: &Normalizer, oem: &str, model: &str, tag: &str) -> bool {
+        let (oem, model) = normalizer.phone(oem, model);
+        let entry = self.phones.entry(key(&oem, &model)).or_insert_with(|| Entry { oem, model, tags: BTreeSet::new() });
+        entry.tags.insert(tag.trim().to_string())
+    }
+
+    // Takes a tag off a phone. False if it didn't have it.
+    pub fn remove(&mut self, normalizer: &Normalizer, oem: &str, model: &str, tag: &str) -> bool {
+        let (oem, model) = normalizer.phone(oem, model);
+        let key = key(&oem, &model);
+        let Some(entry) = self.phones.get_mut(&key) else {
+            return false;
+        };
+
+        let removed = entry.tags.remove(tag.trim());
+        if entry.tags.is_empty() {
+            self.phones.remove(&key);
+        }
+        removed
+    }
+
+    // The tags of a phone, sorted.
+    pub fn get(&self, normalizer: &Normalizer, oem: &str, model: &str) -> Vec<String> {
+        let (oem, model) = normalizer.phone(oem, model);
+        self.normalized(&oem, &model)
+    }
+
+    // The tags of a phone by its normalized oem and model.
+    fn normalized(&self, oem: &str, model: &str) -> Vec<String> {
+        self.phones.get(&key(oem, model)).map_or_else(Vec::new, |entry| entry.tags.iter().cloned().collect())
+    }
+
+    /*
+        Move the tags of a phone to its new oem and model, e.g. after an edit renamed it,
+        merging them with any the new name already has.
+
+        Runtime: O(t log p) for its tags and the tagged phones
+     */
+    pub fn rename(&mut self, normalizer: &Normalizer, oem: &str, model: &str, new_oem: &str, new_model: &str) {
+        let ((oem, model), (new_oem, new_model)) = (normalizer.phone(oem, model), normalizer.phone(new_oem, new_model));
+        if key(&oem, &model) == key(&new_oem, &new_model) {
+            return;
+        }
+        if let Some(entry) = self.phones.remove(&key(&oem, &model)) {
+            for tag in entry.tags {
+                self.add(normalizer, &new_oem, &new_model, &tag);
+            }
+        }
+    }
+
+    // (oem, model, tags) of every tagged phone, by oem and model.
+    pub fn phones(&self) -> impl Iterator<Item = (&str, &str, &BTreeSet<String>)> {
+        self.phones.values().map(|entry| (entry.oem.as_str(), entry.model.as_str(), &entry.tags))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.phones.is_empty()
+    }
+
+    /*
+        Set the tags of every cell from the sidecar, by the names the cell was parsed with.
+        Apply this before model aliases rename any cell, as the sidecar keeps the parsed
+        names, not the aliased ones. Returns the number of tagged cells.
+
+        Runtime: O(n log p)
+     */
+    pub fn apply(&self, cells: &mut [Cell]) -> usize {
+        let mut tagged = 0;
+
+        for cell in cells {
+            cell.tags = match (&cell.oem, &cell.model) {
+                (Some(oem), Some(model)) => self.normalized(oem, model),
+                _ => Vec::new(),
+            };
+            tagged += usize::from(!cell.tags.is_empty());
+        }
+
+        tagged
+    }
+}
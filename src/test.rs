@@ -2,6 +2,77 @@
 mod tests {
     use crate::Cell;
     use super::*;
+    use crate::DeviceClass;
+    use crate::aliases::{ModelAliases, OemAliases};
+    use crate::analysis;
+    use crate::bounds::{Bounds, BoundsAction, Range};
+    use crate::breakdown::Breakdown;
+    use crate::builder::CellBuilder;
+    use crate::cancel::Cancel;
+    use crate::chart;
+    use crate::cli::{self, Cli, Command, CrudCommand, SavedQueryCommand};
+    use crate::column::{Column, FieldValue};
+    use crate::config::Config;
+    use crate::country::{self, CountryMap};
+    use crate::dataset::{Dataset, DatasetPartition, JoinKind, JoinMatch, MatchConfidence};
+    use crate::date::LaunchDate;
+    use crate::dimensions::{self, Dimensions};
+    use crate::display::{self, DisplayFamily};
+    use crate::edits::{Edit, FieldChange, PendingEdits};
+    use crate::enrich::{Enricher, LookupSource, RateLimiter, ResponseCache};
+    use crate::error::CellError;
+    use crate::external::{self, ExternalSorter, SpillGroupBy};
+    use crate::filter::{self, Filter, Op, Preset, PRESETS};
+    use crate::fixture;
+    use crate::format::{NumberFormat, Rounding};
+    use crate::grouping::FloatGrouping;
+    use crate::intern::{self, Interner};
+    use crate::heatmap;
+    use crate::history::{History, RunMetrics};
+    use crate::latex;
+    use crate::memory::MemoryUsage;
+    use crate::metric::Metrics;
+    use crate::normalize::{FieldRules, Normalizer, Stage};
+    use crate::os::{OsFamily, OsInfo, Version};
+    use crate::output::{self, Buffer, FileSink};
+    use crate::parquet::{ParquetType, ParquetValue, ParquetWriter};
+    use crate::parse::{EmptyInput, FieldCounts, ParseIssue, ParseMode, ParseReport};
+    use crate::partition::{self, PartitionFormat};
+    use crate::patch::CellPatch;
+    use crate::provenance::Provenance;
+    use crate::pipeline::{Aggregate, CountBy, Derive, GroupBy, MeanOf, Pipeline, PipelineSummary, Sink};
+    use crate::query::{SavedQuery, SortKey};
+    use crate::random::Rng;
+    use crate::reader::CellReader;
+    use crate::repl::Repl;
+    use crate::record::{self, Record};
+    use crate::report::{self, Analyses, Context, Custom, NoData, OemYearPivot, OemYears, Report, ReportFormat, Sample, Section, Style};
+    use crate::resolution::{self, AspectRatio, Resolution, ResolutionClass};
+    use crate::sensor::{FingerprintPosition, Sensor};
+    use crate::schema::{Mapping, Schema};
+    use crate::sim::{SimForm, SimInfo};
+    use crate::sketch::{BloomFilter, HyperLogLog, TDigest};
+    use crate::status::LaunchStatus;
+    use crate::summary::DatasetStats;
+    use crate::table;
+    use crate::tags::Tags;
+    use crate::text;
+    use crate::timing::Timings;
+    use crate::units::UnitProfile;
+    use crate::tui::{App, Key, Screen};
+    use crate::view::{CellCollection, DatasetView};
+    use crate::viewer::TableViewer;
+    use crate::wizard::{self, Samples};
+    use clap::Parser;
+    use crate::watch::{LiveDataset, Refresh};
+    use crate::weight::{Weight, GRAMS_PER_OUNCE};
+    use crate::workspace::ChangedRow;
+    use crate::worldmap;
+    use std::collections::HashMap;
+    use std::error::Error;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+    use std::time::{Duration, Instant};
 
     // Test if the file being read is empty.
     #[test]
@@ -69,4 +140,3026 @@ mod tests {
             println!("{:?}", cell);
         }
     }
+
+    // Test that platform_os decides whether a phone is a smartphone or a feature phone.
+    #[test]
+    fn check_device_class() {
+        let cells = Cell::read_csv("cells_test.csv").unwrap();
+
+        assert_eq!(cells[0].device_class(), DeviceClass::FeaturePhone);
+        assert_eq!(cells[1].device_class(), DeviceClass::Smartphone);
+        assert_eq!(cells[2].device_class(), DeviceClass::Smartphone);
+
+        let shares = Cell::smartphone_share_per_year(&DatasetView::of(&cells));
+        assert_eq!(shares, vec![(1999, 0.0), (2010, 1.0)]);
+    }
+
+    // Test the built-in OEM country table and that config overrides win over it.
+    #[test]
+    fn check_oem_country() {
+        let cells = Cell::read_csv("cells_test.csv").unwrap();
+        let mut countries = CountryMap::new();
+
+        assert_eq!(cells[0].country(&countries), Some("Finland"));
+        assert_eq!(countries.country_of("samsung"), Some("South Korea"));
+        assert_eq!(countries.country_of("Unknown OEM"), None);
+
+        countries.insert("Benefon", "Nowhere");
+        assert_eq!(cells[0].country(&countries), Some("Nowhere"));
+
+        let rows = country::launches_per_country_per_year(&DatasetView::of(&cells), &countries);
+        assert_eq!(rows, vec![("Nowhere".to_string(), 1999, 1), ("Taiwan".to_string(), 2010, 2)]);
+    }
+
+    // Test left and inner joins against a supplemental price file keyed on oem+model.
+    #[test]
+    fn check_join() {
+        let dataset = Dataset::from_csv("cells_test.csv").unwrap();
+        let on = [Column::Oem, Column::Model];
+
+        let left = dataset.join("prices_test.csv", &on, JoinKind::Left).unwrap();
+        assert_eq!(left.len(), 5);
+        assert_eq!(left[0].supplemental.as_ref().unwrap()["price_usd"], "120");
+        assert!(left[1].supplemental.is_none());
+
+        let inner = dataset.join("prices_test.csv", &on, JoinKind::Inner).unwrap();
+        assert_eq!(inner.len(), 3);
+        assert_eq!(inner[2].cell.model.as_deref(), Some("GSmart G1305 Boston"));
+        assert_eq!(inner[2].supplemental.as_ref().unwrap()["price_usd"], "229");
+
+        assert!(dataset.join("prices_test.csv", &[Column::DisplayType], JoinKind::Left).is_err());
+    }
+
+    // Lookup source that knows one phone and counts how often it is asked.
+    struct FakeSource {
+        calls: std::cell::Cell<usize>,
+    }
+
+    impl LookupSource for FakeSource {
+        fn lookup(&self, _oem: &str, model: &str) -> Result<HashMap<Column, String>, CellError> {
+            self.calls.set(self.calls.get() + 1);
+            let mut values = HashMap::new();
+            if model == "nuvifone M10" {
+                values.insert(Column::BodyWeight, "158".to_string());
+                values.insert(Column::DisplaySize, "9.9".to_string());
+            }
+            Ok(values)
+        }
+    }
+
+    // Test that enrichment fills only missing fields and reuses the on-disk cache.
+    #[test]
+    fn check_enrichment_cache() {
+        let cache_file = std::env::temp_dir().join("cells_enrich_cache_test.csv");
+        let cache_file = cache_file.to_str().unwrap();
+        let _ = std::fs::remove_file(cache_file);
+
+        let mut cells = Cell::read_csv("cells_test.csv").unwrap();
+        let source = FakeSource { calls: std::cell::Cell::new(0) };
+        let mut enricher = Enricher::new(source, RateLimiter::new(Duration::ZERO), ResponseCache::open(cache_file).unwrap());
+
+        let filled = enricher.enrich(&mut cells, |cell| cell.oem.as_deref() == Some("Garmin-Asus")).unwrap();
+        assert_eq!(filled, 1);
+        assert_eq!(cells[1].body_weight, Some(158.0));
+        assert_eq!(cells[1].display_size, Some(3.5));
+
+        let source = FakeSource { calls: std::cell::Cell::new(0) };
+        let mut enricher = Enricher::new(source, RateLimiter::new(Duration::ZERO), ResponseCache::open(cache_file).unwrap());
+        let mut cells = Cell::read_csv("cells_test.csv").unwrap();
+        enricher.enrich(&mut cells, |cell| cell.oem.as_deref() == Some("Garmin-Asus")).unwrap();
+        assert_eq!(cells[1].body_weight, Some(158.0));
+        assert_eq!(enricher.source().calls.get(), 0);
+
+        std::fs::remove_file(cache_file).unwrap();
+    }
+
+    // Lookup source that is slow to answer, for the concurrent enrichment.
+    struct SlowSource {
+        // When each call started and how many were running at the busiest moment.
+        starts: Mutex<Vec<Instant>>,
+        running: AtomicUsize,
+        most_running: AtomicUsize,
+    }
+
+    impl LookupSource for SlowSource {
+        fn lookup(&self, oem: &str, model: &str) -> Result<HashMap<Column, String>, CellError> {
+            self.starts.lock().unwrap().push(Instant::now());
+            let running = self.running.fetch_add(1, Ordering::SeqCst) + 1;
+            self.most_running.fetch_max(running, Ordering::SeqCst);
+            std::thread::sleep(Duration::from_millis(60));
+            self.running.fetch_sub(1, Ordering::SeqCst);
+
+            if oem == "Broken" {
+                return Err(CellError::external("the source is down"));
+            }
+            Ok(HashMap::from([(Column::PlatformOs, format!("{} OS", model))]))
+        }
+    }
+
+    // Test that concurrent enrichment overlaps lookups and keeps the rate limit across workers, and that both kinds cache before failing.
+    #[test]
+    fn check_concurrent_enrichment() {
+        let cache_file = std::env::temp_dir().join("cells_enrich_concurrent_test.csv");
+        let cache_file = cache_file.to_str().unwrap();
+        let _ = std::fs::remove_file(cache_file);
+        let slow = || SlowSource { starts: Mutex::new(Vec::new()), running: AtomicUsize::new(0), most_running: AtomicUsize::new(0) };
+
+        let mut cells: Vec<Cell> = (0..6).map(|n| CellBuilder::new().oem("Acme").model(format!("A{}", n % 4)).build().unwrap()).collect();
+        let mut enricher = Enricher::new(slow(), RateLimiter::new(Duration::from_millis(10)), ResponseCache::open(cache_file).unwrap());
+        let begun = Instant::now();
+        assert_eq!(enricher.enrich_concurrently(&mut cells, |_| true, 4).unwrap(), 6);
+        assert_eq!(cells[5].platform_os.as_deref(), Some("A1 OS"));
+
+        let source = enricher.source();
+        let starts = source.starts.lock().unwrap();
+        assert_eq!(starts.len(), 4);
+        assert!(source.most_running.load(Ordering::SeqCst) > 1);
+        // The fourth call may only start three intervals after the first.
+        assert!(starts.iter().max().unwrap().duration_since(begun) >= Duration::from_millis(30));
+
+        // A failing lookup is returned, and the answers the other workers got are kept.
+        let mut cells: Vec<Cell> = ["Broken", "Acme", "Acme"].iter().zip(["B1", "A9", "A0"]).map(|(oem, model)| CellBuilder::new().oem(*oem).model(model).build().unwrap()).collect();
+        let mut enricher = Enricher::new(slow(), RateLimiter::new(Duration::ZERO), ResponseCache::open(cache_file).unwrap());
+        assert_eq!(enricher.enrich_concurrently(&mut cells, |_| true, 2).unwrap_err().to_string(), "the source is down");
+        assert_eq!(enricher.source().starts.lock().unwrap().len(), 2);
+        let cache = ResponseCache::open(cache_file).unwrap();
+        assert!(cache.get("Acme", "A9").is_some() && cache.get("Broken", "B1").is_none());
+
+        // One lookup at a time saves the answers before the failing one too.
+        std::fs::remove_file(cache_file).unwrap();
+        let mut cells: Vec<Cell> = ["Acme", "Broken", "Acme"].iter().zip(["A5", "B1", "A6"]).map(|(oem, model)| CellBuilder::new().oem(*oem).model(model).build().unwrap()).collect();
+        let mut enricher = Enricher::new(slow(), RateLimiter::new(Duration::ZERO), ResponseCache::open(cache_file).unwrap());
+        assert_eq!(enricher.enrich(&mut cells, |_| true).unwrap_err().to_string(), "the source is down");
+        let cache = ResponseCache::open(cache_file).unwrap();
+        assert!(cache.get("Acme", "A5").is_some() && cache.get("Broken", "B1").is_none() && cache.get("Acme", "A6").is_none());
+
+        std::fs::remove_file(cache_file).unwrap();
+    }
+
+    // Test that model aliases are applied before dedupe and to the other side of a join.
+    #[test]
+    fn check_model_aliases() {
+        let mut dataset = Dataset::from_csv("cells_test.csv").unwrap();
+        let aliases = ModelAliases::from_csv("model_aliases_test.csv").unwrap();
+        assert_eq!(aliases.canonical("nokia", "3310 (2017)"), "3310");
+        assert_eq!(aliases.canonical("Samsung", "3310 (2017)"), "3310 (2017)");
+
+        assert_eq!(dataset.apply_model_aliases(aliases), 1);
+        assert_eq!(dataset.cells()[3].model.as_deref(), Some("GSmart G1305 Boston"));
+        assert_eq!(dataset.dedupe(), 1);
+        assert_eq!(dataset.len(), 3);
+
+        let mut other = ModelAliases::new();
+        other.insert("", "Vega", "Vega Classic");
+        let mut dataset = Dataset::from_csv("cells_test.csv").unwrap();
+        dataset.apply_model_aliases(other);
+        let inner = dataset.join("prices_test.csv", &[Column::Oem, Column::Model], JoinKind::Inner).unwrap();
+        assert_eq!(inner[0].cell.model.as_deref(), Some("Vega Classic"));
+        assert_eq!(inner[0].supplemental.as_ref().unwrap()["price_usd"], "120");
+    }
+
+    // Test that stages can be disabled and that each stage counts the values it changed.
+    #[test]
+    fn check_normalization_stages() {
+        let mut normalizer = Normalizer::default();
+        Cell::read_csv_with("cells_test.csv", &mut normalizer).unwrap();
+        assert_eq!(normalizer.report(), vec![(Stage::Empty, 2), (Stage::Year, 3), (Stage::Numeric, 5)]);
+
+        let mut normalizer = Normalizer::new(vec![Stage::Numeric]);
+        let cells = Cell::read_csv_with("cells_test.csv", &mut normalizer).unwrap();
+        assert_eq!(cells[0].launch_announced, Some(1999));
+        assert_eq!(cells[1].launch_announced, None);
+        assert_eq!(cells[1].launch_status.as_deref(), Some("Discontinued"));
+        assert_eq!(cells[0].display_size, None);
+        assert_eq!(cells[0].platform_os.as_deref(), Some(""));
+        assert_eq!(cells[2].body_weight, Some(118.0));
+    }
+
+    // Test a full source -> filter -> derive -> sink run, and a run with an aggregate.
+    #[test]
+    fn check_pipeline() {
+        let out = std::env::temp_dir().join("cells_pipeline_test.json");
+        let out = out.to_str().unwrap();
+
+        let summary = Pipeline::new()
+            .source("cells_test.csv")
+            .filter(|cell| cell.launch_announced.is_some_and(|year| year > 2000))
+            .derive(Derive::device_class())
+            .sink(Sink::Json(out.to_string()))
+            .run()
+            .unwrap();
+        assert_eq!(summary, PipelineSummary { rows_read: 4, rows_kept: 2 });
+
+        let rows: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(out).unwrap()).unwrap();
+        assert_eq!(rows[0]["oem"], "Garmin-Asus");
+        assert_eq!(rows[1]["device_class"], "Smartphone");
+        assert_eq!(rows[1]["body_weight"], 118.0);
+
+        Pipeline::new()
+            .source("cells_test.csv")
+            .aggregate(CountBy::new(Column::Oem))
+            .sink(Sink::Json(out.to_string()))
+            .run()
+            .unwrap();
+        let results: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(out).unwrap()).unwrap();
+        assert_eq!(results["count_by_oem"]["Gigabyte"], "2");
+
+        std::fs::remove_file(out).unwrap();
+    }
+
+    // Test that the Parquet writer's pages and footer are laid out as parquet.thrift says, and the pipeline writes one.
+    #[test]
+    fn check_parquet_output() {
+        let columns = [("n".to_string(), ParquetType::Int32)];
+        let mut writer = ParquetWriter::new(Vec::new(), &columns).unwrap();
+        writer.write_row(&[Some(ParquetValue::Int32(7))]).unwrap();
+        writer.write_row(&[None]).unwrap();
+        assert_eq!(writer.write_row(&[Some(ParquetValue::Float(1.0))]).unwrap_err().kind(), std::io::ErrorKind::InvalidInput);
+        assert!(writer.write_row(&[None, None]).is_err());
+        let bytes = writer.finish().unwrap();
+
+        // The page header (a data page of 12 bytes: 2 values, PLAIN, RLE levels), then the
+        // levels as two runs of one, then the one value.
+        let page = [0x15, 0x00, 0x15, 0x18, 0x15, 0x18, 0x2c, 0x15, 0x04, 0x15, 0x00, 0x15, 0x06, 0x15, 0x06, 0x00, 0x00];
+        let data = [0x04, 0x00, 0x00, 0x00, 0x02, 0x01, 0x02, 0x00, 0x07, 0x00, 0x00, 0x00];
+        assert_eq!(&bytes[..4], b"PAR1");
+        assert_eq!(&bytes[4..4 + page.len()], &page);
+        assert_eq!(&bytes[4 + page.len()..4 + page.len() + data.len()], &data);
+        let footer = u32::from_le_bytes(bytes[bytes.len() - 8..bytes.len() - 4].try_into().unwrap()) as usize;
+        assert_eq!(bytes.len(), 4 + page.len() + data.len() + footer + 8);
+        assert!(bytes.ends_with(b"PAR1"));
+
+        let out = std::env::temp_dir().join("cells_pipeline_test.parquet");
+        let out = out.to_str().unwrap();
+        let summary = Pipeline::new().source("cells_test.csv").derive(Derive::device_class()).sink(Sink::Parquet(out.to_string())).run().unwrap();
+        assert_eq!(summary.rows_kept, 4);
+        let bytes = std::fs::read(out).unwrap();
+        assert!(bytes.starts_with(b"PAR1") && bytes.ends_with(b"PAR1"));
+        assert!(bytes.windows(b"GSmart G1305 Boston".len()).any(|window| window == b"GSmart G1305 Boston"));
+        assert!(bytes.windows(b"device_class".len()).any(|window| window == b"device_class"));
+        std::fs::remove_file(out).unwrap();
+    }
+
+    // Test parsing filter expressions and matching them numerically and as text.
+    #[test]
+    fn check_filter_expression() {
+        let cells = Cell::read_csv("cells_test.csv").unwrap();
+
+        let filter = Filter::parse("launch_announced >= 2010 && oem = gigabyte").unwrap();
+        assert_eq!(filter.conditions.len(), 2);
+        assert_eq!(filter.conditions[0].op, Op::Ge);
+        let kept: Vec<bool> = cells.iter().map(|cell| filter.matches(cell)).collect();
+        assert_eq!(kept, vec![false, false, true, false]);
+
+        let filter = Filter::parse("display_type ~ \"resistive\"").unwrap();
+        assert!(filter.matches(&cells[1]) && !filter.matches(&cells[2]));
+
+        assert!(Filter::parse("").unwrap().matches(&cells[0]));
+        assert!(Filter::parse("price > 10").is_err());
+        assert!(Filter::parse("oem Samsung").is_err());
+    }
+
+    // Test the table view and that a seeded sample is reproducible and in file order.
+    #[test]
+    fn check_table_and_sample() {
+        let dataset = Dataset::from_csv("cells_test.csv").unwrap();
+        let rows: Vec<&Cell> = dataset.cells().iter().take(2).collect();
+
+        let rendered = table::render_table(&rows, &[Column::Oem, Column::Model, Column::BodyWeight], &NumberFormat::default());
+        let lines: Vec<&str> = rendered.lines().collect();
+        assert_eq!(lines[0], "oem         | model        | body_weight");
+        assert_eq!(lines[2], "Benefon     | Vega         | 190");
+        assert_eq!(lines[3], "Garmin-Asus | nuvifone M10 |");
+        assert_eq!(table::truncate("GSmart G1305 Boston", 8), "GSmart …");
+
+        let first = dataset.sample(2, &mut Rng::new(7));
+        let second = dataset.sample(2, &mut Rng::new(7));
+        assert_eq!(first.len(), 2);
+        assert_eq!(first[0].model, second[0].model);
+        assert_eq!(first[1].model, second[1].model);
+        assert_eq!(dataset.sample(10, &mut Rng::new(7)).len(), 4);
+    }
+
+    // Test that queued edits survive reloading, report their diffs, and only reach the file on save.
+    #[test]
+    fn check_pending_edits() {
+        let file = std::env::temp_dir().join("cells_pending_test.csv");
+        let file = file.to_str().unwrap();
+        std::fs::copy("cells_test.csv", file).unwrap();
+        let _ = std::fs::remove_file(PendingEdits::journal_path(file));
+
+        let mut pending = PendingEdits::load(file).unwrap();
+        pending.push(Edit::Modify { index: 0, values: vec![(Column::Model, "Vega XL".to_string())] }).unwrap();
+        pending.push(Edit::Delete { index: 3 }).unwrap();
+        pending.push(Edit::Insert { index: 0, values: vec![(Column::Oem, "Nokia".to_string())] }).unwrap();
+        assert!(pending.push(Edit::Delete { index: 9 }).is_err());
+
+        let pending = PendingEdits::load(file).unwrap();
+        assert_eq!(pending.summary(), (1, 1, 1));
+        let diffs = pending.diffs().unwrap();
+        assert_eq!(diffs[0].changes, vec![FieldChange { column: Column::Model, before: "Vega".to_string(), after: "Vega XL".to_string() }]);
+        assert_eq!(diffs[1].changes[1].before, "GSmart");
+        assert_eq!(Cell::read_csv(file).unwrap().len(), 4);
+
+        let mut pending = pending;
+        pending.save(&Normalizer::default()).unwrap();
+        let cells = Cell::read_csv(file).unwrap();
+        assert_eq!(cells.len(), 4);
+        assert_eq!(cells[0].oem.as_deref(), Some("Nokia"));
+        assert_eq!(cells[1].model.as_deref(), Some("Vega XL"));
+        assert_eq!(cells[2].launch_status.as_deref(), Some("Discontinued"));
+        assert!(PendingEdits::load(file).unwrap().edits().is_empty());
+
+        std::fs::remove_file(file).unwrap();
+    }
+
+    // Test editing a record in the TUI: invalid input is rejected, valid input changes the dataset, can be undone and is saved.
+    #[test]
+    fn check_tui_edit_mode() {
+        let file = std::env::temp_dir().join("cells_tui_test.csv");
+        let file = file.to_str().unwrap();
+        std::fs::copy("cells_test.csv", file).unwrap();
+
+        let mut app = App::new(file, Dataset::from_csv(file).unwrap(), Normalizer::default());
+        let press = |app: &mut App, keys: &[Key]| {
+            for &key in keys {
+                app.handle(key).unwrap();
+            }
+        };
+        let edit = [Key::Down, Key::Enter, Key::Tab, Key::Tab, Key::Tab, Key::Tab, Key::Tab, Key::Backspace];
+
+        press(&mut app, &edit);
+        press(&mut app, &[Key::Char('x'), Key::Tab]);
+        assert!(app.render(80)[1].contains("body_weight needs a number"));
+
+        press(&mut app, &[Key::Backspace, Key::Char('1'), Key::Char('5'), Key::Char('8'), Key::Enter]);
+        assert!(app.render(80)[0].starts_with("Up/Down"));
+        assert_eq!(app.dataset().cells()[1].body_weight, Some(158.0));
+        assert!(app.render(200)[5].starts_with("> Garmin-Asus"));
+
+        press(&mut app, &[Key::Char('q')]);
+        assert!(!app.should_quit());
+        press(&mut app, &[Key::Char('u')]);
+        assert_eq!(app.dataset().cells()[1].body_weight, None);
+        assert_eq!(app.unsaved(), 0);
+
+        press(&mut app, &edit[1..]);
+        press(&mut app, &[Key::Char('9'), Key::Char('0'), Key::Enter, Key::Char('s')]);
+        assert_eq!(app.unsaved(), 0);
+        assert_eq!(Cell::read_csv(file).unwrap()[1].body_weight, Some(90.0));
+        press(&mut app, &[Key::Char('q')]);
+        assert!(app.should_quit());
+
+        std::fs::remove_file(file).unwrap();
+    }
+
+    // Test that the table viewer keeps oem and model frozen while scrolling and hiding columns.
+    #[test]
+    fn check_table_viewer() {
+        let dataset = Dataset::from_csv("cells_test.csv").unwrap();
+        let cells: Vec<&Cell> = dataset.cells().iter().collect();
+        let columns = [Column::LaunchAnnounced, Column::Model, Column::BodyWeight, Column::Oem, Column::DisplaySize];
+        let mut viewer = TableViewer::from_cells(&cells, &columns, &NumberFormat::default());
+
+        // OEM and model move to the front; at 60 columns only one more column fits.
+        let lines = viewer.render(60);
+        assert!(lines[2].starts_with("  oem"));
+        assert!(lines[2].contains("| launch_announced"));
+        assert!(!lines[2].contains("body_weight"));
+
+        // Scrolling right keeps the frozen columns and brings the selected column into view.
+        viewer.handle(Key::Right).unwrap();
+        viewer.handle(Key::Right).unwrap();
+        let lines = viewer.render(60);
+        assert!(lines[2].starts_with("  oem"));
+        assert!(lines[2].contains("display_size"));
+        assert!(!lines[2].contains("launch_announced"));
+
+        // Hiding the selected column selects its neighbour; frozen columns can't be hidden.
+        viewer.handle(Key::Char('h')).unwrap();
+        assert!(viewer.render(200)[1].contains("column body_weight  1 hidden"));
+        viewer.handle(Key::Char('h')).unwrap();
+        viewer.handle(Key::Char('h')).unwrap();
+        assert!(viewer.render(200)[1].contains("Cannot hide"));
+        assert!(viewer.render(200)[2].starts_with("  oem"));
+
+        viewer.handle(Key::Char('a')).unwrap();
+        viewer.handle(Key::PageDown).unwrap();
+        let lines = viewer.render(200);
+        assert!(lines[1].starts_with("row 4 of 4  column launch_announced  0 hidden"));
+        assert!(lines.last().unwrap().starts_with("> Gigabyte"));
+    }
+
+    // Test LaTeX escaping and the cells and summary tables.
+    #[test]
+    fn check_latex_export() {
+        assert_eq!(latex::escape("50% of R&D_cost ~ $3"), "50\\% of R\\&D\\_cost \\textasciitilde{} \\$3");
+
+        let mut dataset = Dataset::from_csv("cells_test.csv").unwrap();
+        dataset.retain(|cell| Filter::parse("oem = Gigabyte").unwrap().matches(cell));
+        let table = latex::cells_table(dataset.cells(), &[Column::Model, Column::LaunchAnnounced], Some("Gigabyte phones"));
+        assert_eq!(
+            table,
+            "\\begin{table}[ht]\n\\centering\n\\begin{tabular}{lr}\n\\toprule\nmodel & launch\\_announced \\\\\n\\midrule\n\
+             GSmart G1305 Boston & 2010 \\\\\nGSmart &  \\\\\n\\bottomrule\n\\end{tabular}\n\\caption{Gigabyte phones}\n\\end{table}\n"
+        );
+
+        let summary = latex::summary_table(&dataset.view(), &NumberFormat::default(), 2009, None);
+        assert!(summary.contains("Phones & 2 \\\\\n"));
+        assert!(summary.contains("Year with most launches after 2009 & 2010 \\\\\n"));
+        assert!(summary.contains("Most common OEM & Gigabyte \\\\\n"));
+        assert!(!summary.contains("\\caption"));
+    }
+
+    // Test the ASCII bar chart's scaling and shares, with and without a given total.
+    #[test]
+    fn check_bar_chart() {
+        let cells = Cell::read_csv("cells_test.csv").unwrap();
+        let years: Vec<(String, usize)> = Cell::launches_per_year(&DatasetView::of(&cells)).into_iter().map(|(year, count)| (year.to_string(), count)).collect();
+        assert_eq!(chart::bar_chart(&years, None, 10), "1999 | #####      1 (33.3%)\n2010 | ########## 2 (66.7%)\n");
+
+        assert_eq!(Cell::oem_counts(&DatasetView::of(&cells)), vec![("Gigabyte".to_string(), 2), ("Benefon".to_string(), 1), ("Garmin-Asus".to_string(), 1)]);
+        let top: Vec<(String, usize)> = Cell::oem_counts(&DatasetView::of(&cells)).into_iter().take(1).collect();
+        assert_eq!(chart::bar_chart(&top, Some(cells.len()), 4), "Gigabyte | #### 2 (50.0%)\n");
+        assert_eq!(chart::bar_chart(&[], None, 10), "");
+    }
+
+    // Test sparklines and the per-year launch counts of each OEM they are drawn from.
+    #[test]
+    fn check_oem_sparklines() {
+        assert_eq!(chart::sparkline(&[0, 1, 2, 4, 8, 0]), " ▁▂▄█ ");
+        assert_eq!(chart::sparkline(&[]), "");
+
+        let cells = Cell::read_csv("cells_test.csv").unwrap();
+        let (years, pivot) = Cell::oem_year_pivot(&DatasetView::of(&cells));
+        assert_eq!(years, (1999..=2010).collect::<Vec<u32>>());
+        assert_eq!(pivot.len(), 3);
+        assert_eq!(pivot[0].0, "Gigabyte");
+        assert_eq!(chart::sparkline(&pivot[0].1), "           █");
+        assert_eq!(chart::sparkline(&pivot[1].1), "█           ");
+
+        // A stray year far from the rest only adds its own column.
+        let mut cells = cells;
+        cells[1].launch_announced = Some(400_000_000);
+        let (years, pivot) = Cell::oem_year_pivot(&DatasetView::of(&cells));
+        assert_eq!(years, vec![1999, 2010, 400_000_000]);
+        assert_eq!(pivot.iter().map(|(oem, counts)| (oem.as_str(), counts.clone())).collect::<Vec<_>>(), vec![("Gigabyte", vec![0, 1, 0]), ("Benefon", vec![1, 0, 0]), ("Garmin-Asus", vec![0, 0, 1])]);
+        let section = OemYearPivot { years, oems: pivot.into_iter().map(|(oem, launches)| OemYears { oem, launches }).collect() };
+        assert!(section.to_string().starts_with("OEM launches per year (1999-400000000, only the years with launches):\n"));
+    }
+
+    // Test the SVG and terminal heatmaps of launches per OEM and year.
+    #[test]
+    fn check_heatmap() {
+        let years = vec![2019, 2020];
+        let rows = vec![("A&B".to_string(), vec![0, 4]), ("C".to_string(), vec![2, 1])];
+
+        let svg = heatmap::render_svg(&years, &rows);
+        assert!(svg.starts_with("<svg "));
+        assert!(svg.ends_with("</svg>\n"));
+        assert_eq!(svg.matches("<rect ").count(), 4);
+        // Empty years are grey, the largest count gets the darkest colour.
+        assert!(svg.contains("fill=\"#f0f0f0\"><title>A&amp;B 2019: 0</title>"));
+        assert!(svg.contains("fill=\"#bd0026\"><title>A&amp;B 2020: 4</title>"));
+
+        let grid = heatmap::render_terminal(&years, &rows);
+        assert!(grid.starts_with("    19 20\nA&B "));
+        assert!(grid.ends_with("darkest = 4 launches\n"));
+    }
+
+    // Test counting phones per country and placing them on the SVG world map.
+    #[test]
+    fn check_world_map() {
+        let cells = Cell::read_csv("cells_test.csv").unwrap();
+        let mut countries = CountryMap::new();
+        countries.insert("Gigabyte", "Atlantis");
+        let counts = country::devices_per_country(&DatasetView::of(&cells), &countries);
+        assert_eq!(counts, vec![("Atlantis".to_string(), 2), ("Finland".to_string(), 1), ("Taiwan".to_string(), 1)]);
+
+        let svg = worldmap::render_svg(&counts);
+        assert_eq!(svg.matches("<circle ").count(), 2);
+        // Finland sits at 26E 64N on the 720 x 360 map.
+        assert!(svg.contains("cx=\"412.0\" cy=\"52.0\""));
+        assert!(svg.contains("Atlantis: 2 (not on the map)"));
+    }
+
+    // Test that disabled timings record nothing and enabled ones report each stage's rate.
+    #[test]
+    fn check_timings() {
+        let mut disabled = Timings::new(false);
+        assert_eq!(disabled.time("stage", 10, || 42), 42);
+        assert_eq!(disabled.report(), "");
+
+        let mut timings = Timings::new(true);
+        timings.record("load", Duration::from_millis(500), 1000);
+        let cells = Cell::read_csv("cells_test.csv").unwrap();
+        let oem = timings.time("most_common_oem", cells.len(), || Cell::most_common_oem(&DatasetView::of(&cells)));
+        assert_eq!(oem.as_deref(), Some("Gigabyte"));
+
+        let report = timings.report();
+        let lines: Vec<&str> = report.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[1], "load               500.000 ms  2000 rows/s");
+        assert!(lines[2].starts_with("most_common_oem "));
+        assert!(lines[3].starts_with("total "));
+    }
+
+    // Test the memory estimate of loaded cells, with shared strings counted once.
+    #[test]
+    fn check_memory_usage() {
+        let cells = Cell::read_csv("cells_test.csv").unwrap();
+        let usage = MemoryUsage::of(&cells, cells.len());
+
+        let oem = &usage.columns[0];
+        assert_eq!(oem.column, Column::Oem);
+        assert_eq!(oem.inline, 4 * std::mem::size_of::<Option<Arc<str>>>());
+        // "Benefon" + "Garmin-Asus" + "Gigabyte", shared by two cells, each with two counts.
+        assert_eq!(oem.heap, 7 + 11 + 8 + 3 * 2 * std::mem::size_of::<usize>());
+        // Boxed text is two words and None takes no extra space, so a cell stays compact.
+        assert_eq!(std::mem::size_of::<Option<Box<str>>>(), 2 * std::mem::size_of::<usize>());
+        // The source shares its file name, so it only adds its own two words and a line, and
+        // the parsed sensors and the tags add a vector each, the launch and release dates and
+        // the cover display size a word each, and the cover display resolution two.
+        assert!(std::mem::size_of::<Cell>() <= 304);
+        assert_eq!(usage.columns[2].heap, 0);
+
+        let heap: usize = usage.columns.iter().map(|bytes| bytes.heap).sum();
+        assert_eq!(usage.total, 4 * std::mem::size_of::<Cell>() + heap);
+        assert!(usage.to_string().ends_with(&format!("{}\n", usage.total)));
+
+        // Spare capacity counts as overhead.
+        assert!(MemoryUsage::of(&cells, 10).overhead > usage.overhead);
+    }
+
+    // Test parsing memory budgets, and that the spilling sorter and group-by match in-memory results.
+    #[test]
+    fn check_external_algorithms() {
+        assert_eq!(external::parse_bytes("64M").unwrap(), 64 << 20);
+        assert_eq!(external::parse_bytes("512k").unwrap(), 512 << 10);
+        assert_eq!(external::parse_bytes("100").unwrap(), 100);
+        assert!(external::parse_bytes("lots").is_err());
+        assert!(external::parse_bytes("0").is_err());
+        assert!(external::parse_bytes(&format!("{}G", usize::MAX)).is_err());
+
+        // A budget of 16 bytes holds 4 values, so 10 values spill into 3 runs.
+        let values = [5.0, 1.0, 9.0, 3.0, 7.0, 2.0, 8.0, 4.0, 6.0, 10.0];
+        let mut spilled = ExternalSorter::new(16);
+        let mut in_memory = ExternalSorter::new(usize::MAX);
+        for value in values {
+            spilled.push(value).unwrap();
+            in_memory.push(value).unwrap();
+        }
+        assert_eq!(spilled.spilled_runs(), 2);
+        assert_eq!(in_memory.spilled_runs(), 0);
+        assert_eq!(spilled.quantiles(&[0.0, 0.5, 1.0]).unwrap(), vec![Some(1.0), Some(5.5), Some(10.0)]);
+        assert_eq!(in_memory.quantiles(&[0.0, 0.5, 1.0]).unwrap(), vec![Some(1.0), Some(5.5), Some(10.0)]);
+        assert_eq!(ExternalSorter::new(16).quantiles(&[0.5]).unwrap(), vec![None]);
+
+        // The median matches the in-memory analysis on the test file.
+        let cells = Cell::read_csv("cells_test.csv").unwrap();
+        let mut weights = ExternalSorter::new(4);
+        for weight in cells.iter().filter_map(|cell| cell.body_weight) {
+            weights.push(weight).unwrap();
+        }
+        assert_eq!(weights.quantiles(&[0.5]).unwrap(), vec![Cell::median_body_weight(&DatasetView::of(&cells))]);
+
+        let mut group_by = SpillGroupBy::new(1);
+        for oem in ["b", "a", "b", "c", "a", "b"] {
+            group_by.add(oem).unwrap();
+        }
+        assert_eq!(group_by.spilled_runs(), 6);
+        let mut counts = Vec::new();
+        group_by.finish(|key, count| counts.push((key.to_string(), count))).unwrap();
+        assert_eq!(counts, vec![("a".to_string(), 2), ("b".to_string(), 3), ("c".to_string(), 1)]);
+
+        // Far more runs than are merged at once are merged in passes, never all open together.
+        let mut many = ExternalSorter::new(4);
+        let mut group_by = SpillGroupBy::new(1);
+        for value in (0..3000).rev() {
+            many.push(value as f32).unwrap();
+            group_by.add(&format!("{:04}", value % 1500)).unwrap();
+        }
+        assert_eq!(many.spilled_runs(), 3000);
+        assert_eq!(many.quantiles(&[0.0, 0.5, 1.0]).unwrap(), vec![Some(0.0), Some(1499.5), Some(2999.0)]);
+        assert!(many.spilled_runs() <= external::MERGE_FAN_IN);
+        let mut counts = Vec::new();
+        group_by.finish(|key, count| counts.push((key.to_string(), count))).unwrap();
+        assert_eq!(counts.len(), 1500);
+        assert!(counts.iter().all(|&(_, count)| count == 2) && counts[0].0 == "0000");
+    }
+
+    // Test that the t-digest is exact for few values and within 1% for 100k shuffled ones.
+    #[test]
+    fn check_tdigest() {
+        let mut empty = TDigest::new(100.0);
+        assert_eq!(empty.quantile(0.5), None);
+
+        // Few values stay exact.
+        let mut small = TDigest::new(100.0);
+        for value in [5.0, 1.0, 9.0, 3.0, 7.0, 2.0, 8.0, 4.0, 6.0, 10.0] {
+            small.push(value);
+        }
+        assert_eq!(small.quantile(0.5), Some(5.5));
+        assert_eq!(small.quantile(0.0), Some(1.0));
+        assert_eq!(small.quantile(1.0), Some(10.0));
+
+        // 100k values in shuffled order are summarized by a few hundred centroids at most.
+        let mut rng = Rng::new(7);
+        let mut values: Vec<f32> = (1..=100_000).map(|value| value as f32).collect();
+        for index in (1..values.len()).rev() {
+            values.swap(index, rng.below(index + 1));
+        }
+        let mut digest = TDigest::new(100.0);
+        for &value in &values {
+            digest.push(value);
+        }
+        assert_eq!(digest.len(), 100_000);
+        assert!(digest.centroids() <= 200);
+        for (q, expected) in [(0.5, 50_000.5), (0.9, 90_000.0), (0.99, 99_000.0)] {
+            let estimate = digest.quantile(q).unwrap();
+            assert!((estimate - expected).abs() / expected < 0.01, "q{} = {}", q, estimate);
+        }
+    }
+
+    // Test that HyperLogLog counts distinct values exactly when few and within 3% when many.
+    #[test]
+    fn check_hyperloglog() {
+        let mut empty = HyperLogLog::new();
+        assert_eq!(empty.count(), 0);
+        empty.add("Nokia");
+        empty.add("Nokia");
+        assert_eq!(empty.count(), 1);
+
+        let cells = Cell::read_csv("cells_test.csv").unwrap();
+        let mut oems = HyperLogLog::new();
+        for oem in cells.iter().filter_map(|cell| cell.oem.as_ref()) {
+            oems.add(oem);
+        }
+        assert_eq!(oems.count(), 3);
+
+        // Large counts land within a few percent, and repeats don't change the estimate.
+        let mut large = HyperLogLog::new();
+        for round in 0..2 {
+            for value in 0..200_000u32 {
+                large.add(value);
+            }
+            let estimate = large.count() as f64;
+            assert!((estimate - 200_000.0).abs() / 200_000.0 < 0.03, "round {}: {}", round, estimate);
+        }
+    }
+
+    // Test the Bloom filter's false positive rate, and that the join key filter doesn't change joins.
+    #[test]
+    fn check_bloom_filter() {
+        let mut filter = BloomFilter::new(1000, 0.01);
+        for value in 0..1000u32 {
+            filter.insert(value);
+        }
+        assert!((0..1000u32).all(|value| filter.may_contain(value)));
+        let false_positives = (1000..11_000u32).filter(|&value| filter.may_contain(value)).count();
+        assert!(false_positives < 300, "{} false positives", false_positives);
+
+        let mut dataset = Dataset::from_csv("cells_test.csv").unwrap();
+        let unfiltered: Vec<String> = dataset
+            .join("prices_test.csv", &[Column::Oem, Column::Model], JoinKind::Inner)
+            .unwrap()
+            .iter()
+            .map(|row| format!("{:?} {:?}", row.cell.model, row.supplemental.as_ref().map(|values| values["price_usd"].clone())))
+            .collect();
+
+        dataset.build_key_filter(0.01);
+        assert!(dataset.contains_model("Benefon", " Vega "));
+        assert!(!dataset.contains_model("Benefon", "Vega 2"));
+        assert!(!dataset.contains_model("Nokia", "3310"));
+
+        // The filter only skips rows that can't match, so the join result is unchanged.
+        let filtered: Vec<String> = dataset
+            .join("prices_test.csv", &[Column::Model, Column::Oem], JoinKind::Inner)
+            .unwrap()
+            .iter()
+            .map(|row| format!("{:?} {:?}", row.cell.model, row.supplemental.as_ref().map(|values| values["price_usd"].clone())))
+            .collect();
+        assert_eq!(filtered, unfiltered);
+        assert_eq!(filtered.len(), 3);
+    }
+
+    // Test breaking the rows of each OEM down by launch status category.
+    #[test]
+    fn check_breakdown() {
+        let mut cells = Cell::read_csv("cells_test.csv").unwrap();
+        cells[2].launch_status = Some("2010".into());
+
+        let breakdown = Breakdown::of(&DatasetView::of(&cells), Column::Oem, Column::LaunchStatus);
+        assert_eq!(cells[2].status_category(), Some("Available"));
+        assert_eq!(breakdown.splits, vec!["Discontinued".to_string(), "Available".to_string(), "Cancelled".to_string()]);
+        assert_eq!(breakdown.rows[0], ("Gigabyte".to_string(), vec![0, 1, 1], 2));
+        assert_eq!(breakdown.rows[1], ("Benefon".to_string(), vec![1, 0, 0], 1));
+
+        let table = breakdown.render();
+        assert!(table.starts_with("oem         | Discontinued | Available | Cancelled | total\n"));
+        assert!(table.contains("Gigabyte    | 0            | 1         | 1         | 2\n"));
+    }
+
+    // Test ranking the years after a cutoff by launches, and the --after-year option.
+    #[test]
+    fn check_launch_cutoff_year() {
+        let cells = Cell::read_csv("cells_test.csv").unwrap();
+        assert_eq!(Cell::years_ranked_by_launches_after(&DatasetView::of(&cells), 1990), vec![(2010, 2), (1999, 1)]);
+        assert_eq!(Cell::years_ranked_by_launches_after(&DatasetView::of(&cells), 1999), vec![(2010, 2)]);
+        assert_eq!(Cell::year_most_phones_launched__after_year(&DatasetView::of(&cells), 1990), Some(2010));
+        assert_eq!(Cell::year_most_phones_launched__after_year(&DatasetView::of(&cells), 2010), None);
+
+        let args = Cli::try_parse_from(["cells", "--timings", "--after-year", "2005"]).unwrap();
+        assert!(args.timings);
+        assert_eq!(args.after_year, 2005);
+        assert!(args.command.is_none());
+        assert_eq!(Cli::try_parse_from(["cells"]).unwrap().after_year, crate::DEFAULT_AFTER_YEAR);
+        assert!(Cli::try_parse_from(["cells", "--after-year", "soon"]).is_err());
+    }
+
+    // Test the lightest and heaviest phone of each OEM.
+    #[test]
+    fn check_weight_extremes_per_oem() {
+        let mut cells = Cell::read_csv("cells_test.csv").unwrap();
+        cells[3].body_weight = Some(150.0);
+
+        let extremes = Cell::weight_extremes_per_oem(&DatasetView::of(&cells));
+        assert_eq!(extremes.len(), 2);
+        assert_eq!(extremes[0], ("Benefon".to_string(), ("Vega".to_string(), 190.0), ("Vega".to_string(), 190.0)));
+        assert_eq!(extremes[1], ("Gigabyte".to_string(), ("GSmart G1305 Boston".to_string(), 118.0), ("GSmart".to_string(), 150.0)));
+    }
+
+    // Test the mean display size per OEM, and a streaming mean grouped by OEM.
+    #[test]
+    fn check_avg_display_size_per_oem() {
+        let mut cells = Cell::read_csv("cells_test.csv").unwrap();
+        cells[0].display_size = None;
+
+        // Benefon has no display size, so it isn't ranked.
+        assert_eq!(
+            analysis::avg_display_size_per_oem(&DatasetView::of(&cells)),
+            vec![("Garmin-Asus".to_string(), 3.5, 1), ("Gigabyte".to_string(), 3.0, 2)]
+        );
+
+        let mut groups = GroupBy::new(Column::Oem, || MeanOf::new(Column::BodyWeight));
+        for cell in &cells {
+            groups.update(cell);
+        }
+        assert_eq!(groups.name(), "mean_body_weight_by_oem");
+        assert_eq!(groups.finish(&NumberFormat::default())[0], ("Benefon.mean".to_string(), "190".to_string()));
+        assert_eq!(groups.finish(&NumberFormat { trim_zeros: false, ..NumberFormat::default() })[0].1, "190.00");
+    }
+
+    // Test classifying display types into technology families, and the families per year.
+    #[test]
+    fn check_display_families() {
+        let family = DisplayFamily::from_display_type;
+        assert_eq!(family("Super AMOLED capacitive touchscreen, 16M colors"), DisplayFamily::Amoled);
+        assert_eq!(family("P-OLED capacitive touchscreen, 16M colors"), DisplayFamily::Oled);
+        assert_eq!(family("LTPS IPS LCD capacitive touchscreen"), DisplayFamily::Lcd);
+        assert_eq!(family("CSTN, 65K colors"), DisplayFamily::Lcd);
+        assert_eq!(family("Monochrome graphic"), DisplayFamily::Monochrome);
+        // Only a colour count: the technology isn't known.
+        assert_eq!(family("65K colors"), DisplayFamily::Other);
+
+        let cells = Cell::read_csv("cells_test.csv").unwrap();
+        assert_eq!(
+            analysis::display_families_per_year(&DatasetView::of(&cells)),
+            vec![(1999, vec![(DisplayFamily::Monochrome, 1)]), (2010, vec![(DisplayFamily::Lcd, 2)])]
+        );
+    }
+
+    // Test reading dual SIM and eSIM from body_sim, and their shares per year and per OEM.
+    #[test]
+    fn check_sim_share() {
+        let sim = |body_sim: &str| {
+            let info = SimInfo::parse(body_sim);
+            (info.dual_sim, info.esim)
+        };
+        assert_eq!(sim("Mini-SIM"), (false, false));
+        assert_eq!(sim("Single SIM (Nano-SIM) or Hybrid Dual SIM (Nano-SIM, dual stand-by)"), (true, false));
+        assert_eq!(sim("Nano-SIM card & eSIM"), (false, true));
+
+        let mut cells = Cell::read_csv("cells_test.csv").unwrap();
+        cells[2].body_sim = Some("Dual SIM (Nano-SIM/eSIM, dual stand-by)".into());
+        cells[1].body_sim = None;
+
+        let per_year = analysis::sim_share_per_year(&DatasetView::of(&cells));
+        assert_eq!(per_year.len(), 2);
+        assert_eq!(per_year[1], (2010, analysis::SimShare { phones: 1, dual_sim: 1, esim: 1 }));
+        assert_eq!(per_year[0].1.dual_sim_share(), 0.0);
+
+        let per_oem = analysis::sim_share_per_oem(&DatasetView::of(&cells));
+        assert_eq!(per_oem.iter().map(|(oem, _)| oem.as_str()).collect::<Vec<&str>>(), vec!["Benefon", "Gigabyte"]);
+        assert_eq!(per_oem[1].1.dual_sim_share(), 0.5);
+        assert_eq!(per_oem[1].1.esim_share(), 0.5);
+    }
+
+    // Test parsing body dimensions, and the thinnest phones and mean thickness per year.
+    #[test]
+    fn check_thinnest_phones() {
+        assert_eq!(Dimensions::parse("159.8 x 76.1 x 8.1 mm (6.29 x 3.00 x 0.32 in)"), Some(Dimensions { height: 159.8, width: 76.1, depth: 8.1 }));
+        assert_eq!(Dimensions::parse("140 x 50 x 25 mm, 150 cc (5.51 x 1.97 x 0.98 in)").map(|d| d.depth), Some(25.0));
+        assert_eq!(Dimensions::parse("Unfolded: 172 x 72 x 6.9 mmFolded: 94 x 72 x 14mm").map(|d| d.depth), Some(14.0));
+        assert_eq!(Dimensions::parse("7.9 mm thickness"), None);
+        assert_eq!(Dimensions::parse("V1"), None);
+
+        let mut cells = Cell::read_csv("cells_test.csv").unwrap();
+        cells[0].body_dimensions = Some("145 x 56 x 23 mm".into());
+        cells[1].body_dimensions = Some("115 x 59 x 16 mm".into());
+        cells[2].body_dimensions = Some("112 x 58 x 12 mm".into());
+        cells[3].body_dimensions = None;
+
+        let thinnest = analysis::thinnest_phones(&DatasetView::of(&cells), 2);
+        assert_eq!(thinnest.len(), 2);
+        assert_eq!(thinnest[0], ("Gigabyte".to_string(), "GSmart G1305 Boston".to_string(), 12.0));
+        assert_eq!(thinnest[1].2, 16.0);
+
+        assert_eq!(analysis::average_thickness_per_year(&DatasetView::of(&cells)), vec![(1999, 23.0, 1), (2010, 14.0, 2)]);
+    }
+
+    // Test aspect ratios and the estimated screen-to-body ratio, per phone and per year.
+    #[test]
+    fn check_screen_to_body_ratio() {
+        assert_eq!(dimensions::aspect_ratio("1080 x 2400 pixels, 20:9 ratio (~401 ppi density)"), Some(2400.0 / 1080.0));
+        assert_eq!(dimensions::aspect_ratio("128 x 128 pixels, 1:1 ratio"), Some(1.0));
+        assert_eq!(dimensions::aspect_ratio("65K colors"), None);
+
+        let body = Dimensions { height: 159.8, width: 76.1, depth: 8.1 };
+        let ratio = body.screen_to_body_ratio(6.5, 2400.0 / 1080.0).unwrap();
+        assert!((ratio - 0.84).abs() < 0.01, "{}", ratio);
+        assert_eq!(body.screen_to_body_ratio(20.0, 1.0), None);
+
+        let mut cells = Cell::read_csv("cells_test.csv").unwrap();
+        cells[2].body_dimensions = Some("112 x 58 x 12 mm".into());
+        cells[2].display_resolution = Some("240 x 320 pixels".into());
+        assert!(cells[2].screen_to_body_ratio().is_some());
+
+        let per_year = analysis::screen_to_body_ratio_per_year(&DatasetView::of(&cells));
+        assert_eq!(per_year.len(), 1);
+        assert_eq!((per_year[0].0, per_year[0].2), (2010, 1));
+        assert_eq!(Derive::screen_to_body_ratio().name(), "screen_to_body_ratio");
+    }
+
+    // Test parsing the subcommands and their options, and rejecting invalid ones.
+    #[test]
+    fn check_cli_subcommands() {
+        let args = Cli::try_parse_from(["cells", "query", "oem = Gigabyte", "-n", "5", "--columns", "oem,model", "--file", "cells_test.csv"]).unwrap();
+        assert_eq!(args.file, "cells_test.csv");
+        match args.command {
+            Some(Command::Query(query)) => {
+                assert_eq!(query.filter, "oem = Gigabyte");
+                assert_eq!(query.limit, Some(5));
+                assert_eq!(query.columns.columns, vec![Column::Oem, Column::Model]);
+            }
+            other => panic!("expected a query, got {:?}", other),
+        }
+
+        let args = Cli::try_parse_from(["cells", "--file", "phones.csv", "show", "3"]).unwrap();
+        assert_eq!(args.file, "phones.csv");
+        assert!(matches!(args.command, Some(Command::Show(show)) if show.index == 3));
+
+        let args = Cli::try_parse_from(["cells", "crud", "modify", "2", "--set", "oem=Nokia", "--set", "body_weight=80"]).unwrap();
+        match args.command {
+            Some(Command::Crud { command: CrudCommand::Modify { index, values } }) => {
+                assert_eq!(index, 2);
+                assert_eq!(values, vec![(Column::Oem, "Nokia".to_string()), (Column::BodyWeight, "80".to_string())]);
+            }
+            other => panic!("expected a modify, got {:?}", other),
+        }
+
+        let args = Cli::try_parse_from(["cells", "breakdown", "--split", "status"]).unwrap();
+        assert!(matches!(args.command, Some(Command::Breakdown(breakdown)) if breakdown.by == Column::Oem && breakdown.split == Column::LaunchStatus));
+        let args = Cli::try_parse_from(["cells", "report", "--only", "most_common_oem,thinnest_phones"]).unwrap();
+        assert!(matches!(args.command, Some(Command::Report(report)) if report.only == ["most_common_oem", "thinnest_phones"]));
+
+        assert!(Cli::try_parse_from(["cells", "crud", "modify", "2", "--set", "colour=red"]).is_err());
+        assert!(Cli::try_parse_from(["cells", "stats", "--max-memory", "1M", "--approximate"]).is_err());
+        assert!(Cli::try_parse_from(["cells", "show"]).is_err());
+    }
+
+    // Test resolution classes and the typical phone of each year.
+    #[test]
+    fn check_profile_per_year() {
+        assert_eq!(resolution::pixels("1080 x 2340 pixels, 19.5:9 ratio (~403 ppi density)"), Some((1080, 2340)));
+        assert_eq!(ResolutionClass::from_display_resolution("128 x 160 pixels"), Some(ResolutionClass::Low));
+        assert_eq!(ResolutionClass::from_display_resolution("1440 x 3200 pixels"), Some(ResolutionClass::QuadHd));
+        assert_eq!(ResolutionClass::from_display_resolution("6 lines"), None);
+
+        let mut cells = Cell::read_csv("cells_test.csv").unwrap();
+        cells[1].body_weight = Some(130.0);
+        cells[1].display_resolution = Some("320 x 480 pixels".into());
+        cells[2].display_resolution = Some("240 x 320 pixels".into());
+        cells[1].features_sensors = Some("Accelerometer, proximity, compass".into());
+        cells[2].features_sensors = Some("Accelerometer".into());
+
+        let profiles = analysis::profile_per_year(&DatasetView::of(&cells));
+        assert_eq!(profiles.iter().map(|profile| profile.year).collect::<Vec<u32>>(), vec![1999, 2010]);
+        assert_eq!(
+            profiles[1],
+            analysis::PhoneProfile {
+                year: 2010,
+                phones: 2,
+                body_weight: Some(124.0),
+                display_size: Some(3.35),
+                resolution: Some(ResolutionClass::Sd),
+                sensors: Some(2.0),
+            }
+        );
+        assert_eq!(profiles[0].resolution, None);
+    }
+
+    // Test that cells round-trip through JSON and that missing fields are None.
+    #[test]
+    fn check_serde_round_trip() {
+        let cells = Cell::read_csv("cells_test.csv").unwrap();
+
+        let json = serde_json::to_string(&cells[2]).unwrap();
+        assert!(json.starts_with(r#"{"oem":"Gigabyte","model":"GSmart G1305 Boston","launch_announced":2010,"#));
+        assert_eq!(serde_json::from_str::<Cell>(&json).unwrap(), cells[2]);
+
+        let partial: Cell = serde_json::from_str(r#"{"oem": "Nokia", "body_weight": 80.5}"#).unwrap();
+        assert_eq!((partial.oem.as_deref(), partial.body_weight, partial.model), (Some("Nokia"), Some(80.5), None));
+
+        let out = std::env::temp_dir().join("cells_serde_test.json");
+        Cell::write_json(&cells, out.to_str().unwrap()).unwrap();
+        assert_eq!(Cell::read_json(out.to_str().unwrap()).unwrap(), cells);
+        std::fs::remove_file(out).unwrap();
+    }
+
+    // Test the launches of each decade and the OEM that led it.
+    #[test]
+    fn check_launches_per_decade() {
+        let mut cells = Cell::read_csv("cells_test.csv").unwrap();
+        cells[3].launch_announced = Some(2021);
+        cells.push(Cell { oem: Some("Alcatel".into()), launch_announced: Some(2014), ..Cell::new() });
+
+        let decades = analysis::launches_per_decade(&DatasetView::of(&cells));
+        assert_eq!(decades.iter().map(|decade| decade.label()).collect::<Vec<String>>(), vec!["1990s", "2010s", "2020s"]);
+        assert_eq!(decades[0], analysis::DecadeSummary { decade: 1990, launches: 1, leader: Some(("Benefon".to_string(), 1)) });
+        // Alcatel, Garmin-Asus and Gigabyte have one launch each in the 2010s.
+        assert_eq!(decades[1].launches, 3);
+        assert_eq!(decades[1].leader, Some(("Alcatel".to_string(), 1)));
+        assert_eq!(decades[2].leader, Some(("Gigabyte".to_string(), 1)));
+    }
+
+    // Test that views filter a dataset's cells without copying them, and that analyses run on them.
+    #[test]
+    fn check_dataset_view() {
+        let dataset = Dataset::from_csv("cells_test.csv").unwrap();
+        let view = dataset.view();
+        assert_eq!(view.len(), 4);
+
+        let after_2000 = view.matching(&Filter::parse("launch_announced > 2000").unwrap());
+        let gigabyte = after_2000.filter(|cell| cell.oem.as_deref() == Some("Gigabyte"));
+        assert_eq!(after_2000.len(), 2);
+        assert_eq!(gigabyte.len(), 1);
+        // The view borrows the dataset's cells rather than copying them.
+        assert!(std::ptr::eq(&gigabyte[0], &dataset.cells()[2]));
+
+        assert_eq!(Cell::most_common_oem(&view), Some("Gigabyte".to_string()));
+        assert_eq!(Cell::mean_body_weight(&gigabyte), Some(118.0));
+        assert_eq!(Cell::launches_per_year(&after_2000), vec![(2010, 2)]);
+        assert!(view.filter(|_| false).is_empty());
+        assert_eq!(Cell::median_body_weight(&view.filter(|_| false)), None);
+    }
+
+    // Test the errors of out-of-bounds edits, unparsable values and unreadable files.
+    #[test]
+    fn check_cell_errors() {
+        let mut cells = Cell::read_csv("cells_test.csv").unwrap();
+
+        assert_eq!(Cell::insert_cell(&mut cells, 4, Cell::new()).unwrap(), None);
+        assert_eq!(cells.len(), 5);
+        assert!(matches!(Cell::insert_cell(&mut cells, 9, Cell::new()), Err(CellError::IndexOutOfBounds { index: 9, len: 5 })));
+        assert_eq!(Cell::modify_cell(&mut cells, 4, Cell { oem: Some("Nokia".into()), ..Cell::new() }).unwrap(), Some(Cell::new()));
+        assert_eq!(cells[4].oem.as_deref(), Some("Nokia"));
+        assert!(matches!(Cell::modify_cell(&mut cells, 5, Cell::new()), Err(CellError::IndexOutOfBounds { index: 5, len: 5 })));
+        assert_eq!(Cell::delete_cell(&mut cells, 4).unwrap().and_then(|cell| cell.oem).as_deref(), Some("Nokia"));
+        let error = Cell::delete_cell(&mut cells, 4).unwrap_err();
+        assert_eq!(error.to_string(), "row 4 is out of bounds (4 rows)");
+        assert_eq!(cells.len(), 4);
+
+        let error = cells[0].set_text(Column::BodyWeight, "heavy").unwrap_err();
+        assert!(matches!(&error, CellError::Parse { row: None, column: Column::BodyWeight, value } if value == "heavy"));
+        assert_eq!(error.to_string(), "invalid body_weight 'heavy'");
+        assert_eq!(cells[0].body_weight, Some(190.0));
+
+        assert!(matches!(Cell::read_csv("no_such_file.csv"), Err(CellError::Io(_))));
+        assert!(matches!(Cell::read_json("cells_test.csv"), Err(CellError::Json(_))));
+    }
+
+    // Test that columns are found by header name in any order, and a missing one is an error.
+    #[test]
+    fn check_columns_by_header_name() {
+        let reordered = std::env::temp_dir().join("cells_reordered_test.csv");
+        let mut csv = String::from("Model,oem,notes,launch_announced,launch_status,body_dimensions,body_weight,body_sim,display_type,display_size,display_resolution,features_sensors,platform_os\n");
+        csv.push_str("Vega,Benefon,first,1999,Discontinued,-,190 g,Mini-SIM,Monochrome,-,-,V1,-\n");
+        std::fs::write(&reordered, csv).unwrap();
+
+        let cells = Cell::read_csv(reordered.to_str().unwrap()).unwrap();
+        assert_eq!((cells[0].oem.as_deref(), cells[0].model.as_deref()), (Some("Benefon"), Some("Vega")));
+        assert_eq!((cells[0].launch_announced, cells[0].body_weight), (Some(1999), Some(190.0)));
+        std::fs::remove_file(reordered).unwrap();
+
+        let missing = std::env::temp_dir().join("cells_missing_column_test.csv");
+        std::fs::write(&missing, "oem,model\nBenefon,Vega\n").unwrap();
+        let error = Cell::read_csv(missing.to_str().unwrap()).unwrap_err();
+        assert!(matches!(error, CellError::MissingColumn { column: Column::LaunchAnnounced }));
+        assert_eq!(error.to_string(), "the CSV file has no 'launch_announced' column");
+        std::fs::remove_file(missing).unwrap();
+    }
+
+    // Test saved queries from the config: filter, sort, columns and limit, and the query subcommands.
+    #[test]
+    fn check_saved_queries() {
+        let config: Config = toml::from_str(r#"
+            [queries.heavy]
+            filter = "body_weight > 100"
+            sort = "body_weight:desc"
+            columns = ["oem", "body_weight"]
+            limit = 1
+        "#).unwrap();
+        let heavy = &config.queries["heavy"];
+        assert_eq!(heavy.columns, vec![Column::Oem, Column::BodyWeight]);
+        assert_eq!(heavy.limit, Some(1));
+
+        let cells = vec![
+            Cell { oem: Some("Benefon".into()), body_weight: Some(190.0), ..Cell::new() },
+            Cell { oem: Some("alcatel".into()), body_weight: Some(80.0), ..Cell::new() },
+            Cell { oem: Some("Nokia".into()), body_weight: Some(250.0), ..Cell::new() },
+            Cell { oem: Some("Acer".into()), ..Cell::new() },
+        ];
+        let view = DatasetView::of(&cells);
+        let rows = heavy.select(&view).unwrap();
+        let oems: Vec<&str> = rows.iter().map(|cell| cell.oem.as_deref().unwrap()).collect();
+        assert_eq!(oems, vec!["Nokia", "Benefon"]);
+
+        // Text sorts ignore case; missing values go last either way.
+        let by_weight = SavedQuery { sort: Some("body_weight".to_string()), ..SavedQuery::default() };
+        let oems: Vec<&str> = by_weight.select(&view).unwrap().iter().map(|cell| cell.oem.as_deref().unwrap()).collect();
+        assert_eq!(oems, vec!["alcatel", "Benefon", "Nokia", "Acer"]);
+        let key = SortKey::parse("oem").unwrap();
+        assert_eq!(key.compare(&cells[1], &cells[0]), std::cmp::Ordering::Less);
+        assert!(SortKey::parse("oem:sideways").is_err());
+        assert!(SortKey::parse("colour").is_err());
+        assert_eq!(SavedQuery::default().columns().len(), Column::ALL.len());
+
+        let cli = Cli::try_parse_from(["cells", "query", "run", "heavy"]).unwrap();
+        assert!(matches!(cli.command, Some(Command::Query(args)) if matches!(&args.saved, Some(SavedQueryCommand::Run { name }) if name == "heavy")));
+        let cli = Cli::try_parse_from(["cells", "query", "oem = Nokia", "--sort", "body_weight:desc"]).unwrap();
+        assert!(matches!(cli.command, Some(Command::Query(args)) if args.saved.is_none() && args.sort.as_deref() == Some("body_weight:desc")));
+    }
+
+    // Test that a schema renames and ignores headers when reading a file.
+    #[test]
+    fn check_schema_mapping() {
+        let schema: Schema = toml::from_str(r#"
+            [columns]
+            "Brand" = "oem"
+            "phone name" = "model"
+            "model" = "ignore"
+        "#).unwrap();
+        assert_eq!(schema.mapping(" BRAND "), Some(Mapping::Field(Column::Oem)));
+        assert_eq!(schema.mapping("Model"), Some(Mapping::Ignore));
+        assert_eq!(schema.mapping("body_weight"), Some(Mapping::Field(Column::BodyWeight)));
+        assert_eq!(schema.mapping("price"), None);
+        assert!(toml::from_str::<Schema>("[columns]\nx = \"colour\"").is_err());
+
+        let variant = std::env::temp_dir().join("cells_schema_test.csv");
+        let mut csv = String::from("Brand,model,Phone Name,launch_announced,launch_status,body_dimensions,body_weight,body_sim,display_type,display_size,display_resolution,features_sensors,platform_os\n");
+        csv.push_str("Benefon,junk,Vega,1999,Discontinued,-,190 g,Mini-SIM,Monochrome,-,-,V1,-\n");
+        std::fs::write(&variant, csv).unwrap();
+
+        let cells = Cell::read_csv_with_schema(variant.to_str().unwrap(), &schema, &mut Normalizer::default()).unwrap();
+        assert_eq!((cells[0].oem.as_deref(), cells[0].model.as_deref()), (Some("Benefon"), Some("Vega")));
+        assert!(matches!(Cell::read_csv(variant.to_str().unwrap()), Err(CellError::MissingColumn { column: Column::Oem })));
+        std::fs::remove_file(variant).unwrap();
+    }
+
+    // Test exporting the rows a query selects to a CSV file, cleaned or raw.
+    #[test]
+    fn check_filter_export() {
+        let cleaned = std::env::temp_dir().join("cells_filter_export_test.csv");
+        let cleaned = cleaned.to_str().unwrap();
+        let cli = Cli::try_parse_from(["cells", "filter", "oem = Gigabyte", "--sort", "model", "--out", cleaned]).unwrap();
+        let Some(Command::Query(args)) = &cli.command else { panic!("expected the query command") };
+        cli::query("cells_test.csv", args, &Config::default()).unwrap();
+
+        let cells = Cell::read_csv(cleaned).unwrap();
+        let models: Vec<&str> = cells.iter().map(|cell| cell.model.as_deref().unwrap()).collect();
+        assert_eq!(models, vec!["GSmart", "GSmart G1305 Boston"]);
+        assert_eq!(cells[1].launch_announced, Some(2010));
+        std::fs::remove_file(cleaned).unwrap();
+
+        let raw = std::env::temp_dir().join("cells_filter_raw_test.csv");
+        let raw = raw.to_str().unwrap();
+        let cli = Cli::try_parse_from(["cells", "query", "oem = Gigabyte", "-n", "1", "--out", raw, "--raw"]).unwrap();
+        let Some(Command::Query(args)) = &cli.command else { panic!("expected the query command") };
+        cli::query("cells_test.csv", args, &Config::default()).unwrap();
+
+        let text = std::fs::read_to_string(raw).unwrap();
+        assert_eq!(text.lines().count(), 2);
+        assert!(text.contains("\"2010, April. Released 2010, April\""));
+        std::fs::remove_file(raw).unwrap();
+
+        assert!(Cli::try_parse_from(["cells", "query", "--raw"]).is_err());
+    }
+
+    // Test that cached column statistics stay right through inserts, edits and deletes.
+    #[test]
+    fn check_column_stats_cache() {
+        let weighing = |weight: Option<f32>| Cell { oem: Some("Nokia".into()), body_weight: weight, ..Cell::new() };
+        let mut dataset = Dataset::new(vec![weighing(Some(100.0)), weighing(Some(150.0)), weighing(None)]);
+
+        let weights = dataset.column_stats(Column::BodyWeight);
+        assert_eq!((weights.count, weights.nulls, weights.sum), (2, 1, Some(250.0)));
+        assert_eq!((weights.min, weights.max, weights.mean()), (Some(100.0), Some(150.0), Some(125.0)));
+        let oems = dataset.column_stats(Column::Oem);
+        assert_eq!((oems.count, oems.nulls, oems.sum, oems.min), (3, 0, None, None));
+
+        dataset.insert(0, weighing(Some(80.0))).unwrap();
+        dataset.modify(3, weighing(Some(200.0))).unwrap();
+        dataset.delete(1).unwrap();
+        assert!(dataset.delete(9).is_err());
+        let weights = dataset.column_stats(Column::BodyWeight);
+        assert_eq!((weights.count, weights.nulls, weights.sum), (3, 0, Some(430.0)));
+        assert_eq!((weights.min, weights.max), (Some(80.0), Some(200.0)));
+
+        // Removing the current minimum falls back to the next smallest value.
+        dataset.delete(0).unwrap();
+        assert_eq!(dataset.column_stats(Column::BodyWeight).min, Some(150.0));
+        dataset.retain(|cell| cell.body_weight != Some(150.0));
+        let weights = dataset.column_stats(Column::BodyWeight);
+        assert_eq!((weights.count, weights.min, weights.max), (1, Some(200.0), Some(200.0)));
+
+        let cached = DatasetStats::of(dataset.cells()).summary(Column::BodyWeight);
+        assert_eq!(cached, weights);
+        assert_eq!(Dataset::default().column_stats(Column::DisplaySize).mean(), None);
+    }
+
+    // Test that the streaming reader yields the same cells and reads on past a bad record.
+    #[test]
+    fn check_cell_reader() {
+        let mut reader = CellReader::open("cells_test.csv").unwrap();
+        let first = reader.next().unwrap().unwrap();
+        assert_eq!(first.model.as_deref(), Some("Vega"));
+        let rest: Vec<Cell> = reader.by_ref().map(|cell| cell.unwrap()).collect();
+        let mut streamed = vec![first];
+        streamed.extend(rest);
+        assert_eq!(streamed, Cell::read_csv("cells_test.csv").unwrap());
+        assert_eq!(reader.into_normalizer().report(), vec![(Stage::Empty, 2), (Stage::Year, 3), (Stage::Numeric, 5)]);
+
+        // A bad record is reported where it is and the records after it can still be read.
+        let csv = "oem,model,launch_announced,launch_status,body_dimensions,body_weight,body_sim,display_type,display_size,display_resolution,features_sensors,platform_os\n\
+                   Benefon,Vega,1999,-,-,190 g,-,-,-,-,-,-\n\
+                   too,short\n\
+                   Nokia,3310,2000,-,-,133 g,-,-,-,-,-,-\n";
+        let results: Vec<Result<Cell, CellError>> = CellReader::from_reader(csv.as_bytes(), &Schema::default(), Normalizer::default()).unwrap().collect();
+        assert_eq!(results.len(), 3);
+        assert!(matches!(results[1], Err(CellError::Csv(_))));
+        assert_eq!(results[2].as_ref().unwrap().body_weight, Some(133.0));
+        assert!(matches!(CellReader::open("no_such_file.csv"), Err(CellError::Io(_))));
+    }
+
+    // Test the most common values of a column, with ties going to the first name.
+    #[test]
+    fn check_mode() {
+        let phone = |oem: &str, size: Option<f32>| Cell { oem: Some(oem.into()), display_size: size, ..Cell::new() };
+        let cells = vec![phone("Nokia", Some(6.1)), phone("Acer", Some(6.1)), phone("Nokia", None), phone("Acer", Some(5.0)), phone("Benefon", None)];
+        let view = DatasetView::of(&cells);
+
+        // Acer and Nokia tie on two phones each; the tie goes to the name that sorts first.
+        assert_eq!(analysis::mode(&view, Column::Oem, 5, &FloatGrouping::DEFAULT), vec![("Acer".to_string(), 2), ("Nokia".to_string(), 2), ("Benefon".to_string(), 1)]);
+        assert_eq!(analysis::mode(&view, Column::DisplaySize, 1, &FloatGrouping::DEFAULT), vec![("6.1".to_string(), 2)]);
+        assert!(analysis::mode(&view, Column::PlatformOs, 3, &FloatGrouping::DEFAULT).is_empty());
+        assert_eq!(Cell::most_common_oem(&view), Some("Acer".to_string()));
+        assert_eq!(Cell::most_common_display_size(&view), Some("6.1".to_string()));
+
+        let cli = Cli::try_parse_from(["cells", "mode", "display_size", "-n", "2"]).unwrap();
+        assert!(matches!(cli.command, Some(Command::Mode(args)) if args.column == Column::DisplaySize && args.runners_up == 2));
+        assert!(Cli::try_parse_from(["cells", "mode", "colour"]).is_err());
+    }
+
+    // Test that the parallel reader gives the same cells and counts as the sequential one.
+    #[test]
+    fn check_parallel_parse() {
+        // Several batches' worth of rows on two threads, so the order of the chunks and of the batches matters.
+        let big = std::env::temp_dir().join("cells_parallel_test.csv");
+        let text = std::fs::read_to_string("cells.csv").unwrap();
+        let (header, rows) = text.split_once('\n').unwrap();
+        let rows = format!("{}\n", rows.trim_end());
+        std::fs::write(&big, format!("{}\n{}", header, rows.repeat(10))).unwrap();
+        let big = big.to_str().unwrap();
+
+        let mut sequential = Normalizer::default();
+        let mut parallel = Normalizer::default();
+        let expected = Cell::read_csv_with(big, &mut sequential).unwrap();
+        let pool = rayon::ThreadPoolBuilder::new().num_threads(2).build().unwrap();
+        let cells = pool.install(|| Cell::read_csv_parallel(big, &Schema::default(), &mut parallel, &mut ParseReport::default())).unwrap();
+        assert!(cells.len() > 2 * 4096);
+        assert_eq!(cells, expected);
+        assert_eq!(parallel.report(), sequential.report());
+        assert_eq!(parallel.text_cleaned(), sequential.text_cleaned());
+        std::fs::remove_file(big).unwrap();
+
+        assert!(matches!(Cell::read_csv_parallel("no_such_file.csv", &Schema::default(), &mut Normalizer::default(), &mut ParseReport::default()), Err(CellError::Io(_))));
+    }
+
+    // Test splitting the rows by a column into one file each, with a manifest of them.
+    #[test]
+    fn check_partitioned_export() {
+        let phone = |oem: &str, year: Option<u32>| Cell { oem: Some(oem.into()), launch_announced: year, ..Cell::new() };
+        let cells = vec![phone("Nokia", Some(2010)), phone("Sony Ericsson", Some(999)), phone("Acer", None), phone("Sony/Ericsson", Some(2010))];
+        let view = DatasetView::of(&cells);
+
+        let keys: Vec<(String, usize)> = partition::partition_by(&view, Column::LaunchAnnounced).into_iter().map(|(key, rows)| (key, rows.len())).collect();
+        assert_eq!(keys, vec![("999".to_string(), 1), ("2010".to_string(), 2), ("unknown".to_string(), 1)]);
+        assert_eq!(partition::partition_file(Column::Oem, "Sony Ericsson", PartitionFormat::Csv), "oem=Sony_Ericsson.csv");
+
+        let dir = std::env::temp_dir().join("cells_partition_test");
+        let manifest = partition::export_partitioned(&view, Column::Oem, dir.to_str().unwrap(), PartitionFormat::Csv, &NumberFormat::default()).unwrap();
+        let files: Vec<&str> = manifest.partitions.iter().map(|partition| partition.file.as_str()).collect();
+        assert_eq!(files, vec!["oem=Acer.csv", "oem=Nokia.csv", "oem=Sony_Ericsson.csv", "oem=Sony_Ericsson-2.csv"]);
+        assert_eq!(manifest.rows, 4);
+
+        let nokia = Cell::read_csv(dir.join("oem=Nokia.csv").to_str().unwrap()).unwrap();
+        assert_eq!(nokia.len(), 1);
+        assert_eq!((nokia[0].oem.as_deref(), nokia[0].launch_announced), (Some("Nokia"), Some(2010)));
+        let index: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(dir.join(partition::MANIFEST_FILE)).unwrap()).unwrap();
+        assert_eq!(index["column"], "oem");
+        assert_eq!(index["partitions"][3]["key"], "Sony/Ericsson");
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    // Test that a lenient load reports unparsable values and a strict load fails on them.
+    #[test]
+    fn check_parse_modes() {
+        let lenient = Dataset::from_csv_parallel("cells_test.csv", &Schema::default(), &mut Normalizer::default(), ParseMode::Lenient).unwrap();
+        let issue = ParseIssue { line: 5, column: Column::LaunchAnnounced, value: "Not officially announced yet".to_string() };
+        assert_eq!(lenient.parse_report().issues, vec![issue.clone()]);
+        assert_eq!(lenient.parse_report().counts(), vec![(Column::LaunchAnnounced, 1)]);
+        assert_eq!(lenient.cells()[3].launch_announced, None);
+
+        let error = Dataset::from_csv_parallel("cells_test.csv", &Schema::default(), &mut Normalizer::default(), ParseMode::Strict).unwrap_err();
+        assert!(matches!(&error, CellError::Invalid(report) if report.issues == vec![issue.clone()]));
+        assert_eq!(error.to_string(), "1 value could not be parsed:\n    line 5: invalid launch_announced 'Not officially announced yet'");
+
+        // The streaming reader records the same issues; blank and '-' values are only missing.
+        let mut reader = CellReader::open("cells_test.csv").unwrap();
+        reader.by_ref().for_each(drop);
+        assert_eq!(reader.report().issues, vec![issue]);
+
+        let config: Config = toml::from_str("parse_mode = \"strict\"").unwrap();
+        assert_eq!(config.parse_mode, ParseMode::Strict);
+        assert_eq!(Config::default().parse_mode, ParseMode::Lenient);
+
+        // Text the cleaning finds no number in is unparsed, not whatever str::parse makes of it.
+        let path = std::env::temp_dir().join("cells_not_numbers_test.csv");
+        let header = "oem,model,launch_announced,launch_status,body_dimensions,body_weight,body_sim,display_type,display_size,display_resolution,features_sensors,platform_os\n";
+        std::fs::write(&path, format!("{}Acme,One,400000000,-,-,NaN,-,-,inf,-,-,-\nAcme,Two,2019,-,-,150 g,-,-,6.1 inches,-,-,-\n", header)).unwrap();
+        let dataset = Dataset::from_csv_parallel(path.to_str().unwrap(), &Schema::default(), &mut Normalizer::default(), ParseMode::Lenient).unwrap();
+        std::fs::remove_file(path).unwrap();
+        let first = &dataset.cells()[0];
+        assert_eq!((first.launch_announced, first.body_weight, first.display_size), (None, None, None));
+        let unparsed: Vec<(Column, &str)> = dataset.parse_report().issues.iter().map(|issue| (issue.column, issue.value.as_str())).collect();
+        assert_eq!(unparsed, vec![(Column::LaunchAnnounced, "400000000"), (Column::BodyWeight, "NaN"), (Column::DisplaySize, "inf")]);
+        assert_eq!(dataset.column_stats(Column::BodyWeight).max, Some(150.0));
+        assert_eq!(dataset.column_stats(Column::DisplaySize).mean(), Some(6.1f32 as f64));
+    }
+
+    // Test chaining filters, groupings and sorts on a collection of cells.
+    #[test]
+    fn check_cell_collection() {
+        let phone = |oem: &str, year: u32, weight: Option<f32>| Cell { oem: Some(oem.into()), launch_announced: Some(year), body_weight: weight, ..Cell::new() };
+        let cells = vec![
+            phone("Nokia", 2010, Some(100.0)),
+            phone("Acer", 2016, Some(180.0)),
+            phone("Nokia", 2018, Some(150.0)),
+            phone("Nokia", 2019, Some(170.0)),
+            phone("Acer", 2019, None),
+            Cell { body_weight: Some(500.0), launch_announced: Some(2020), ..Cell::new() },
+        ];
+        let collection = CellCollection::of(&cells);
+
+        let means = collection.filter(|cell| cell.launch_announced > Some(2015)).group_by_oem().mean_weight();
+        assert_eq!(means, vec![("Acer".to_string(), Some(180.0)), ("Nokia".to_string(), Some(160.0))]);
+        assert_eq!(collection.group_by_oem().counts(), vec![("Acer".to_string(), 2), ("Nokia".to_string(), 3)]);
+        assert_eq!(collection.mean_weight(), Some(220.0));
+        assert_eq!(collection.filter(|cell| cell.body_weight.is_none()).mean_weight(), None);
+
+        let heaviest = collection.sort_by_field(Column::BodyWeight, true);
+        let weights: Vec<Option<f32>> = heaviest.iter().map(|cell| cell.body_weight).collect();
+        assert_eq!(weights, vec![Some(500.0), Some(180.0), Some(170.0), Some(150.0), Some(100.0), None]);
+
+        let groups = collection.group_by(Column::LaunchAnnounced).filter(|_, group| group.len() > 1);
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups.get("2019").map(DatasetView::len), Some(2));
+        assert!(groups.get("2010").is_none());
+        assert_eq!(cells[0].number(Column::LaunchAnnounced), Some(2010.0));
+        assert_eq!(cells[0].number(Column::Oem), None);
+    }
+
+    // Test that a watched file's complete appended records are added, parsed as a load parses them, and a rewritten file is reloaded.
+    #[test]
+    fn check_incremental_refresh() {
+        use std::io::Write;
+
+        let path = std::env::temp_dir().join("cells_watch_test.csv");
+        let header = "oem,model,launch_announced,launch_status,body_dimensions,body_weight,body_sim,display_type,display_size,display_resolution,features_sensors,platform_os\n";
+        std::fs::write(&path, format!("{}Benefon,Vega,1999,-,-,190 g,-,-,-,-,-,-\n", header)).unwrap();
+        let mut live = LiveDataset::open(path.to_str().unwrap(), &Config::default()).unwrap();
+        assert_eq!(live.dataset().len(), 1);
+        assert_eq!(live.refresh().unwrap(), Refresh::Unchanged);
+
+        // Only complete lines are parsed; the rest waits for its line break.
+        let mut file = std::fs::OpenOptions::new().append(true).open(&path).unwrap();
+        write!(file, "Nokia,3310,2000,-,-,133 g,-,-,-,-,-,-\nNokia,8210,1999,-,-,79").unwrap();
+        assert_eq!(live.refresh().unwrap(), Refresh::Appended(1));
+        writeln!(file, " g,-,-,-,-,-,-").unwrap();
+        assert_eq!(live.refresh().unwrap(), Refresh::Appended(1));
+        let models: Vec<&str> = live.dataset().cells().iter().map(|cell| cell.model.as_deref().unwrap()).collect();
+        assert_eq!(models, vec!["Vega", "3310", "8210"]);
+        let weights = live.dataset().column_stats(Column::BodyWeight);
+        assert_eq!((weights.count, weights.min, weights.max), (3, Some(79.0), Some(190.0)));
+
+        // A rewritten file is loaded again from the start.
+        std::fs::write(&path, format!("{}Acer,Liquid,2010,-,-,135 g,-,-,-,-,-,-\n", header)).unwrap();
+        assert_eq!(live.refresh().unwrap(), Refresh::Reloaded);
+        assert_eq!(live.dataset().cells()[0].oem.as_deref(), Some("Acer"));
+        assert_eq!(live.dataset().len(), 1);
+
+        // So is a half-written last line when the file is first opened.
+        std::fs::write(&path, format!("{}Acer,Liquid,2010,-,-,135 g,-,-,-,-,-,-\nNokia,3310,2000,-,-,1", header)).unwrap();
+        let mut live = LiveDataset::open(path.to_str().unwrap(), &Config::default()).unwrap();
+        assert_eq!(live.dataset().len(), 1);
+        writeln!(std::fs::OpenOptions::new().append(true).open(&path).unwrap(), "33 g,-,-,-,-,-,-").unwrap();
+        assert_eq!(live.refresh().unwrap(), Refresh::Appended(1));
+        assert_eq!(live.dataset().cells()[1].body_weight, Some(133.0));
+
+        // A line break inside a quoted field doesn't end the record.
+        writeln!(std::fs::OpenOptions::new().append(true).open(&path).unwrap(), "Nokia,N95,2007,-,-,120 g,-,-,-,-,\"GPS").unwrap();
+        assert_eq!(live.refresh().unwrap(), Refresh::Unchanged);
+        writeln!(std::fs::OpenOptions::new().append(true).open(&path).unwrap(), "Accelerometer\",Symbian").unwrap();
+        assert_eq!(live.refresh().unwrap(), Refresh::Appended(1));
+        assert_eq!(live.dataset().cells()[2].features_sensors.as_deref(), Some("GPS Accelerometer"));
+        assert_eq!(live.dataset().cells()[2].source.as_ref().map(|source| source.line), Some(4));
+
+        // A strict load rejects an appended weight that is not a number, as it rejects the file.
+        let strict: Config = toml::from_str("parse_mode = \"strict\"").unwrap();
+        let mut live = LiveDataset::open(path.to_str().unwrap(), &strict).unwrap();
+        writeln!(std::fs::OpenOptions::new().append(true).open(&path).unwrap(), "Nokia,8210,1999,-,-,light,-,-,-,-,-,-").unwrap();
+        assert!(matches!(live.refresh(), Err(CellError::Invalid(_))));
+        assert!(matches!(LiveDataset::open(path.to_str().unwrap(), &strict), Err(CellError::Invalid(_))));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    // Test that the cell builder sets fields and rejects missing or invalid ones.
+    #[test]
+    fn check_cell_builder() {
+        let cell = Cell::builder().oem("Apple").model("iPhone 15").launch_announced(2023).body_weight(171.0).build().unwrap();
+        assert_eq!(cell.oem.as_deref(), Some("Apple"));
+        assert_eq!(cell.model.as_deref(), Some("iPhone 15"));
+        assert_eq!(cell.launch_announced, Some(2023));
+        assert_eq!(cell.body_weight, Some(171.0));
+        assert_eq!(cell.display_size, None);
+
+        assert!(matches!(CellBuilder::new().oem("Apple").build(), Err(CellError::Required { column: Column::Model })));
+        assert!(matches!(CellBuilder::new().oem(" ").model("X").build(), Err(CellError::Required { column: Column::Oem })));
+        let error = CellBuilder::new().oem("Apple").model("X").display_size(-6.1).build().unwrap_err();
+        assert_eq!(error.to_string(), "invalid display_size '-6.1'");
+    }
+
+    // Test decimal places, rounding modes and zero trimming of formatted numbers and rounded cells.
+    #[test]
+    fn check_number_format() {
+        let format = NumberFormat::default();
+        assert_eq!(format.format(150.5), "150.5");
+        assert_eq!(format.format(190.0), "190");
+        assert_eq!(format.format(2.345678), "2.35");
+        assert_eq!(format.format(-0.001), "0");
+        assert_eq!(format.percent(0.4251), "42.51%");
+
+        let fixed = NumberFormat { decimals: 1, rounding: Rounding::HalfEven, trim_zeros: false };
+        assert_eq!(fixed.format(190.0), "190.0");
+        assert_eq!(fixed.format(0.25), "0.2");
+        assert_eq!(fixed.format(0.35), "0.4");
+        assert_eq!(NumberFormat { rounding: Rounding::Floor, ..fixed }.format(4.99), "4.9");
+        assert_eq!(NumberFormat { rounding: Rounding::Ceil, ..fixed }.format(4.01), "4.1");
+
+        let config: Config = toml::from_str("[number_format]\ndecimals = 0\nrounding = \"ceil\"").unwrap();
+        assert_eq!(config.number_format.format(4.2), "5");
+        assert!(config.number_format.trim_zeros);
+
+        let cell = Cell::builder().oem("Apple").model("iPhone 15").body_weight(171.456).display_size(6.1).build().unwrap();
+        assert_eq!(fixed.text(&cell, Column::BodyWeight).as_deref(), Some("171.5"));
+        assert_eq!(fixed.text(&cell, Column::Model).as_deref(), Some("iPhone 15"));
+        assert_eq!(fixed.round_cell(&cell).body_weight, Some(171.5));
+        assert!(serde_json::to_string(&format.round_cell(&cell)).unwrap().contains("\"body_weight\":171.46,"));
+    }
+
+    // Test that display sizes and body weights group by the configured step.
+    #[test]
+    fn check_float_grouping() {
+        let sized = |size: f32| Cell::builder().oem("Acer").model("X").display_size(size).body_weight(size * 20.0).build().unwrap();
+        let cells = vec![sized(6.0), sized(6.01), sized(6.04), sized(6.1), sized(6.12), sized(6.5)];
+        let view = DatasetView::of(&cells);
+
+        // 6.0, 6.01 and 6.04 are the same size to a tenth of an inch.
+        assert_eq!(analysis::mode(&view, Column::DisplaySize, 2, &FloatGrouping::DEFAULT), vec![("6".to_string(), 3), ("6.1".to_string(), 2)]);
+        assert_eq!(analysis::mode(&view, Column::DisplaySize, 1, &FloatGrouping::EXACT), vec![("6".to_string(), 1)]);
+        assert_eq!(Cell::most_common_display_size(&view), Some("6".to_string()));
+        assert_eq!(view.group_by(Column::DisplaySize).counts(), vec![("6".to_string(), 3), ("6.1".to_string(), 2), ("6.5".to_string(), 1)]);
+
+        let coarse = FloatGrouping { display_size: Some(0.5), body_weight: Some(10.0) };
+        assert_eq!(view.group_by_with(Column::DisplaySize, &coarse).counts(), vec![("6".to_string(), 5), ("6.5".to_string(), 1)]);
+        assert_eq!(coarse.key(&cells[3], Column::BodyWeight).as_deref(), Some("120"));
+        assert_eq!(coarse.key(&cells[3], Column::Oem).as_deref(), Some("Acer"));
+
+        let mut counts = CountBy::new(Column::DisplaySize).grouping(coarse);
+        cells.iter().for_each(|cell| counts.update(cell));
+        assert_eq!(counts.finish(&NumberFormat::default()), vec![("6".to_string(), "5".to_string()), ("6.5".to_string(), "1".to_string())]);
+
+        let config: Config = toml::from_str("[float_grouping]\nbody_weight = 5.0").unwrap();
+        assert_eq!(config.float_grouping.display_size, Some(0.1));
+        assert_eq!(config.float_grouping.step(Column::BodyWeight), Some(5.0));
+    }
+
+    // Test that what each edit displaces is enough to undo it, statistics included.
+    #[test]
+    fn check_crud_undo() {
+        let mut dataset = Dataset::from_csv("cells_test.csv").unwrap();
+        let original = dataset.cells().to_vec();
+        let weights = dataset.column_stats(Column::BodyWeight);
+
+        // Each change hands back what it displaced, which is enough to undo it.
+        let replacement = Cell::builder().oem("Nokia").model("3310").body_weight(133.0).build().unwrap();
+        let replaced = dataset.modify(0, replacement).unwrap().unwrap();
+        assert_eq!(replaced, original[0]);
+        let removed = dataset.delete(1).unwrap().unwrap();
+        assert_eq!(removed, original[1]);
+        assert_eq!(dataset.insert(1, removed).unwrap(), None);
+        assert_eq!(dataset.modify(0, replaced).unwrap().and_then(|cell| cell.model).as_deref(), Some("3310"));
+
+        assert_eq!(dataset.cells(), &original[..]);
+        assert_eq!(dataset.column_stats(Column::BodyWeight), weights);
+        assert!(matches!(dataset.delete(9), Err(CellError::IndexOutOfBounds { index: 9, len: 4 })));
+    }
+
+    // Test that a patch changes only the fields it names.
+    #[test]
+    fn check_cell_patch() {
+        let mut dataset = Dataset::from_csv("cells_test.csv").unwrap();
+        let before = dataset.cells()[0].clone();
+
+        let patch = CellPatch { launch_status: Some("Discontinued".into()), body_weight: Some(185.0), ..CellPatch::default() };
+        assert!(!patch.is_empty());
+        assert_eq!(dataset.patch(0, &patch).unwrap(), Some(before.clone()));
+
+        let after = &dataset.cells()[0];
+        assert_eq!(after.launch_status.as_deref(), Some("Discontinued"));
+        assert_eq!(after.body_weight, Some(185.0));
+        assert_eq!(Cell { launch_status: before.launch_status.clone(), body_weight: before.body_weight, ..after.clone() }, before);
+        assert_eq!(dataset.column_stats(Column::BodyWeight).max, Some(185.0));
+
+        // An empty patch changes nothing; a patch read from JSON names only what it changes.
+        let mut cell = before.clone();
+        cell.apply_patch(&CellPatch::default());
+        assert_eq!(cell, before);
+        let patch: CellPatch = serde_json::from_str(r#"{"platform_os": "Android 14"}"#).unwrap();
+        cell.apply_patch(&patch);
+        assert_eq!(cell.platform_os.as_deref(), Some("Android 14"));
+        assert_eq!(cell.model, before.model);
+        assert!(matches!(dataset.patch(7, &patch), Err(CellError::IndexOutOfBounds { index: 7, len: 4 })));
+    }
+
+    // Test that numbers outside the bounds are flagged, clamped or rejected as configured.
+    #[test]
+    fn check_plausibility_bounds() {
+        let path = std::env::temp_dir().join("cells_bounds_test.csv");
+        let header = "oem,model,launch_announced,launch_status,body_dimensions,body_weight,body_sim,display_type,display_size,display_resolution,features_sensors,platform_os\n";
+        std::fs::write(&path, format!("{}Benefon,Vega,1999,-,-,190 g,-,-,-,-,-,-\nNokia,Tablet,2010,-,-,5000 g,-,-,0.2 inches,-,-,-\n", header)).unwrap();
+        let load = |action: BoundsAction| {
+            let mut normalizer = Normalizer::default().with_bounds(Bounds { action, ..Bounds::PLAUSIBLE });
+            Dataset::from_csv_parallel(path.to_str().unwrap(), &Schema::default(), &mut normalizer, ParseMode::Strict).unwrap()
+        };
+
+        let flagged = load(BoundsAction::Flag);
+        assert_eq!((flagged.cells()[1].body_weight, flagged.cells()[1].display_size), (Some(5000.0), Some(0.2)));
+        let issues: Vec<String> = flagged.parse_report().out_of_range.iter().map(|issue| issue.to_string()).collect();
+        assert_eq!(issues, vec!["line 3: body_weight 5000 is outside 20-1000 (kept)", "line 3: display_size 0.2 is outside 1-15 (kept)"]);
+        assert!(flagged.parse_report().is_empty());
+
+        let clamped = load(BoundsAction::Clamp);
+        assert_eq!((clamped.cells()[1].body_weight, clamped.cells()[1].display_size), (Some(1000.0), Some(1.0)));
+        assert_eq!(clamped.cells()[0].body_weight, Some(190.0));
+        assert_eq!(clamped.column_stats(Column::BodyWeight).max, Some(1000.0));
+
+        let rejected = load(BoundsAction::Reject);
+        assert_eq!((rejected.cells()[1].body_weight, rejected.cells()[1].display_size), (None, None));
+        assert_eq!(rejected.parse_report().out_of_range.len(), 2);
+
+        // Without bounds nothing is checked.
+        let unchecked = Dataset::from_csv_parallel(path.to_str().unwrap(), &Schema::default(), &mut Normalizer::default(), ParseMode::Lenient).unwrap();
+        assert!(unchecked.parse_report().out_of_range.is_empty());
+
+        let config: Config = toml::from_str("[bounds]\naction = \"clamp\"\nbody_weight = { min = 20, max = 1000 }").unwrap();
+        assert_eq!(config.bounds.action, BoundsAction::Clamp);
+        assert_eq!(Normalizer::from_config(&config).bounds().range(Column::BodyWeight), Some(Range { min: 20.0, max: 1000.0 }));
+        assert_eq!(config.bounds.display_size, None);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    // Test inserting several rows and patching or deleting every row that matches.
+    #[test]
+    fn check_batch_edits() {
+        let mut dataset = Dataset::from_csv("cells_test.csv").unwrap();
+        let original = dataset.cells().to_vec();
+        let phone = |oem: &str, weight: f32| Cell::builder().oem(oem).model("Galaxy").body_weight(weight).build().unwrap();
+
+        dataset.insert_many(1, vec![phone("samsung", 150.0), phone("SAMSUNG", 160.0), phone("Nokia", 90.0)]).unwrap();
+        let oems: Vec<&str> = dataset.cells().iter().map(|cell| cell.oem.as_deref().unwrap()).collect();
+        assert_eq!(oems, vec!["Benefon", "samsung", "SAMSUNG", "Nokia", "Garmin-Asus", "Gigabyte", "Gigabyte"]);
+        assert!(matches!(dataset.insert_many(9, vec![Cell::new()]), Err(CellError::IndexOutOfBounds { index: 9, len: 7 })));
+
+        // Fix the casing of every Samsung record at once.
+        let is_samsung = |cell: &Cell| cell.oem.as_deref().is_some_and(|oem| oem.eq_ignore_ascii_case("samsung"));
+        let patch = CellPatch { oem: Some("Samsung".into()), ..CellPatch::default() };
+        let changed = dataset.modify_where(is_samsung, &patch);
+        assert_eq!(changed.iter().map(|(index, old)| (*index, old.oem.as_deref().unwrap().to_string())).collect::<Vec<_>>(), vec![(1, "samsung".to_string()), (2, "SAMSUNG".to_string())]);
+        assert_eq!(dataset.view().filter(|cell| cell.oem.as_deref() == Some("Samsung")).len(), 2);
+
+        let removed = dataset.delete_where(|cell| cell.model.as_deref() == Some("Galaxy"));
+        assert_eq!(removed.iter().map(|(index, _)| *index).collect::<Vec<_>>(), vec![1, 2, 3]);
+        assert_eq!(dataset.cells(), &original[..]);
+        assert_eq!(dataset.column_stats(Column::BodyWeight), Dataset::from_csv("cells_test.csv").unwrap().column_stats(Column::BodyWeight));
+        assert!(dataset.delete_where(|_| false).is_empty());
+    }
+
+    // Test that the import wizard asks for a mapping, asks again on bad answers and saves it.
+    #[test]
+    fn check_import_wizard() {
+        let dir = std::env::temp_dir().join("cells_wizard_test");
+        std::fs::create_dir_all(&dir).unwrap();
+        let csv = dir.join("phones.csv");
+        std::fs::write(
+            &csv,
+            "Brand,Phone,Year,Status,Size mm,Weight,SIM,Screen,Inches,Pixels,Sensors,OS,Price\n\
+             Nokia,3310,2000,Discontinued,-,133 g,Mini-SIM,Mono,1.5,84x48,-,-,50\n\
+             Nokia,3210,1999,Discontinued,-,151 g,Mini-SIM,Mono,1.5,84x48,-,-,40\n",
+        )
+        .unwrap();
+        let samples = Samples::read(csv.to_str().unwrap(), wizard::SAMPLE_ROWS).unwrap();
+        assert_eq!(samples.values[0], vec!["Nokia"]);
+        assert_eq!(samples.values[1], vec!["3310", "3210"]);
+
+        // Unknown and repeated answers are asked again; a blank answer takes the suggestion.
+        let answers = "oem\nmodle\nmodel\nlaunch_announced\nlaunch_status\nbody_dimensions\nbody_weight\nbody_sim\ndisplay_type\noem\ndisplay_size\n\
+                       display_resolution\nfeatures_sensors\nplatform_os\n\n";
+        let mut output = Vec::new();
+        let schema = wizard::ask_mapping(&samples, &mut answers.as_bytes(), &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("Phone: 3310 | 3210\n"));
+        assert!(output.contains("unknown column 'modle'"));
+        assert!(output.contains("oem is already mapped from 'Brand'"));
+        assert_eq!(schema.mapping("Inches"), Some(Mapping::Field(Column::DisplaySize)));
+        assert_eq!(schema.mapping("Price"), Some(Mapping::Ignore));
+
+        // The saved schema and config read the file on later runs.
+        let schema_path = dir.join("phones.schema.toml");
+        let config_path = dir.join("cells.toml");
+        std::fs::write(&config_path, "parse_mode = \"strict\"\n").unwrap();
+        schema.save(schema_path.to_str().unwrap()).unwrap();
+        wizard::save_schema_path(config_path.to_str().unwrap(), schema_path.to_str().unwrap()).unwrap();
+        let config = Config::load(config_path.to_str().unwrap()).unwrap();
+        assert_eq!(config.parse_mode, ParseMode::Strict);
+        assert_eq!(Schema::from_config(&config).unwrap(), schema);
+        let cells = Cell::read_csv_with_schema(csv.to_str().unwrap(), &schema, &mut Normalizer::default()).unwrap();
+        assert_eq!((cells[1].model.as_deref(), cells[1].body_weight), (Some("3210"), Some(151.0)));
+
+        let incomplete = wizard::ask_mapping(&samples, &mut "ignore\n".repeat(13).as_bytes(), &mut Vec::new()).unwrap_err();
+        assert!(incomplete.to_string().starts_with("no header was mapped to oem, model"));
+        assert!(wizard::ask_mapping(&samples, &mut "oem\n".as_bytes(), &mut Vec::new()).is_err());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    // Test that generated fixtures are reproducible by seed and begin with the edge-case rows.
+    #[test]
+    fn check_make_fixture() {
+        // cells_test.csv is the edge-case rows, so it can be written again from code.
+        let mut reader = csv::Reader::from_path("cells_test.csv").unwrap();
+        let records: Vec<Vec<String>> = reader.records().map(|record| record.unwrap().iter().map(String::from).collect()).collect();
+        assert_eq!(fixture::generate(4, true, 1), records);
+
+        let rows = fixture::generate(50, true, 7);
+        assert_eq!(rows, fixture::generate(50, true, 7));
+        assert_ne!(rows, fixture::generate(50, true, 8));
+        assert_eq!(fixture::generate(2, true, 7).len(), 2);
+        assert_eq!(fixture::generate(3, false, 7)[0][1], "Model 1");
+
+        let path = std::env::temp_dir().join("cells_fixture_test.csv");
+        fixture::write(&rows, std::fs::File::create(&path).unwrap()).unwrap();
+        assert!(std::fs::read_to_string(&path).unwrap().starts_with("oem,model,launch_announced,"));
+        let dataset = Dataset::from_csv_parallel(path.to_str().unwrap(), &Schema::default(), &mut Normalizer::default(), ParseMode::Lenient).unwrap();
+        assert_eq!(dataset.len(), 50);
+        assert_eq!(dataset.parse_report().issues.len(), 1);
+        assert!(dataset.cells()[4..].iter().all(|cell| cell.launch_announced.is_some_and(|year| (1999..2024).contains(&year))));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    // Test reading and writing fields by column name, and rejecting values of the wrong type.
+    #[test]
+    fn check_field_access() {
+        let mut cell = Cell::read_csv("cells_test.csv").unwrap().remove(0);
+        assert_eq!(cell.get_field("body_weight").unwrap(), FieldValue::Float(190.0));
+        assert_eq!(cell.get_field(" launch_announced ").unwrap(), FieldValue::Int(1999));
+        assert_eq!(cell.get_field("oem").unwrap(), FieldValue::Text("Benefon".to_string()));
+        assert!(cell.get_field("platform_os").unwrap().is_none());
+        assert!(matches!(cell.get_field("weight"), Err(CellError::UnknownColumn { name }) if name == "weight"));
+
+        // Every column reads the same through its name as through text().
+        for column in Column::ALL {
+            assert_eq!(cell.get_field(column.name()).unwrap().into_text(), cell.text(column));
+        }
+
+        cell.set_field("body_weight", FieldValue::Int(185)).unwrap();
+        cell.set_field("display_size", FieldValue::Text("2.4".to_string())).unwrap();
+        cell.set_field("launch_status", FieldValue::Text("Cancelled".to_string())).unwrap();
+        cell.set_field("model", FieldValue::None).unwrap();
+        assert_eq!((cell.body_weight, cell.display_size, cell.model.as_deref()), (Some(185.0), Some(2.4), None));
+        assert_eq!(cell.launch_status.as_deref(), Some("Cancelled"));
+
+        let error = cell.set_field("launch_announced", FieldValue::Float(1999.5)).unwrap_err();
+        assert_eq!(error.to_string(), "invalid launch_announced '1999.5'");
+        assert!(cell.set_field("oem", FieldValue::Int(3)).is_err());
+        assert_eq!(cell.launch_announced, Some(1999));
+        assert_eq!(serde_json::to_string(&cell.field(Column::BodyWeight)).unwrap(), "185.0");
+        assert_eq!(FieldValue::None.to_string(), "");
+    }
+
+    // Test that results from few values are flagged as low confidence, in aggregates and pipelines.
+    #[test]
+    fn check_low_confidence() {
+        assert_eq!(analysis::low_confidence(3, analysis::MIN_SAMPLE_SIZE).as_deref(), Some("low confidence (n=3)"));
+        assert_eq!(analysis::low_confidence(5, 5), None);
+        assert_eq!(analysis::low_confidence(0, 5), None);
+
+        let cells = Cell::read_csv("cells_test.csv").unwrap();
+        let mut mean = MeanOf::new(Column::BodyWeight);
+        cells.iter().for_each(|cell| mean.update(cell));
+        assert!(mean.low_confidence());
+        assert_eq!(mean.finish(&NumberFormat::default())[2], ("low_confidence".to_string(), "true".to_string()));
+
+        let mut relaxed = MeanOf::new(Column::BodyWeight).min_sample(2);
+        cells.iter().for_each(|cell| relaxed.update(cell));
+        assert!(!relaxed.low_confidence());
+
+        // The flag is in the JSON written by a pipeline.
+        let out = std::env::temp_dir().join("cells_low_confidence_test.json");
+        Pipeline::new().source("cells_test.csv").aggregate(MeanOf::new(Column::BodyWeight)).sink(Sink::Json(out.to_str().unwrap().to_string())).run().unwrap();
+        let json: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&out).unwrap()).unwrap();
+        assert_eq!(json["mean_body_weight"]["low_confidence"], "true");
+        assert_eq!(json["mean_body_weight"]["count"], "2");
+        std::fs::remove_file(out).unwrap();
+
+        let config: Config = toml::from_str("min_sample_size = 2").unwrap();
+        assert_eq!((config.min_sample_size(), Config::default().min_sample_size()), (2, analysis::MIN_SAMPLE_SIZE));
+    }
+
+    // Test that unknown columns are kept as extras, can be filtered on and round-trip through JSON.
+    #[test]
+    fn check_extras() {
+        let file = std::env::temp_dir().join("cells_extras_test.csv");
+        let csv = std::fs::read_to_string("cells_test.csv").unwrap();
+        let mut lines = csv.lines();
+        let mut text = format!("{},Battery, \n", lines.next().unwrap());
+        for (i, line) in lines.enumerate() {
+            text.push_str(&format!("{},{},x\n", line, if i == 0 { "4000 mAh" } else { "-" }));
+        }
+        std::fs::write(&file, text).unwrap();
+
+        let cells = Cell::read_csv(file.to_str().unwrap()).unwrap();
+        assert_eq!(cells[0].extras.len(), 1);
+        assert_eq!(cells[0].extra("battery"), Some("4000 mAh"));
+        assert!(cells[1].extras.is_empty());
+
+        let filter = Filter::parse("extras.battery ~ mah").unwrap();
+        assert_eq!(cells.iter().filter(|cell| filter.matches(cell)).count(), 1);
+        assert!(Filter::parse("extras. = 1").is_err());
+
+        // Extras survive a JSON round trip, and cells without any serialize as before.
+        let json = serde_json::to_string(&cells[0]).unwrap();
+        assert!(json.contains("\"extras\":{\"Battery\":\"4000 mAh\"}"));
+        assert!(!serde_json::to_string(&cells[1]).unwrap().contains("\"extras\""));
+        assert_eq!(serde_json::from_str::<Cell>(&json).unwrap(), cells[0]);
+        std::fs::remove_file(file).unwrap();
+    }
+
+    // Test that report sections print as text and serialize as JSON keyed by analysis name.
+    #[test]
+    fn check_report_sections() {
+        let mut normalizer = Normalizer::default();
+        let dataset = Dataset::from_csv_with("cells_test.csv", &mut normalizer).unwrap();
+        let view = dataset.view();
+        let countries = CountryMap::default();
+        let context = Context {
+            cells: &view,
+            normalizer: &normalizer,
+            parse_report: dataset.parse_report(),
+            countries: &countries,
+            float_grouping: FloatGrouping::DEFAULT,
+            after_year: 1999,
+            style: Style::default(),
+        };
+
+        let mut results = Report::default();
+        for name in ["most_common_oem", "mean_body_weight", "launches_per_year"] {
+            results.push(name, report::analyse(name, &context).unwrap());
+        }
+        assert!(report::analyse("no_such_analysis", &context).is_none());
+        assert!(report::ANALYSES.iter().all(|name| report::analyse(name, &context).is_some()));
+
+        // The same results print as text and serialize as JSON keyed by analysis name.
+        assert!(matches!(results.get("most_common_oem"), Some(Section::MostCommonOem(result)) if result.oem.as_deref() == Some("Gigabyte")));
+        let text = results.to_string();
+        assert!(text.starts_with("Most Common OEM: Gigabyte\nMean Body Weight: 154 g [low confidence (n=2)]\nLaunches per year:\n"));
+        let json = serde_json::to_value(&results).unwrap();
+        assert_eq!(json["most_common_oem"]["oem"], "Gigabyte");
+        assert_eq!(json["mean_body_weight"]["count"], 2);
+        assert_eq!(json["mean_body_weight"]["low_confidence"], true);
+        assert!(json["mean_body_weight"].get("style").is_none());
+        let written = serde_json::to_string(&results).unwrap();
+        assert!(written.find("most_common_oem") < written.find("launches_per_year"));
+    }
+
+    // A second kind of record, to check the generic layers work beyond phones.
+    #[derive(Debug, Clone, Default, PartialEq)]
+    struct Laptop {
+        brand: Option<String>,
+        price: Option<f32>,
+    }
+
+    impl Record for Laptop {
+        type Field = &'static str;
+
+        fn fields() -> &'static [&'static str] {
+            &["brand", "price"]
+        }
+
+        fn field_name(field: &'static str) -> &'static str {
+            field
+        }
+
+        fn is_numeric(field: &'static str) -> bool {
+            field == "price"
+        }
+
+        fn text(&self, field: &'static str) -> Option<String> {
+            match field {
+                "brand" => self.brand.clone(),
+                _ => self.price.map(|price| price.to_string()),
+            }
+        }
+
+        fn number(&self, field: &'static str) -> Option<f32> {
+            if field == "price" { self.price } else { None }
+        }
+
+        fn set_text(&mut self, field: &'static str, value: &str) -> Result<(), CellError> {
+            let value = (!record::is_missing(value)).then(|| value.trim().to_string());
+            match field {
+                "brand" => self.brand = value,
+                _ => self.price = value.map(|price| price.parse()).transpose().map_err(|_| CellError::UnknownColumn { name: field.to_string() })?,
+            }
+            Ok(())
+        }
+    }
+
+    // Test that the generic record layers read, count and edit a record type other than phones.
+    #[test]
+    fn check_generic_records() {
+        let file = std::env::temp_dir().join("cells_laptops_test.csv");
+        std::fs::write(&file, "Price,brand\n999.5,Lenovo\n-,Dell\n1500,-\n").unwrap();
+        let mut laptops: Vec<Laptop> = record::read_records(file.to_str().unwrap()).unwrap();
+        assert_eq!(laptops[0], Laptop { brand: Some("Lenovo".to_string()), price: Some(999.5) });
+        assert_eq!(laptops[1].price, None);
+
+        let stats = DatasetStats::of(&laptops).summary("price");
+        assert_eq!((stats.count, stats.nulls, stats.min, stats.max), (2, 1, Some(999.5), Some(1500.0)));
+        assert_eq!(DatasetStats::of(&laptops).summary("brand").sum, None);
+
+        record::insert(&mut laptops, 3, Laptop::default()).unwrap();
+        assert!(laptops[3].is_empty() && !laptops[0].is_empty());
+        assert_eq!(record::delete(&mut laptops, 1).unwrap().and_then(|laptop| laptop.brand).as_deref(), Some("Dell"));
+        assert!(matches!(record::modify(&mut laptops, 9, Laptop::default()), Err(CellError::IndexOutOfBounds { index: 9, len: 3 })));
+
+        std::fs::write(&file, "brand\nAcer\n").unwrap();
+        assert!(matches!(record::read_records::<Laptop>(file.to_str().unwrap()), Err(CellError::MissingField { field: "price" })));
+        std::fs::write(&file, "brand,price\nAcer,cheap\n").unwrap();
+        let error = record::read_records::<Laptop>(file.to_str().unwrap()).unwrap_err();
+        assert_eq!(error.to_string(), "row 1: invalid price 'cheap'");
+        std::fs::remove_file(file).unwrap();
+
+        // Cell is a record too. Without the cleaning, a raw "190 g (6.70 oz)" is no weight.
+        let error = record::read_records::<Cell>("cells_test.csv").unwrap_err();
+        assert_eq!(error.to_string(), "row 1: invalid body_weight '190 g (6.70 oz)'");
+        assert_eq!(<Cell as Record>::field_by_name(" Body_Weight "), Some(Column::BodyWeight));
+    }
+
+    // Test that empty and header-only files are told apart and every analysis of them is no data.
+    #[test]
+    fn check_empty_input() {
+        let empty = std::env::temp_dir().join("cells_empty_test.csv");
+        let header_only = std::env::temp_dir().join("cells_header_only_test.csv");
+        std::fs::write(&empty, "").unwrap();
+        std::fs::write(&header_only, std::fs::read_to_string("cells_test.csv").unwrap().lines().next().unwrap()).unwrap();
+        let (empty, header_only) = (empty.to_str().unwrap(), header_only.to_str().unwrap());
+
+        assert!(Cell::read_csv(empty).unwrap().is_empty());
+        for (file, kind) in [(empty, EmptyInput::NoHeader), (header_only, EmptyInput::HeaderOnly)] {
+            let dataset = Dataset::from_csv(file).unwrap();
+            assert!(dataset.is_empty());
+            assert_eq!(dataset.parse_report().empty_input, Some(kind));
+            let parallel = Dataset::from_csv_parallel(file, &Schema::default(), &mut Normalizer::default(), ParseMode::Strict).unwrap();
+            assert_eq!(parallel.parse_report().empty_input, Some(kind));
+            let mut reader = CellReader::open(file).unwrap();
+            assert!(reader.next().is_none());
+            assert_eq!(reader.report().empty_input, Some(kind));
+        }
+        assert_eq!(cli::nothing_to_analyse("x.csv", Dataset::from_csv(header_only).unwrap().parse_report(), ""), "Nothing to analyse: the file has a header but no rows (x.csv)");
+        assert_eq!(Dataset::from_csv("cells_test.csv").unwrap().parse_report().empty_input, None);
+
+        // Every analysis of no rows is a typed "no data" result, null in JSON.
+        let dataset = Dataset::from_csv(empty).unwrap();
+        let view = dataset.view();
+        let (normalizer, countries) = (Normalizer::default(), CountryMap::default());
+        let context = Context {
+            cells: &view,
+            normalizer: &normalizer,
+            parse_report: dataset.parse_report(),
+            countries: &countries,
+            float_grouping: FloatGrouping::DEFAULT,
+            after_year: 1999,
+            style: Style::default(),
+        };
+        assert!(report::ANALYSES.iter().skip(1).all(|name| report::analyse(name, &context) == Some(Section::NoData(NoData))));
+        assert!(matches!(report::analyse("normalization", &context), Some(Section::Normalization(result)) if result.warnings == ["Warning: the file is empty"]));
+        assert_eq!(serde_json::to_string(&report::analyse("mean_body_weight", &context).unwrap()).unwrap(), "null");
+        assert!(report::analyse("no_such_analysis", &context).is_none());
+        std::fs::remove_file(empty).unwrap();
+        std::fs::remove_file(header_only).unwrap();
+    }
+
+    // Test that each cell records the file and line it came from, and exports carry it.
+    #[test]
+    fn check_provenance() {
+        let copy = std::env::temp_dir().join("cells_provenance_test.csv");
+        let export = std::env::temp_dir().join("cells_provenance_export_test.csv");
+        std::fs::copy("cells_test.csv", &copy).unwrap();
+        let (copy, export) = (copy.to_str().unwrap(), export.to_str().unwrap());
+
+        // Each cell of a merged dataset points back to its own file, the header being line 1.
+        let files = ["cells_test.csv", copy];
+        let dataset = Dataset::from_csv_files(&files, &Schema::default(), &mut Normalizer::default(), ParseMode::Lenient).unwrap();
+        assert_eq!(dataset.len(), 8);
+        assert_eq!(dataset.provenance(0), Some(&Provenance::new("cells_test.csv", 2)));
+        assert_eq!(dataset.provenance(5), Some(&Provenance::new(copy, 3)));
+        assert_eq!(dataset.provenance(8), None);
+        assert_eq!(Cell::read_csv("cells_test.csv").unwrap()[3].source, Some(Provenance::new("cells_test.csv", 5)));
+        assert_eq!(Cell::new().source, None);
+
+        // Exports carry it, and reading an export back keeps the original source.
+        Cell::write_csv(dataset.cells(), export).unwrap();
+        let exported = Cell::read_csv(export).unwrap();
+        assert_eq!(exported[5].source, Some(Provenance::new(copy, 3)));
+        assert!(exported[5].extras.is_empty());
+        assert!(serde_json::to_string(&dataset.cells()[0]).unwrap().contains("\"source\":{\"file\":\"cells_test.csv\",\"line\":2}"));
+        assert_eq!(dataset.provenance(0).unwrap().to_string(), "cells_test.csv:2");
+
+        // Cells without a source export only the usual columns.
+        Cell::write_csv(&[Cell::new()], export).unwrap();
+        assert!(!std::fs::read_to_string(export).unwrap().contains("source_file"));
+        std::fs::remove_file(copy).unwrap();
+        std::fs::remove_file(export).unwrap();
+    }
+
+    // Test that parsed cells share interned strings, and that counts still add up unshared ones.
+    #[test]
+    fn check_interning() {
+        let mut interner = Interner::new();
+        let first = interner.intern("Samsung");
+        assert!(Arc::ptr_eq(&first, &interner.intern("Samsung")));
+        assert_eq!(interner.len(), 1);
+
+        // Cells parsed from one file share the strings of the low-cardinality columns.
+        let cells = Cell::read_csv("cells_test.csv").unwrap();
+        assert!(Arc::ptr_eq(cells[2].oem.as_ref().unwrap(), cells[3].oem.as_ref().unwrap()));
+        let parallel = Cell::read_csv_parallel("cells_test.csv", &Schema::default(), &mut Normalizer::default(), &mut ParseReport::default()).unwrap();
+        assert!(Arc::ptr_eq(parallel[2].oem.as_ref().unwrap(), parallel[3].oem.as_ref().unwrap()));
+        assert_eq!(parallel, cells);
+
+        // A value from another pool is shared once it is interned here.
+        let mut built = CellBuilder::new().oem("Gigabyte").model("GSmart").build().unwrap();
+        assert!(!Arc::ptr_eq(built.oem.as_ref().unwrap(), cells[2].oem.as_ref().unwrap()));
+        let (mut pool, mut parsed) = (Interner::new(), cells[2].clone());
+        pool.intern_cell(&mut parsed);
+        pool.intern_cell(&mut built);
+        assert!(Arc::ptr_eq(built.oem.as_ref().unwrap(), cells[2].oem.as_ref().unwrap()));
+
+        // Counting by pointer still adds up equal values that are not shared.
+        let other: Arc<str> = "Gigabyte".into();
+        let mut counts = intern::count([cells[2].oem.as_ref().unwrap(), &other, cells[0].oem.as_ref().unwrap()]);
+        counts.sort();
+        assert_eq!(counts, vec![("Benefon", 1), ("Gigabyte", 2)]);
+        let mut with_built = cells.clone();
+        with_built.push(CellBuilder::new().oem("Gigabyte").model("GSmart 2").build().unwrap());
+        assert_eq!(analysis::mode(&DatasetView::of(&with_built), Column::Oem, 1, &FloatGrouping::DEFAULT), vec![("Gigabyte".to_string(), 3)]);
+        // So do the OEM group-bys, which group by the same pointers.
+        let view = DatasetView::of(&with_built);
+        assert_eq!(Cell::oem_counts(&view)[0], ("Gigabyte".to_string(), 3));
+        let groups = intern::group([(&other, 2), (cells[2].oem.as_ref().unwrap(), 3)], |sum: &mut i32, n| *sum += n, |sum, other| *sum += other);
+        assert_eq!(groups, vec![("Gigabyte", 5)]);
+        assert_eq!(Cell::new().interned(Column::Model), None);
+    }
+
+    // Test that exports and reports written to a buffer match what is written to a file.
+    #[test]
+    fn check_output_sinks() {
+        let cells = Cell::read_csv("cells_test.csv").unwrap();
+
+        // Exports and reports written to a buffer match what goes to a file.
+        let mut buffer = Buffer::new();
+        Cell::write_csv_to(&cells, &mut buffer).unwrap();
+        let file = std::env::temp_dir().join("cells_sink_test.csv");
+        let file = file.to_str().unwrap();
+        Cell::write_csv(&cells, file).unwrap();
+        assert_eq!(buffer.text(), std::fs::read_to_string(file).unwrap());
+        assert!(buffer.text().starts_with("oem,model,"));
+
+        let mut json = Buffer::new();
+        Cell::write_json_to(&cells, &mut json).unwrap();
+        assert_eq!(serde_json::from_str::<Vec<Cell>>(&json.text()).unwrap(), cells);
+
+        let normalizer = Normalizer::default();
+        let (view, countries) = (DatasetView::of(&cells), CountryMap::default());
+        let context = Context {
+            cells: &view,
+            normalizer: &normalizer,
+            parse_report: &ParseReport::default(),
+            countries: &countries,
+            float_grouping: FloatGrouping::DEFAULT,
+            after_year: 1999,
+            style: Style::default(),
+        };
+        let mut results = Report::default();
+        results.push("most_common_oem", report::analyse("most_common_oem", &context).unwrap());
+        let mut text = Buffer::new();
+        results.write_to(&mut text, false).unwrap();
+        assert_eq!(text.text(), "Most Common OEM: Gigabyte\n");
+        let mut sink = FileSink::create(file).unwrap();
+        results.write_to(&mut sink, true).unwrap();
+        assert_eq!(std::fs::read_to_string(file).unwrap(), format!("{}\n", serde_json::to_string_pretty(&results).unwrap()));
+
+        assert!(Buffer::new().is_empty());
+        assert!(output::open(Some("/no/such/dir/out.txt")).is_err());
+        std::fs::remove_file(file).unwrap();
+    }
+
+    // Test parsing display resolutions, and comparing and classifying them.
+    #[test]
+    fn check_resolution() {
+        let parse = |text: &str| Resolution::parse(text).map(|resolution| (resolution.width, resolution.height));
+        assert_eq!(parse("1080 x 2340 pixels, 19.5:9 ratio (~403 ppi density)"), Some((1080, 2340)));
+        assert_eq!(parse("240 x 320 pixels, 8 lines"), Some((240, 320)));
+        assert_eq!(parse("8 lines, 128 x 160 pixels"), Some((128, 160)));
+        assert_eq!(parse("720  x 1280 (~294 ppi density)"), Some((720, 1280)));
+        assert_eq!(parse("128 x 64 pixels, 16 x 4 chars"), Some((128, 64)));
+        assert_eq!(parse("1080x2400"), Some((1080, 2400)));
+        for text in ["4 lines", "up to 6 lines", "16 x 2 chars", "16x 2 chars", "0 x 320 pixels", "", "-"] {
+            assert_eq!(Resolution::parse(text), None, "{}", text);
+        }
+
+        // Resolutions compare by their pixels, whatever the order of the sides.
+        let hd = Resolution { width: 720, height: 1280 };
+        let full_hd = Resolution::parse("1080 x 1920 pixels").unwrap();
+        assert!(hd < full_hd);
+        assert!(Resolution { width: 1920, height: 1080 } > hd);
+        assert_eq!((full_hd.short_side(), full_hd.long_side(), full_hd.pixels()), (1080, 1920, 2_073_600));
+        assert_eq!(full_hd.class(), ResolutionClass::FullHd);
+        assert_eq!(full_hd.to_string(), "1080 x 1920");
+
+        let cell = CellBuilder::new().oem("Nokia").model("3310").display_resolution("84 x 48 pixels, 5 lines").build().unwrap();
+        assert_eq!(cell.resolution(), Some(Resolution { width: 84, height: 48 }));
+        assert_eq!(cell.resolution_class(), Some(ResolutionClass::Low));
+        assert_eq!(Cell::new().resolution(), None);
+    }
+
+    // Test each OEM's launch rate, the gaps between its launches and when it went dormant.
+    #[test]
+    fn check_oem_cadence() {
+        let phone = |oem: &str, year: u32| CellBuilder::new().oem(oem).model("Phone").launch_announced(year).build().unwrap();
+        let cells = [phone("Acme", 2010), phone("Acme", 2010), phone("Acme", 2011), phone("Acme", 2014), phone("Acme", 2020), phone("Old", 2005), phone("Old", 2017), phone("New", 2019)];
+        let cadences = analysis::oem_cadence(&DatasetView::of(&cells));
+
+        assert_eq!(cadences.iter().map(|oem| oem.oem.as_str()).collect::<Vec<_>>(), ["Acme", "Old", "New"]);
+        let acme = &cadences[0];
+        assert_eq!((acme.launches, acme.first_year, acme.last_year, acme.active_years), (5, 2010, 2020, 4));
+        assert_eq!(acme.launches_per_active_year(), 1.25);
+        assert_eq!(acme.gaps, vec![(2012, 2013), (2015, 2019)]);
+        assert_eq!(acme.dormant_since, None);
+        // Old last launched 2017, three years before the latest launch; New only one year before.
+        assert_eq!(cadences[1].dormant_since, Some(2018));
+        assert_eq!(cadences[1].gaps, vec![(2006, 2016)]);
+        assert_eq!(cadences[2].dormant_since, None);
+        assert!(analysis::oem_cadence(&DatasetView::of(&[Cell::new()])).is_empty());
+
+        let normalizer = Normalizer::default();
+        let (view, countries) = (DatasetView::of(&cells), CountryMap::default());
+        let context = Context {
+            cells: &view,
+            normalizer: &normalizer,
+            parse_report: &ParseReport::default(),
+            countries: &countries,
+            float_grouping: FloatGrouping::DEFAULT,
+            after_year: 1999,
+            style: Style::default(),
+        };
+        let section = report::analyse("oem_cadence", &context).unwrap();
+        let text = section.to_string();
+        assert!(text.contains("Acme 2010-2020, 1.25 launches a year, no launches in 2012-2013, 2015-2019\n"));
+        assert!(text.ends_with("Dormant OEMs: 1, latest Old (since 2018)\n"));
+        assert_eq!(serde_json::to_value(&section).unwrap()["oems"][1]["gaps"][0]["to"], 2016);
+    }
+
+    // Test pixel densities, with no density for missing or implausible values, and the densest per year.
+    #[test]
+    fn check_ppi() {
+        let phone = |model: &str, year: u32, resolution: &str, inches: f32| {
+            CellBuilder::new().oem("Acme").model(model).launch_announced(year).display_resolution(resolution).display_size(inches).build().unwrap()
+        };
+        let pixel = phone("Pixel", 2019, "1440 x 3040 pixels, 19:9 ratio (~537 ppi density)", 6.3);
+        assert_eq!(pixel.ppi().map(f32::round), Some(534.0));
+        assert_eq!(Resolution { width: 3, height: 4 }.ppi(0.1), Some(50.0));
+
+        // Missing, zero or implausible values give no density rather than a wrong one.
+        assert_eq!(phone("Text", 2001, "4 lines", 1.5).ppi(), None);
+        assert_eq!(Resolution { width: 1080, height: 1920 }.ppi(0.0), None);
+        assert_eq!(Resolution { width: 1080, height: 1920 }.ppi(f32::NAN), None);
+        assert_eq!(phone("Size in mm", 2012, "720 x 1280 pixels", 65.0).ppi(), None);
+        assert_eq!(phone("Typo", 2012, "1280 x 800 pixels", 0.5).ppi(), None);
+        assert_eq!(Cell::new().ppi(), None);
+
+        let cells = [pixel, phone("Small", 2019, "720 x 1280 pixels", 5.0), phone("Old", 2005, "176 x 220 pixels", 2.0), phone("Text", 2001, "4 lines", 1.5)];
+        let densest = analysis::highest_ppi_per_year(&DatasetView::of(&cells));
+        assert_eq!(densest.iter().map(|(year, _, model, _)| (*year, model.as_str())).collect::<Vec<_>>(), [(2005, "Old"), (2019, "Pixel")]);
+        assert_eq!(densest[0].3.round(), 141.0);
+    }
+
+    // Test stacked bars and the resolution classes of each year, as the report shows them.
+    #[test]
+    fn check_resolution_classes_per_year() {
+        assert_eq!(chart::stacked_bar(&[('S', 1), ('H', 3)], 8), "SSHHHHHH");
+        assert_eq!(chart::stacked_bar(&[('S', 1), ('H', 1), ('F', 1)], 10).len(), 10);
+        assert_eq!(chart::stacked_bar(&[('S', 0)], 10), "");
+        assert_eq!(ResolutionClass::ALL.map(|class| class.short_name()), ["Low", "SD", "HD", "FHD", "QHD", "4K"]);
+
+        let phone = |year: u32, resolution: &str| CellBuilder::new().oem("Acme").model("Phone").launch_announced(year).display_resolution(resolution).build().unwrap();
+        let cells = [phone(2019, "1080 x 2340 pixels"), phone(2019, "720 x 1520 pixels"), phone(2019, "1080 x 2400 pixels"), phone(2020, "2160 x 3840 pixels"), phone(2020, "5 lines")];
+        let years = analysis::resolution_classes_per_year(&DatasetView::of(&cells));
+        assert_eq!(years, vec![(2019, vec![(ResolutionClass::Hd, 1), (ResolutionClass::FullHd, 2)]), (2020, vec![(ResolutionClass::UltraHd, 1)])]);
+
+        let normalizer = Normalizer::default();
+        let (view, countries) = (DatasetView::of(&cells), CountryMap::default());
+        let context = Context {
+            cells: &view,
+            normalizer: &normalizer,
+            parse_report: &ParseReport::default(),
+            countries: &countries,
+            float_grouping: FloatGrouping::DEFAULT,
+            after_year: 1999,
+            style: Style::default(),
+        };
+        let section = report::analyse("resolution_classes_per_year", &context).unwrap();
+        let text = section.to_string();
+        assert!(text.starts_with("Resolution classes per year (L Low, S SD, H HD, F FHD, Q QHD, 4 4K):\n"));
+        assert!(text.contains(&format!("2019 |{}{}| 3 phones\n", "H".repeat(13), "F".repeat(27))));
+        assert_eq!(serde_json::to_value(&section).unwrap()["years"][1]["classes"][0]["key"], "4K");
+    }
+
+    // Test naming aspect ratios, snapping near ones to common shapes, and their shares per year.
+    #[test]
+    fn check_aspect_ratios() {
+        let ratio = |width: u32, height: u32| AspectRatio::of(&Resolution { width, height }).to_string();
+        assert_eq!(ratio(1080, 1920), "16:9");
+        assert_eq!(ratio(1920, 1080), "16:9");
+        assert_eq!(ratio(1080, 2340), "19.5:9");
+        assert_eq!(ratio(1080, 2400), "20:9");
+        assert_eq!(ratio(240, 320), "4:3");
+        assert_eq!(ratio(320, 480), "3:2");
+        assert_eq!(ratio(1440, 1440), "1:1");
+        // Close to a common shape counts as it; otherwise over 9 to the nearest half.
+        assert_eq!(ratio(1080, 2248), "18.5:9");
+        assert_eq!(ratio(1000, 1950), "17.5:9");
+        assert!(AspectRatio { long: 4.0, short: 3.0 } < AspectRatio { long: 16.0, short: 9.0 });
+
+        let phone = |year: u32, resolution: &str| CellBuilder::new().oem("Acme").model("Phone").launch_announced(year).display_resolution(resolution).build().unwrap();
+        assert_eq!(phone(2015, "720 x 1280 pixels").aspect_ratio(), Some(AspectRatio { long: 16.0, short: 9.0 }));
+        assert_eq!(phone(2001, "4 lines").aspect_ratio(), None);
+
+        let cells = [phone(2015, "720 x 1280 pixels"), phone(2015, "1080 x 1920 pixels"), phone(2015, "480 x 640 pixels"), phone(2020, "1080 x 2400 pixels")];
+        let years = analysis::aspect_ratios_per_year(&DatasetView::of(&cells));
+        let shown: Vec<(u32, Vec<(String, usize)>)> = years.iter().map(|(year, ratios)| (*year, ratios.iter().map(|(ratio, count)| (ratio.to_string(), *count)).collect())).collect();
+        assert_eq!(shown, vec![(2015, vec![("4:3".to_string(), 1), ("16:9".to_string(), 2)]), (2020, vec![("20:9".to_string(), 1)])]);
+
+        let normalizer = Normalizer::default();
+        let (view, countries) = (DatasetView::of(&cells), CountryMap::default());
+        let context = Context {
+            cells: &view,
+            normalizer: &normalizer,
+            parse_report: &ParseReport::default(),
+            countries: &countries,
+            float_grouping: FloatGrouping::DEFAULT,
+            after_year: 1999,
+            style: Style::default(),
+        };
+        let text = report::analyse("aspect_ratios_per_year", &context).unwrap().to_string();
+        assert!(text.contains("2015: 4:3 33.33%, 16:9 66.67%\n"));
+        assert!(text.contains(&format!("2015 | {:<40} 66.67%\n", "#".repeat(27))));
+        assert!(text.ends_with(&format!("2020 | {:<40} 0%\n", "")));
+    }
+
+    // Test comparing the column statistics of two filtered groups, and the compare-groups command.
+    #[test]
+    fn check_compare_groups() {
+        let phone = |oem: &str, weight: f32| CellBuilder::new().oem(oem).model("Phone").launch_announced(2015).body_weight(weight).build().unwrap();
+        let cells = [phone("Apple", 140.0), phone("Apple", 160.0), phone("Samsung", 170.0), phone("Samsung", 190.0), phone("Samsung", 210.0)];
+        let view = DatasetView::of(&cells);
+        let (apple, samsung) = (view.matching(&Filter::parse("oem=Apple").unwrap()), view.matching(&Filter::parse("oem=Samsung").unwrap()));
+
+        let comparisons = apple.stats().compare(&samsung.stats());
+        assert_eq!(comparisons.len(), Column::ALL.len());
+        let weight = comparisons.iter().find(|comparison| comparison.left.column == Column::BodyWeight).unwrap();
+        assert_eq!((weight.left.count, weight.right.count), (2, 3));
+        assert_eq!(weight.delta(|summary| summary.mean()), Some(40.0));
+        assert_eq!(weight.delta(|summary| summary.min.map(f64::from)), Some(30.0));
+        // A statistic one group lacks has no delta.
+        let size = comparisons.iter().find(|comparison| comparison.left.column == Column::DisplaySize).unwrap();
+        assert_eq!(size.delta(|summary| summary.mean()), None);
+
+        let args = Cli::try_parse_from(["cells", "compare-groups", "oem=Apple", "oem=Samsung"]).unwrap();
+        match args.command {
+            Some(Command::CompareGroups(compare)) => assert_eq!((compare.left.as_str(), compare.right.as_str()), ("oem=Apple", "oem=Samsung")),
+            other => panic!("unexpected command {:?}", other),
+        }
+        assert!(Cli::try_parse_from(["cells", "compare-groups", "oem=Apple"]).is_err());
+    }
+
+    // Test parsing sensor lists, including the placeholder for none, when cells are built or edited.
+    #[test]
+    fn check_sensors() {
+        assert_eq!(
+            Sensor::parse_list("Fingerprint (under display, optical), accelerometer, proximity, compass - India only"),
+            vec![Sensor::Fingerprint { position: FingerprintPosition::UnderDisplay }, Sensor::Accelerometer, Sensor::Proximity, Sensor::Compass]
+        );
+        assert_eq!(Sensor::parse_list("Dual fingerprint (side-mounted), Face ID, baroceptor"), vec![Sensor::Fingerprint { position: FingerprintPosition::Side }, Sensor::FaceId, Sensor::Barometer]);
+        assert_eq!(Sensor::parse_list("altimeter, SpO2"), vec![Sensor::Other("altimeter".into()), Sensor::SpO2]);
+        // The dataset's placeholder for phones without a sensor list.
+        assert_eq!(Sensor::parse_list("V1"), vec![]);
+        assert_eq!(Sensor::Fingerprint { position: FingerprintPosition::Rear }.to_string(), "Fingerprint (rear-mounted)");
+        assert_eq!(Sensor::parse("Fingerprint").to_string(), "Fingerprint");
+
+        // Sensors are parsed when a cell is built or edited.
+        let mut cell = CellBuilder::new().oem("Acme").model("Phone").features_sensors("Fingerprint (under display, optical)").build().unwrap();
+        assert_eq!(cell.sensors.len(), 1);
+        cell.set_text(Column::FeaturesSensors, "Accelerometer, gyro").unwrap();
+        assert_eq!(cell.sensors, vec![Sensor::Accelerometer, Sensor::Gyro]);
+        cell.apply_patch(&CellPatch { features_sensors: Some("Iris scanner".into()), ..CellPatch::default() });
+        assert_eq!(cell.sensors, vec![Sensor::IrisScanner]);
+
+        let cells = Cell::read_csv("cells_test.csv").unwrap();
+        assert!(cells.iter().all(|cell| cell.sensors == cell.features_sensors.as_deref().map_or_else(Vec::new, Sensor::parse_list)));
+        let phones = [cell, CellBuilder::new().oem("Acme").model("Old").features_sensors("V1").build().unwrap()];
+        assert_eq!(Cell::count_phones_with_single_sensor(&DatasetView::of(&phones)), 1);
+    }
+
+    // Test per-field normalizers, registered in code or from the config, run before the stages.
+    #[test]
+    fn check_field_normalizers() {
+        // A registered function runs before the stages, so "190 grams" still parses.
+        let mut normalizer = Normalizer::default()
+            .with_field_normalizer(Column::BodyWeight, Arc::new(|raw: &str| Some(raw.replace("grams", "g"))))
+            .with_field_normalizer(Column::BodyWeight, Arc::new(|raw: &str| (raw.trim() != "unknown").then(|| raw.to_string())));
+        assert_eq!(normalizer.normalize(Column::BodyWeight, "190 grams").as_deref(), Some("190"));
+        assert_eq!(normalizer.normalize(Column::BodyWeight, "unknown"), None);
+        // Other columns are left to the stages.
+        assert_eq!(normalizer.normalize(Column::DisplaySize, "5.5 grams").as_deref(), Some("5.5"));
+        assert_eq!(normalizer.fork().normalize(Column::BodyWeight, "80 grams").as_deref(), Some("80"));
+
+        let config: Config = toml::from_str(
+            r#"
+            [normalization.fields.body_weight]
+            strip = ["grams"]
+
+            [normalization.fields.launch_status]
+            map = { Yes = "Available", No = "Discontinued" }
+            "#,
+        )
+        .unwrap();
+        let mut normalizer = Normalizer::from_config(&config);
+        assert_eq!(normalizer.normalize(Column::BodyWeight, "1.5 grams").as_deref(), Some("1.5"));
+        assert_eq!(normalizer.normalize(Column::LaunchStatus, " No ").as_deref(), Some("Discontinued"));
+        assert_eq!(normalizer.normalize(Column::LaunchStatus, "Cancelled").as_deref(), Some("Cancelled"));
+        assert_eq!(FieldRules::default().normalizer()("  spaced  ").as_deref(), Some("spaced"));
+
+        let csv = "oem,model,launch_announced,launch_status,body_dimensions,body_weight,body_sim,display_type,display_size,display_resolution,features_sensors,platform_os\n\
+                   Acme,One,2020,Yes,-,150 grams,Nano-SIM,OLED,6.1,-,-,-\n";
+        let file = std::env::temp_dir().join("cells_field_normalizers_test.csv");
+        std::fs::write(&file, csv).unwrap();
+        let dataset = Dataset::from_csv_with(file.to_str().unwrap(), &mut normalizer).unwrap();
+        assert_eq!((dataset.cells()[0].body_weight, dataset.cells()[0].launch_status.as_deref()), (Some(150.0), Some("Available")));
+        std::fs::remove_file(file).unwrap();
+    }
+
+    // Test parsing the OS family and version, and the mean Android and iOS versions per year.
+    #[test]
+    fn check_os_info() {
+        let os = |text: &str| OsInfo::parse(text).unwrap();
+        assert_eq!(os("Android 11"), OsInfo { family: OsFamily::Android, version: Some(Version { major: 11, minor: 0, patch: 0 }), max_version: None });
+        assert_eq!(os("iOS 14.1").to_string(), "iOS 14.1");
+        assert_eq!(os("Android 9.0 (Pie), EMUI 9.1").to_string(), "Android 9");
+        assert_eq!(os("Android 8.1 Oreo (Go edition)").to_string(), "Android 8.1");
+        assert_eq!(os("Microsoft Windows Mobile 6.5.3 Professional").to_string(), "Windows 6.5.3");
+        assert_eq!(os("Symbian 9.4, Series 60 rel. 5").to_string(), "Symbian 9.4");
+        // A year is not a version, and unknown systems are Other.
+        assert_eq!(os("Microsoft Smartphone 2003"), OsInfo { family: OsFamily::Other, version: None, max_version: None });
+        assert_eq!(os("Huawei wearable platform").family, OsFamily::Other);
+        assert_eq!(OsInfo::parse("  "), None);
+        assert!(Version::parse("4.2.2").unwrap() > Version::parse("4.2").unwrap());
+        assert_eq!(Version::parse("4.4.2").unwrap().value(), 4.4);
+
+        let phone = |year: u32, os: &str| CellBuilder::new().oem("Acme").model("Phone").launch_announced(year).platform_os(os).build().unwrap();
+        let cells = [phone(2019, "Android 9.0 (Pie)"), phone(2019, "Android 10, MIUI 11"), phone(2019, "iOS 13"), phone(2020, "Android 10")];
+        assert_eq!(cells[2].os().map(|os| os.family), Some(OsFamily::Ios));
+        assert_eq!(analysis::average_os_version_per_year(&DatasetView::of(&cells), OsFamily::Android), vec![(2019, 9.5, 2), (2020, 10.0, 1)]);
+        assert_eq!(analysis::average_os_version_per_year(&DatasetView::of(&cells), OsFamily::Ios), vec![(2019, 13.0, 1)]);
+    }
+
+    // Test the full report in every format, with registered analyses and a callback that stops it.
+    #[test]
+    fn check_report_all() {
+        let cells = Cell::read_csv("cells_test.csv").unwrap();
+        let normalizer = Normalizer::default();
+        let (view, countries) = (DatasetView::of(&cells), CountryMap::default());
+        let context = Context {
+            cells: &view,
+            normalizer: &normalizer,
+            parse_report: &ParseReport::default(),
+            countries: &countries,
+            float_grouping: FloatGrouping::DEFAULT,
+            after_year: 1999,
+            style: Style::default(),
+        };
+
+        let all = Report::all(&context);
+        assert_eq!(all.sections.iter().map(|(name, _)| name.as_str()).collect::<Vec<&str>>(), report::ANALYSES.to_vec());
+        assert_eq!(Report::of(&["most_common_oem", "no_such_analysis"], &context).sections.len(), 1);
+
+        // A registered analysis runs with the built-in ones, or replaces one of the same name.
+        let mut analyses = Analyses::default();
+        analyses.register("model_count", |context| Section::Custom(Custom { text: format!("Models: {}\n", context.cells.len()), value: serde_json::json!(context.cells.len()) }));
+        analyses.register("records", |_| Section::NoData(NoData));
+        let extended = Report::all_with(&analyses, &context);
+        assert_eq!(extended.sections.len(), report::ANALYSES.len() + 1);
+        assert_eq!(extended.get("model_count").unwrap().to_string(), "Models: 4\n");
+        assert_eq!(serde_json::to_value(&extended).unwrap()["model_count"], 4);
+        assert_eq!(extended.get("records"), Some(&Section::NoData(NoData)));
+        let mut seen = Vec::new();
+        let result: Result<Report, &str> = Report::of_each(&analyses, &["model_count", "most_common_oem"], &context, |name, _| {
+            seen.push(name.to_string());
+            Err("stopped")
+        });
+        assert_eq!((result, seen), (Err("stopped"), vec!["model_count".to_string()]));
+
+        let render = |format: ReportFormat| {
+            let mut buffer = Buffer::new();
+            all.write_as(&mut buffer, format).unwrap();
+            buffer.text()
+        };
+        assert_eq!(render(ReportFormat::Text), all.to_string());
+        let json: serde_json::Value = serde_json::from_str(&render(ReportFormat::Json)).unwrap();
+        assert_eq!(json.as_object().unwrap().len(), report::ANALYSES.len());
+        let markdown = render(ReportFormat::Markdown);
+        assert!(markdown.starts_with("## normalization\n\n```text\n"));
+        assert_eq!(markdown.matches("\n## ").count() + 1, report::ANALYSES.len());
+
+        let args = Cli::try_parse_from(["cells", "report-all", "--format", "markdown", "--out", "report.md"]).unwrap();
+        match args.command {
+            Some(Command::ReportAll(all_args)) => assert_eq!((all_args.format, all_args.out.as_deref()), (ReportFormat::Markdown, Some("report.md"))),
+            other => panic!("unexpected command {:?}", other),
+        }
+    }
+
+    // Test reading the version an OS can be upgraded to, and the mean upgrades per OEM and year.
+    #[test]
+    fn check_os_max_version() {
+        let os = |text: &str| OsInfo::parse(text).unwrap();
+        let version = |text: &str| Version::parse(text);
+        assert_eq!(os("Android 11, upgradable to Android 13").max_version, version("13"));
+        assert_eq!(os("Android 9.0 (Pie), planned upgrade to Android 10, ColorOS 7.0").max_version, version("10"));
+        assert_eq!(os("Android 2.1 (Eclair), upgradable to 2.2 (Froyo)").max_version, version("2.2"));
+        assert_eq!(os("Android 4.1 (Jelly Bean), not upgradable to 4.2 (Jelly Bean)").max_version, None);
+        assert_eq!(os("Android 10, MIUI 11").max_version, None);
+
+        assert_eq!(os("Android 9.0 (Pie), upgradable to Android 11").upgrades(), Some(2));
+        assert_eq!(os("Android 4.0, upgradable to 4.0.4").upgrades(), Some(1));
+        assert_eq!(os("Android 10").upgrades(), Some(0));
+        assert_eq!(os("Linux").upgrades(), None);
+
+        let phone = |oem: &str, year: u32, os: &str| CellBuilder::new().oem(oem).model("Phone").launch_announced(year).platform_os(os).build().unwrap();
+        let cells = [
+            phone("Xiaomi", 2019, "Android 9.0 (Pie), upgradable to Android 10, MIUI 11"),
+            phone("Xiaomi", 2019, "Android 9.0 (Pie), MIUI 10"),
+            phone("Xiaomi", 2020, "Android 10, MIUI 12"),
+            phone("Nokia", 2019, "Android 9.0 (Pie), upgradable to Android 11, Android One"),
+            phone("Nokia", 2019, "Proprietary OS"),
+        ];
+        assert_eq!(cells[0].os_max_version(), version("10"));
+        assert_eq!(
+            analysis::os_upgrades_per_oem_year(&DatasetView::of(&cells)),
+            vec![("Nokia".to_string(), vec![(2019, 2.0, 1)]), ("Xiaomi".to_string(), vec![(2019, 0.5, 2), (2020, 0.0, 1)])]
+        );
+    }
+
+    // Test that a cancelled or timed-out token stops filters, group-bys and queries.
+    #[test]
+    fn check_cancellation() {
+        let cells: Vec<Cell> = (0..5000).map(|index| CellBuilder::new().oem(if index % 2 == 0 { "Even" } else { "Odd" }).model(index.to_string()).build().unwrap()).collect();
+        let view = DatasetView::of(&cells);
+        let filter = Filter::parse("oem = Even").unwrap();
+
+        let cancel = Cancel::new();
+        assert_eq!(view.try_matching(&filter, &cancel).unwrap().len(), 2500);
+        assert_eq!(view.try_group_by_with(Column::Oem, &FloatGrouping::DEFAULT, &cancel).unwrap().counts(), vec![("Even".to_string(), 2500), ("Odd".to_string(), 2500)]);
+        let saved = SavedQuery { filter: "oem = Odd".to_string(), sort: Some("model:desc".to_string()), ..SavedQuery::default() };
+        assert_eq!(saved.select_until(&view, &cancel).unwrap(), saved.select(&view).unwrap());
+        assert_eq!(saved.select_indices_until(&cells, &cancel).unwrap(), saved.select_indices(&cells).unwrap());
+
+        // A clone cancels the query holding the original; the cells are untouched.
+        cancel.clone().cancel();
+        assert!(matches!(view.try_matching(&filter, &cancel), Err(CellError::Cancelled)));
+        assert!(matches!(view.try_group_by_with(Column::Oem, &FloatGrouping::DEFAULT, &cancel), Err(CellError::Cancelled)));
+        assert_eq!(saved.select_until(&view, &cancel).unwrap_err().to_string(), "the query was cancelled");
+        assert!(saved.select_indices_until(&cells, &cancel).is_err());
+        assert_eq!(cells.len(), 5000);
+
+        let expired = Cancel::with_timeout(std::time::Duration::ZERO);
+        assert!(matches!(expired.check(), Err(CellError::TimedOut { .. })));
+        let err = saved.select_indices_until(&cells, &expired).unwrap_err();
+        assert!(matches!(err, CellError::TimedOut { .. }));
+        assert!(Cancel::with_timeout(std::time::Duration::from_secs(60)).check().is_ok());
+
+        let args = Cli::try_parse_from(["cells", "query", "oem = Apple", "--timeout", "2.5"]).unwrap();
+        match args.command {
+            Some(Command::Query(query)) => assert_eq!(query.timeout, Some(2.5)),
+            other => panic!("unexpected command {:?}", other),
+        }
+    }
+
+    // Test splitting a dataset into partitions that cover it and can be counted on threads.
+    #[test]
+    fn check_dataset_partitions() {
+        let cells: Vec<Cell> = (0..10).map(|index| CellBuilder::new().oem(if index < 4 { "Nokia" } else { "Sony" }).model(index.to_string()).body_weight(100.0 + index as f32).build().unwrap()).collect();
+        let dataset = Dataset::new(cells);
+
+        let partitions: Vec<DatasetPartition> = dataset.partitions(3).collect();
+        assert_eq!(partitions.iter().map(|partition| (partition.index, partition.start, partition.len())).collect::<Vec<_>>(), vec![(0, 0, 4), (1, 4, 3), (2, 7, 3)]);
+        assert_eq!(partitions[2].dataset_index(1), 8);
+        assert_eq!(partitions[2].cells[1].model.as_deref(), Some("8"));
+        assert_eq!(dataset.partitions(0).len(), 1);
+        assert_eq!(dataset.partitions(50).len(), 10);
+        assert!(dataset.partitions(50).all(|partition| !partition.is_empty()));
+        assert_eq!(Dataset::new(Vec::new()).partitions(4).len(), 0);
+
+        // Each partition counts on its own thread and the counts merge to the whole.
+        let counts: Vec<Vec<(String, usize)>> = std::thread::scope(|scope| {
+            let handles: Vec<_> = dataset.partitions(3).map(|partition| scope.spawn(move || partition.view().group_by_oem().counts())).collect();
+            handles.into_iter().map(|handle| handle.join().unwrap()).collect()
+        });
+        let mut merged: HashMap<String, usize> = HashMap::new();
+        for (oem, count) in counts.into_iter().flatten() {
+            *merged.entry(oem).or_insert(0) += count;
+        }
+        assert_eq!((merged["Nokia"], merged["Sony"]), (4, 6));
+    }
+
+    // Test parsing SIM forms, slots, hybrid and eSIM support, and the dual SIM and eSIM counts.
+    #[test]
+    fn check_sim_info() {
+        assert_eq!(
+            SimInfo::parse("Nano-SIM, eSIM or Dual SIM"),
+            SimInfo { forms: vec![SimForm::Nano], slots: Some(2), dual_sim: true, hybrid: false, esim: true }
+        );
+        let hybrid = SimInfo::parse("Hybrid Dual SIM (Micro-SIM/ Nano-SIM, dual stand-by)");
+        assert_eq!((hybrid.forms, hybrid.slots, hybrid.hybrid), (vec![SimForm::Micro, SimForm::Nano], Some(2), true));
+        assert_eq!(SimInfo::parse("MIni-SIM").forms, vec![SimForm::Mini]);
+        assert_eq!(SimInfo::parse("Mini-SIM").slots, Some(1));
+        // An eSIM-only phone, or one that takes no SIM, has no physical slot.
+        assert_eq!(SimInfo::parse("eSIM").slots, Some(0));
+        assert_eq!(SimInfo::parse("No").slots, Some(0));
+        assert_eq!(SimInfo::parse("Yes").slots, None);
+
+        let phone = |year: u32, body_sim: &str| CellBuilder::new().oem("Acme").model("Phone").launch_announced(year).body_sim(body_sim).build().unwrap();
+        let cells = [phone(2019, "Dual SIM (Nano-SIM, dual stand-by)"), phone(2019, "Nano-SIM, eSIM"), phone(2020, "Nano-SIM card & eSIM"), phone(2020, "Mini-SIM")];
+        let view = DatasetView::of(&cells);
+        assert_eq!(analysis::count_dual_sim(&view), 1);
+        assert_eq!(analysis::esim_share_per_year(&view), vec![(2019, 0.5, 2), (2020, 0.5, 2)]);
+    }
+
+    // Test parsing launch statuses, and the phones released late or never released.
+    #[test]
+    fn check_launch_status() {
+        assert_eq!(LaunchStatus::parse("2019"), Some(LaunchStatus::Released { year: 2019, quarter: None }));
+        assert_eq!(LaunchStatus::parse("Available. Released 2019, September"), Some(LaunchStatus::Released { year: 2019, quarter: Some(3) }));
+        assert_eq!(LaunchStatus::parse("Released 2021 Q1"), Some(LaunchStatus::Released { year: 2021, quarter: Some(1) }));
+        assert_eq!(LaunchStatus::parse("Coming soon. Exp. release 2020, July"), Some(LaunchStatus::ComingSoon { year: Some(2020) }));
+        assert_eq!(LaunchStatus::parse("Discontinued"), Some(LaunchStatus::Discontinued));
+        assert_eq!(LaunchStatus::parse("Cancelled"), Some(LaunchStatus::Cancelled));
+        assert_eq!(LaunchStatus::parse("Available"), None);
+        assert_eq!(LaunchStatus::Released { year: 2019, quarter: Some(3) }.to_string(), "Released 2019 Q3");
+
+        let phone = |model: &str, status: &str| CellBuilder::new().oem("Acme").model(model).launch_announced(2019).launch_status(status).build().unwrap();
+        let cells = [phone("Late", "2020"), phone("On time", "2019"), phone("Gone", "Discontinued"), phone("Never", "Cancelled"), phone("Soon", "Coming soon")];
+        let view = DatasetView::of(&cells);
+        // A discontinued phone has no release year, so it is not a mismatch.
+        assert_eq!(Cell::phones_announced_in_one_year_released_in_another(&view), vec![("Acme".to_string(), "Late".to_string())]);
+        assert_eq!(Cell::never_released(&view), vec![("Acme".to_string(), "Never".to_string()), ("Acme".to_string(), "Soon".to_string())]);
+        assert_eq!(cells[2].status_category(), Some("Discontinued"));
+        assert_eq!(cells[4].status_category(), Some("Coming soon"));
+    }
+
+    // Test that every filter preset parses, and that presets combine with an expression and each other.
+    #[test]
+    fn check_filter_presets() {
+        // Every preset is a valid filter expression.
+        for preset in &PRESETS {
+            assert!(Filter::parse(preset.expression).is_ok(), "{}", preset.name);
+        }
+        assert!(Preset::find("modern-smartphone").is_some());
+        assert!(Preset::find("nope").is_none());
+
+        let presets = |names: &[&str]| names.iter().map(|name| name.to_string()).collect::<Vec<_>>();
+        assert_eq!(filter::with_presets("", &[]).unwrap(), "");
+        assert_eq!(filter::with_presets(" oem = Nokia ", &presets(&["phablet"])).unwrap(), "oem = Nokia && display_size >= 6.5");
+        assert!(filter::with_presets("", &presets(&["nope"])).is_err());
+
+        let phone = |year: u32, os: &str, size: f32| CellBuilder::new().oem("Acme").model("Phone").launch_announced(year).platform_os(os).display_size(size).build().unwrap();
+        let modern = Filter::parse(&filter::with_presets("", &presets(&["modern-smartphone"])).unwrap()).unwrap();
+        assert!(modern.matches(&phone(2019, "Android 9.0 (Pie)", 6.1)));
+        assert!(!modern.matches(&phone(2014, "Android 4.4", 5.2)));
+        assert!(!modern.matches(&phone(2019, "Proprietary OS", 5.5)));
+        assert!(!modern.matches(&phone(2019, "iOS 13", 4.7)));
+        // Presets compose: both must hold.
+        let both = Filter::parse(&filter::with_presets("", &presets(&["modern-smartphone", "android"])).unwrap()).unwrap();
+        assert!(!both.matches(&phone(2019, "iOS 13", 6.1)));
+        assert!(Filter::parse("os_family = ios").unwrap().matches(&phone(2019, "iOS 13", 6.1)));
+    }
+
+    // Test parsing announcement dates to a month or quarter, and keeping them through CSV and edits.
+    #[test]
+    fn check_launch_date() {
+        assert_eq!(LaunchDate::parse("2019, February"), Some(LaunchDate { year: 2019, month: Some(2), quarter: Some(1) }));
+        assert_eq!(LaunchDate::parse("2004, Q1"), Some(LaunchDate { year: 2004, month: None, quarter: Some(1) }));
+        assert_eq!(LaunchDate::parse("Q3 2020"), Some(LaunchDate { year: 2020, month: None, quarter: Some(3) }));
+        assert_eq!(LaunchDate::parse("2003"), Some(LaunchDate::of_year(2003)));
+        // Only the announcement counts, not the release after it.
+        assert_eq!(LaunchDate::parse("2010, January. Released 2010, March").and_then(|date| date.month), Some(1));
+        assert_eq!(LaunchDate::parse("Not officially announced yet"), None);
+        assert_eq!(LaunchDate::parse("2019, September").unwrap().to_string(), "2019-09");
+        assert_eq!(LaunchDate::parse("2004, Q1").unwrap().to_string(), "2004 Q1");
+        assert!(LaunchDate::of_year(2019) < LaunchDate::parse("2019, January").unwrap());
+
+        // Read from CSV, the month survives the year cleaning stage.
+        let cells = Cell::read_csv("cells_test.csv").unwrap();
+        for cell in &cells {
+            assert_eq!(cell.launch_date.map(|date| date.year), cell.launch_announced);
+        }
+        assert!(cells.iter().any(|cell| cell.launch_date.is_some_and(|date| date.month.is_some())));
+
+        // Setting the year keeps the date to it.
+        let mut cell = CellBuilder::new().oem("Acme").model("Phone").launch_date(LaunchDate { year: 2019, month: Some(5), quarter: Some(2) }).build().unwrap();
+        assert_eq!(cell.launch_announced, Some(2019));
+        cell.set_text(Column::LaunchAnnounced, "2020").unwrap();
+        assert_eq!(cell.launch_date, Some(LaunchDate::of_year(2020)));
+        cell.set_text(Column::LaunchAnnounced, "").unwrap();
+        assert_eq!(cell.launch_date, None);
+
+        let phone = |year: u32, quarter: Option<u8>| CellBuilder::new().oem("Acme").model("Phone").launch_date(LaunchDate { year, month: None, quarter }).build().unwrap();
+        let cells = [phone(2019, Some(3)), phone(2019, Some(3)), phone(2019, None), phone(2020, Some(1))];
+        assert_eq!(analysis::launches_per_quarter(&DatasetView::of(&cells)), vec![(2019, 3, 2), (2020, 1, 1)]);
+    }
+
+    // Test metrics by name, registering new ones, histograms, regression and their subcommands.
+    #[test]
+    fn check_metrics() {
+        let mut metrics = Metrics::default();
+        let cell = CellBuilder::new()
+            .oem("Apple")
+            .model("iPhone 11")
+            .launch_announced(2019)
+            .display_size(6.1)
+            .display_resolution("828 x 1792 pixels, 19.5:9 ratio (~326 ppi density)")
+            .build()
+            .unwrap();
+        assert_eq!(metrics.get("display_size").unwrap()(&cell), Some(6.1));
+        assert_eq!(metrics.get(" Pixel_Count ").unwrap()(&cell), Some(828.0 * 1792.0));
+        assert!(metrics.get("ppi").unwrap()(&cell).is_some_and(|ppi| (ppi - 324.0).abs() < 1.0));
+        assert_eq!(metrics.get("thickness").unwrap()(&cell), None);
+        assert!(matches!(metrics.get("nope"), Err(CellError::UnknownColumn { .. })));
+
+        // A registered name is accepted like the built-in ones, and replaces one of the same name.
+        metrics.register("inches_squared", |cell| cell.display_size.map(|size| size * size));
+        metrics.register("body_weight", |_| Some(1.0));
+        assert_eq!(metrics.values("inches_squared", [&cell]).unwrap(), vec![6.1 * 6.1]);
+        assert_eq!(metrics.values("body_weight", [&cell]).unwrap(), vec![1.0]);
+        assert_eq!(metrics.names().filter(|name| *name == "body_weight").count(), 1);
+
+        assert_eq!(analysis::histogram(&[1.0, 2.0, 3.0, 4.0], 3), vec![(1.0, 2.0, 1), (2.0, 3.0, 1), (3.0, 4.0, 2)]);
+        assert_eq!(analysis::histogram(&[5.0, 5.0], 4), vec![(5.0, 5.0, 2)]);
+        assert!(analysis::histogram(&[], 4).is_empty());
+
+        let fit = analysis::linear_regression(&[(0.0, 1.0), (1.0, 3.0), (2.0, 5.0)]).unwrap();
+        assert_eq!((fit.slope, fit.intercept, fit.r_squared, fit.n), (2.0, 1.0, 1.0, 3));
+        assert_eq!(analysis::linear_regression(&[(1.0, 1.0), (1.0, 2.0)]), None);
+        assert_eq!(analysis::linear_regression(&[(1.0, 1.0)]), None);
+
+        let cli = Cli::try_parse_from(["cells", "stats", "--column", "ppi,pixel_count"]).unwrap();
+        assert!(matches!(cli.command, Some(Command::Stats(args)) if args.column == ["ppi", "pixel_count"]));
+        let cli = Cli::try_parse_from(["cells", "regression", "launch_announced", "ppi"]).unwrap();
+        assert!(matches!(cli.command, Some(Command::Regression(args)) if args.x == "launch_announced" && args.y == "ppi"));
+    }
+
+    // Test release dates and the months from announcement to release.
+    #[test]
+    fn check_launch_delay() {
+        assert_eq!(LaunchDate::released("Available. Released 2019, September"), Some(LaunchDate { year: 2019, month: Some(9), quarter: Some(3) }));
+        assert_eq!(LaunchDate::released("2010, January. Released 2010, March").and_then(|date| date.month), Some(3));
+        assert_eq!(LaunchDate::released("Discontinued"), None);
+        let date = |year: u32, month: Option<u8>, quarter: Option<u8>| LaunchDate { year, month, quarter };
+        assert_eq!(date(2019, Some(11), Some(4)).months_until(&date(2020, Some(2), Some(1))), 3);
+        assert_eq!(date(2004, None, Some(1)).months_until(&date(2004, Some(8), Some(3))), 6);
+        assert_eq!(date(2004, Some(8), Some(3)).months_until(&LaunchDate::of_year(2005)), 12);
+
+        // Read from CSV, the release month survives the year cleaning of launch_status.
+        let cells = Cell::read_csv("cells_test.csv").unwrap();
+        for cell in &cells {
+            assert_eq!(cell.release_date.map(|date| date.year), cell.status().and_then(|status| status.released_year()));
+        }
+        let path = std::env::temp_dir().join("cells_release_date.csv");
+        let header = "oem,model,launch_announced,launch_status,body_dimensions,body_weight,body_sim,display_type,display_size,display_resolution,features_sensors,platform_os\n";
+        std::fs::write(&path, format!("{}Acme,One,\"2019, May\",\"Available. Released 2019, July\",-,-,-,-,-,-,-,-\n", header)).unwrap();
+        let cells = Cell::read_csv(path.to_str().unwrap()).unwrap();
+        std::fs::remove_file(path).unwrap();
+        assert_eq!(cells[0].launch_status.as_deref(), Some("2019"));
+        assert_eq!(cells[0].release_date, Some(date(2019, Some(7), Some(3))));
+
+        let phone = |oem: &str, announced: LaunchDate, released: LaunchDate| {
+            CellBuilder::new().oem(oem).model("Phone").launch_date(announced).launch_status(released.year.to_string()).release_date(released).build().unwrap()
+        };
+        let mut cells = vec![
+            phone("Acme", date(2019, Some(9), Some(3)), date(2019, Some(10), Some(4))),
+            phone("Acme", date(2019, Some(11), Some(4)), date(2020, Some(2), Some(1))),
+            phone("Bolt", date(2019, None, None), LaunchDate::of_year(2020)),
+        ];
+        // A discontinued phone has no release date, so it has no delay.
+        cells.push(CellBuilder::new().oem("Bolt").model("Old").launch_announced(2001).launch_status("Discontinued").release_date(LaunchDate::of_year(2002)).build().unwrap());
+        assert_eq!(cells[3].release_date, None);
+        let delays = analysis::average_launch_delay_by_oem(&DatasetView::of(&cells));
+        assert_eq!(delays.iter().map(|delay| (delay.oem.as_str(), delay.mean_months, delay.phones, delay.by_month)).collect::<Vec<_>>(), vec![("Bolt", 12.0, 1, 0), ("Acme", 2.0, 2, 2)]);
+
+        // Changing the status keeps the release date to it.
+        cells[0].set_text(Column::LaunchStatus, "Cancelled").unwrap();
+        assert_eq!(cells[0].release_date, None);
+    }
+
+    // Test that run metrics are appended to the history and drift in null rates is found.
+    #[test]
+    fn check_history() {
+        let path = std::env::temp_dir().join("cells_history_test.jsonl");
+        let path = path.to_str().unwrap();
+        let _ = std::fs::remove_file(path);
+        assert_eq!(History::load(path).unwrap(), History::default());
+
+        let dataset = Dataset::new(Cell::read_csv("cells_test.csv").unwrap());
+        let mut first = RunMetrics::of(&dataset, "cells_test.csv");
+        assert_eq!(first.rows, 4);
+        assert_eq!(first.mean_weight, dataset.column_stats(Column::BodyWeight).mean());
+        assert_eq!(first.null_rates["body_weight"], 0.5);
+        first.recorded_at = 951_782_400;
+        assert_eq!(first.date(), "2000-02-29");
+
+        // The next version of the file gained weights.
+        let mut second = first.clone();
+        second.rows = 5;
+        second.null_rates.insert("body_weight".to_string(), 0.2);
+        second.null_rates.insert("oem".to_string(), 0.005);
+        History::append(path, &first).unwrap();
+        History::append(path, &second).unwrap();
+
+        let history = History::load(path).unwrap();
+        std::fs::remove_file(path).unwrap();
+        assert_eq!(history.runs, vec![first, second]);
+        assert_eq!(history.null_drift(0.01), vec![("body_weight".to_string(), 0.5, 0.2)]);
+        assert!(history.to_string().contains("2000-02-29"));
+    }
+
+    // Test that a seeded sample keeps the view's order and scales counts up to the whole dataset.
+    #[test]
+    fn check_quick_sample() {
+        let cells: Vec<Cell> = (0..100).map(|index| CellBuilder::new().oem(if index % 4 == 0 { "Nokia" } else { "Sony" }).model(index.to_string()).launch_announced(2000 + index % 2).build().unwrap()).collect();
+        let view = DatasetView::of(&cells);
+        let sample = view.sample(0.1, &mut Rng::new(7));
+        assert_eq!(sample.len(), 10);
+        // The sample keeps the order of the view and has no repeats.
+        let models: Vec<u32> = sample.iter().map(|cell| cell.model.as_deref().unwrap().parse().unwrap()).collect();
+        assert!(models.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(view.sample(0.0001, &mut Rng::new(7)).len(), 1);
+        assert_eq!(view.sample(2.0, &mut Rng::new(7)).len(), 100);
+        assert!(DatasetView::default().sample(0.1, &mut Rng::new(7)).is_empty());
+
+        // Counts scale up to the whole dataset; the sample's own sizes are kept.
+        let info = Sample { rows: sample.len(), of: view.len() };
+        assert_eq!(info.factor(), 10.0);
+        let mut section = Section::LaunchesPerYear(report::LaunchesPerYear { years: vec![report::Count { key: 2000, count: 4 }, report::Count { key: 2001, count: 6 }] });
+        section.extrapolate(info.factor());
+        assert!(matches!(&section, Section::LaunchesPerYear(result) if result.years.iter().map(|year| year.count).collect::<Vec<_>>() == [40, 60]));
+        let mut sensors = Section::SingleSensorPhones(report::SingleSensorPhones { phones: 3 });
+        sensors.extrapolate(2.5);
+        assert_eq!(sensors, Section::SingleSensorPhones(report::SingleSensorPhones { phones: 8 }));
+        let ratios = vec![report::Count { key: "4:3".to_string(), count: 1 }, report::Count { key: "16:9".to_string(), count: 3 }];
+        let mut aspects = Section::AspectRatios(report::AspectRatios { years: vec![report::YearRatios { year: 2000, phones: 4, ratios }], style: Style::default() });
+        aspects.extrapolate(10.0);
+        assert!(matches!(&aspects, Section::AspectRatios(result) if result.years[0].phones == 40 && result.years[0].ratios[1].count == 30));
+        let families = vec![report::YearFamilies { year: 2000, families: vec![report::Count { key: "LCD".to_string(), count: 2 }] }];
+        let mut families = Section::DisplayFamilies(report::DisplayFamilies { years: families });
+        families.extrapolate(10.0);
+        assert!(families.to_string().contains("2000: LCD 20"));
+        // The phones behind an estimate stay the sample's, and say so.
+        let years = vec![report::Average { key: 2000, value: 8.0, count: 3, low_confidence: true }];
+        let mut thickness = Section::ThicknessPerYear(report::ThicknessPerYear { years, style: Style::default() });
+        assert!(thickness.to_string().contains("(3 phones)"));
+        thickness.extrapolate(10.0);
+        assert!(matches!(&thickness, Section::ThicknessPerYear(result) if result.years[0].count == 3));
+        assert!(thickness.to_string().contains("(3 sampled phones) [low confidence"));
+
+        let report = Report { sections: vec![("launches_per_year".to_string(), section)], sample: Some(info) };
+        assert!(report.to_string().starts_with("APPROXIMATE: computed on a random sample of 10 of 100 rows."));
+        let json: serde_json::Value = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["sample"]["of"], 100);
+        assert!(Cli::try_parse_from(["cells", "--quick"]).unwrap().quick);
+    }
+
+    // Test reading grams and ounces from body_weight, and warning when they disagree.
+    #[test]
+    fn check_weight_units() {
+        let weight = Weight::parse("190 g (6.70 oz)");
+        assert_eq!((weight.grams, weight.ounces), (vec![190.0], Some(6.70)));
+        assert!(!Weight::parse("190 g (6.70 oz)").mismatch());
+        assert!(!Weight::parse("183 g (Global)/ 185 g (Verizon) (6.53 oz)").mismatch());
+        assert!(Weight::parse("190 g (7.70 oz)").mismatch());
+        assert!(Weight::parse("680 g (1.50 lb)").ounces_in_grams().is_some_and(|grams| (grams - 680.4).abs() < 0.1));
+        assert!(!Weight::parse("680 g").mismatch());
+        assert_eq!(Weight::parse("135 (4.76 oz)").grams, vec![135.0]);
+
+        // A weight given only in ounces is converted, and units that disagree are warned about.
+        let path = std::env::temp_dir().join("cells_weight_units.csv");
+        let header = "oem,model,launch_announced,launch_status,body_dimensions,body_weight,body_sim,display_type,display_size,display_resolution,features_sensors,platform_os\n";
+        let rows = "Acme,One,2019,2019,-,190 g (6.70 oz),-,-,-,-,-,-\nAcme,Two,2019,2019,-,6.70 oz,-,-,-,-,-,-\nAcme,Three,2019,2019,-,190 g (9.70 oz),-,-,-,-,-,-\n";
+        std::fs::write(&path, format!("{}{}", header, rows)).unwrap();
+        let dataset = Dataset::from_csv_parallel(path.to_str().unwrap(), &Schema::default(), &mut Normalizer::default(), ParseMode::Lenient).unwrap();
+        std::fs::remove_file(path).unwrap();
+        assert_eq!(dataset.cells()[0].body_weight, Some(190.0));
+        assert!(dataset.cells()[1].body_weight.is_some_and(|grams| (grams - 189.9).abs() < 0.1));
+        assert_eq!(dataset.cells()[2].body_weight, Some(190.0));
+        let mismatches = &dataset.parse_report().weight_mismatches;
+        assert_eq!(mismatches.iter().map(|issue| issue.line).collect::<Vec<_>>(), vec![4]);
+        assert_eq!(mismatches[0].to_string(), "line 4: body_weight 190 g disagrees with 9.70 oz (275 g)");
+        assert!(dataset.parse_report().warnings().contains(&mismatches[0].to_string()));
+    }
+
+    // Test adding, filtering on and saving tags, which follow a phone through saved edits.
+    #[test]
+    fn check_tags() {
+        let normalizer = Normalizer::default();
+        let mut tags = Tags::default();
+        assert!(tags.add(&normalizer, "Acme", "One", "verified"));
+        assert!(!tags.add(&normalizer, " acme", "ONE ", "verified"));
+        assert!(tags.add(&normalizer, "Acme", "One", "suspect-weight"));
+        assert_eq!(tags.get(&normalizer, "ACME", "one"), vec!["suspect-weight", "verified"]);
+        assert!(tags.remove(&normalizer, "Acme", "One", "suspect-weight"));
+        assert!(!tags.remove(&normalizer, "Acme", "Two", "verified"));
+
+        let mut cells = vec![
+            CellBuilder::new().oem("Acme").model("One").build().unwrap(),
+            CellBuilder::new().oem("Acme").model("Two").build().unwrap(),
+        ];
+        assert_eq!(tags.apply(&mut cells), 1);
+        let matching = |expression: &str| {
+            let filter = Filter::parse(expression).unwrap();
+            cells.iter().filter(|cell| filter.matches(cell)).map(|cell| cell.model.as_deref().unwrap().to_string()).collect::<Vec<_>>()
+        };
+        assert_eq!(matching("tag = Verified"), vec!["One"]);
+        assert_eq!(matching("tag != verified"), vec!["Two"]);
+        assert_eq!(matching("tag ~ veri && oem = Acme"), vec!["One"]);
+
+        // The sidecar survives a save of queued edits, and follows a renamed phone.
+        let path = std::env::temp_dir().join("cells_tags.csv");
+        let file = path.to_str().unwrap();
+        let header = "oem,model,launch_announced,launch_status,body_dimensions,body_weight,body_sim,display_type,display_size,display_resolution,features_sensors,platform_os\n";
+        std::fs::write(&path, format!("{}Acme,One,2019,2019,-,-,-,-,-,-,-,-\nAcme,Two,2019,2019,-,-,-,-,-,-,-,-\n", header)).unwrap();
+        tags.save(file).unwrap();
+        let mut pending = PendingEdits::load(file).unwrap();
+        pending.push(Edit::Insert { index: 0, values: vec![(Column::Oem, "Bolt".to_string()), (Column::Model, "Zero".to_string())] }).unwrap();
+        pending.push(Edit::Modify { index: 1, values: vec![(Column::Model, "One Plus".to_string())] }).unwrap();
+        pending.save(&normalizer).unwrap();
+        let tags = Tags::load(file, &normalizer).unwrap();
+        assert_eq!(tags.get(&normalizer, "Acme", "One Plus"), vec!["verified"]);
+        assert!(tags.get(&normalizer, "Acme", "One").is_empty());
+
+        let mut dataset = Dataset::from_csv(file).unwrap();
+        assert_eq!(dataset.apply_tags(&tags), 1);
+        assert_eq!(dataset.cells()[1].tags, vec!["verified"]);
+
+        // A row is tagged by its names in the file, but found by the names it is parsed with.
+        std::fs::write(&path, format!("{}HTC Corporation,One\u{a0} M8,2014,2014,-,-,-,-,-,-,-,-\n", header)).unwrap();
+        let mut tags = Tags::default();
+        assert!(tags.add(&normalizer, "HTC Corporation", "One\u{a0} M8", "verified"));
+        assert_eq!(tags.get(&normalizer, "htc", "one m8"), vec!["verified"]);
+        assert_eq!(tags.phones().map(|(oem, model, _)| (oem, model)).collect::<Vec<_>>(), vec![("HTC", "One M8")]);
+        tags.save(file).unwrap();
+        let mut dataset = Dataset::from_csv(file).unwrap();
+        assert_eq!(dataset.apply_tags(&Tags::load(file, &normalizer).unwrap()), 1);
+        let filter = Filter::parse("tag = verified").unwrap();
+        assert_eq!(dataset.cells().iter().filter(|cell| filter.matches(cell)).count(), 1);
+
+        Tags::default().save(file).unwrap();
+        assert!(!std::path::Path::new(&Tags::path(file)).exists());
+        std::fs::remove_file(path).unwrap();
+    }
+
+    // Test reading cover displays and folded dimensions, and counting foldables.
+    #[test]
+    fn check_foldables() {
+        assert_eq!(display::cover_display_size("7.6 inches, 180.9 cm2 (~84.9% screen-to-body ratio); 6.2 inches (cover)"), Some(6.2));
+        assert_eq!(display::cover_display_size("8.0 inches, 205.0 cm (~86.9% screen-to-body ratio)"), None);
+        assert_eq!(display::cover_display_resolution("1768 x 2208 pixels (~373 ppi density); 832 x 2268 pixels (cover)").as_deref(), Some("832 x 2268 pixels"));
+        assert_eq!(display::cover_display_resolution("2200 x 2480 pixels (~414 ppi density)"), None);
+
+        // Read from CSV, display_size and display_resolution stay the main display.
+        let path = std::env::temp_dir().join("cells_foldables.csv");
+        let header = "oem,model,launch_announced,launch_status,body_dimensions,body_weight,body_sim,display_type,display_size,display_resolution,features_sensors,platform_os\n";
+        let rows = "Acme,Fold,2021,2021,-,-,-,Foldable OLED,\"7.6 inches; 6.2 inches (cover)\",\"1768 x 2208 pixels; 832 x 2268 pixels (cover)\",-,-\n\
+                    Acme,Flip,2020,2020,\"Unfolded: 172 x 72 x 6.9 mmFolded: 94 x 72 x 14mm\",-,-,-,6.2 inches,876 x 2142 pixels,-,-\n\
+                    Acme,Bar,2020,2020,150 x 70 x 8 mm,-,-,IPS LCD,6.1 inches,828 x 1792 pixels,-,-\n";
+        std::fs::write(&path, format!("{}{}", header, rows)).unwrap();
+        let cells = Cell::read_csv(path.to_str().unwrap()).unwrap();
+        std::fs::remove_file(path).unwrap();
+        assert_eq!((cells[0].display_size, cells[0].cover_display_size), (Some(7.6), Some(6.2)));
+        assert_eq!(cells[0].display_resolution.as_deref(), Some("1768 x 2208 pixels; 832 x 2268 pixels (cover)"));
+        assert_eq!(cells[0].cover_display_resolution.as_deref(), Some("832 x 2268 pixels"));
+        assert_eq!(cells.iter().map(Cell::is_foldable).collect::<Vec<_>>(), vec![true, true, false]);
+        assert_eq!(Cell::count_foldables(&DatasetView::of(&cells)), (2, 1));
+
+        let built = CellBuilder::new().oem("Acme").model("Fold").cover_display(6.2, "832 x 2268 pixels").build().unwrap();
+        assert!(built.is_foldable());
+        assert_eq!(report::Foldables { phones: 2, with_cover_display: 1 }.to_string(), "Foldable phones: 2 (1 with a cover display)\n");
+    }
+
+    // Test that a fuzzy join matches spelling variants by launch year, and exact matches win.
+    #[test]
+    fn check_fuzzy_join() {
+        let dataset = Dataset::from_csv("cells_test.csv").unwrap();
+        let on = [Column::Oem, Column::Model];
+        let path = std::env::temp_dir().join("cells_fuzzy_join.csv");
+        let other = path.to_str().unwrap();
+        std::fs::write(&path, "oem,model,launch_announced,price_usd\nbenefon,VEGA,1999,120\nGarmin-Asus,Nuvifone-M10,2011,300\nGigabyte,GSmart G1305 Boston,2012,249\n").unwrap();
+
+        // An exact join only finds the one written the same, whatever its year.
+        assert_eq!(dataset.join(other, &on, JoinKind::Inner).unwrap().len(), 1);
+        let joined = dataset.join_matching(other, &on, JoinKind::Left, JoinMatch::Fuzzy).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let confidence: Vec<Option<MatchConfidence>> = joined.iter().map(|row| row.confidence).collect();
+        assert_eq!(confidence, vec![Some(MatchConfidence::SameYear), Some(MatchConfidence::NearYear), Some(MatchConfidence::Exact), None]);
+        assert_eq!(joined[1].supplemental.as_ref().unwrap()["price_usd"], "300");
+        assert_eq!(MatchConfidence::NearYear.to_string(), "fuzzy, one year apart");
+        assert!(MatchConfidence::Exact.score() > MatchConfidence::SameYear.score());
+
+        // Exact matches still win, and a fuzzy join needs the other file's launch years.
+        let exact = dataset.join_matching("prices_test.csv", &on, JoinKind::Inner, JoinMatch::Exact).unwrap();
+        assert!(exact.iter().all(|row| row.confidence == Some(MatchConfidence::Exact)));
+        assert!(dataset.join_matching("prices_test.csv", &on, JoinKind::Inner, JoinMatch::Fuzzy).is_err());
+
+        // The other file's keys are cleaned and renamed as the dataset's cells were, key filter or not.
+        let mut aliases = OemAliases::new();
+        aliases.insert("Benefon Oy", "Benefon");
+        let mut dataset = Dataset::from_csv_with("cells_test.csv", &mut Normalizer::default().with_oem_aliases(aliases)).unwrap();
+        std::fs::write(&path, "oem,model,price_usd\nBenefon Oy,Vega,120\nGigabyte,\"GSmart\u{a0}G1305 \tBoston\u{200b}\",249\n").unwrap();
+        assert_eq!(dataset.join(other, &on, JoinKind::Inner).unwrap().len(), 2);
+        dataset.build_key_filter(0.01);
+        assert_eq!(dataset.join(other, &on, JoinKind::Inner).unwrap().len(), 2);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    // Test the built-in and configured OEM aliases, applied while parsing.
+    #[test]
+    fn check_oem_aliases() {
+        let aliases = OemAliases::new();
+        assert_eq!(aliases.canonical("HTC Corporation"), "HTC");
+        assert_eq!(aliases.canonical(" htc "), "HTC");
+        assert_eq!(aliases.canonical("VIVO"), "vivo");
+        assert_eq!(aliases.canonical("Acme Phones"), "Acme Phones");
+
+        let config: Config = toml::from_str("[oem_aliases]\n\"Acme Phones\" = \"Acme\"\n\"HTC Corp\" = \"High Tech Computer\"").unwrap();
+        let aliases = OemAliases::from_config(&config);
+        assert_eq!(aliases.canonical("acme phones"), "Acme");
+        assert_eq!(aliases.canonical("HTC Corp"), "High Tech Computer");
+        assert_eq!(aliases.canonical("HTC Corporation"), "HTC");
+
+        // Applied while parsing, so the per-OEM analyses see one OEM.
+        let path = std::env::temp_dir().join("cells_oem_aliases.csv");
+        let header = "oem,model,launch_announced,launch_status,body_dimensions,body_weight,body_sim,display_type,display_size,display_resolution,features_sensors,platform_os\n";
+        let rows = "HTC Corporation,One,2013,2013,-,143 g,-,-,-,-,-,-\nhtc,Desire,2010,2010,-,135 g,-,-,-,-,-,-\nSamsung,Galaxy,2010,2010,-,118 g,-,-,-,-,-,-\n";
+        std::fs::write(&path, format!("{}{}", header, rows)).unwrap();
+        let dataset = Dataset::from_csv_parallel(path.to_str().unwrap(), &Schema::default(), &mut Normalizer::from_config(&config), ParseMode::Lenient).unwrap();
+        std::fs::remove_file(path).unwrap();
+        assert_eq!(dataset.cells().iter().map(|cell| cell.oem.as_deref().unwrap()).collect::<Vec<_>>(), vec!["HTC", "HTC", "Samsung"]);
+        assert_eq!(Cell::most_common_oem(&dataset.view()).as_deref(), Some("HTC"));
+    }
+
+    // Test counting how each field's values were taken: verbatim, extracted, none or failed.
+    #[test]
+    fn check_field_counts() {
+        let mut report = ParseReport::default();
+        report.count_field(Column::Oem, " Acme ", Some("Acme"));
+        report.count_field(Column::BodyWeight, "190 g (6.70 oz)", Some("190"));
+        report.count_field(Column::BodyWeight, "-", Some("-"));
+        report.count_field(Column::DisplaySize, "", None);
+        report.record(2, Column::BodyWeight, "heavy");
+        let counts: HashMap<Column, FieldCounts> = report.field_counts().into_iter().collect();
+        assert_eq!(counts[&Column::Oem], FieldCounts { verbatim: 1, ..FieldCounts::default() });
+        assert_eq!(counts[&Column::BodyWeight], FieldCounts { verbatim: 0, extracted: 1, none: 1, failed: 1 });
+        assert_eq!(counts[&Column::DisplaySize].to_string(), "0 verbatim, 0 extracted, 1 none, 0 failed");
+
+        // Counted while parsing, also in parallel, and kept in the report and the run metrics.
+        let dataset = Dataset::from_csv_parallel("cells_test.csv", &Schema::default(), &mut Normalizer::default(), ParseMode::Lenient).unwrap();
+        let counts: HashMap<Column, FieldCounts> = dataset.parse_report().field_counts().into_iter().collect();
+        assert_eq!(counts[&Column::Oem].verbatim, dataset.len());
+        assert_eq!(counts[&Column::LaunchAnnounced].failed, dataset.parse_report().counts().iter().find(|(column, _)| *column == Column::LaunchAnnounced).map_or(0, |&(_, count)| count));
+        assert!(counts.values().all(|field| field.verbatim + field.extracted + field.none == dataset.len()));
+        let metrics = RunMetrics::of(&dataset, "cells_test.csv");
+        assert_eq!(metrics.parse["body_weight"], counts[&Column::BodyWeight]);
+        let line = serde_json::to_string(&metrics).unwrap();
+        assert!(line.contains("\"parse\":{"));
+        let old_line = line.split(",\"parse\"").next().unwrap().to_string() + "}";
+        assert!(serde_json::from_str::<RunMetrics>(&old_line).unwrap().parse.is_empty());
+    }
+
+    // Test that metric, imperial and mixed profiles convert report units while JSON keeps the dataset's.
+    #[test]
+    fn check_unit_profiles() {
+        assert_eq!(UnitProfile::default(), UnitProfile::Mixed);
+        assert_eq!(UnitProfile::Imperial.weight(GRAMS_PER_OUNCE * 2.0), (2.0, "oz"));
+        assert_eq!(UnitProfile::Metric.length(7.5), (7.5, "mm"));
+        assert_eq!(UnitProfile::Imperial.length(25.4), (1.0, "in"));
+        let (centimetres, unit) = UnitProfile::Metric.display_size(6.0);
+        assert!((centimetres - 15.24).abs() < 0.001 && unit == "cm");
+
+        // One run of the analyses prints in each profile; JSON keeps the dataset's units.
+        let mut normalizer = Normalizer::default();
+        let dataset = Dataset::from_csv_with("cells_test.csv", &mut normalizer).unwrap();
+        let view = dataset.view();
+        let countries = CountryMap::default();
+        let context = Context {
+            cells: &view,
+            normalizer: &normalizer,
+            parse_report: dataset.parse_report(),
+            countries: &countries,
+            float_grouping: FloatGrouping::DEFAULT,
+            after_year: 1999,
+            style: Style { units: UnitProfile::Metric, ..Style::default() },
+        };
+        let metric = Report::of(&["mean_body_weight", "thinnest_phones", "avg_display_size_per_oem"], &context);
+        let imperial = metric.with_units(UnitProfile::Imperial);
+        assert!(metric.to_string().starts_with("Mean Body Weight: 154 g"));
+        assert!(imperial.to_string().starts_with("Mean Body Weight: 5.43 oz"));
+        assert!(metric.to_string().contains(" cm (") && !imperial.to_string().contains(" cm ("));
+        assert!(imperial.to_string().contains(" inches (") && imperial.to_string().contains(" in\n"));
+        assert_eq!(serde_json::to_value(&metric).unwrap(), serde_json::to_value(&imperial).unwrap());
+
+        let config: Config = toml::from_str("units = \"imperial\"").unwrap();
+        assert_eq!(Style::from_config(&config).units, UnitProfile::Imperial);
+    }
+
+    // Test that text cleaning collapses whitespace, drops invisible characters and normalizes to NFC.
+    #[test]
+    fn check_text_cleaning() {
+        assert_eq!(text::clean("Galaxy\u{a0}S10\t 5G "), "Galaxy S10 5G");
+        assert_eq!(text::clean("Sony Xperia \u{201c}Z\u{201d}\u{2019}s"), "Sony Xperia \"Z\"'s");
+        assert_eq!(text::clean("Nokia\u{200b} 3310\u{7}"), "Nokia 3310");
+        assert_eq!(text::clean("Vodafone Sme\u{301}ral"), "Vodafone Sméral");
+        assert_eq!(text::clean("Ala\u{308}"), "Alä");
+        assert_eq!(text::clean("x \u{301}"), "x \u{301}");
+
+        // Full NFC: marks in any order and of any script, Hangul jamo, and the characters NFC
+        // leaves decomposed or maps to another.
+        assert_eq!(text::clean("Nokia Xpress Vie\u{302}\u{323}t"), "Nokia Xpress Vi\u{1ec7}t");
+        assert_eq!(text::clean("Nokia Xpress Vie\u{323}\u{302}t"), "Nokia Xpress Vi\u{1ec7}t");
+        assert_eq!(text::clean("\u{1112}\u{1161}\u{11ab}\u{1100}\u{1173}\u{11af}"), "\u{d55c}\u{ae00}");
+        assert_eq!(text::clean("\u{415}\u{308}\u{3b1}\u{301}"), "\u{401}\u{3ac}");
+        assert_eq!(text::clean("\u{212b}ngstr\u{f6}m"), "\u{c5}ngstr\u{f6}m");
+        assert_eq!(text::clean("\u{915}\u{93c}"), "\u{915}\u{93c}");
+        assert_eq!(text::nfc("a\u{301}\u{301}"), "\u{e1}\u{301}");
+        assert_eq!(text::nfc("Galaxy S10"), "Galaxy S10");
+
+        // Two spellings of a value group as one, unless the cleaning is turned off.
+        let mut normalizer = Normalizer::default();
+        assert_eq!(normalizer.normalize(Column::Model, "Moto\u{a0}G\t"), normalizer.normalize(Column::Model, "Moto G"));
+        assert_eq!(normalizer.normalize(Column::Oem, " Huawei\u{feff}").as_deref(), Some("Huawei"));
+        assert_eq!(normalizer.text_cleaned(), 2);
+        let mut raw = Normalizer::from_config(&Config { raw_text: true, ..Config::default() });
+        assert_eq!(raw.normalize(Column::Model, "Moto\u{a0}G").as_deref(), Some("Moto\u{a0}G"));
+        assert_eq!(raw.text_cleaned(), 0);
+
+        // Forks count separately and merge their counts back.
+        let mut fork = normalizer.fork();
+        fork.normalize(Column::PlatformOs, "Android\t10");
+        normalizer.merge(&fork);
+        assert_eq!(normalizer.text_cleaned(), 3);
+    }
+
+    // Test the REPL over a workspace: loading, counting, queries, cancelling, diffs, joins and unions.
+    #[test]
+    fn check_workspace() {
+        let path = std::env::temp_dir().join("cells_workspace_new.csv");
+        let lines: Vec<&str> = include_str!("../cells_test.csv").lines().collect();
+        let nokia = "Nokia,3310,2000,Discontinued,-,133 g,Mini-SIM,Monochrome graphic,1.5 inches,84 x 48 pixels,-,-";
+        std::fs::write(&path, [lines[0], &lines[1].replace("190 g (6.70 oz)", "200 g"), lines[3], nokia].join("\n")).unwrap();
+        let new = path.to_str().unwrap().to_string();
+
+        let config: Config = toml::from_str("[queries.heavy]\nfilter = \"body_weight > 150\"\nsort = \"body_weight:desc\"\ncolumns = [\"model\"]").unwrap();
+        let cancel = Cancel::new();
+        let mut repl = Repl::new(&config, 1999).with_cancel(cancel.clone());
+        assert_eq!(repl.execute("load cells_test.csv as old").unwrap().unwrap(), "Loaded 4 rows from cells_test.csv as old\n");
+        assert!(repl.execute(&format!("load {}", new)).unwrap().unwrap().ends_with("as cells_workspace_new\n"));
+        assert!(repl.execute(&format!("load {} as new", new)).unwrap().is_some());
+        assert!(repl.execute("drop cells_workspace_new").is_ok());
+        assert_eq!(repl.workspace.datasets().map(|(name, _)| name).collect::<Vec<_>>(), vec!["new", "old"]);
+
+        // Commands work on the dataset they name.
+        assert_eq!(repl.execute("count old").unwrap().unwrap(), "4 of 4 rows\n");
+        assert_eq!(repl.execute("count new oem = Nokia").unwrap().unwrap(), "1 of 3 rows\n");
+        assert!(repl.execute("report new mean_body_weight").unwrap().unwrap().starts_with("Mean Body Weight: 150.33 g"));
+        assert!(repl.execute("count missing").unwrap_err().to_string().contains("unknown dataset 'missing'"));
+        assert!(repl.execute("frobnicate old").is_err());
+        assert_eq!(repl.execute("queries").unwrap().unwrap(), "heavy: body_weight > 150\n");
+        let heavy = repl.execute("query new heavy").unwrap().unwrap();
+        assert!(heavy.contains("Vega") && heavy.ends_with("1 of 3 rows match\n"));
+        assert!(repl.execute("query new light").is_err());
+
+        // Each command starts with the token cleared; a cancelled or timed out one stops it.
+        cancel.cancel();
+        assert_eq!(repl.execute("count old").unwrap().unwrap(), "4 of 4 rows\n");
+        let mut timed_out = Repl::new(&config, 1999).with_cancel(Cancel::with_timeout(Duration::ZERO));
+        timed_out.execute("load cells_test.csv").unwrap();
+        assert!(matches!(timed_out.execute("count cells_test").unwrap_err(), CellError::TimedOut { .. }));
+        assert!(timed_out.execute("report cells_test").is_err() && timed_out.execute("query cells_test heavy").is_err());
+        assert!(timed_out.execute("datasets").is_ok());
+
+        // Rows pair up on oem and model across datasets.
+        let diff = repl.workspace.diff("old", "new").unwrap();
+        assert_eq!(diff.removed, vec![("Garmin-Asus".to_string(), "nuvifone M10".to_string()), ("Gigabyte".to_string(), "GSmart".to_string())]);
+        assert_eq!(diff.added, vec![("Nokia".to_string(), "3310".to_string())]);
+        assert_eq!(diff.changed, vec![ChangedRow { oem: "Benefon".to_string(), model: "Vega".to_string(), columns: vec![Column::BodyWeight] }]);
+        assert_eq!(diff.unchanged, 1);
+        assert!(repl.execute("diff old new").unwrap().unwrap().contains("~ Benefon Vega (body_weight)\n"));
+        assert_eq!(
+            repl.execute("join old new").unwrap().unwrap(),
+            "Joined old with new on oem and model: 2 matches (2 exact), 2 rows of old unmatched\n"
+        );
+        assert_eq!(repl.execute("union old new as both").unwrap().unwrap(), "both: 5 rows (2 duplicates left out)\n");
+
+        // Joins use the loaded rows of both sides, so a dataset made in the session joins too.
+        let joined = repl.workspace.join("old", "new", JoinMatch::Exact).unwrap();
+        assert_eq!(joined[0].supplemental.as_ref().unwrap()["body_weight"], "200");
+        assert!(!joined[0].supplemental.as_ref().unwrap().contains_key("oem"));
+        assert_eq!(repl.execute("join new both").unwrap().unwrap(), "Joined new with both on oem and model: 3 matches (3 exact), 0 rows of new unmatched\n");
+        std::fs::remove_file(&path).unwrap();
+        assert!(repl.execute("join old new fuzzy").is_ok());
+
+        // A session reads commands until quit, and errors don't end it.
+        let mut output = Vec::new();
+        repl.run(&mut "count both\nbogus\nquit\ncount both\n".as_bytes(), &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert_eq!(output.matches("5 of 5 rows").count(), 1);
+        assert!(output.contains("Error: unknown command 'bogus'"));
+    }
 }
\ No newline at end of file
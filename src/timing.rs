@@ -0,0 +1,57 @@
+use std::time::{Duration, Instant};
+
+/*
+    Wall-clock time of each stage of a run, for `--timings`. When disabled the stages still
+    run but nothing is recorded.
+ */
+pub struct Timings {
+    enabled: bool,
+    stages: Vec<(String, Duration, usize)>,
+}
+
+impl Timings {
+    pub fn new(enabled: bool) -> Timings {
+        Timings { enabled, stages: Vec::new() }
+    }
+
+    // Record a stage that was timed elsewhere and processed `rows` rows.
+    pub fn record(&mut self, name: &str, elapsed: Duration, rows: usize) {
+        if self.enabled {
+            self.stages.push((name.to_string(), elapsed, rows));
+        }
+    }
+
+    // Run `stage`, recording how long it took to go through `rows` rows.
+    pub fn time<T>(&mut self, name: &str, rows: usize, stage: impl FnOnce() -> T) -> T {
+        let start = Instant::now();
+        let result = stage();
+        self.record(name, start.elapsed(), rows);
+        result
+    }
+
+    /*
+        One line per stage with its wall time and throughput, then the total. Empty when
+        disabled.
+
+        Runtime: O(s)
+     */
+    pub fn report(&self) -> String {
+        if !self.enabled {
+            return String::new();
+        }
+
+        let name_width = self.stages.iter().map(|(name, _, _)| name.len()).max().unwrap_or(0).max("total".len());
+        let mut report = String::from("Timings:\n");
+        let mut total = Duration::ZERO;
+
+        for (name, elapsed, rows) in &self.stages {
+            let seconds = elapsed.as_secs_f64();
+            let rate = if seconds > 0.0 { format!("{:.0} rows/s", *rows as f64 / seconds) } else { "-".to_string() };
+            report.push_str(&format!("{:<width$} {:>10.3} ms  {}\n", name, seconds * 1000.0, rate, width = name_width));
+            total += *elapsed;
+        }
+        report.push_str(&format!("{:<width$} {:>10.3} ms\n", "total", total.as_secs_f64() * 1000.0, width = name_width));
+
+        report
+    }
+}
@@ -0,0 +1,383 @@
+use crate::column::Column;
+use crate::config::Config;
+use crate::dataset::Dataset;
+use crate::edits;
+use crate::error::CellError;
+use crate::normalize::Normalizer;
+use crate::table;
+use crate::tags::Tags;
+use crate::Cell;
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::{cursor, execute, queue, style::Print, terminal};
+use std::collections::HashMap;
+use std::io::{self, Write};
+
+// Lines used by the title, status and table header above the rows.
+const HEADER_LINES: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+// The keys the TUI reacts to, independent of the terminal library.
+pub enum Key {
+    Up,
+    Down,
+    Left,
+    Right,
+    PageUp,
+    PageDown,
+    Tab,
+    BackTab,
+    Enter,
+    Esc,
+    Backspace,
+    Char(char),
+}
+
+impl Key {
+    fn from_event(event: KeyEvent) -> Option<Key> {
+        match event.code {
+            KeyCode::Up => Some(Key::Up),
+            KeyCode::Down => Some(Key::Down),
+            KeyCode::Left => Some(Key::Left),
+            KeyCode::Right => Some(Key::Right),
+            KeyCode::PageUp => Some(Key::PageUp),
+            KeyCode::PageDown => Some(Key::PageDown),
+            KeyCode::Tab => Some(Key::Tab),
+            KeyCode::BackTab => Some(Key::BackTab),
+            KeyCode::Enter => Some(Key::Enter),
+            KeyCode::Esc => Some(Key::Esc),
+            KeyCode::Backspace => Some(Key::Backspace),
+            KeyCode::Char('c') if event.modifiers.contains(KeyModifiers::CONTROL) => Some(Key::Esc),
+            KeyCode::Char(c) => Some(Key::Char(c)),
+            _ => None,
+        }
+    }
+}
+
+// A full-screen view driven by key presses. Nothing in it touches the terminal.
+pub trait Screen {
+    // Number of rows that fit on one screen.
+    fn set_page(&mut self, page: usize);
+    // React to one key press.
+    fn handle(&mut self, key: Key) -> Result<(), CellError>;
+    // The screen contents as lines, each cut to the terminal width.
+    fn render(&self, width: usize) -> Vec<String>;
+    fn should_quit(&self) -> bool;
+}
+
+// The record being edited: a working copy of the cell, its fields as text and the text typed
+// into one of them.
+pub struct EditState {
+    row: usize,
+    field: usize,
+    cell: Cell,
+    values: Vec<String>,
+    input: String,
+    error: Option<String>,
+}
+
+enum Mode {
+    Browse,
+    Edit(Box<EditState>),
+}
+
+/*
+    State of the TUI: the dataset being edited, the changes since the last save as the
+    cells they replaced (newest last, so `u` undoes them in reverse), the selected row,
+    whether a record is being edited, and the normalizer typed values are cleaned with.
+    Key handling and rendering don't touch the terminal, so the whole interaction can be
+    driven from tests.
+ */
+pub struct App {
+    csv_file: String,
+    dataset: Dataset,
+    normalizer: Normalizer,
+    changes: Vec<(usize, Cell)>,
+    selected: usize,
+    top: usize,
+    page: usize,
+    mode: Mode,
+    status: String,
+    quit: bool,
+}
+
+impl App {
+    pub fn new(csv_file: &str, dataset: Dataset, normalizer: Normalizer) -> App {
+        App {
+            csv_file: csv_file.to_string(),
+            dataset,
+            normalizer,
+            changes: Vec::new(),
+            selected: 0,
+            top: 0,
+            page: 20,
+            mode: Mode::Browse,
+            status: String::new(),
+            quit: false,
+        }
+    }
+
+    pub fn dataset(&self) -> &Dataset {
+        &self.dataset
+    }
+
+    // The changes made since the last save.
+    pub fn unsaved(&self) -> usize {
+        self.changes.len()
+    }
+
+    fn handle_browse(&mut self, key: Key) -> Result<(), CellError> {
+        let last = self.dataset.len().saturating_sub(1);
+
+        match key {
+            Key::Up => self.selected = self.selected.saturating_sub(1),
+            Key::Down => self.selected = (self.selected + 1).min(last),
+            Key::PageUp => self.selected = self.selected.saturating_sub(self.page),
+            Key::PageDown => self.selected = (self.selected + self.page).min(last),
+            Key::Enter | Key::Char('e') if !self.dataset.is_empty() => {
+                let cell = self.dataset.cells()[self.selected].clone();
+                let values = row_text(&cell);
+                let input = values[0].clone();
+                self.mode = Mode::Edit(Box::new(EditState { row: self.selected, field: 0, cell, values, input, error: None }));
+            }
+            Key::Char('u') => {
+                self.status = match self.changes.pop() {
+                    Some((index, old)) => {
+                        self.dataset.modify(index, old)?;
+                        self.selected = index;
+                        format!("Undid the change to row {}", index)
+                    }
+                    None => "Nothing to undo".to_string(),
+                };
+            }
+            Key::Char('s') => {
+                let count = self.changes.len();
+                self.save()?;
+                self.status = format!("Saved {} changes", count);
+            }
+            Key::Char('q') | Key::Esc if !self.changes.is_empty() && !self.status.starts_with("Unsaved") => {
+                self.status = format!("Unsaved changes: {} (s to save, q again to quit)", self.changes.len());
+            }
+            Key::Char('q') | Key::Esc => self.quit = true,
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    fn handle_edit(&mut self, key: Key) -> Result<(), CellError> {
+        let Mode::Edit(state) = &mut self.mode else {
+            return Ok(());
+        };
+        let fields = Column::ALL.len();
+
+        match key {
+            Key::Tab | Key::BackTab if commit_field(state, &mut self.normalizer) => {
+                state.field = if key == Key::Tab { (state.field + 1) % fields } else { (state.field + fields - 1) % fields };
+                state.input = state.values[state.field].clone();
+            }
+            Key::Char(c) => {
+                state.input.push(c);
+                state.error = None;
+            }
+            Key::Backspace => {
+                state.input.pop();
+                state.error = None;
+            }
+            Key::Esc => {
+                self.mode = Mode::Browse;
+                self.status = "Edit cancelled".to_string();
+            }
+            Key::Enter if commit_field(state, &mut self.normalizer) => {
+                let row = state.row;
+                self.status = if state.cell == self.dataset.cells()[row] {
+                    "No fields changed".to_string()
+                } else {
+                    if let Some(old) = self.dataset.modify(row, state.cell.clone())? {
+                        self.changes.push((row, old));
+                    }
+                    format!("Changed row {} (u to undo, s to save)", row)
+                };
+                self.mode = Mode::Browse;
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    /*
+        Write the dataset back to its file and forget the changes, so they can no longer be
+        undone. A changed row whose phone was tagged takes its tags along to its new oem and
+        model.
+
+        Runtime: O(n + m) for m changes
+     */
+    fn save(&mut self) -> Result<(), CellError> {
+        let mut tags = Tags::load(&self.csv_file, &self.normalizer)?;
+        // The earliest replaced cell of a row is the row as it was saved last.
+        let mut saved: HashMap<usize, &Cell> = HashMap::new();
+        for (index, old) in &self.changes {
+            saved.entry(*index).or_insert(old);
+        }
+        for (index, old) in saved {
+            let new = &self.dataset.cells()[index];
+            if let (Some(oem), Some(model), Some(new_oem), Some(new_model)) = (&old.oem, &old.model, &new.oem, &new.model) {
+                tags.rename(&self.normalizer, oem, model, new_oem, new_model);
+            }
+        }
+
+        Cell::write_csv(self.dataset.cells(), &self.csv_file)?;
+        tags.save(&self.csv_file)?;
+        self.changes.clear();
+        Ok(())
+    }
+
+    fn scroll_to_selected(&mut self) {
+        if self.selected < self.top {
+            self.top = self.selected;
+        } else if self.selected >= self.top + self.page {
+            self.top = self.selected + 1 - self.page;
+        }
+    }
+}
+
+impl Screen for App {
+    fn set_page(&mut self, page: usize) {
+        self.page = page.max(1);
+        self.scroll_to_selected();
+    }
+
+    /*
+        Runtime: O(log n) when an edit is committed or undone, O(n) for a save, O(1) otherwise
+     */
+    fn handle(&mut self, key: Key) -> Result<(), CellError> {
+        match &mut self.mode {
+            Mode::Browse => self.handle_browse(key)?,
+            Mode::Edit(_) => self.handle_edit(key)?,
+        }
+
+        self.scroll_to_selected();
+        Ok(())
+    }
+
+    /*
+        Runtime: O(page * c)
+     */
+    fn render(&self, width: usize) -> Vec<String> {
+        let mut lines = Vec::new();
+        let headers: Vec<String> = Column::ALL.iter().map(|column| column.name().to_string()).collect();
+        let unsaved = format!("{} unsaved changes", self.changes.len());
+
+        match &self.mode {
+            Mode::Browse => {
+                lines.push("Up/Down move  PgUp/PgDn page  Enter edit  u undo  s save  q quit".to_string());
+                lines.push(format!("{}  {}", unsaved, self.status));
+
+                let end = (self.top + self.page).min(self.dataset.len());
+                let visible: Vec<Vec<String>> = self.dataset.cells()[self.top..end].iter().map(row_text).collect();
+                let widths = table::column_widths(&headers, &visible);
+                lines.push(format!("  {}", table::format_row(&headers, &widths)));
+                let rule: Vec<String> = widths.iter().map(|&width| "-".repeat(width)).collect();
+                lines.push(format!("  {}", table::format_row(&rule, &widths)));
+
+                for (offset, row) in visible.iter().enumerate() {
+                    let marker = if self.top + offset == self.selected { "> " } else { "  " };
+                    lines.push(format!("{}{}", marker, table::format_row(row, &widths)));
+                }
+            }
+            Mode::Edit(state) => {
+                lines.push(format!("Editing row {}  Tab/Shift-Tab field  Enter commit  Esc cancel", state.row));
+                lines.push(state.error.clone().unwrap_or(unsaved));
+
+                let name_width = headers.iter().map(|header| header.len()).max().unwrap_or(0);
+                for (index, header) in headers.iter().enumerate() {
+                    if index == state.field {
+                        lines.push(format!("> {:<width$} : {}_", header, state.input, width = name_width));
+                    } else {
+                        lines.push(format!("  {:<width$} : {}", header, state.values[index], width = name_width));
+                    }
+                }
+            }
+        }
+
+        lines.into_iter().map(|line| table::truncate(&line, width)).collect()
+    }
+
+    fn should_quit(&self) -> bool {
+        self.quit
+    }
+}
+
+// The fields of a cell as text, in column order, with missing values blank.
+fn row_text(cell: &Cell) -> Vec<String> {
+    Column::ALL.iter().map(|&column| cell.text(column).unwrap_or_default()).collect()
+}
+
+/*
+    Validate the typed input, clean it as loading the file would and set it on the working
+    copy. Returns false, with the error shown, if it is invalid.
+
+    Runtime: O(n) for n typed characters
+ */
+fn commit_field(state: &mut EditState, normalizer: &mut Normalizer) -> bool {
+    let column = Column::ALL[state.field];
+    let cleaned = normalizer.normalize(column, &state.input).unwrap_or_default();
+    let result = edits::validate_raw(column, &state.input).and_then(|_| state.cell.set_text(column, &cleaned).map_err(|error| error.to_string()));
+    if let Err(message) = result {
+        state.error = Some(message);
+        return false;
+    }
+
+    state.values[state.field] = state.cell.text(column).unwrap_or_default();
+    state.error = None;
+    true
+}
+
+/*
+    cells tui [--file cells.csv]
+
+    Browse the loaded file and edit records in place. Each committed record replaces its
+    cell through Dataset::modify, `u` puts the replaced cell back, and `s` writes the
+    dataset to the file, with its values as loaded and cleaned. Unlike `cells crud modify`,
+    nothing is queued in the file's journal.
+ */
+pub fn run(csv_file: &str, config: &Config) -> Result<(), CellError> {
+    let (dataset, normalizer) = Dataset::load(csv_file, config)?;
+    show(&mut App::new(csv_file, dataset, normalizer))
+}
+
+// Draws a screen in the alternate screen buffer until it asks to quit.
+pub fn show(screen: &mut impl Screen) -> Result<(), CellError> {
+    let mut out = io::stdout();
+
+    terminal::enable_raw_mode()?;
+    execute!(out, terminal::EnterAlternateScreen, cursor::Hide)?;
+    let result = event_loop(screen, &mut out);
+    execute!(out, cursor::Show, terminal::LeaveAlternateScreen)?;
+    terminal::disable_raw_mode()?;
+
+    result
+}
+
+fn event_loop(screen: &mut impl Screen, out: &mut io::Stdout) -> Result<(), CellError> {
+    while !screen.should_quit() {
+        let (width, height) = terminal::size()?;
+        screen.set_page((height as usize).saturating_sub(HEADER_LINES));
+
+        queue!(out, terminal::Clear(terminal::ClearType::All))?;
+        for (line_number, line) in screen.render(width as usize).iter().enumerate() {
+            queue!(out, cursor::MoveTo(0, line_number as u16), Print(line))?;
+        }
+        out.flush()?;
+
+        if let Event::Key(key) = event::read()? {
+            if key.kind == KeyEventKind::Press {
+                if let Some(key) = Key::from_event(key) {
+                    screen.handle(key)?;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
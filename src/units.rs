@@ -0,0 +1,59 @@
+use crate::dimensions::MM_PER_INCH;
+use crate::weight::GRAMS_PER_OUNCE;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+/// The units a report prints weights and lengths in. The analyses work in the dataset's
+/// own units, so one set of results can be printed in each profile.
+pub enum UnitProfile {
+    /// Grams, display sizes in centimetres and body sizes in millimetres.
+    Metric,
+    /// Ounces, and inches for display and body sizes.
+    Imperial,
+    /// As the dataset gives them: grams, display sizes in inches and body sizes in millimetres.
+    #[default]
+    Mixed,
+}
+
+impl UnitProfile {
+    pub fn name(&self) -> &'static str {
+        match self {
+            UnitProfile::Metric => "metric",
+            UnitProfile::Imperial => "imperial",
+            UnitProfile::Mixed => "mixed",
+        }
+    }
+
+    // A body weight in grams in this profile's unit, with the unit.
+    pub fn weight(&self, grams: f32) -> (f32, &'static str) {
+        match self {
+            UnitProfile::Imperial => (grams / GRAMS_PER_OUNCE, "oz"),
+            UnitProfile::Metric | UnitProfile::Mixed => (grams, "g"),
+        }
+    }
+
+    // A display diagonal in inches in this profile's unit, with the unit.
+    pub fn display_size(&self, inches: f32) -> (f32, &'static str) {
+        match self {
+            UnitProfile::Metric => (inches * MM_PER_INCH / 10.0, "cm"),
+            UnitProfile::Imperial | UnitProfile::Mixed => (inches, "inches"),
+        }
+    }
+
+    // A body size in millimetres in this profile's unit, with the unit.
+    pub fn length(&self, millimetres: f32) -> (f32, &'static str) {
+        match self {
+            UnitProfile::Imperial => (millimetres / MM_PER_INCH, "in"),
+            UnitProfile::Metric | UnitProfile::Mixed => (millimetres, "mm"),
+        }
+    }
+}
+
+impl fmt::Display for UnitProfile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
@@ -0,0 +1,216 @@
+use crate::cancel::{self, Cancel};
+use crate::column::Column;
+use crate::error::CellError;
+use crate::filter::Filter;
+use crate::grouping::FloatGrouping;
+use crate::query::SortKey;
+use crate::random::Rng;
+use crate::summary::DatasetStats;
+use crate::Cell;
+use std::collections::BTreeMap;
+use std::iter::Copied;
+use std::ops::Index;
+use std::slice::Iter;
+
+/// The name the chainable methods read best under:
+/// `collection.filter(...).group_by_oem().mean_weight()`.
+pub type CellCollection<'a> = DatasetView<'a>;
+
+#[derive(Debug, Clone, Default)]
+/// A selection of cells borrowed from a dataset, in their original order. Filtering a view
+/// narrows it without copying any cell, and every analysis takes a view, so "the most common
+/// OEM of the phones after 2015" is a filter followed by the analysis.
+pub struct DatasetView<'a> {
+    cells: Vec<&'a Cell>,
+}
+
+impl<'a> DatasetView<'a> {
+    /// A view of every cell.
+    pub fn of(cells: &'a [Cell]) -> DatasetView<'a> {
+        DatasetView { cells: cells.iter().collect() }
+    }
+
+    /// The cells of this view for which `keep` returns true.
+    pub fn filter(&self, keep: impl Fn(&Cell) -> bool) -> DatasetView<'a> {
+        DatasetView { cells: self.cells.iter().copied().filter(|cell| keep(cell)).collect() }
+    }
+
+    /// The cells of this view matching a filter expression.
+    pub fn matching(&self, filter: &Filter) -> DatasetView<'a> {
+        self.filter(|cell| filter.matches(cell))
+    }
+
+    /// A random `fraction` (0 to 1) of the cells, at least one of a non-empty view, in the
+    /// order of this view.
+    pub fn sample(&self, fraction: f64, rng: &mut Rng) -> DatasetView<'a> {
+        let len = self.cells.len();
+        let wanted = ((len as f64 * fraction.clamp(0.0, 1.0)).round() as usize).clamp(len.min(1), len);
+
+        // A partial Fisher-Yates shuffle of the indices picks them without repeats.
+        let mut indices: Vec<usize> = (0..len).collect();
+        for picked in 0..wanted {
+            let swap = picked + rng.below(len - picked);
+            indices.swap(picked, swap);
+        }
+        let mut picked = indices[..wanted].to_vec();
+        picked.sort_unstable();
+
+        DatasetView { cells: picked.into_iter().map(|index| self.cells[index]).collect() }
+    }
+
+    /// Like `filter`, but stops with an error once `cancel` is cancelled or timed out.
+    pub fn try_filter(&self, keep: impl Fn(&Cell) -> bool, cancel: &Cancel) -> Result<DatasetView<'a>, CellError> {
+        let mut cells = Vec::new();
+        for chunk in self.cells.chunks(cancel::CHECK_EVERY) {
+            cancel.check()?;
+            cells.extend(chunk.iter().copied().filter(|cell| keep(cell)));
+        }
+
+        Ok(DatasetView { cells })
+    }
+
+    /// Like `matching`, but stops with an error once `cancel` is cancelled or timed out.
+    pub fn try_matching(&self, filter: &Filter, cancel: &Cancel) -> Result<DatasetView<'a>, CellError> {
+        self.try_filter(|cell| filter.matches(cell), cancel)
+    }
+
+    /// The cells sorted by a column, numerically where both values are numbers, with missing
+    /// values last. Equal values keep their order.
+    pub fn sort_by_field(&self, column: Column, descending: bool) -> DatasetView<'a> {
+        let key = SortKey { column, descending };
+        let mut cells = self.cells.clone();
+        cells.sort_by(|a, b| key.compare(a, b));
+
+        DatasetView { cells }
+    }
+
+    /// The cells grouped by the text of a column, in key order, with float columns bucketed as
+    /// `FloatGrouping::DEFAULT` does. Cells without a value are left out.
+    pub fn group_by(&self, column: Column) -> Groups<'a> {
+        self.group_by_with(column, &FloatGrouping::DEFAULT)
+    }
+
+    /// The cells grouped by a column, with float columns bucketed by `grouping`.
+    pub fn group_by_with(&self, column: Column, grouping: &FloatGrouping) -> Groups<'a> {
+        let mut groups: BTreeMap<String, Vec<&'a Cell>> = BTreeMap::new();
+        for cell in self.iter() {
+            if let Some(key) = grouping.key(cell, column) {
+                groups.entry(key).or_default().push(cell);
+            }
+        }
+
+        Groups { groups: groups.into_iter().map(|(key, cells)| (key, DatasetView { cells })).collect() }
+    }
+
+    /// Like `group_by_with`, but stops with an error once `cancel` is cancelled or timed out.
+    pub fn try_group_by_with(&self, column: Column, grouping: &FloatGrouping, cancel: &Cancel) -> Result<Groups<'a>, CellError> {
+        let mut groups: BTreeMap<String, Vec<&'a Cell>> = BTreeMap::new();
+        for chunk in self.cells.chunks(cancel::CHECK_EVERY) {
+            cancel.check()?;
+            for &cell in chunk {
+                if let Some(key) = grouping.key(cell, column) {
+                    groups.entry(key).or_default().push(cell);
+                }
+            }
+        }
+
+        Ok(Groups { groups: groups.into_iter().map(|(key, cells)| (key, DatasetView { cells })).collect() })
+    }
+
+    pub fn group_by_oem(&self) -> Groups<'a> {
+        self.group_by(Column::Oem)
+    }
+
+    /// The mean of a numeric column over the cells that have a value; None if none do.
+    pub fn mean(&self, column: Column) -> Option<f32> {
+        let values: Vec<f32> = self.iter().filter_map(|cell| cell.number(column)).collect();
+        (!values.is_empty()).then(|| values.iter().sum::<f32>() / values.len() as f32)
+    }
+
+    pub fn mean_weight(&self) -> Option<f32> {
+        self.mean(Column::BodyWeight)
+    }
+
+    /// The count, nulls, sum, min and max of every column over the cells of the view.
+    pub fn stats(&self) -> DatasetStats {
+        DatasetStats::of_refs(self.iter())
+    }
+
+    pub fn iter(&self) -> Copied<Iter<'_, &'a Cell>> {
+        self.cells.iter().copied()
+    }
+
+    /// The cells of the view, e.g. for the table renderers.
+    pub fn cells(&self) -> &[&'a Cell] {
+        &self.cells
+    }
+
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+}
+
+impl<'a, 'b> IntoIterator for &'b DatasetView<'a> {
+    type Item = &'a Cell;
+    type IntoIter = Copied<Iter<'b, &'a Cell>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl Index<usize> for DatasetView<'_> {
+    type Output = Cell;
+
+    fn index(&self, index: usize) -> &Cell {
+        self.cells[index]
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+/// A view split into groups by the value of a column, each group a view of its own.
+pub struct Groups<'a> {
+    groups: Vec<(String, DatasetView<'a>)>,
+}
+
+impl<'a> Groups<'a> {
+    /// The group with this key, if any cell has it.
+    pub fn get(&self, key: &str) -> Option<&DatasetView<'a>> {
+        self.groups.iter().find(|(group, _)| group == key).map(|(_, view)| view)
+    }
+
+    /// Keeps the groups for which `keep` returns true.
+    pub fn filter(self, keep: impl Fn(&str, &DatasetView<'a>) -> bool) -> Groups<'a> {
+        Groups { groups: self.groups.into_iter().filter(|(key, view)| keep(key, view)).collect() }
+    }
+
+    /// How many cells each group has.
+    pub fn counts(&self) -> Vec<(String, usize)> {
+        self.groups.iter().map(|(key, view)| (key.clone(), view.len())).collect()
+    }
+
+    /// The mean of a numeric column per group, None for groups without any value.
+    pub fn mean(&self, column: Column) -> Vec<(String, Option<f32>)> {
+        self.groups.iter().map(|(key, view)| (key.clone(), view.mean(column))).collect()
+    }
+
+    pub fn mean_weight(&self) -> Vec<(String, Option<f32>)> {
+        self.mean(Column::BodyWeight)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &DatasetView<'a>)> {
+        self.groups.iter().map(|(key, view)| (key.as_str(), view))
+    }
+
+    pub fn len(&self) -> usize {
+        self.groups.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.groups.is_empty()
+    }
+}
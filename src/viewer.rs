@@ -0,0 +1,209 @@
+use crate::column::Column;
+use crate::error::CellError;
+use crate::format::NumberFormat;
+use crate::table;
+use crate::tui::{Key, Screen};
+use crate::Cell;
+
+// Columns kept on screen while the others scroll sideways, in this order.
+pub const FROZEN_COLUMNS: [Column; 2] = [Column::Oem, Column::Model];
+
+/*
+    A read-only, scrollable view of a table. The leading `frozen` columns are always drawn;
+    the rest scroll left and right with the selected column and can be hidden one by one.
+ */
+pub struct TableViewer {
+    headers: Vec<String>,
+    rows: Vec<Vec<String>>,
+    frozen: usize,
+    hidden: Vec<bool>,
+    // Selected column, never one of the frozen ones.
+    column: usize,
+    // First scrollable column considered when drawing.
+    left: usize,
+    selected: usize,
+    top: usize,
+    page: usize,
+    status: String,
+    quit: bool,
+}
+
+impl TableViewer {
+    pub fn new(headers: Vec<String>, rows: Vec<Vec<String>>, frozen: usize) -> TableViewer {
+        let frozen = frozen.min(headers.len());
+
+        TableViewer {
+            hidden: vec![false; headers.len()],
+            headers,
+            rows,
+            frozen,
+            column: frozen,
+            left: frozen,
+            selected: 0,
+            top: 0,
+            page: 20,
+            status: String::new(),
+            quit: false,
+        }
+    }
+
+    /*
+        View the given columns of some cells, with the OEM and model columns moved to the
+        front and frozen if they are among them. Numbers are written by `format`.
+
+        Runtime: O(n * c)
+     */
+    pub fn from_cells(cells: &[&Cell], columns: &[Column], format: &NumberFormat) -> TableViewer {
+        let mut ordered: Vec<Column> = FROZEN_COLUMNS.iter().copied().filter(|column| columns.contains(column)).collect();
+        let frozen = ordered.len();
+        ordered.extend(columns.iter().copied().filter(|column| !FROZEN_COLUMNS.contains(column)));
+
+        let headers = ordered.iter().map(|column| column.name().to_string()).collect();
+        let rows = cells
+            .iter()
+            .map(|cell| ordered.iter().map(|&column| format.text(cell, column).unwrap_or_default()).collect())
+            .collect();
+
+        TableViewer::new(headers, rows, frozen)
+    }
+
+    // Visible scrollable columns, left to right.
+    fn scrollable(&self) -> Vec<usize> {
+        (self.frozen..self.headers.len()).filter(|&index| !self.hidden[index]).collect()
+    }
+
+    // Moves the column selection by one visible column to the left (-1) or right (+1).
+    fn move_column(&mut self, step: isize) {
+        let scrollable = self.scrollable();
+        let Some(position) = scrollable.iter().position(|&index| index == self.column) else {
+            return;
+        };
+
+        let next = position as isize + step;
+        if next >= 0 && (next as usize) < scrollable.len() {
+            self.column = scrollable[next as usize];
+        }
+        self.left = self.left.min(self.column);
+    }
+
+    fn hide_column(&mut self) {
+        let scrollable = self.scrollable();
+        if scrollable.len() <= 1 {
+            self.status = "Cannot hide the last scrolling column".to_string();
+            return;
+        }
+
+        let position = scrollable.iter().position(|&index| index == self.column).unwrap_or(0);
+        self.hidden[self.column] = true;
+        self.status = format!("Hid {}", self.headers[self.column]);
+
+        // Select the column that took its place, or the one before it at the right edge.
+        self.column = scrollable.get(position + 1).copied().unwrap_or(scrollable[position.saturating_sub(1)]);
+        self.left = self.left.min(self.column);
+    }
+
+    fn scroll_to_selected(&mut self) {
+        if self.selected < self.top {
+            self.top = self.selected;
+        } else if self.selected >= self.top + self.page {
+            self.top = self.selected + 1 - self.page;
+        }
+    }
+
+    /*
+        The columns drawn at the given width: the frozen ones, then as many scrollable ones as
+        fit, starting far enough right that the selected column is one of them.
+
+        Runtime: O(c^2)
+     */
+    fn drawn_columns(&self, widths: &[usize], width: usize) -> Vec<usize> {
+        let scrollable: Vec<usize> = self.scrollable().into_iter().filter(|&index| index >= self.left).collect();
+        let frozen: Vec<usize> = (0..self.frozen).collect();
+        let line_width = |columns: &[usize]| 2 + columns.iter().map(|&index| widths[index] + 3).sum::<usize>();
+
+        let mut start = 0;
+        loop {
+            let mut drawn = frozen.clone();
+            for &index in &scrollable[start..] {
+                drawn.push(index);
+                if line_width(&drawn) > width + 3 {
+                    drawn.pop();
+                    break;
+                }
+            }
+
+            let shows_selected = drawn.contains(&self.column) || !scrollable.contains(&self.column);
+            if shows_selected || start + 1 >= scrollable.len() {
+                return drawn;
+            }
+            start += 1;
+        }
+    }
+}
+
+impl Screen for TableViewer {
+    fn set_page(&mut self, page: usize) {
+        self.page = page.max(1);
+        self.scroll_to_selected();
+    }
+
+    fn handle(&mut self, key: Key) -> Result<(), CellError> {
+        let last = self.rows.len().saturating_sub(1);
+        self.status.clear();
+
+        match key {
+            Key::Up => self.selected = self.selected.saturating_sub(1),
+            Key::Down => self.selected = (self.selected + 1).min(last),
+            Key::PageUp => self.selected = self.selected.saturating_sub(self.page),
+            Key::PageDown => self.selected = (self.selected + self.page).min(last),
+            Key::Left => self.move_column(-1),
+            Key::Right => self.move_column(1),
+            Key::Char('h') => self.hide_column(),
+            Key::Char('a') => {
+                self.hidden.iter_mut().for_each(|hidden| *hidden = false);
+                self.status = "Showing all columns".to_string();
+            }
+            Key::Char('q') | Key::Esc => self.quit = true,
+            _ => {}
+        }
+
+        self.scroll_to_selected();
+        Ok(())
+    }
+
+    /*
+        Runtime: O(page * c)
+     */
+    fn render(&self, width: usize) -> Vec<String> {
+        let mut lines = vec!["Up/Down/PgUp/PgDn rows  Left/Right columns  h hide  a show all  q quit".to_string()];
+        let hidden = self.hidden.iter().filter(|&&hidden| hidden).count();
+        let column = self.headers.get(self.column).map(String::as_str).unwrap_or("-");
+        lines.push(format!("row {} of {}  column {}  {} hidden  {}", self.selected + 1, self.rows.len(), column, hidden, self.status));
+
+        let end = (self.top + self.page).min(self.rows.len());
+        let visible = &self.rows[self.top..end];
+        let widths = table::column_widths(&self.headers, visible);
+        let drawn = self.drawn_columns(&widths, width);
+        let drawn_widths: Vec<usize> = drawn.iter().map(|&index| widths[index]).collect();
+        let pick = |row: &[String]| -> Vec<String> { drawn.iter().map(|&index| row.get(index).cloned().unwrap_or_default()).collect() };
+
+        lines.push(format!("  {}", table::format_row(&pick(&self.headers), &drawn_widths)));
+        // The rule under the selected column is drawn with '=' instead of '-'.
+        let rule: Vec<String> = drawn
+            .iter()
+            .map(|&index| if index == self.column { "=" } else { "-" }.repeat(widths[index]))
+            .collect();
+        lines.push(format!("  {}", table::format_row(&rule, &drawn_widths)));
+
+        for (offset, row) in visible.iter().enumerate() {
+            let marker = if self.top + offset == self.selected { "> " } else { "  " };
+            lines.push(format!("{}{}", marker, table::format_row(&pick(row), &drawn_widths)));
+        }
+
+        lines.into_iter().map(|line| table::truncate(&line, width)).collect()
+    }
+
+    fn should_quit(&self) -> bool {
+        self.quit
+    }
+}
@@ -0,0 +1,233 @@
+use crate::aliases::ModelAliases;
+use crate::column::HeaderIndex;
+use crate::config::Config;
+use crate::dataset::Dataset;
+use crate::error::CellError;
+use crate::normalize::Normalizer;
+use crate::parse::{ParseMode, ParseReport};
+use crate::schema::Schema;
+use crate::tags::Tags;
+use crate::Cell;
+use std::collections::hash_map::DefaultHasher;
+use std::fs::File;
+use std::hash::Hasher;
+use std::io::{Cursor, Read, Seek, SeekFrom};
+
+// How many bytes before the end of the parsed part are compared to tell appends from rewrites.
+const FINGERPRINT_BYTES: u64 = 4096;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+// What a refresh found.
+pub enum Refresh {
+    // The file is as it was.
+    Unchanged,
+    // The file only grew; this many new rows were parsed and appended.
+    Appended(usize),
+    // The file shrank or its parsed part changed, so it was loaded again from the start.
+    Reloaded,
+}
+
+/*
+    A dataset kept in step with a CSV file that other programs append to. It is loaded as
+    `cells` loads a file: parsed with the config's schema, normalization and parse mode, then
+    given its tags and model aliases. refresh() parses only the complete records added since
+    the last call, and falls back to a full reload when the file was rewritten instead of
+    appended to.
+ */
+pub struct LiveDataset {
+    path: String,
+    schema: Schema,
+    normalizer: Normalizer,
+    mode: ParseMode,
+    model_aliases: Option<String>,
+    tags: Tags,
+    headers: HeaderIndex,
+    // Fields in the header, which every record must have.
+    fields: usize,
+    dataset: Dataset,
+    // Bytes parsed so far, always the end of a complete record (or of the file).
+    offset: u64,
+    // Records parsed so far, the header included, so appended rows get their line numbers.
+    records: u64,
+    // Hash of the bytes just before the offset, to notice rewrites.
+    fingerprint: u64,
+}
+
+impl LiveDataset {
+    /*
+        Load the whole file.
+
+        Runtime: O(n)
+     */
+    pub fn open(path: &str, config: &Config) -> Result<LiveDataset, CellError> {
+        let mut live = LiveDataset {
+            path: path.to_string(),
+            schema: Schema::from_config(config)?,
+            normalizer: Normalizer::from_config(config),
+            mode: config.parse_mode,
+            model_aliases: config.model_aliases.clone(),
+            tags: Tags::default(),
+            // Filled in by load, like everything below.
+            headers: HeaderIndex::from_headers(&csv::StringRecord::new())?,
+            fields: 0,
+            dataset: Dataset::default(),
+            offset: 0,
+            records: 0,
+            fingerprint: 0,
+        };
+        live.load()?;
+
+        Ok(live)
+    }
+
+    pub fn dataset(&self) -> &Dataset {
+        &self.dataset
+    }
+
+    /*
+        Bring the dataset up to date with the file: append the rows of any complete records
+        added since the last refresh, or reload if the file was rewritten. A last record still
+        being written is left for the next refresh.
+
+        Runtime: O(m) for m appended bytes, O(n) for a reload
+     */
+    pub fn refresh(&mut self) -> Result<Refresh, CellError> {
+        let mut file = File::open(&self.path)?;
+        let len = file.metadata()?.len();
+
+        if len < self.offset || fingerprint(&mut file, self.offset)? != self.fingerprint {
+            self.load()?;
+            return Ok(Refresh::Reloaded);
+        }
+
+        let mut appended = Vec::new();
+        file.seek(SeekFrom::Start(self.offset))?;
+        file.read_to_end(&mut appended)?;
+        let (records, parsed) = complete_records(&appended, self.offset, self.records)?;
+        if records.is_empty() {
+            return Ok(Refresh::Unchanged);
+        }
+
+        let (mut cells, report) = self.parse(&records)?;
+        self.tags.apply(&mut cells);
+        let rows = cells.len();
+        self.dataset.append_parsed(cells, report);
+
+        self.offset += parsed as u64;
+        self.records += rows as u64;
+        self.fingerprint = fingerprint(&mut file, self.offset)?;
+        Ok(Refresh::Appended(rows))
+    }
+
+    /*
+        Parse the whole file again, with its tags and model aliases as they are now. The
+        header is always taken; a file without any complete record after it is read as a
+        header alone.
+
+        Runtime: O(n)
+     */
+    fn load(&mut self) -> Result<(), CellError> {
+        let mut bytes = Vec::new();
+        File::open(&self.path)?.read_to_end(&mut bytes)?;
+
+        let mut reader = csv::ReaderBuilder::new().has_headers(false).from_reader(bytes.as_slice());
+        let mut header = csv::StringRecord::new();
+        reader.read_record(&mut header)?;
+        let header_end = reader.position().byte();
+        self.headers = HeaderIndex::from_headers_with(&header, &self.schema)?.in_file(&self.path);
+        self.fields = header.len();
+
+        let (records, parsed) = complete_records(&bytes[header_end as usize..], header_end, 1)?;
+        let (mut cells, report) = self.parse(&records)?;
+        self.tags = Tags::load(&self.path, &self.normalizer)?;
+        self.tags.apply(&mut cells);
+        self.dataset = Dataset::default();
+        self.dataset.append_parsed(cells, report);
+        if let Some(path) = &self.model_aliases {
+            self.dataset.apply_model_aliases(ModelAliases::from_csv(path)?);
+        }
+
+        self.offset = header_end + parsed as u64;
+        self.records = 1 + records.len() as u64;
+        self.fingerprint = fingerprint(&mut File::open(&self.path)?, self.offset)?;
+        Ok(())
+    }
+
+    /*
+        Parse records into cells with the file's normalizer, reporting the values that are not
+        numbers. In strict mode any such value fails the whole batch, as it fails a full load.
+
+        Runtime: O(m) for m records
+     */
+    fn parse(&mut self, records: &[csv::StringRecord]) -> Result<(Vec<Cell>, ParseReport), CellError> {
+        let mut report = ParseReport::default();
+        let mut cells = Vec::with_capacity(records.len());
+
+        for record in records {
+            let line = record.position().map_or(0, |position| position.record() as usize + 1);
+            if record.len() != self.fields {
+                return Err(CellError::InvalidField { row: Some(line), field: "number of fields", value: record.len().to_string() });
+            }
+            cells.push(Cell::parse_record(record, &self.headers, &mut self.normalizer, &mut report));
+        }
+
+        if self.mode == ParseMode::Strict && !report.is_empty() {
+            return Err(CellError::Invalid(report));
+        }
+        Ok((cells, report))
+    }
+}
+
+/*
+    Read the complete records of some bytes of the file, which start at byte `offset` after
+    `records` earlier records, and return them with the number of bytes they take. csv also
+    ends a record at the end of the bytes, so the last one only counts as complete if one
+    more byte after it would start a new record instead of joining it: a line break outside
+    quotes ends it. A record still being written, maybe inside a quoted field that holds a
+    line break, is left for the next refresh.
+
+    Runtime: O(m) for m bytes
+ */
+fn complete_records(bytes: &[u8], offset: u64, records: u64) -> Result<(Vec<csv::StringRecord>, usize), CellError> {
+    let mut reader = csv::ReaderBuilder::new().has_headers(false).flexible(true).from_reader(Cursor::new(bytes));
+    let mut position = csv::Position::new();
+    position.set_byte(offset).set_record(records);
+    reader.seek_raw(SeekFrom::Start(0), position)?;
+
+    let mut complete = Vec::new();
+    let mut end = 0;
+    let mut record = csv::StringRecord::new();
+    while reader.read_record(&mut record)? {
+        let next = (reader.position().byte() - offset) as usize;
+        if next == bytes.len() && !ends_record(&bytes[end..], &record)? {
+            break;
+        }
+        complete.push(record.clone());
+        end = next;
+    }
+
+    Ok((complete, end))
+}
+
+// Whether the bytes of a record read up to the end of the input end it: parsed with one more
+// byte after them, the first record is still the same.
+fn ends_record(bytes: &[u8], record: &csv::StringRecord) -> Result<bool, CellError> {
+    let probe = [bytes, b"x"].concat();
+    let mut reader = csv::ReaderBuilder::new().has_headers(false).flexible(true).from_reader(probe.as_slice());
+    let mut first = csv::StringRecord::new();
+    reader.read_record(&mut first)?;
+
+    Ok(first.iter().eq(record.iter()))
+}
+
+// Hash of up to FINGERPRINT_BYTES bytes ending at the offset.
+fn fingerprint(file: &mut File, offset: u64) -> Result<u64, CellError> {
+    let start = offset.saturating_sub(FINGERPRINT_BYTES);
+    let mut bytes = vec![0; (offset - start) as usize];
+    file.seek(SeekFrom::Start(start))?;
+    file.read_exact(&mut bytes)?;
+
+    let mut hasher = DefaultHasher::new();
+    hasher.write(&bytes);
+    Ok(hasher.finish())
+}
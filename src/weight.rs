@@ -0,0 +1,65 @@
+// Grams in an ounce and in a pound, to compare a weight's two units.
+pub const GRAMS_PER_OUNCE: f32 = 28.3495;
+const GRAMS_PER_POUND: f32 = 16.0 * GRAMS_PER_OUNCE;
+
+// How far the two units of a weight may differ, as a share of the grams, before they
+// disagree. Ounces are given to two decimals, so rounding alone stays well inside it. Light
+// phones get at least a gram either way.
+pub const WEIGHT_TOLERANCE: f32 = 0.02;
+const MIN_TOLERANCE_GRAMS: f32 = 1.0;
+
+#[derive(Debug, Clone, PartialEq, Default)]
+// Both units a body_weight gives. Phones sold in variants list a weight for each.
+pub struct Weight {
+    pub grams: Vec<f32>,
+    // The imperial weight in ounces, converted from pounds where the text gives those.
+    pub ounces: Option<f32>,
+}
+
+impl Weight {
+    /*
+        Parse a body_weight such as "190 g (6.70 oz)", a tablet's "680 g (1.50 lb)", "135
+        (4.76 oz)" or "183 g (Global)/ 185 g (Verizon) (6.46 oz)". A number followed by "g"
+        is a weight in grams, and so is a leading number with no unit.
+
+        Runtime: O(n)
+     */
+    pub fn parse(body_weight: &str) -> Weight {
+        let words: Vec<&str> = body_weight.split(|c: char| c.is_whitespace() || c == '(' || c == ')' || c == '/').filter(|word| !word.is_empty()).collect();
+        let number = |index: usize| words.get(index).and_then(|word| word.parse::<f32>().ok());
+
+        let mut weight = Weight::default();
+        for index in 0..words.len() {
+            let Some(value) = number(index) else { continue };
+            match words.get(index + 1).copied() {
+                Some("g") => weight.grams.push(value),
+                Some("oz") => weight.ounces = Some(value),
+                Some("lb") => weight.ounces = Some(value * GRAMS_PER_POUND / GRAMS_PER_OUNCE),
+                _ if index == 0 => weight.grams.push(value),
+                _ => {}
+            }
+        }
+
+        weight
+    }
+
+    // The imperial weight in grams.
+    pub fn ounces_in_grams(&self) -> Option<f32> {
+        self.ounces.map(|ounces| ounces * GRAMS_PER_OUNCE)
+    }
+
+    /*
+        Whether the two units disagree: none of the gram weights is within the tolerance of
+        the converted ounces. A weight with only one unit can't disagree.
+
+        Runtime: O(v) for the variants
+     */
+    pub fn mismatch(&self) -> bool {
+        match self.ounces_in_grams() {
+            Some(converted) if !self.grams.is_empty() => {
+                !self.grams.iter().any(|grams| (grams - converted).abs() <= (grams * WEIGHT_TOLERANCE).max(MIN_TOLERANCE_GRAMS))
+            }
+            _ => false,
+        }
+    }
+}
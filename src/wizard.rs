@@ -0,0 +1,118 @@
+use crate::column::Column;
+use crate::error::CellError;
+use crate::schema::{Mapping, Schema};
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+
+// How many values of each column the wizard shows.
+pub const SAMPLE_ROWS: usize = 3;
+
+// The headers of a CSV file and, per header, its first few non-blank values.
+pub struct Samples {
+    pub headers: Vec<String>,
+    pub values: Vec<Vec<String>>,
+}
+
+impl Samples {
+    /*
+        Read the header row and up to `rows` non-blank values of each column, looking at no
+        more than the first hundred records.
+
+        Runtime: O(c)
+     */
+    pub fn read(path: &str, rows: usize) -> Result<Samples, CellError> {
+        let mut reader = csv::Reader::from_path(path)?;
+        let headers: Vec<String> = reader.headers()?.iter().map(|header| header.to_string()).collect();
+        let mut values = vec![Vec::new(); headers.len()];
+
+        for record in reader.records().take(100) {
+            let record = record?;
+            for (column, value) in values.iter_mut().zip(record.iter()) {
+                if column.len() < rows && !value.trim().is_empty() && !column.iter().any(|seen| seen == value) {
+                    column.push(value.to_string());
+                }
+            }
+        }
+
+        Ok(Samples { headers, values })
+    }
+}
+
+/*
+    Ask for the mapping of every header, one at a time. Each prompt shows the header, its
+    sample values and a suggestion (the column of the same name, or 'ignore'), which an
+    empty answer accepts. A column can only be chosen once, and every column must be chosen
+    by some header.
+
+    Runtime: O(c^2)
+ */
+pub fn ask_mapping(samples: &Samples, input: &mut impl BufRead, output: &mut impl Write) -> Result<Schema, CellError> {
+    let names: Vec<&str> = Column::ALL.iter().map(|column| column.name()).collect();
+    writeln!(output, "Map each of the {} columns to one of: {}, ignore", samples.headers.len(), names.join(", "))?;
+
+    let mut schema = Schema::default();
+    let mut chosen: Vec<(Column, &str)> = Vec::new();
+
+    for (header, values) in samples.headers.iter().zip(&samples.values) {
+        let suggested = match Schema::default().mapping(header) {
+            Some(Mapping::Field(column)) if !chosen.iter().any(|(taken, _)| *taken == column) => Mapping::Field(column),
+            _ => Mapping::Ignore,
+        };
+        writeln!(output)?;
+        writeln!(output, "{}: {}", header, if values.is_empty() { "(no values)".to_string() } else { values.join(" | ") })?;
+
+        let mapping = loop {
+            write!(output, "  maps to [{}]: ", suggested)?;
+            output.flush()?;
+
+            let mut answer = String::new();
+            if input.read_line(&mut answer)? == 0 {
+                return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "the import was cancelled").into());
+            }
+            let mapping = match answer.trim() {
+                "" => suggested,
+                answer => match answer.parse::<Mapping>() {
+                    Ok(mapping) => mapping,
+                    Err(error) => {
+                        writeln!(output, "  {}", error)?;
+                        continue;
+                    }
+                },
+            };
+            if let Mapping::Field(column) = mapping {
+                if let Some((_, other)) = chosen.iter().find(|(taken, _)| *taken == column) {
+                    writeln!(output, "  {} is already mapped from '{}'", column, other)?;
+                    continue;
+                }
+                chosen.push((column, header));
+            }
+            break mapping;
+        };
+        schema.insert(header, mapping);
+    }
+
+    let missing: Vec<Column> = Column::ALL.into_iter().filter(|column| !chosen.iter().any(|(taken, _)| taken == column)).collect();
+    if !missing.is_empty() {
+        return Err(CellError::Unmapped { columns: missing });
+    }
+
+    Ok(schema)
+}
+
+/*
+    Point the config file at a schema file, keeping its other settings. A missing config
+    file is created. Comments in the file are not kept.
+
+    Runtime: O(n)
+ */
+pub fn save_schema_path(config_path: &str, schema_path: &str) -> Result<(), CellError> {
+    let mut config: toml::Table = match Path::new(config_path).exists() {
+        true => toml::from_str(&fs::read_to_string(config_path)?).map_err(|error| CellError::Toml { path: config_path.to_string(), error })?,
+        false => toml::Table::new(),
+    };
+    config.insert("schema".to_string(), toml::Value::String(schema_path.to_string()));
+    fs::write(config_path, toml::to_string(&config)?)?;
+
+    Ok(())
+}
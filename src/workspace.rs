@@ -0,0 +1,211 @@
+use crate::column::Column;
+use crate::config::Config;
+use crate::dataset::{Dataset, JoinKind, JoinMatch, JoinedRow};
+use crate::error::CellError;
+use crate::normalize::Normalizer;
+use crate::Cell;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::path::Path;
+
+// Rows of each kind a diff prints before summing up the rest.
+pub const DIFF_LIST_LIMIT: usize = 20;
+
+// One dataset of a workspace, with the normalizer that cleaned it and the file it came from.
+pub struct Loaded {
+    // None for a dataset made in the workspace, e.g. a union.
+    pub file: Option<String>,
+    pub dataset: Dataset,
+    pub normalizer: Normalizer,
+}
+
+#[derive(Default)]
+/*
+    Several datasets held at once under names, e.g. an old and a new export of the same
+    source, so they can be compared, joined and combined without reloading them.
+ */
+pub struct Workspace {
+    datasets: BTreeMap<String, Loaded>,
+}
+
+// The key diff pairs rows on, as dedupe() compares them.
+fn key(cell: &Cell) -> (String, String) {
+    (cell.oem.as_deref().unwrap_or_default().to_lowercase(), cell.model.as_deref().unwrap_or_default().trim().to_lowercase())
+}
+
+// The oem and model a diff prints for a row.
+fn phone(cell: &Cell) -> (String, String) {
+    (cell.oem.as_deref().unwrap_or("-").to_string(), cell.model.as_deref().unwrap_or("-").to_string())
+}
+
+impl Workspace {
+    pub fn new() -> Workspace {
+        Workspace::default()
+    }
+
+    // The name a file is loaded as when none is given: its file name without the extension.
+    pub fn default_name(file: &str) -> String {
+        Path::new(file).file_stem().map_or_else(|| file.to_string(), |stem| stem.to_string_lossy().into_owned())
+    }
+
+    /*
+        Load a CSV file under a name the way the commands load --file: with the config's
+        schema, cleaning and model aliases, and the file's tags. A dataset already loaded
+        under the name is replaced.
+
+        Runtime: O(n)
+     */
+    pub fn load(&mut self, file: &str, name: &str, config: &Config) -> Result<&Loaded, CellError> {
+        let (dataset, normalizer) = Dataset::load(file, config)?;
+        self.insert(name, Loaded { file: Some(file.to_string()), dataset, normalizer });
+        Ok(&self.datasets[name])
+    }
+
+    // Adds a dataset under a name, returning the one it replaced.
+    pub fn insert(&mut self, name: &str, loaded: Loaded) -> Option<Loaded> {
+        self.datasets.insert(name.to_string(), loaded)
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<Loaded> {
+        self.datasets.remove(name)
+    }
+
+    pub fn get(&self, name: &str) -> Result<&Loaded, CellError> {
+        self.datasets.get(name).ok_or_else(|| CellError::Unknown { kind: "dataset", name: name.to_string() })
+    }
+
+    // The datasets by name.
+    pub fn datasets(&self) -> impl Iterator<Item = (&str, &Loaded)> {
+        self.datasets.iter().map(|(name, loaded)| (name.as_str(), loaded))
+    }
+
+    pub fn len(&self) -> usize {
+        self.datasets.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.datasets.is_empty()
+    }
+
+    /*
+        Compare two datasets row by row, pairing rows on oem and model (ignoring case, as
+        dedupe does). Rows of a phone listed more than once pair up in file order, and a
+        paired row is changed when any of its columns differ.
+
+        Runtime: O(n + m)
+     */
+    pub fn diff(&self, left: &str, right: &str) -> Result<DatasetDiff, CellError> {
+        let (left_cells, right_cells) = (self.get(left)?.dataset.cells(), self.get(right)?.dataset.cells());
+        let mut diff = DatasetDiff { left: left.to_string(), right: right.to_string(), ..DatasetDiff::default() };
+
+        let mut right_rows: HashMap<(String, String), Vec<&Cell>> = HashMap::new();
+        for cell in right_cells {
+            right_rows.entry(key(cell)).or_default().push(cell);
+        }
+
+        // key -> rows of the right dataset paired so far
+        let mut paired: HashMap<(String, String), usize> = HashMap::new();
+        for cell in left_cells {
+            let key = key(cell);
+            let taken = paired.entry(key.clone()).or_default();
+            let Some(other) = right_rows.get(&key).and_then(|rows| rows.get(*taken)) else {
+                diff.removed.push(phone(cell));
+                continue;
+            };
+            *taken += 1;
+
+            let columns: Vec<Column> = Column::ALL.into_iter().filter(|&column| cell.text(column) != other.text(column)).collect();
+            if columns.is_empty() {
+                diff.unchanged += 1;
+            } else {
+                let (oem, model) = phone(cell);
+                diff.changed.push(ChangedRow { oem, model, columns });
+            }
+        }
+
+        for cell in right_cells {
+            match paired.get_mut(&key(cell)) {
+                Some(taken) if *taken > 0 => *taken -= 1,
+                _ => diff.added.push(phone(cell)),
+            }
+        }
+
+        Ok(diff)
+    }
+
+    /*
+        Put the rows of two datasets together as a new dataset under a name, leaving out the
+        rows of a phone already in it (see dedupe). Returns the rows left out.
+
+        Runtime: O(n + m)
+     */
+    pub fn union(&mut self, left: &str, right: &str, name: &str, config: &Config) -> Result<usize, CellError> {
+        let cells: Vec<Cell> = self.get(left)?.dataset.cells().iter().chain(self.get(right)?.dataset.cells()).cloned().collect();
+        let mut dataset = Dataset::new(cells);
+        let duplicates = dataset.dedupe();
+        let normalizer = Normalizer::from_config(config);
+        dataset.set_key_normalizer(normalizer.fork());
+
+        self.insert(name, Loaded { file: None, dataset, normalizer });
+        Ok(duplicates)
+    }
+
+    /*
+        Join the rows of one dataset with the loaded rows of another on oem and model,
+        keeping the rows without a match (see Dataset::join_with).
+
+        Runtime: O(n + m)
+     */
+    pub fn join(&self, left: &str, right: &str, matching: JoinMatch) -> Result<Vec<JoinedRow<'_>>, CellError> {
+        let other = &self.get(right)?.dataset;
+        Ok(self.get(left)?.dataset.join_with(other, &[Column::Oem, Column::Model], JoinKind::Left, matching))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+// A row of both datasets of a diff whose columns differ.
+pub struct ChangedRow {
+    pub oem: String,
+    pub model: String,
+    pub columns: Vec<Column>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+// How the rows of one dataset differ from another's.
+pub struct DatasetDiff {
+    pub left: String,
+    pub right: String,
+    // (oem, model) of the rows only the left dataset has, in its order.
+    pub removed: Vec<(String, String)>,
+    // (oem, model) of the rows only the right dataset has, in its order.
+    pub added: Vec<(String, String)>,
+    pub changed: Vec<ChangedRow>,
+    pub unchanged: usize,
+}
+
+impl fmt::Display for DatasetDiff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let more = |f: &mut fmt::Formatter, rows: usize| match rows.checked_sub(DIFF_LIST_LIMIT) {
+            Some(more) if more > 0 => writeln!(f, "  ... and {} more", more),
+            _ => Ok(()),
+        };
+
+        writeln!(f, "Only in {}: {}", self.left, self.removed.len())?;
+        for (oem, model) in self.removed.iter().take(DIFF_LIST_LIMIT) {
+            writeln!(f, "- {} {}", oem, model)?;
+        }
+        more(f, self.removed.len())?;
+        writeln!(f, "Only in {}: {}", self.right, self.added.len())?;
+        for (oem, model) in self.added.iter().take(DIFF_LIST_LIMIT) {
+            writeln!(f, "+ {} {}", oem, model)?;
+        }
+        more(f, self.added.len())?;
+        writeln!(f, "Changed: {}", self.changed.len())?;
+        for row in self.changed.iter().take(DIFF_LIST_LIMIT) {
+            let columns: Vec<&str> = row.columns.iter().map(|column| column.name()).collect();
+            writeln!(f, "~ {} {} ({})", row.oem, row.model, columns.join(", "))?;
+        }
+        more(f, self.changed.len())?;
+        writeln!(f, "Unchanged: {}", self.unchanged)
+    }
+}
@@ -0,0 +1,116 @@
+use crate::heatmap;
+
+// Size of the map; longitude and latitude are projected linearly (equirectangular).
+const WIDTH: f32 = 720.0;
+const HEIGHT: f32 = 360.0;
+// Radius of the marker of the country with the most phones.
+const MAX_RADIUS: f32 = 24.0;
+
+// Approximate (latitude, longitude) centre of each country a marker can be drawn for.
+const CENTROIDS: [(&str, f32, f32); 40] = [
+    ("Argentina", -34.0, -64.0),
+    ("Australia", -25.0, 134.0),
+    ("Austria", 47.5, 14.5),
+    ("Belgium", 50.5, 4.5),
+    ("Brazil", -10.0, -52.0),
+    ("Canada", 56.0, -106.0),
+    ("China", 35.0, 103.0),
+    ("Czech Republic", 49.8, 15.5),
+    ("Denmark", 56.0, 10.0),
+    ("Finland", 64.0, 26.0),
+    ("France", 46.5, 2.5),
+    ("Germany", 51.0, 10.5),
+    ("Hong Kong", 22.3, 114.2),
+    ("India", 22.0, 79.0),
+    ("Indonesia", -2.5, 118.0),
+    ("Ireland", 53.4, -8.0),
+    ("Israel", 31.0, 35.0),
+    ("Italy", 42.8, 12.5),
+    ("Japan", 36.5, 138.0),
+    ("Malaysia", 4.0, 102.0),
+    ("Mexico", 23.5, -102.5),
+    ("Netherlands", 52.2, 5.3),
+    ("Norway", 61.0, 9.0),
+    ("Pakistan", 30.0, 70.0),
+    ("Philippines", 12.5, 122.0),
+    ("Poland", 52.0, 19.5),
+    ("Russia", 60.0, 95.0),
+    ("Singapore", 1.35, 103.8),
+    ("South Africa", -29.0, 24.5),
+    ("South Korea", 36.5, 128.0),
+    ("Spain", 40.0, -3.7),
+    ("Sweden", 62.0, 15.0),
+    ("Switzerland", 46.8, 8.2),
+    ("Taiwan", 23.7, 121.0),
+    ("Thailand", 15.0, 101.0),
+    ("Turkey", 39.0, 35.0),
+    ("United Arab Emirates", 24.0, 54.0),
+    ("United Kingdom", 54.0, -2.0),
+    ("United States", 39.5, -98.5),
+    ("Vietnam", 16.0, 107.5),
+];
+
+// Map position of a country's centre, if it is known.
+fn position(country: &str) -> Option<(f32, f32)> {
+    CENTROIDS
+        .iter()
+        .find(|(name, _, _)| name.eq_ignore_ascii_case(country))
+        .map(|&(_, latitude, longitude)| ((longitude + 180.0) / 360.0 * WIDTH, (90.0 - latitude) / 180.0 * HEIGHT))
+}
+
+/*
+    Draw phone counts per country (as returned by country::devices_per_country) on an SVG
+    world map. Country outlines aren't bundled, so each country is a circle at its centre on
+    a latitude/longitude grid; its area and colour grow with the count. Countries without a
+    known centre are listed under the map instead.
+
+    Runtime: O(k)
+ */
+pub fn render_svg(counts: &[(String, usize)]) -> String {
+    let largest = counts.iter().map(|&(_, count)| count).max().unwrap_or(0);
+    let unplaced: Vec<&(String, usize)> = counts.iter().filter(|(country, _)| position(country).is_none()).collect();
+    let height = HEIGHT + 20.0 + 14.0 * unplaced.len() as f32;
+
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" font-family=\"sans-serif\" font-size=\"10\">\n",
+        WIDTH, height
+    );
+    svg.push_str(&format!("<rect width=\"{}\" height=\"{}\" fill=\"#dbe9f6\"/>\n", WIDTH, HEIGHT));
+
+    // Graticule every 30 degrees.
+    for step in 1..12 {
+        let x = step as f32 * WIDTH / 12.0;
+        svg.push_str(&format!("<line x1=\"{x}\" y1=\"0\" x2=\"{x}\" y2=\"{}\" stroke=\"#ffffff\"/>\n", HEIGHT, x = x));
+    }
+    for step in 1..6 {
+        let y = step as f32 * HEIGHT / 6.0;
+        svg.push_str(&format!("<line x1=\"0\" y1=\"{y}\" x2=\"{}\" y2=\"{y}\" stroke=\"#ffffff\"/>\n", WIDTH, y = y));
+    }
+
+    // Largest first, so smaller markers are drawn on top of overlapping ones.
+    for (country, count) in counts {
+        let Some((x, y)) = position(country) else {
+            continue;
+        };
+        let (r, g, b) = heatmap::color(*count, largest);
+        let radius = MAX_RADIUS * (*count as f32 / largest as f32).sqrt();
+        svg.push_str(&format!(
+            "<circle cx=\"{:.1}\" cy=\"{:.1}\" r=\"{:.1}\" fill=\"#{:02x}{:02x}{:02x}\" fill-opacity=\"0.85\" stroke=\"#555555\"><title>{}: {}</title></circle>\n",
+            x,
+            y,
+            radius.max(2.0),
+            r,
+            g,
+            b,
+            heatmap::escape(country),
+            count
+        ));
+    }
+
+    for (line, (country, count)) in unplaced.iter().enumerate() {
+        svg.push_str(&format!("<text x=\"4\" y=\"{}\">{}: {} (not on the map)</text>\n", HEIGHT + 16.0 + 14.0 * line as f32, heatmap::escape(country), count));
+    }
+
+    svg.push_str("</svg>\n");
+    svg
+}